@@ -0,0 +1,368 @@
+//! Command line of `termix`: what the first terminal runs, which settings
+//! apply to this launch, and the exit codes scripts can rely on.
+
+#[cfg(feature = "ssh")]
+use crate::config::ssh::SshConnection;
+use crate::config::profile::Profile;
+use crate::config::{self, AppSettings};
+#[cfg(all(unix, feature = "gui"))]
+use crate::ipc::Request;
+use rust_i18n::t;
+use std::path::PathBuf;
+
+/// Everything went fine (also for `--help` and `--version`).
+pub const EXIT_OK: i32 = 0;
+/// TermiX or the program it should run could not be started.
+pub const EXIT_FAILURE: i32 = 1;
+/// Invalid arguments: unknown option, missing value, unknown name.
+pub const EXIT_USAGE: i32 = 2;
+
+/// What to do besides opening TermiX; the options apply to all of them.
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    Run,
+    Help,
+    Version,
+    #[cfg(unix)]
+    Server,
+    #[cfg(unix)]
+    Sessions,
+    #[cfg(unix)]
+    Attach(Option<u64>),
+    #[cfg(all(unix, feature = "gui"))]
+    Msg(Request),
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct Options {
+    pub tui: bool,
+    pub command: Vec<String>, // program and arguments; empty = the user's shell
+    pub cwd: Option<PathBuf>,
+    pub title: Option<String>,
+    pub ssh: Option<String>, // saved connection name or user@host[:port]
+    pub layout: Option<String>,
+    pub profile: Option<String>,
+    pub profile_config: Option<Profile>, // `profile` as looked up by `check`
+    pub config: Option<PathBuf>,
+    pub theme: Option<String>, // one of `config::THEMES`
+    pub lang: Option<String>,  // one of `config::LANGUAGES`
+    #[cfg(feature = "ssh")]
+    pub ssh_connection: Option<SshConnection>, // `ssh` as looked up by `check`
+}
+
+impl Options {
+    /// Whether the first terminal differs from a plain shell tab.
+    #[cfg(any(feature = "tui", feature = "gui"))]
+    pub fn opens_terminal(&self) -> bool {
+        !self.command.is_empty()
+            || self.cwd.is_some()
+            || self.title.is_some()
+            || self.ssh.is_some()
+            || self.profile.is_some()
+    }
+
+    /// Look up the names given to `--ssh` and `--profile`. Call it once
+    /// `--config` is in effect, both live in the config dir.
+    pub fn check(&mut self, settings: &AppSettings) -> Result<(), String> {
+        if let Some(name) = &self.profile {
+            let profile = settings.profile(name).ok_or_else(|| t!("cli.unknown_profile", profile = name))?;
+            self.profile_config = Some(profile.clone());
+        }
+        #[cfg(feature = "ssh")]
+        if let Some(target) = &self.ssh {
+            let mut conn = config::ssh::find_connection(target)?;
+            if let Some(profile) = &self.profile_config {
+                conn.profile = Some(profile.name.clone());
+            }
+            self.ssh_connection = Some(conn);
+        }
+        Ok(())
+    }
+
+    /// `--theme` and `--lang` on top of the saved settings.
+    pub fn apply(&self, settings: &mut AppSettings) {
+        if let Some(theme) = &self.theme {
+            settings.theme = theme.clone();
+        }
+        if let Some(lang) = &self.lang {
+            settings.language = lang.clone();
+        }
+    }
+}
+
+/// The value of `name`: after `=` or the next argument.
+fn value(name: &str, inline: Option<String>, args: &mut impl Iterator<Item = String>) -> Result<String, String> {
+    inline
+        .or_else(|| args.next())
+        .ok_or_else(|| t!("cli.missing_value", option = name).to_string())
+}
+
+#[cfg(unix)]
+fn number<T: std::str::FromStr>(name: &str, value: String) -> Result<T, String> {
+    value.parse().map_err(|_| t!("cli.invalid_number", option = name, value = value).to_string())
+}
+
+/// `value` when it names an entry of `allowed`, in the spelling of the list.
+fn one_of(name: &str, value: String, allowed: &[&str]) -> Result<String, String> {
+    allowed
+        .iter()
+        .find(|a| a.eq_ignore_ascii_case(&value))
+        .map(|a| a.to_string())
+        .ok_or_else(|| t!("cli.invalid_value", option = name, value = value, allowed = allowed.join(", ")).to_string())
+}
+
+/// Parse the arguments after the program name. Everything after `-e` belongs
+/// to the command, so `termix -e vim -p a b` passes `-p a b` on to vim.
+pub fn parse(args: impl IntoIterator<Item = String>) -> Result<(Command, Options), String> {
+    let mut args = args.into_iter().peekable();
+    let mut options = Options::default();
+    #[cfg_attr(not(unix), allow(unused_mut))]
+    let mut command = Command::Run;
+    while let Some(arg) = args.next() {
+        let (name, inline) = match arg.split_once('=') {
+            Some((name, value)) if name.starts_with("--") => (name.to_string(), Some(value.to_string())),
+            _ => (arg, None),
+        };
+        match name.as_str() {
+            "-h" | "--help" => return Ok((Command::Help, options)),
+            "-V" | "--version" => return Ok((Command::Version, options)),
+            "--tui" => options.tui = true,
+            "-e" | "--command" => {
+                options.command.extend(inline);
+                options.command.extend(args.by_ref());
+                if options.command.is_empty() {
+                    return Err(t!("cli.missing_value", option = name).into());
+                }
+            }
+            "--cwd" => {
+                let dir = PathBuf::from(value(&name, inline, &mut args)?);
+                if !dir.is_dir() {
+                    return Err(t!("cli.not_a_directory", path = dir.display()).into());
+                }
+                options.cwd = Some(dir);
+            }
+            "--title" => options.title = Some(value(&name, inline, &mut args)?),
+            "--ssh" => options.ssh = Some(value(&name, inline, &mut args)?),
+            "--layout" => options.layout = Some(value(&name, inline, &mut args)?),
+            "--profile" => options.profile = Some(value(&name, inline, &mut args)?),
+            "--config" => options.config = Some(PathBuf::from(value(&name, inline, &mut args)?)),
+            "--theme" => options.theme = Some(one_of(&name, value(&name, inline, &mut args)?, config::THEMES)?),
+            "--lang" => options.lang = Some(one_of(&name, value(&name, inline, &mut args)?, config::LANGUAGES)?),
+            #[cfg(unix)]
+            "--server" => command = Command::Server,
+            #[cfg(unix)]
+            "--sessions" => command = Command::Sessions,
+            #[cfg(unix)]
+            "--attach" => {
+                let id = match inline {
+                    Some(id) => Some(id),
+                    None => args.next_if(|a| a.parse::<u64>().is_ok()),
+                };
+                let id = id.map(|id| number(&name, id)).transpose()?;
+                command = Command::Attach(id);
+            }
+            #[cfg(all(unix, feature = "gui"))]
+            "msg" => command = Command::Msg(parse_msg(&mut args)?),
+            _ => return Err(t!("cli.unknown_option", option = name).into()),
+        }
+    }
+    #[cfg(not(feature = "gui"))]
+    if options.layout.is_some() {
+        return Err(t!("cli.layout_needs_gui").into());
+    }
+    #[cfg(feature = "gui")]
+    if options.layout.is_some() && options.tui {
+        return Err(t!("cli.layout_needs_gui").into());
+    }
+    #[cfg(not(feature = "ssh"))]
+    if options.ssh.is_some() {
+        return Err(t!("cli.ssh_unsupported").into());
+    }
+    Ok((command, options))
+}
+
+/// `termix msg <command> [options] [args]`; without text, `send-text`
+/// leaves `text` empty for the caller to fill from stdin.
+#[cfg(all(unix, feature = "gui"))]
+fn parse_msg(args: &mut impl Iterator<Item = String>) -> Result<Request, String> {
+    let command = args.next().ok_or_else(|| t!("cli.missing_value", option = "msg").to_string())?;
+    let (mut tab, mut title, mut cwd, mut shell_command, mut lines, mut vertical) = (None, None, None, None, None, false);
+    let mut rest = Vec::new();
+    while let Some(arg) = args.next() {
+        let (name, inline) = match arg.split_once('=') {
+            Some((name, value)) if name.starts_with("--") => (name.to_string(), Some(value.to_string())),
+            _ => (arg, None),
+        };
+        match name.as_str() {
+            "--tab" => tab = Some(number(&name, value(&name, inline, args)?)?),
+            "--title" => title = Some(value(&name, inline, args)?),
+            "--cwd" => cwd = Some(value(&name, inline, args)?),
+            "--lines" => lines = Some(number(&name, value(&name, inline, args)?)?),
+            "--vertical" => vertical = true,
+            // Typed into the new shell, like the start commands of layouts
+            "-e" | "--command" => {
+                let words: Vec<String> = inline.into_iter().chain(args.by_ref()).collect();
+                if words.is_empty() {
+                    return Err(t!("cli.missing_value", option = name).into());
+                }
+                shell_command = Some(words.join(" "));
+            }
+            "--" => rest.extend(args.by_ref()),
+            _ if name.starts_with('-') && name.len() > 1 => return Err(t!("cli.unknown_option", option = name).into()),
+            _ => rest.push(name),
+        }
+    }
+    let text = rest.join(" ");
+    Ok(match command.as_str() {
+        "list" => Request::List,
+        "new-tab" => Request::NewTab { title, cwd, command: shell_command },
+        "split" => Request::Split { vertical, cwd, command: shell_command },
+        "send-text" => Request::SendText { tab, text },
+        "get-text" => Request::GetText { tab, lines },
+        "set-title" if !text.is_empty() => Request::SetTitle { tab, title: text },
+        "set-title" => return Err(t!("cli.missing_value", option = "set-title").into()),
+        "focus-tab" => match (tab, rest.first()) {
+            (Some(tab), _) => Request::FocusTab { tab },
+            (None, Some(id)) => Request::FocusTab { tab: number("focus-tab", id.clone())? },
+            (None, None) => return Err(t!("cli.missing_value", option = "focus-tab").into()),
+        },
+        "close-tab" => Request::CloseTab { tab },
+        _ => return Err(t!("cli.unknown_msg_command", command = command).into()),
+    })
+}
+
+/// `--help` text in the current language.
+pub fn help() -> String {
+    let options = [
+        ("-e, --command <cmd>…", t!("cli.help_command")),
+        ("    --cwd <dir>", t!("cli.help_cwd")),
+        ("    --title <title>", t!("cli.help_title")),
+        ("    --ssh <name|user@host>", t!("cli.help_ssh")),
+        ("    --layout <name>", t!("cli.help_layout")),
+        ("    --profile <name>", t!("cli.help_profile")),
+        ("    --config <path>", t!("cli.help_config")),
+        ("    --theme <theme>", t!("cli.help_theme")),
+        ("    --lang <de|en>", t!("cli.help_lang")),
+        ("    --tui", t!("cli.help_tui")),
+        #[cfg(unix)]
+        ("    --server", t!("cli.help_server")),
+        #[cfg(unix)]
+        ("    --sessions", t!("cli.help_sessions")),
+        #[cfg(unix)]
+        ("    --attach [id]", t!("cli.help_attach")),
+        #[cfg(all(unix, feature = "gui"))]
+        ("    msg <command> …", t!("cli.help_msg")),
+        ("-h, --help", t!("cli.help_help")),
+        ("-V, --version", t!("cli.help_version")),
+    ];
+    #[cfg(all(unix, feature = "gui"))]
+    let msg_commands = [
+        ("list", t!("cli.msg_list")),
+        ("new-tab [--title T] [--cwd D] [-e CMD…]", t!("cli.msg_new_tab")),
+        ("split [--vertical] [--cwd D] [-e CMD…]", t!("cli.msg_split")),
+        ("send-text [--tab ID] [--] [TEXT…]", t!("cli.msg_send_text")),
+        ("get-text [--tab ID] [--lines N]", t!("cli.msg_get_text")),
+        ("set-title [--tab ID] TITLE", t!("cli.msg_set_title")),
+        ("focus-tab ID", t!("cli.msg_focus_tab")),
+        ("close-tab [--tab ID]", t!("cli.msg_close_tab")),
+    ];
+    #[cfg(all(unix, feature = "gui"))]
+    let rows = options.iter().chain(msg_commands.iter());
+    #[cfg(not(all(unix, feature = "gui")))]
+    let rows = options.iter();
+    let width = rows.map(|(flags, _)| flags.chars().count()).max().unwrap_or(0) + 2;
+    let mut help = format!("{}\n\n{}\n", t!("cli.usage"), t!("cli.options"));
+    for (flags, text) in &options {
+        help.push_str(&format!("  {:<width$}{}\n", flags, text));
+    }
+    #[cfg(all(unix, feature = "gui"))]
+    {
+        help.push_str(&format!("\n{}\n", t!("cli.msg_commands")));
+        for (command, text) in &msg_commands {
+            help.push_str(&format!("  {:<width$}{}\n", command, text));
+        }
+    }
+    help.push_str(&format!(
+        "\n{}\n  {}  {}\n  {}  {}\n  {}  {}\n",
+        t!("cli.exit_status"),
+        EXIT_OK,
+        t!("cli.exit_ok"),
+        EXIT_FAILURE,
+        t!("cli.exit_failure"),
+        EXIT_USAGE,
+        t!("cli.exit_usage")
+    ));
+    help
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Command, String> {
+        super::parse(args.iter().map(|a| a.to_string())).map(|(command, _)| command)
+    }
+
+    fn options(args: &[&str]) -> Options {
+        match super::parse(args.iter().map(|a| a.to_string())) {
+            Ok((Command::Run, options)) => options,
+            other => panic!("{:?} parsed as {:?}", args, other),
+        }
+    }
+
+    #[test]
+    fn command_takes_the_remaining_arguments() {
+        let options = options(&["--title", "logs", "-e", "tail", "-f", "--title", "x"]);
+        assert_eq!(options.title.as_deref(), Some("logs"));
+        assert_eq!(options.command, ["tail", "-f", "--title", "x"]);
+        assert!(parse(&["-e"]).is_err());
+    }
+
+    #[test]
+    fn values_follow_or_are_joined_with_equals() {
+        assert_eq!(options(&["--theme", "nord"]).theme.as_deref(), Some("Nord"));
+        assert_eq!(options(&["--theme=solarized dark"]).theme.as_deref(), Some("Solarized Dark"));
+        assert_eq!(options(&["--lang=en"]).lang.as_deref(), Some("en"));
+        assert!(parse(&["--theme", "Plaid"]).is_err());
+        assert!(parse(&["--lang"]).is_err());
+    }
+
+    #[test]
+    fn rejects_unknown_options_and_missing_directories() {
+        assert!(parse(&["--frobnicate"]).is_err());
+        assert!(parse(&["--cwd", "/no/such/termix/dir"]).is_err());
+        assert_eq!(options(&["--cwd", "/"]).cwd, Some(PathBuf::from("/")));
+    }
+
+    #[test]
+    fn help_and_version_win() {
+        assert_eq!(parse(&["--tui", "--help", "--frobnicate"]), Ok(Command::Help));
+        assert_eq!(parse(&["-V"]), Ok(Command::Version));
+    }
+
+    #[cfg(all(unix, feature = "gui"))]
+    #[test]
+    fn msg_builds_requests() {
+        assert_eq!(parse(&["msg", "list"]), Ok(Command::Msg(Request::List)));
+        assert_eq!(
+            parse(&["msg", "new-tab", "--title=build", "-e", "cargo", "build"]),
+            Ok(Command::Msg(Request::NewTab { title: Some("build".into()), cwd: None, command: Some("cargo build".into()) }))
+        );
+        assert_eq!(parse(&["msg", "focus-tab", "4"]), Ok(Command::Msg(Request::FocusTab { tab: 4 })));
+        assert_eq!(
+            parse(&["msg", "send-text", "--tab", "2", "--", "ls", "-l"]),
+            Ok(Command::Msg(Request::SendText { tab: Some(2), text: "ls -l".into() }))
+        );
+        assert!(parse(&["msg", "send-text", "ls", "-l"]).is_err());
+        assert!(parse(&["msg", "set-title"]).is_err());
+        assert!(parse(&["msg", "frobnicate"]).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn attach_takes_an_optional_id() {
+        assert_eq!(parse(&["--attach", "3"]), Ok(Command::Attach(Some(3))));
+        assert_eq!(parse(&["--attach", "--tui"]), Ok(Command::Attach(None)));
+        assert!(parse(&["--attach=x"]).is_err());
+    }
+}
@@ -0,0 +1,232 @@
+//! Command history across sessions (opt-in with `record_history`).
+//!
+//! Every command run in a terminal is appended to `history.jsonl` in the
+//! data directory, one JSON object per line, with the time, the SSH host
+//! (none for local shells), the working directory and the exit code when the
+//! shell reports them. Commands typed with a leading space are not recorded,
+//! like `HISTCONTROL=ignorespace` in bash.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Entries kept; older ones are dropped when the file is compacted.
+pub const LIMIT: usize = 10_000;
+
+pub fn history_path() -> PathBuf {
+    super::data_dir().join("history.jsonl")
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub command: String,
+    pub time: u64, // seconds since the Unix epoch
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub host: Option<String>, // SSH connection name; None = local shell
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+}
+
+impl HistoryEntry {
+    /// An entry for a command starting now.
+    pub fn new(command: &str, host: Option<String>, cwd: Option<String>) -> Self {
+        let time = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+        HistoryEntry { command: command.to_string(), time, host, cwd, exit_code: None }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Status {
+    #[default]
+    Any,
+    Succeeded,
+    Failed,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Filter {
+    pub query: String,
+    pub host: Option<Option<String>>, // None = every host, Some(None) = local shells
+    pub cwd: Option<String>,
+    pub status: Status,
+}
+
+impl Filter {
+    fn keeps(&self, entry: &HistoryEntry) -> bool {
+        self.host.as_ref().is_none_or(|host| *host == entry.host)
+            && self.cwd.as_ref().is_none_or(|cwd| Some(cwd) == entry.cwd.as_ref())
+            && match self.status {
+                Status::Any => true,
+                Status::Succeeded => entry.exit_code == Some(0),
+                Status::Failed => entry.exit_code.is_some_and(|code| code != 0),
+            }
+    }
+}
+
+pub struct History {
+    path: PathBuf,
+    entries: Vec<HistoryEntry>, // oldest first
+}
+
+impl History {
+    pub fn load() -> Self {
+        Self::load_from(history_path())
+    }
+
+    /// Read `path`, skipping lines that do not parse; a file grown past
+    /// `LIMIT` is cut down to the newest entries.
+    pub fn load_from(path: PathBuf) -> Self {
+        let entries = fs::read_to_string(&path)
+            .map(|content| content.lines().filter_map(|line| serde_json::from_str(line).ok()).collect())
+            .unwrap_or_default();
+        let mut history = History { path, entries };
+        if history.entries.len() > LIMIT {
+            let _ = history.compact();
+        }
+        history
+    }
+
+    pub fn entries(&self) -> &[HistoryEntry] {
+        &self.entries
+    }
+
+    /// Append `entry` to the file. Empty commands and those starting with a
+    /// space are skipped.
+    pub fn record(&mut self, mut entry: HistoryEntry) -> std::io::Result<()> {
+        if entry.command.starts_with(' ') || entry.command.trim().is_empty() {
+            return Ok(());
+        }
+        entry.command = entry.command.trim_end().to_string();
+        let line = serde_json::to_string(&entry)?;
+        self.entries.push(entry);
+        // Compact in batches instead of rewriting the file for every command
+        if self.entries.len() > LIMIT + LIMIT / 10 {
+            return self.compact();
+        }
+        if let Some(dir) = self.path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+        let mut file = fs::OpenOptions::new().create(true).append(true).open(&self.path)?;
+        writeln!(file, "{line}")
+    }
+
+    pub fn clear(&mut self) -> std::io::Result<()> {
+        self.entries.clear();
+        match fs::remove_file(&self.path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+
+    /// Entries passing `filter`, newest first and each command once (its
+    /// latest run). With a query they are ranked by fuzzy match, the newer
+    /// one first among equally good matches.
+    pub fn search(&self, filter: &Filter) -> Vec<&HistoryEntry> {
+        let mut seen = std::collections::HashSet::new();
+        let mut found: Vec<(i64, &HistoryEntry)> = self
+            .entries
+            .iter()
+            .rev()
+            .filter(|entry| filter.keeps(entry))
+            .filter(|entry| seen.insert(entry.command.as_str()))
+            .filter_map(|entry| Some((crate::fuzzy::score(&filter.query, &entry.command)?, entry)))
+            .collect();
+        found.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+        found.into_iter().map(|(_, entry)| entry).collect()
+    }
+
+    /// Hosts that appear in the history, for the panel's filter.
+    pub fn hosts(&self) -> Vec<&str> {
+        let mut hosts: Vec<&str> = self.entries.iter().filter_map(|e| e.host.as_deref()).collect();
+        hosts.sort_unstable();
+        hosts.dedup();
+        hosts
+    }
+
+    fn compact(&mut self) -> std::io::Result<()> {
+        let excess = self.entries.len().saturating_sub(LIMIT);
+        self.entries.drain(..excess);
+        let mut content = String::new();
+        for entry in &self.entries {
+            content.push_str(&serde_json::to_string(entry)?);
+            content.push('\n');
+        }
+        super::write_atomic(&self.path, &content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("termix-history-{}-{name}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir.join("history.jsonl")
+    }
+
+    fn entry(command: &str, host: Option<&str>, exit_code: Option<i32>) -> HistoryEntry {
+        HistoryEntry { exit_code, ..HistoryEntry::new(command, host.map(String::from), Some("/srv".into())) }
+    }
+
+    #[test]
+    fn records_and_reloads() {
+        let path = scratch("reload");
+        let mut history = History::load_from(path.clone());
+        history.record(entry("make test", None, Some(2))).unwrap();
+        history.record(entry(" secret --token x", None, None)).unwrap();
+        history.record(entry("uptime", Some("web"), None)).unwrap();
+        fs::OpenOptions::new().append(true).open(&path).unwrap().write_all(b"not json\n").unwrap();
+
+        let history = History::load_from(path.clone());
+        let commands: Vec<&str> = history.entries().iter().map(|e| e.command.as_str()).collect();
+        assert_eq!(commands, ["make test", "uptime"]);
+        assert_eq!(history.entries()[0].exit_code, Some(2));
+        assert_eq!(history.hosts(), ["web"]);
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn search_dedups_filters_and_ranks() {
+        let mut history = History::load_from(scratch("search"));
+        history.entries = vec![
+            entry("git status", None, Some(0)),
+            entry("cargo test", None, Some(101)),
+            entry("git stash", Some("web"), Some(0)),
+            entry("git status", None, Some(1)),
+        ];
+        let commands = |filter: &Filter| history.search(filter).iter().map(|e| e.command.clone()).collect::<Vec<_>>();
+
+        assert_eq!(commands(&Filter::default()), ["git status", "git stash", "cargo test"]);
+        let query = Filter { query: "stat".into(), ..Default::default() };
+        assert_eq!(commands(&query), ["git status"]);
+        let failed = Filter { status: Status::Failed, ..Default::default() };
+        assert_eq!(commands(&failed), ["git status", "cargo test"]);
+        let remote = Filter { host: Some(Some("web".into())), ..Default::default() };
+        assert_eq!(commands(&remote), ["git stash"]);
+        let elsewhere = Filter { cwd: Some("/tmp".into()), ..Default::default() };
+        assert!(commands(&elsewhere).is_empty());
+    }
+
+    #[test]
+    fn compacts_to_the_limit() {
+        let path = scratch("compact");
+        let content: String = (0..LIMIT + 5)
+            .map(|i| serde_json::to_string(&entry(&format!("echo {i}"), None, None)).unwrap() + "\n")
+            .collect();
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, content).unwrap();
+
+        let mut history = History::load_from(path.clone());
+        assert_eq!(history.entries().len(), LIMIT);
+        assert_eq!(history.entries()[0].command, "echo 5");
+        assert_eq!(fs::read_to_string(&path).unwrap().lines().count(), LIMIT);
+        history.clear().unwrap();
+        assert!(!path.exists());
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+    }
+}
@@ -0,0 +1,435 @@
+//! Keyboard shortcuts of the GUI: the actions they run, the key chords that
+//! run them and the `[keybindings]` table of `settings.toml`, which maps
+//! action ids to chords (`new_tab = "Ctrl+Shift+T"`, `""` unbinds).
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    NewTab,
+    CloseTab,
+    NextTab,
+    PreviousTab,
+    SplitHorizontal,
+    SplitVertical,
+    ZoomPane,
+    FocusLeft,
+    FocusRight,
+    FocusUp,
+    FocusDown,
+    FocusPane(u8), // 1..=9: the n-th split pane
+    ToggleBroadcast,
+    Copy,
+    Paste,
+    Search,
+    FontLarger,
+    FontSmaller,
+    FontReset,
+    PassThrough, // send every other chord to the terminal until pressed again
+    CommandPalette,
+    HistorySearch, // Ctrl+R over the recorded history; the shell's own without recording
+    InsertSnippet,
+}
+
+/// Every action, in the order the settings list them.
+pub const ACTIONS: &[Action] = &[
+    Action::NewTab,
+    Action::CloseTab,
+    Action::NextTab,
+    Action::PreviousTab,
+    Action::SplitHorizontal,
+    Action::SplitVertical,
+    Action::ZoomPane,
+    Action::FocusLeft,
+    Action::FocusRight,
+    Action::FocusUp,
+    Action::FocusDown,
+    Action::FocusPane(1),
+    Action::FocusPane(2),
+    Action::FocusPane(3),
+    Action::FocusPane(4),
+    Action::FocusPane(5),
+    Action::FocusPane(6),
+    Action::FocusPane(7),
+    Action::FocusPane(8),
+    Action::FocusPane(9),
+    Action::ToggleBroadcast,
+    Action::Copy,
+    Action::Paste,
+    Action::Search,
+    Action::FontLarger,
+    Action::FontSmaller,
+    Action::FontReset,
+    Action::PassThrough,
+    Action::CommandPalette,
+    Action::HistorySearch,
+    Action::InsertSnippet,
+];
+
+impl Action {
+    /// Key of the action in `[keybindings]`.
+    pub fn id(self) -> String {
+        let id = match self {
+            Action::NewTab => "new_tab",
+            Action::CloseTab => "close_tab",
+            Action::NextTab => "next_tab",
+            Action::PreviousTab => "previous_tab",
+            Action::SplitHorizontal => "split_horizontal",
+            Action::SplitVertical => "split_vertical",
+            Action::ZoomPane => "zoom_pane",
+            Action::FocusLeft => "focus_left",
+            Action::FocusRight => "focus_right",
+            Action::FocusUp => "focus_up",
+            Action::FocusDown => "focus_down",
+            Action::FocusPane(n) => return format!("focus_pane_{}", n),
+            Action::ToggleBroadcast => "toggle_broadcast",
+            Action::Copy => "copy",
+            Action::Paste => "paste",
+            Action::Search => "search",
+            Action::FontLarger => "font_larger",
+            Action::FontSmaller => "font_smaller",
+            Action::FontReset => "font_reset",
+            Action::PassThrough => "pass_through",
+            Action::CommandPalette => "command_palette",
+            Action::HistorySearch => "history_search",
+            Action::InsertSnippet => "insert_snippet",
+        };
+        id.to_string()
+    }
+
+    pub fn from_id(id: &str) -> Option<Action> {
+        ACTIONS.iter().copied().find(|a| a.id() == id.trim())
+    }
+
+    pub fn name(self) -> String {
+        match self {
+            Action::NewTab => rust_i18n::t!("keys.new_tab"),
+            Action::CloseTab => rust_i18n::t!("keys.close_tab"),
+            Action::NextTab => rust_i18n::t!("keys.next_tab"),
+            Action::PreviousTab => rust_i18n::t!("keys.previous_tab"),
+            Action::SplitHorizontal => rust_i18n::t!("keys.split_horizontal"),
+            Action::SplitVertical => rust_i18n::t!("keys.split_vertical"),
+            Action::ZoomPane => rust_i18n::t!("keys.zoom_pane"),
+            Action::FocusLeft => rust_i18n::t!("keys.focus_left"),
+            Action::FocusRight => rust_i18n::t!("keys.focus_right"),
+            Action::FocusUp => rust_i18n::t!("keys.focus_up"),
+            Action::FocusDown => rust_i18n::t!("keys.focus_down"),
+            Action::FocusPane(n) => rust_i18n::t!("keys.focus_pane", n = n),
+            Action::ToggleBroadcast => rust_i18n::t!("keys.toggle_broadcast"),
+            Action::Copy => rust_i18n::t!("keys.copy"),
+            Action::Paste => rust_i18n::t!("keys.paste"),
+            Action::Search => rust_i18n::t!("keys.search"),
+            Action::FontLarger => rust_i18n::t!("keys.font_larger"),
+            Action::FontSmaller => rust_i18n::t!("keys.font_smaller"),
+            Action::FontReset => rust_i18n::t!("keys.font_reset"),
+            Action::PassThrough => rust_i18n::t!("keys.pass_through"),
+            Action::CommandPalette => rust_i18n::t!("keys.command_palette"),
+            Action::HistorySearch => rust_i18n::t!("keys.history_search"),
+            Action::InsertSnippet => rust_i18n::t!("keys.insert_snippet"),
+        }
+        .into()
+    }
+
+    /// Chord of a fresh install. Ctrl+letter chords carry Shift, since shells
+    /// and editors use the plain ones (Ctrl+W, Ctrl+H, Ctrl+T, ...); only the
+    /// history search takes the shell's Ctrl+R, and only while it records.
+    pub fn default_chord(self) -> Option<KeyChord> {
+        let chord = match self {
+            Action::NewTab => "Ctrl+Shift+T",
+            Action::CloseTab => "Ctrl+Shift+W",
+            Action::NextTab => "Ctrl+Tab",
+            Action::PreviousTab => "Ctrl+Shift+Tab",
+            Action::SplitHorizontal => "Ctrl+Shift+H",
+            Action::SplitVertical => "Ctrl+Shift+E",
+            Action::ZoomPane => "Ctrl+Shift+Z",
+            Action::FocusLeft => "Ctrl+Alt+Left",
+            Action::FocusRight => "Ctrl+Alt+Right",
+            Action::FocusUp => "Ctrl+Alt+Up",
+            Action::FocusDown => "Ctrl+Alt+Down",
+            Action::FocusPane(n) => return KeyChord::parse(&format!("Ctrl+{}", n)).ok(),
+            Action::ToggleBroadcast => "Ctrl+Shift+B",
+            Action::Copy => "Ctrl+Shift+C",
+            Action::Paste => "Ctrl+Shift+V",
+            Action::Search => "Ctrl+Shift+F",
+            Action::FontLarger => "Ctrl+Plus",
+            Action::FontSmaller => "Ctrl+Minus",
+            Action::FontReset => "Ctrl+0",
+            Action::PassThrough => "Ctrl+Shift+K",
+            Action::CommandPalette => "Ctrl+Shift+P",
+            Action::HistorySearch => "Ctrl+R",
+            Action::InsertSnippet => "Ctrl+Shift+S",
+        };
+        KeyChord::parse(chord).ok()
+    }
+}
+
+/// Key names a chord can end in; `parse` also takes the aliases below.
+pub const KEYS: &[&str] = &[
+    "A", "B", "C", "D", "E", "F", "G", "H", "I", "J", "K", "L", "M", "N", "O", "P", "Q", "R", "S", "T", "U", "V", "W",
+    "X", "Y", "Z", "0", "1", "2", "3", "4", "5", "6", "7", "8", "9", "F1", "F2", "F3", "F4", "F5", "F6", "F7", "F8",
+    "F9", "F10", "F11", "F12", "Tab", "Enter", "Space", "Backspace", "Escape", "Insert", "Delete", "Home", "End",
+    "PageUp", "PageDown", "Left", "Right", "Up", "Down", "Plus", "Minus", "Equals", "Comma", "Period", "Slash",
+];
+
+const KEY_ALIASES: &[(&str, &str)] = &[
+    ("Esc", "Escape"),
+    ("Return", "Enter"),
+    ("Del", "Delete"),
+    ("Ins", "Insert"),
+    ("ArrowLeft", "Left"),
+    ("ArrowRight", "Right"),
+    ("ArrowUp", "Up"),
+    ("ArrowDown", "Down"),
+    ("-", "Minus"),
+    ("=", "Equals"),
+    (",", "Comma"),
+    (".", "Period"),
+    ("/", "Slash"),
+];
+
+/// A key with the modifiers held while pressing it, like `Ctrl+Shift+T`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct KeyChord {
+    pub ctrl: bool,
+    pub alt: bool,
+    pub shift: bool,
+    pub key: String, // one of `KEYS`
+}
+
+impl KeyChord {
+    pub fn new(key: &str, ctrl: bool, alt: bool, shift: bool) -> Option<Self> {
+        let key = canonical_key(key)?;
+        Some(KeyChord { ctrl, alt, shift, key })
+    }
+
+    /// `Ctrl+Alt+Shift+<key>` with the modifiers in any order and case.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let invalid = || rust_i18n::t!("keys.invalid_chord", chord = text.trim()).to_string();
+        // "Ctrl++" names the plus key
+        let text = text.trim();
+        let (mods, key) = match text.strip_suffix("++") {
+            Some(mods) => (mods, "Plus"),
+            None => text.rsplit_once('+').unwrap_or(("", text)),
+        };
+        let mut chord = KeyChord::new(key.trim(), false, false, false).ok_or_else(invalid)?;
+        for m in mods.split('+').map(str::trim).filter(|m| !m.is_empty()) {
+            match m.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" | "strg" => chord.ctrl = true,
+                "alt" | "option" => chord.alt = true,
+                "shift" => chord.shift = true,
+                _ => return Err(invalid()),
+            }
+        }
+        Ok(chord)
+    }
+
+    /// Whether a program in the terminal would get this chord if it were not
+    /// bound: typed keys, control characters (Ctrl+letter) and Alt+letter.
+    pub fn shadows_terminal(&self) -> bool {
+        let letter = self.key.len() == 1 && self.key.chars().all(|c| c.is_ascii_alphabetic());
+        (!self.ctrl && !self.alt) || (!self.shift && letter)
+    }
+
+    /// Like `Display`, with the modifier names of the current language.
+    pub fn label(&self) -> String {
+        let mut parts = Vec::new();
+        if self.ctrl {
+            parts.push(rust_i18n::t!("keys.ctrl").to_string());
+        }
+        if self.alt {
+            parts.push(rust_i18n::t!("keys.alt").to_string());
+        }
+        if self.shift {
+            parts.push(rust_i18n::t!("keys.shift").to_string());
+        }
+        parts.push(self.key.clone());
+        parts.join("+")
+    }
+}
+
+impl fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.ctrl {
+            f.write_str("Ctrl+")?;
+        }
+        if self.alt {
+            f.write_str("Alt+")?;
+        }
+        if self.shift {
+            f.write_str("Shift+")?;
+        }
+        f.write_str(&self.key)
+    }
+}
+
+fn canonical_key(name: &str) -> Option<String> {
+    let name = KEY_ALIASES.iter().find(|(alias, _)| alias.eq_ignore_ascii_case(name)).map_or(name, |(_, key)| key);
+    KEYS.iter().find(|k| k.eq_ignore_ascii_case(name)).map(|k| k.to_string())
+}
+
+/// The chord of every action: the defaults with `[keybindings]` applied.
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    bindings: Vec<(Action, Option<KeyChord>)>, // in `ACTIONS` order
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Keymap { bindings: ACTIONS.iter().map(|a| (*a, a.default_chord())).collect() }
+    }
+}
+
+impl Keymap {
+    /// Entries `check` would reject keep their default chord.
+    pub fn new(overrides: &BTreeMap<String, String>) -> Self {
+        let mut keymap = Keymap::default();
+        for (id, chord) in overrides {
+            if let (Some(action), Ok(chord)) = (Action::from_id(id), parse_binding(chord)) {
+                keymap.set(action, chord);
+            }
+        }
+        keymap
+    }
+
+    /// Reject unknown actions, unreadable chords and chords bound twice.
+    pub fn check(overrides: &BTreeMap<String, String>) -> Result<(), String> {
+        let mut keymap = Keymap::default();
+        for (id, chord) in overrides {
+            let action = Action::from_id(id).ok_or_else(|| rust_i18n::t!("keys.unknown_action", action = id).to_string())?;
+            keymap.set(action, parse_binding(chord)?);
+        }
+        match keymap.conflicts().first() {
+            Some((chord, actions)) => Err(rust_i18n::t!(
+                "keys.conflict",
+                chord = chord,
+                first = actions[0].id(),
+                second = actions[1].id()
+            )
+            .into()),
+            None => Ok(()),
+        }
+    }
+
+    pub fn chord(&self, action: Action) -> Option<&KeyChord> {
+        self.bindings.iter().find(|(a, _)| *a == action).and_then(|(_, c)| c.as_ref())
+    }
+
+    pub fn action(&self, chord: &KeyChord) -> Option<Action> {
+        self.bindings.iter().find(|(_, c)| c.as_ref() == Some(chord)).map(|(a, _)| *a)
+    }
+
+    /// `None` unbinds the action.
+    pub fn set(&mut self, action: Action, chord: Option<KeyChord>) {
+        if let Some(binding) = self.bindings.iter_mut().find(|(a, _)| *a == action) {
+            binding.1 = chord;
+        }
+    }
+
+    /// Chords bound to more than one action, with those actions.
+    pub fn conflicts(&self) -> Vec<(KeyChord, Vec<Action>)> {
+        let mut conflicts: Vec<(KeyChord, Vec<Action>)> = Vec::new();
+        for (action, chord) in &self.bindings {
+            let Some(chord) = chord else { continue };
+            match conflicts.iter_mut().find(|(c, _)| c == chord) {
+                Some((_, actions)) => actions.push(*action),
+                None => conflicts.push((chord.clone(), vec![*action])),
+            }
+        }
+        conflicts.retain(|(_, actions)| actions.len() > 1);
+        conflicts
+    }
+
+    /// The `[keybindings]` entries that differ from the defaults.
+    pub fn overrides(&self) -> BTreeMap<String, String> {
+        self.bindings
+            .iter()
+            .filter(|(action, chord)| *chord != action.default_chord())
+            .map(|(action, chord)| (action.id(), chord.as_ref().map(KeyChord::to_string).unwrap_or_default()))
+            .collect()
+    }
+
+    /// Chord of `action` for menus and tooltips.
+    pub fn label(&self, action: Action) -> String {
+        match self.chord(action) {
+            Some(chord) => chord.label(),
+            None => rust_i18n::t!("keys.unbound").into(),
+        }
+    }
+}
+
+/// A `[keybindings]` value: a chord, or `""`/`"none"` for no chord.
+fn parse_binding(text: &str) -> Result<Option<KeyChord>, String> {
+    if text.trim().is_empty() || text.trim().eq_ignore_ascii_case("none") {
+        return Ok(None);
+    }
+    KeyChord::parse(text).map(Some)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{settings_to_toml, AppSettings};
+
+    #[test]
+    fn chords_parse_and_print() {
+        let chord = KeyChord::parse(" shift+ctrl+t ").unwrap();
+        assert_eq!(chord.to_string(), "Ctrl+Shift+T");
+        assert_eq!(KeyChord::parse("Ctrl++").unwrap().to_string(), "Ctrl+Plus");
+        assert_eq!(KeyChord::parse("Ctrl+Alt+ArrowLeft").unwrap().to_string(), "Ctrl+Alt+Left");
+        assert_eq!(KeyChord::parse("F12").unwrap().to_string(), "F12");
+        assert!(KeyChord::parse("Ctrl+Hyper+T").is_err());
+        assert!(KeyChord::parse("Ctrl+").is_err());
+    }
+
+    #[test]
+    fn terminal_chords_are_flagged() {
+        for chord in ["Ctrl+W", "Alt+B", "Q", "Tab"] {
+            assert!(KeyChord::parse(chord).unwrap().shadows_terminal(), "{}", chord);
+        }
+        for chord in ["Ctrl+Shift+W", "Ctrl+Tab", "Ctrl+1", "Ctrl+Alt+Left"] {
+            assert!(!KeyChord::parse(chord).unwrap().shadows_terminal(), "{}", chord);
+        }
+    }
+
+    #[test]
+    fn defaults_have_no_conflicts() {
+        let keymap = Keymap::default();
+        assert!(keymap.conflicts().is_empty());
+        assert!(keymap.overrides().is_empty());
+        for action in ACTIONS {
+            assert_eq!(Action::from_id(&action.id()), Some(*action));
+            let shadows = keymap.chord(*action).map(|c| c.shadows_terminal());
+            assert_eq!(shadows, Some(*action == Action::HistorySearch), "{:?}", action);
+        }
+    }
+
+    #[test]
+    fn overrides_apply_and_conflicts_are_rejected() {
+        let mut overrides = BTreeMap::new();
+        overrides.insert("new_tab".to_string(), "Ctrl+Alt+T".to_string());
+        overrides.insert("zoom_pane".to_string(), "none".to_string());
+        assert!(Keymap::check(&overrides).is_ok());
+        let keymap = Keymap::new(&overrides);
+        assert_eq!(keymap.action(&KeyChord::parse("Ctrl+Alt+T").unwrap()), Some(Action::NewTab));
+        assert_eq!(keymap.chord(Action::ZoomPane), None);
+        assert_eq!(keymap.overrides()["zoom_pane"], "");
+
+        overrides.insert("search".to_string(), "ctrl+alt+t".to_string());
+        assert!(Keymap::check(&overrides).is_err());
+        assert_eq!(Keymap::new(&overrides).conflicts()[0].1, [Action::NewTab, Action::Search]);
+        overrides.clear();
+        overrides.insert("launch_rockets".to_string(), "Ctrl+R".to_string());
+        assert!(Keymap::check(&overrides).is_err());
+    }
+
+    #[test]
+    fn keybindings_round_trip_through_settings() {
+        let mut keymap = Keymap::default();
+        keymap.set(Action::CloseTab, KeyChord::parse("Ctrl+Alt+W").ok());
+        let settings = AppSettings { keybindings: keymap.overrides(), ..Default::default() };
+        let settings: AppSettings = toml::from_str(&settings_to_toml(&settings).unwrap()).unwrap();
+        assert!(settings.validate().is_ok());
+        assert_eq!(Keymap::new(&settings.keybindings).label(Action::CloseTab), KeyChord::parse("Ctrl+Alt+W").unwrap().label());
+    }
+}
@@ -0,0 +1,213 @@
+//! Named terminal layouts: tab and split arrangement with per-tab command,
+//! working directory and SSH target. Stored as `layouts/<name>.toml` in the
+//! config dir; paths below the home directory are written as `~/…` so the
+//! files can be shared.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const LAYOUT_VERSION: u32 = 1;
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct Layout {
+    #[serde(default = "default_version")]
+    pub version: u32,
+    pub name: String,
+    pub root: LayoutNode,
+}
+
+fn default_version() -> u32 {
+    LAYOUT_VERSION
+}
+
+impl Layout {
+    pub fn new(name: impl Into<String>, root: LayoutNode) -> Self {
+        Self { version: LAYOUT_VERSION, name: name.into(), root }
+    }
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum LayoutNode {
+    Pane {
+        #[serde(default)]
+        active: usize,
+        tabs: Vec<LayoutTab>,
+    },
+    Split {
+        orientation: LayoutOrientation,
+        #[serde(default = "default_size")]
+        size: f32,
+        first: Box<LayoutNode>,
+        second: Box<LayoutNode>,
+    },
+}
+
+fn default_size() -> f32 {
+    0.5
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum LayoutOrientation {
+    Horizontal,
+    Vertical,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, Default)]
+pub struct LayoutTab {
+    pub title: String,
+    /// Typed into the shell after start.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<String>,
+    /// Terminal profile, by name; tabs whose profile is gone start a plain shell.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    /// Last lines of output; only written for session restore.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scrollback: Option<String>,
+    /// Server session the tab was attached to; only written for session restore.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub server: Option<u64>,
+    /// Last, as TOML wants plain values before tables.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ssh: Option<LayoutSsh>,
+}
+
+/// SSH target of a tab. Never contains secrets.
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct LayoutSsh {
+    pub name: String,
+    pub host: String,
+    pub port: u16,
+    pub username: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub identity_file: Option<String>,
+}
+
+pub fn layouts_dir() -> PathBuf {
+    super::config_dir().join("layouts")
+}
+
+fn layout_path(name: &str) -> Result<PathBuf, String> {
+    let name = name.trim();
+    if name.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '.' | ' ')) || name.starts_with('.') {
+        return Err(format!("Invalid layout name: '{}'", name));
+    }
+    Ok(layouts_dir().join(format!("{}.toml", name)))
+}
+
+/// Names of all saved layouts, sorted.
+pub fn list_layouts() -> Vec<String> {
+    let mut names: Vec<String> = fs::read_dir(layouts_dir())
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|e| e == "toml"))
+        .filter_map(|p| p.file_stem().map(|s| s.to_string_lossy().into_owned()))
+        .collect();
+    names.sort();
+    names
+}
+
+pub fn load_layout(name: &str) -> Result<Layout, String> {
+    let path = layout_path(name)?;
+    if !path.exists() {
+        return Err(format!("Layout '{}' not found in {}", name, layouts_dir().display()));
+    }
+    import_layout(&path)
+}
+
+pub fn save_layout(layout: &Layout) -> Result<(), String> {
+    let path = layout_path(&layout.name)?;
+    let content = toml::to_string_pretty(layout).map_err(|e| e.to_string())?;
+    super::write_atomic(&path, &content).map_err(|e| e.to_string())
+}
+
+pub fn delete_layout(name: &str) -> Result<(), String> {
+    fs::remove_file(layout_path(name)?).map_err(|e| e.to_string())
+}
+
+pub fn export_layout(layout: &Layout, export_path: &Path) -> Result<(), String> {
+    let content = toml::to_string_pretty(layout).map_err(|e| e.to_string())?;
+    fs::write(export_path, content).map_err(|e| e.to_string())
+}
+
+pub fn import_layout(import_path: &Path) -> Result<Layout, String> {
+    let content = fs::read_to_string(import_path).map_err(|e| e.to_string())?;
+    let layout: Layout = toml::from_str(&content).map_err(|e| e.to_string())?;
+    if layout.version > LAYOUT_VERSION {
+        return Err(format!("Layout '{}' needs a newer TermiX (version {})", layout.name, layout.version));
+    }
+    Ok(layout)
+}
+
+/// `/home/me/src` -> `~/src`, for layouts that are shared between users.
+pub fn shorten_home(path: &Path) -> String {
+    if let Some(rest) = super::dirs_home().and_then(|home| path.strip_prefix(home).ok().map(Path::to_path_buf)) {
+        if rest.as_os_str().is_empty() {
+            return "~".into();
+        }
+        return format!("~/{}", rest.display());
+    }
+    path.display().to_string()
+}
+
+pub fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix('~'), super::dirs_home()) {
+        (Some(rest), Some(home)) if rest.is_empty() || rest.starts_with('/') => {
+            home.join(rest.trim_start_matches('/'))
+        }
+        _ => PathBuf::from(path),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pane(tabs: Vec<LayoutTab>) -> LayoutNode {
+        LayoutNode::Pane { active: tabs.len() - 1, tabs }
+    }
+
+    #[test]
+    fn nested_splits_round_trip() {
+        let ssh = LayoutSsh { name: "web".into(), host: "web.example".into(), port: 2222, username: "me".into(), identity_file: Some("~/.ssh/id_ed25519".into()) };
+        let editor = LayoutTab { title: "edit".into(), command: Some("vim".into()), cwd: Some("~/src".into()), ..Default::default() };
+        let remote = LayoutTab { title: "web".into(), ssh: Some(ssh), profile: Some("Work".into()), ..Default::default() };
+        let root = LayoutNode::Split {
+            orientation: LayoutOrientation::Horizontal,
+            size: 0.3,
+            first: Box::new(pane(vec![editor.clone(), remote])),
+            second: Box::new(LayoutNode::Split {
+                orientation: LayoutOrientation::Vertical,
+                size: 0.5,
+                first: Box::new(pane(vec![LayoutTab { title: "logs".into(), ..Default::default() }])),
+                second: Box::new(pane(vec![editor])),
+            }),
+        };
+        let layout = Layout::new("dev", root);
+
+        let path = std::env::temp_dir().join(format!("termix-layout-{}-dev.toml", std::process::id()));
+        export_layout(&layout, &path).unwrap();
+        let loaded = import_layout(&path);
+        let _ = fs::remove_file(&path);
+        assert_eq!(loaded.unwrap(), layout);
+    }
+
+    #[test]
+    fn newer_layouts_are_refused() {
+        let mut layout = Layout::new("next", pane(vec![LayoutTab::default()]));
+        layout.version = LAYOUT_VERSION + 1;
+        let path = std::env::temp_dir().join(format!("termix-layout-{}-next.toml", std::process::id()));
+        export_layout(&layout, &path).unwrap();
+        let loaded = import_layout(&path);
+        let _ = fs::remove_file(&path);
+        assert!(loaded.is_err());
+        assert!(layout_path("../escape").is_err() && layout_path(".hidden").is_err());
+    }
+}
@@ -1,7 +1,27 @@
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::{Path, PathBuf};
 
+pub mod keybindings;
+pub mod layout;
+pub mod profile;
+pub mod session;
+pub mod snippets;
+#[cfg(feature = "ssh")]
+pub mod ssh;
+pub mod history;
+#[cfg(feature = "gui")]
+pub mod vault;
+
+use profile::Profile;
+
+/// Values the front-ends understand for the string settings.
+pub const THEMES: &[&str] = &["Dark", "Light", "Dracula", "Monokai", "Solarized Dark", "Nord", "Gruvbox Dark"];
+pub const CURSOR_SHAPES: &[&str] = &["Block", "Underline", "VerticalBar", "DoubleUnderscore", "Box", "Cross"];
+pub const LANGUAGES: &[&str] = &["de", "en"];
+pub const SCROLLBACK_RANGE: std::ops::RangeInclusive<usize> = 100..=100000;
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, Default)]
 pub struct Rgba {
     pub r: u8,
@@ -10,6 +30,31 @@ pub struct Rgba {
     pub a: u8,
 }
 
+impl Rgba {
+    /// `#rrggbb`, or `#rrggbbaa` when not opaque.
+    pub fn to_hex(self) -> String {
+        if self.a == 255 {
+            format!("#{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+        } else {
+            format!("#{:02x}{:02x}{:02x}{:02x}", self.r, self.g, self.b, self.a)
+        }
+    }
+
+    pub fn from_hex(hex: &str) -> Result<Self, String> {
+        let digits = hex.trim().trim_start_matches('#');
+        let byte = |i: usize| u8::from_str_radix(digits.get(i..i + 2).unwrap_or("?"), 16);
+        let parsed = match digits.len() {
+            6 => (byte(0), byte(2), byte(4), Ok(255)),
+            8 => (byte(0), byte(2), byte(4), byte(6)),
+            _ => return Err(rust_i18n::t!("settings.invalid_color", color = hex.trim()).into()),
+        };
+        match parsed {
+            (Ok(r), Ok(g), Ok(b), Ok(a)) => Ok(Rgba { r, g, b, a }),
+            _ => Err(rust_i18n::t!("settings.invalid_color", color = hex.trim()).into()),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AppSettings {
     // Theme and scaling
@@ -35,6 +80,33 @@ pub struct AppSettings {
 
     // Terminal settings
     pub scrollback_lines: usize,  // Default: 2000
+
+    // Session restore (opt-in)
+    #[serde(default)]
+    pub restore_session: bool,
+    #[serde(default = "default_session_scrollback")]
+    pub session_scrollback_lines: usize, // per terminal, 0 = none
+
+    // Run shells in the background server (`termix --server`)
+    #[serde(default)]
+    pub use_server: bool,
+
+    // Terminal profiles; new tabs use `default_profile` (empty = plain shell)
+    #[serde(default)]
+    pub default_profile: String,
+    #[serde(default)]
+    pub profiles: Vec<Profile>,
+
+    // Keyboard shortcuts: action id -> chord, only where it differs from the
+    // default; `pass_through_keys` sends them all to the terminal instead
+    #[serde(default)]
+    pub keybindings: BTreeMap<String, String>,
+    #[serde(default)]
+    pub pass_through_keys: bool,
+
+    // Command history across sessions (opt-in), see `history`
+    #[serde(default)]
+    pub record_history: bool,
     
     // Language
     pub language: String,  // "de" or "en"
@@ -55,12 +127,78 @@ impl Default for AppSettings {
             custom_font_path: None,
             sidebar_collapsed: false,
             scrollback_lines: 2000,
+            restore_session: false,
+            session_scrollback_lines: default_session_scrollback(),
+            use_server: false,
+            default_profile: String::new(),
+            profiles: Vec::new(),
+            keybindings: BTreeMap::new(),
+            pass_through_keys: false,
+            record_history: false,
             language: "de".into(),  // Default to German
         }
     }
 }
 
+fn default_session_scrollback() -> usize {
+    500
+}
+
+impl AppSettings {
+    /// Reject values no front-end can use (hand-edited or imported files).
+    pub fn validate(&self) -> Result<(), String> {
+        if !THEMES.contains(&self.theme.as_str()) {
+            return Err(rust_i18n::t!("settings.unknown_theme", theme = self.theme).into());
+        }
+        if !CURSOR_SHAPES.contains(&self.cursor_shape.as_str()) {
+            return Err(rust_i18n::t!("settings.unknown_cursor_shape", shape = self.cursor_shape).into());
+        }
+        if !LANGUAGES.contains(&self.language.as_str()) {
+            return Err(rust_i18n::t!("settings.unknown_language", language = self.language).into());
+        }
+        if !SCROLLBACK_RANGE.contains(&self.scrollback_lines) {
+            return Err(rust_i18n::t!(
+                "settings.scrollback_range",
+                min = SCROLLBACK_RANGE.start(),
+                max = SCROLLBACK_RANGE.end()
+            )
+            .into());
+        }
+        for (i, p) in self.profiles.iter().enumerate() {
+            p.validate()?;
+            if profile::find(&self.profiles[..i], &p.name).is_some() {
+                return Err(rust_i18n::t!("profile.duplicate", profile = p.name).into());
+            }
+        }
+        if !self.default_profile.is_empty() && self.profile(&self.default_profile).is_none() {
+            return Err(rust_i18n::t!("profile.unknown", profile = self.default_profile).into());
+        }
+        keybindings::Keymap::check(&self.keybindings)
+    }
+
+    pub fn profile(&self, name: &str) -> Option<&Profile> {
+        profile::find(&self.profiles, name)
+    }
+
+    /// The profile new tabs start with; `None` for a plain shell.
+    pub fn default_profile(&self) -> Option<&Profile> {
+        Some(self.default_profile.as_str()).filter(|n| !n.is_empty()).and_then(|n| self.profile(n))
+    }
+}
+
+/// Settings file given with `--config`; see `use_settings_path`.
+static SETTINGS_PATH: std::sync::OnceLock<PathBuf> = std::sync::OnceLock::new();
+
+/// Read and write the settings at `path` for the rest of the run. The other
+/// data files then live next to it, since `config_dir` follows along.
+pub fn use_settings_path(path: PathBuf) {
+    let _ = SETTINGS_PATH.set(path);
+}
+
 pub fn settings_path() -> PathBuf {
+    if let Some(path) = SETTINGS_PATH.get() {
+        return path.clone();
+    }
     // Prefer XDG config dir on Unix, APPDATA on Windows, ~/Library/Application Support on macOS
     #[cfg(target_os = "windows")]
     {
@@ -95,10 +233,47 @@ pub fn settings_path() -> PathBuf {
     }
 }
 
+/// Directory holding `settings.toml` and the other TermiX data files.
+pub fn config_dir() -> PathBuf {
+    settings_path()
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_default()
+}
+
+/// Directory for data that is not configuration, like the command history:
+/// `$XDG_DATA_HOME/termix` (or `~/.local/share/termix`) on Linux, the config
+/// directory on Windows and macOS and whenever `--config` is given.
+pub fn data_dir() -> PathBuf {
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    if SETTINGS_PATH.get().is_none() {
+        if let Some(xdg) = std::env::var_os("XDG_DATA_HOME").filter(|d| !d.is_empty()) {
+            return PathBuf::from(xdg).join("termix");
+        }
+        if let Some(home) = dirs_home() {
+            return home.join(".local").join("share").join("termix");
+        }
+    }
+    config_dir()
+}
+
 fn dirs_home() -> Option<PathBuf> {
     std::env::var_os("HOME").map(PathBuf::from)
 }
 
+/// Write `content` through a temporary file and rename it into place, so a
+/// crash mid-write never leaves a truncated file behind.
+pub fn write_atomic(path: &Path, content: &str) -> std::io::Result<()> {
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)?;
+    }
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+    fs::write(&tmp, content)?;
+    fs::rename(&tmp, path)
+}
+
 pub fn load_settings() -> AppSettings {
     let path = settings_path();
     if let Ok(content) = fs::read_to_string(&path) {
@@ -109,24 +284,29 @@ pub fn load_settings() -> AppSettings {
 }
 
 pub fn save_settings(settings: &AppSettings) {
-    let path = settings_path();
-    if let Some(dir) = path.parent() {
-        let _ = fs::create_dir_all(dir);
-    }
-    if let Ok(content) = toml::to_string_pretty(settings) {
-        let _ = fs::write(path, content);
-    }
+    let _ = write_settings(settings);
+}
+
+/// Like `save_settings`, but reports failures.
+pub fn write_settings(settings: &AppSettings) -> Result<(), String> {
+    write_atomic(&settings_path(), &settings_to_toml(settings)?).map_err(|e| e.to_string())
+}
+
+/// The color tables sit between plain values in `AppSettings`, which the
+/// TOML serializer rejects; going through `toml::Value` reorders them.
+fn settings_to_toml(settings: &AppSettings) -> Result<String, String> {
+    let value = toml::Value::try_from(settings).map_err(|e| e.to_string())?;
+    toml::to_string_pretty(&value).map_err(|e| e.to_string())
 }
 
 pub fn export_settings(settings: &AppSettings, export_path: &Path) -> Result<(), String> {
-    if let Ok(content) = toml::to_string_pretty(settings) {
-        fs::write(export_path, content).map_err(|e| e.to_string())
-    } else {
-        Err("Failed to serialize settings".to_string())
-    }
+    let content = settings_to_toml(settings).map_err(|e| rust_i18n::t!("settings.serialize_failed", error = e).to_string())?;
+    fs::write(export_path, content).map_err(|e| e.to_string())
 }
 
 pub fn import_settings(import_path: &Path) -> Result<AppSettings, String> {
     let content = fs::read_to_string(import_path).map_err(|e| e.to_string())?;
-    toml::from_str::<AppSettings>(&content).map_err(|e| e.to_string())
+    let settings = toml::from_str::<AppSettings>(&content).map_err(|e| e.to_string())?;
+    settings.validate()?;
+    Ok(settings)
 }
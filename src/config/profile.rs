@@ -0,0 +1,122 @@
+//! Terminal profiles: named ways to start a terminal (program, environment,
+//! start directory) and how it looks. Kept as `[[profiles]]` in
+//! `settings.toml`; unset fields fall back to the global settings.
+
+use super::{layout::expand_home, Rgba, SCROLLBACK_RANGE};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// Same bounds as the global font scale.
+pub const FONT_SCALE_RANGE: std::ops::RangeInclusive<f32> = 0.5..=3.0;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Profile {
+    pub name: String,
+    #[serde(default)]
+    pub command: Option<String>, // program; None = the user's shell
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+    #[serde(default)]
+    pub cwd: Option<String>, // `~/…` allowed
+    #[serde(default)]
+    pub term: Option<String>, // TERM, also requested for SSH shells
+    #[serde(default)]
+    pub scrollback_lines: Option<usize>,
+    // Color scheme
+    #[serde(default)]
+    pub text_color: Option<Rgba>,
+    #[serde(default)]
+    pub background_color: Option<Rgba>,
+    #[serde(default)]
+    pub cursor_color: Option<Rgba>,
+    #[serde(default)]
+    pub font_scale: Option<f32>, // of the terminal text, on top of the global one
+}
+
+impl Profile {
+    /// Program and arguments; empty when the profile runs the user's shell.
+    pub fn program(&self) -> Vec<String> {
+        match self.command.as_deref().map(str::trim).filter(|c| !c.is_empty()) {
+            Some(command) => std::iter::once(command.to_string()).chain(self.args.iter().cloned()).collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Variables to set for the program, `TERM` included.
+    pub fn environment(&self) -> Vec<(String, String)> {
+        let mut env: Vec<(String, String)> = self.env.iter().map(|(k, v)| (k.clone(), v.clone())).collect();
+        if let Some(term) = self.term.as_deref().filter(|t| !t.trim().is_empty()) {
+            env.retain(|(k, _)| k != "TERM");
+            env.push(("TERM".into(), term.trim().to_string()));
+        }
+        env
+    }
+
+    pub fn start_dir(&self) -> Option<PathBuf> {
+        self.cwd.as_deref().filter(|d| !d.trim().is_empty()).map(|d| expand_home(d.trim()))
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err(rust_i18n::t!("profile.name_required").into());
+        }
+        if let Some(key) = self.env.keys().find(|k| k.is_empty() || k.contains('=')) {
+            return Err(rust_i18n::t!("profile.invalid_env", profile = self.name, name = key).into());
+        }
+        if self.scrollback_lines.is_some_and(|n| !SCROLLBACK_RANGE.contains(&n)) {
+            return Err(rust_i18n::t!(
+                "settings.scrollback_range",
+                min = SCROLLBACK_RANGE.start(),
+                max = SCROLLBACK_RANGE.end()
+            )
+            .into());
+        }
+        if self.font_scale.is_some_and(|s| !FONT_SCALE_RANGE.contains(&s)) {
+            return Err(rust_i18n::t!(
+                "profile.font_scale_range",
+                profile = self.name,
+                min = FONT_SCALE_RANGE.start(),
+                max = FONT_SCALE_RANGE.end()
+            )
+            .into());
+        }
+        Ok(())
+    }
+}
+
+/// The profile called `name`, ignoring case.
+pub fn find<'a>(profiles: &'a [Profile], name: &str) -> Option<&'a Profile> {
+    profiles.iter().find(|p| p.name.eq_ignore_ascii_case(name.trim()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{settings_to_toml, AppSettings};
+
+    #[test]
+    fn program_and_environment() {
+        let mut profile = Profile { name: "py".into(), command: Some("python3".into()), args: vec!["-q".into()], ..Default::default() };
+        profile.env.insert("TERM".into(), "dumb".into());
+        profile.env.insert("LANG".into(), "C".into());
+        profile.term = Some("xterm-256color".into());
+        assert_eq!(profile.program(), ["python3", "-q"]);
+        assert_eq!(profile.environment(), [("LANG".into(), "C".into()), ("TERM".into(), "xterm-256color".into())]);
+        assert!(Profile { command: Some(" ".into()), ..profile }.program().is_empty());
+    }
+
+    #[test]
+    fn profiles_round_trip_through_settings() {
+        let mut work = Profile { name: "work".into(), cwd: Some("~/src".into()), ..Default::default() };
+        work.env.insert("EDITOR".into(), "vim".into());
+        let settings = AppSettings { default_profile: "Work".into(), profiles: vec![work], ..Default::default() };
+        let settings: AppSettings = toml::from_str(&settings_to_toml(&settings).unwrap()).unwrap();
+        let profile = settings.default_profile().unwrap();
+        assert_eq!(profile.name, "work");
+        assert_eq!(profile.env["EDITOR"], "vim");
+        assert!(settings.validate().is_ok());
+    }
+}
@@ -0,0 +1,110 @@
+//! The previous session, restored on launch when `AppSettings.restore_session`
+//! is on: terminal tabs and splits (reusing the layout tree, optionally with
+//! scrollback), markdown tabs including unsaved text, and active indices.
+
+use super::layout::LayoutNode;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+const SESSION_VERSION: u32 = 1;
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct Session {
+    pub version: u32,
+    pub selected_view: usize,
+    pub focused_pane: usize, // index in layout order
+    pub active_markdown: usize,
+    pub terminals: LayoutNode,
+    // An empty list would be a plain value after the `terminals` table
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub markdown: Vec<SessionMarkdown>,
+}
+
+impl Session {
+    pub fn new(terminals: LayoutNode) -> Self {
+        Self {
+            version: SESSION_VERSION,
+            selected_view: 0,
+            focused_pane: 0,
+            active_markdown: 0,
+            terminals,
+            markdown: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct SessionMarkdown {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// Only stored when the buffer differs from the file on disk.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+}
+
+pub fn session_path() -> PathBuf {
+    super::config_dir().join("session.toml")
+}
+
+pub fn load_session() -> Option<Session> {
+    parse_session(&fs::read_to_string(session_path()).ok()?)
+}
+
+/// A session written by this or an older TermiX; anything else is ignored.
+fn parse_session(content: &str) -> Option<Session> {
+    toml::from_str::<Session>(content)
+        .ok()
+        .filter(|s| s.version <= SESSION_VERSION)
+}
+
+pub fn save_session(session: &Session) -> Result<(), String> {
+    let content = toml::to_string_pretty(session).map_err(|e| e.to_string())?;
+    super::write_atomic(&session_path(), &content).map_err(|e| e.to_string())
+}
+
+pub fn clear_session() {
+    let _ = fs::remove_file(session_path());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::layout::{LayoutOrientation, LayoutTab};
+
+    #[test]
+    fn tabs_and_scrollback_round_trip() {
+        let shell = LayoutTab {
+            title: "build".into(),
+            cwd: Some("~/src".into()),
+            scrollback: Some("$ cargo build\n   Compiling termix\n\"quoted\" \\ and tabs\t\n".into()),
+            server: Some(7),
+            ..Default::default()
+        };
+        let terminals = LayoutNode::Split {
+            orientation: LayoutOrientation::Vertical,
+            size: 0.6,
+            first: Box::new(LayoutNode::Pane { active: 1, tabs: vec![LayoutTab { title: "a".into(), ..Default::default() }, shell] }),
+            second: Box::new(LayoutNode::Pane { active: 0, tabs: vec![LayoutTab { title: "b".into(), ..Default::default() }] }),
+        };
+        let mut session = Session::new(terminals);
+        session.selected_view = 1;
+        session.focused_pane = 1;
+        session.markdown = vec![
+            SessionMarkdown { name: "notes.md".into(), path: Some("/tmp/notes.md".into()), content: None },
+            SessionMarkdown { name: "Untitled".into(), path: None, content: Some("# Draft\n\n- item\n".into()) },
+        ];
+
+        let content = toml::to_string_pretty(&session).unwrap();
+        assert_eq!(parse_session(&content), Some(session));
+    }
+
+    #[test]
+    fn newer_sessions_are_ignored() {
+        let mut session = Session::new(LayoutNode::Pane { active: 0, tabs: vec![LayoutTab::default()] });
+        session.version = SESSION_VERSION + 1;
+        assert_eq!(parse_session(&toml::to_string_pretty(&session).unwrap()), None);
+        assert_eq!(parse_session("not a session"), None);
+    }
+}
@@ -0,0 +1,259 @@
+//! Snippet library: named commands with `{{placeholders}}` that are asked
+//! for when the snippet is inserted. Kept as `[[snippets]]` in
+//! `snippets.toml` in the config dir:
+//!
+//! ```toml
+//! [[snippets]]
+//! name = "Pod logs"
+//! command = "kubectl -n {{namespace:default}} logs -f {{pod}}"
+//! tags = ["k8s"]
+//! hosts = ["bastion", "prod-*"]
+//! ```
+//!
+//! `{{name:value}}` pre-fills the prompt with `value`. A snippet with
+//! `hosts` is only offered in terminals connected to one of those SSH
+//! connections (`*` at the end matches any rest, `local` stands for local
+//! shells); without it, everywhere.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+/// Host name that scopes a snippet to local shells.
+pub const LOCAL_HOST: &str = "local";
+
+pub fn snippets_path() -> PathBuf {
+    super::config_dir().join("snippets.toml")
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Snippet {
+    pub name: String,
+    pub command: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub description: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub hosts: Vec<String>, // SSH connection names; empty = everywhere
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Placeholder {
+    pub name: String,
+    pub default: String,
+}
+
+impl Snippet {
+    /// Placeholders in the order they first appear, each once.
+    pub fn placeholders(&self) -> Vec<Placeholder> {
+        let mut found: Vec<Placeholder> = Vec::new();
+        for (_, inner) in placeholder_spans(&self.command) {
+            let (name, default) = split_placeholder(inner);
+            if !found.iter().any(|p| p.name == name) {
+                found.push(Placeholder { name: name.to_string(), default: default.to_string() });
+            }
+        }
+        found
+    }
+
+    /// The command with every placeholder replaced by its value in
+    /// `values`, or by its default where there is none.
+    pub fn fill(&self, values: &HashMap<String, String>) -> String {
+        let mut filled = String::new();
+        let mut rest = 0;
+        for (range, inner) in placeholder_spans(&self.command) {
+            let (name, default) = split_placeholder(inner);
+            filled.push_str(&self.command[rest..range.start]);
+            filled.push_str(values.get(name).map_or(default, String::as_str));
+            rest = range.end;
+        }
+        filled.push_str(&self.command[rest..]);
+        filled
+    }
+
+    /// Whether the snippet is offered in a terminal on `host` (None = local
+    /// shell).
+    pub fn applies_to(&self, host: Option<&str>) -> bool {
+        let host = host.unwrap_or(LOCAL_HOST);
+        self.hosts.is_empty()
+            || self.hosts.iter().map(|h| h.trim()).any(|pattern| match pattern.strip_suffix('*') {
+                Some(prefix) => host.to_lowercase().starts_with(&prefix.to_lowercase()),
+                None => pattern.eq_ignore_ascii_case(host),
+            })
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err(rust_i18n::t!("snippets.name_required").into());
+        }
+        if self.command.trim().is_empty() {
+            return Err(rust_i18n::t!("snippets.command_required", name = self.name).into());
+        }
+        Ok(())
+    }
+}
+
+/// Byte range and inside of every `{{…}}` with a name in `command`.
+fn placeholder_spans(command: &str) -> Vec<(std::ops::Range<usize>, &str)> {
+    let mut spans = Vec::new();
+    let mut from = 0;
+    while let Some(start) = command[from..].find("{{").map(|i| from + i) {
+        let Some(len) = command[start + 2..].find("}}") else {
+            break;
+        };
+        let inner = &command[start + 2..start + 2 + len];
+        let end = start + len + 4;
+        if split_placeholder(inner).0.is_empty() || inner.contains("{{") {
+            from = start + 2;
+        } else {
+            spans.push((start..end, inner));
+            from = end;
+        }
+    }
+    spans
+}
+
+/// `name:default` -> (name, default).
+fn split_placeholder(inner: &str) -> (&str, &str) {
+    match inner.split_once(':') {
+        Some((name, default)) => (name.trim(), default),
+        None => (inner.trim(), ""),
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Library {
+    #[serde(default)]
+    pub snippets: Vec<Snippet>,
+}
+
+impl Library {
+    /// The saved snippets; none when the file does not exist yet.
+    pub fn load() -> Result<Self, String> {
+        match fs::read_to_string(snippets_path()) {
+            Ok(content) => toml::from_str(&content).map_err(|e| format!("{}: {}", snippets_path().display(), e)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Library::default()),
+            Err(e) => Err(format!("{}: {}", snippets_path().display(), e)),
+        }
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let content = toml::to_string_pretty(self).map_err(|e| e.to_string())?;
+        super::write_atomic(&snippets_path(), &content).map_err(|e| e.to_string())
+    }
+
+    /// The snippet called `name`, ignoring case.
+    pub fn find(&self, name: &str) -> Option<&Snippet> {
+        self.snippets.iter().find(|s| s.name.eq_ignore_ascii_case(name.trim()))
+    }
+
+    /// Every tag in use, sorted.
+    pub fn tags(&self) -> Vec<&str> {
+        let mut tags: Vec<&str> = self.snippets.iter().flat_map(|s| s.tags.iter().map(String::as_str)).collect();
+        tags.sort_unstable_by_key(|t| t.to_lowercase());
+        tags.dedup_by(|a, b| a.eq_ignore_ascii_case(b));
+        tags
+    }
+
+    /// Indices of the snippets offered on `host` that match `query`, best
+    /// first. Words like `#k8s` in the query require that tag; the rest is
+    /// matched fuzzily against name, tags, description and command.
+    pub fn search(&self, query: &str, host: Option<&str>) -> Vec<usize> {
+        let (tags, words): (Vec<&str>, Vec<&str>) = query.split_whitespace().partition(|w| w.len() > 1 && w.starts_with('#'));
+        let text = words.join(" ");
+        let mut found: Vec<(i64, usize)> = self
+            .snippets
+            .iter()
+            .enumerate()
+            .filter(|(_, s)| s.applies_to(host))
+            .filter(|(_, s)| tags.iter().all(|tag| s.tags.iter().any(|t| t.eq_ignore_ascii_case(&tag[1..]))))
+            .filter_map(|(idx, s)| {
+                let fields = [s.name.as_str(), s.description.as_str(), s.command.as_str()];
+                let score = crate::fuzzy::best_score(&text, fields.into_iter().chain(s.tags.iter().map(String::as_str)))?;
+                Some((score, idx))
+            })
+            .collect();
+        // Stable, so equal scores keep the library's order
+        found.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+        found.into_iter().map(|(_, idx)| idx).collect()
+    }
+
+    /// Every snippet valid, with a name of its own.
+    pub fn validate(&self) -> Result<(), String> {
+        for (idx, snippet) in self.snippets.iter().enumerate() {
+            snippet.validate()?;
+            if self.snippets[..idx].iter().any(|s| s.name.trim().eq_ignore_ascii_case(snippet.name.trim())) {
+                return Err(rust_i18n::t!("snippets.duplicate", name = snippet.name.trim()).into());
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snippet(name: &str, command: &str, tags: &[&str], hosts: &[&str]) -> Snippet {
+        Snippet {
+            name: name.into(),
+            command: command.into(),
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            hosts: hosts.iter().map(|h| h.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn placeholders_are_filled() {
+        let logs = snippet("logs", "kubectl -n {{ns:default}} logs {{pod}} && echo {{pod}} {{}} {{ {{x", &[], &[]);
+        let names: Vec<(String, String)> = logs.placeholders().into_iter().map(|p| (p.name, p.default)).collect();
+        assert_eq!(names, [("ns".into(), "default".into()), ("pod".into(), String::new())]);
+
+        let values = HashMap::from([("pod".to_string(), "web-1".to_string())]);
+        assert_eq!(logs.fill(&values), "kubectl -n default logs web-1 && echo web-1 {{}} {{ {{x");
+        assert!(snippet("plain", "uptime", &[], &[]).placeholders().is_empty());
+    }
+
+    #[test]
+    fn hosts_scope_snippets() {
+        let anywhere = snippet("a", "uptime", &[], &[]);
+        let prod = snippet("b", "uptime", &[], &["bastion", "Prod-*"]);
+        let local = snippet("c", "uptime", &[], &[LOCAL_HOST]);
+        assert!(anywhere.applies_to(None) && anywhere.applies_to(Some("web")));
+        assert!(prod.applies_to(Some("bastion")) && prod.applies_to(Some("prod-db")));
+        assert!(!prod.applies_to(Some("staging")) && !prod.applies_to(None));
+        assert!(local.applies_to(None) && !local.applies_to(Some("web")));
+    }
+
+    #[test]
+    fn search_by_text_and_tag() {
+        let library = Library {
+            snippets: vec![
+                snippet("Pod logs", "kubectl logs -f {{pod}}", &["k8s"], &[]),
+                snippet("Table sizes", "psql -c 'select pg_size_pretty(…)'", &["sql"], &["db-*"]),
+                snippet("Restart deployment", "kubectl rollout restart deploy/{{name}}", &["k8s", "ops"], &[]),
+            ],
+        };
+        assert_eq!(library.search("", None), [0, 2]);
+        assert_eq!(library.search("", Some("db-1")), [0, 1, 2]);
+        assert_eq!(library.search("rollout", None), [2]);
+        assert_eq!(library.search("#K8S logs", None), [0]);
+        assert_eq!(library.search("#sql", None), Vec::<usize>::new());
+        assert_eq!(library.tags(), ["k8s", "ops", "sql"]);
+    }
+
+    #[test]
+    fn library_round_trips_and_validates() {
+        let library = Library { snippets: vec![snippet("logs", "kubectl logs {{pod}}", &["k8s"], &[])] };
+        let parsed: Library = toml::from_str(&toml::to_string_pretty(&library).unwrap()).unwrap();
+        assert_eq!(parsed.snippets, library.snippets);
+        assert!(parsed.validate().is_ok());
+
+        let twice = Library { snippets: vec![library.snippets[0].clone(), snippet("Logs ", "tail", &[], &[])] };
+        assert!(twice.validate().is_err());
+        assert!(snippet(" ", "uptime", &[], &[]).validate().is_err());
+    }
+}
@@ -0,0 +1,225 @@
+//! Saved SSH hosts, shared by the GUI and the TUI. Stored as
+//! `ssh_connections.toml` in the config dir; passwords never go to this file
+//! (the GUI keeps them in the credential store).
+
+use super::Rgba;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Current layout of `ssh_connections.toml`. Bump it and add a step to
+/// `migrate_schema` whenever the stored format changes.
+const SCHEMA_VERSION: u32 = 1;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SshConnection {
+    pub name: String,
+    pub host: String,
+    pub port: u16,
+    pub username: String,
+    #[serde(default)]
+    pub identity_file: Option<String>, // private key; `password` is then its passphrase
+    #[serde(skip)]
+    pub password: String, // Not saved to disk
+    // Organisation
+    #[serde(default)]
+    pub group: String, // empty = ungrouped
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub favorite: bool,
+    #[serde(default)]
+    pub color: Option<Rgba>, // label color in the list
+    #[serde(default)]
+    pub profile: Option<String>, // terminal profile: TERM, environment, colors
+}
+
+impl SshConnection {
+    /// Key under which the password/passphrase is kept in the credential store.
+    pub fn credential_key(&self) -> String {
+        format!("ssh:{}@{}:{}", self.username, self.host, self.port)
+    }
+
+    /// Fuzzy match over name, host, user, group and tags.
+    pub fn search_score(&self, query: &str) -> Option<i64> {
+        crate::fuzzy::best_score(
+            query,
+            [self.name.as_str(), self.host.as_str(), self.username.as_str(), self.group.as_str()]
+                .into_iter()
+                .chain(self.tags.iter().map(String::as_str)),
+        )
+    }
+
+    /// Name, host and user must be set and the port must not be 0.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err(rust_i18n::t!("ssh.name_required").into());
+        }
+        if self.host.trim().is_empty() {
+            return Err(rust_i18n::t!("ssh.host_required").into());
+        }
+        if self.username.trim().is_empty() {
+            return Err(rust_i18n::t!("ssh.username_required").into());
+        }
+        if self.port == 0 {
+            return Err(rust_i18n::t!("ssh.invalid_port").into());
+        }
+        Ok(())
+    }
+}
+
+impl Default for SshConnection {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            host: String::new(),
+            port: 22,
+            username: String::new(),
+            identity_file: None,
+            password: String::new(),
+            group: String::new(),
+            tags: Vec::new(),
+            favorite: false,
+            color: None,
+            profile: None,
+        }
+    }
+}
+
+pub fn connections_path() -> PathBuf {
+    super::config_dir().join("ssh_connections.toml")
+}
+
+/// Upgrade a parsed connections file to `SCHEMA_VERSION`.
+/// Files without a `version` field predate versioning and count as version 0.
+fn migrate_schema(mut doc: toml::Value) -> Result<toml::Value, String> {
+    let table = doc.as_table_mut().ok_or("Invalid SSH connections file")?;
+    let mut version = table.get("version").and_then(|v| v.as_integer()).unwrap_or(0) as u32;
+    if version > SCHEMA_VERSION {
+        return Err(rust_i18n::t!("ssh.version_too_new", version = version, supported = SCHEMA_VERSION).into());
+    }
+    while version < SCHEMA_VERSION {
+        // 0 -> 1: only the version field was added
+        version += 1;
+    }
+    table.insert("version".into(), toml::Value::Integer(version as i64));
+    Ok(doc)
+}
+
+#[derive(Serialize, Deserialize)]
+struct ConnectionsFile {
+    version: u32,
+    #[serde(default)]
+    connections: Vec<SshConnection>,
+}
+
+pub fn parse_connections(content: &str) -> Result<Vec<SshConnection>, String> {
+    let doc: toml::Value = toml::from_str(content).map_err(|e| e.to_string())?;
+    let file: ConnectionsFile = migrate_schema(doc)?.try_into().map_err(|e: toml::de::Error| e.to_string())?;
+    Ok(file.connections)
+}
+
+pub fn connections_to_toml(connections: &[SshConnection]) -> Result<String, String> {
+    let file = ConnectionsFile { version: SCHEMA_VERSION, connections: connections.to_vec() };
+    toml::to_string_pretty(&file).map_err(|e| e.to_string())
+}
+
+/// Saved connections; none when the file does not exist yet. A file left in
+/// the working directory by older builds is moved over once, and its path
+/// is returned alongside.
+pub fn load_connections() -> Result<(Vec<SshConnection>, Option<PathBuf>), String> {
+    let path = connections_path();
+    if let Ok(content) = fs::read_to_string(&path) {
+        return parse_connections(&content)
+            .map(|connections| (connections, None))
+            .map_err(|e| rust_i18n::t!("ssh.load_failed", path = path.display(), error = e).to_string());
+    }
+    let legacy_path = PathBuf::from("ssh_connections.toml");
+    if let Some(connections) = fs::read_to_string(&legacy_path).ok().and_then(|c| parse_connections(&c).ok()) {
+        save_connections(&connections)?;
+        return Ok((connections, Some(legacy_path)));
+    }
+    Ok((Vec::new(), None))
+}
+
+/// The saved connection called `target`, or an unsaved one for
+/// `user@host[:port]` (as given to `termix --ssh`).
+pub fn find_connection(target: &str) -> Result<SshConnection, String> {
+    let (connections, _) = load_connections()?;
+    if let Some(conn) = connections.into_iter().find(|c| c.name == target) {
+        return Ok(conn);
+    }
+    let unknown = || rust_i18n::t!("ssh.unknown_target", target = target).to_string();
+    let (username, address) = target.split_once('@').ok_or_else(unknown)?;
+    let (host, port) = match address.rsplit_once(':') {
+        Some((host, port)) => (host, port.parse().map_err(|_| unknown())?),
+        None => (address, 22),
+    };
+    let conn = SshConnection {
+        name: target.to_string(),
+        host: host.to_string(),
+        port,
+        username: username.to_string(),
+        ..Default::default()
+    };
+    conn.validate().map(|_| conn)
+}
+
+pub fn save_connections(connections: &[SshConnection]) -> Result<(), String> {
+    super::write_atomic(&connections_path(), &connections_to_toml(connections)?).map_err(|e| e.to_string())
+}
+
+pub fn export_connections(connections: &[SshConnection], export_path: &Path) -> Result<(), String> {
+    let content = connections_to_toml(connections).map_err(|e| rust_i18n::t!("ssh.serialize_failed", error = e).to_string())?;
+    fs::write(export_path, content).map_err(|e| e.to_string())
+}
+
+pub fn import_connections(import_path: &Path) -> Result<Vec<SshConnection>, String> {
+    let content = fs::read_to_string(import_path).map_err(|e| e.to_string())?;
+    parse_connections(&content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unversioned_files_are_migrated() {
+        let doc: toml::Value = toml::from_str("[[connections]]\nname = \"web\"\nhost = \"web\"\nport = 22\nusername = \"me\"\n").unwrap();
+        let migrated = migrate_schema(doc).unwrap();
+        assert_eq!(migrated.get("version").and_then(|v| v.as_integer()), Some(SCHEMA_VERSION as i64));
+
+        let connections = parse_connections("[[connections]]\nname = \"web\"\nhost = \"web\"\nport = 2222\nusername = \"me\"\n").unwrap();
+        assert_eq!(connections.len(), 1);
+        assert_eq!(connections[0].port, 2222);
+        assert!(connections[0].tags.is_empty() && !connections[0].favorite);
+    }
+
+    #[test]
+    fn newer_files_are_refused() {
+        let content = format!("version = {}\n", SCHEMA_VERSION + 1);
+        let error = parse_connections(&content).unwrap_err();
+        assert!(error.contains(&(SCHEMA_VERSION + 1).to_string()), "{}", error);
+        assert!(migrate_schema(toml::Value::Integer(1)).is_err());
+    }
+
+    #[test]
+    fn connections_round_trip() {
+        let conn = SshConnection {
+            name: "db".into(),
+            host: "10.0.0.5".into(),
+            username: "admin".into(),
+            password: "not written".into(),
+            group: "prod".into(),
+            tags: vec!["sql".into()],
+            favorite: true,
+            ..Default::default()
+        };
+        let content = connections_to_toml(std::slice::from_ref(&conn)).unwrap();
+        assert!(content.starts_with(&format!("version = {}", SCHEMA_VERSION)));
+        assert!(!content.contains("not written"));
+        let parsed = parse_connections(&content).unwrap();
+        assert_eq!((parsed[0].name.as_str(), parsed[0].group.as_str(), parsed[0].favorite), ("db", "prod", true));
+        assert_eq!(parsed[0].credential_key(), conn.credential_key());
+    }
+}
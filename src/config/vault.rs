@@ -0,0 +1,387 @@
+//! Credential storage for saved SSH passwords and key passphrases.
+//!
+//! Secrets live in `vault.toml` next to `settings.toml`, encrypted with a key
+//! derived from a master password (Argon2id + XChaCha20-Poly1305). The vault is
+//! unlocked once per session. When no vault has been created, credentials go to
+//! the desktop Secret Service (via `secret-tool`) if one is reachable.
+
+use argon2::{Algorithm, Argon2, Params, Version};
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const VAULT_VERSION: u32 = 1;
+const SALT_LEN: usize = 16;
+const KEY_LEN: usize = 32;
+
+pub fn vault_path() -> PathBuf {
+    super::config_dir().join("vault.toml")
+}
+
+/// On-disk representation; everything except the KDF parameters is opaque.
+#[derive(Serialize, Deserialize)]
+struct VaultFile {
+    version: u32,
+    kdf: String,
+    m_cost: u32,
+    t_cost: u32,
+    p_cost: u32,
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct VaultContents {
+    entries: BTreeMap<String, String>,
+}
+
+/// An unlocked vault. The derived key is kept in memory until `lock`.
+struct Vault {
+    path: PathBuf,
+    params: Params,
+    salt: [u8; SALT_LEN],
+    key: [u8; KEY_LEN],
+    contents: VaultContents,
+}
+
+impl Vault {
+    fn create(path: PathBuf, master: &str, params: Params) -> Result<Self, String> {
+        let mut salt = [0u8; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+        let key = derive_key(master, &salt, &params)?;
+        let vault = Self { path, params, salt, key, contents: VaultContents::default() };
+        vault.save()?;
+        Ok(vault)
+    }
+
+    fn open(path: PathBuf, master: &str) -> Result<Self, String> {
+        let content = fs::read_to_string(&path).map_err(|e| e.to_string())?;
+        let file: VaultFile = toml::from_str(&content).map_err(|e| e.to_string())?;
+        if file.version > VAULT_VERSION || file.kdf != "argon2id" {
+            return Err(rust_i18n::t!("vault.unsupported", version = file.version, kdf = file.kdf).into());
+        }
+        let params = Params::new(file.m_cost, file.t_cost, file.p_cost, None)
+            .map_err(|e| e.to_string())?;
+        let salt: [u8; SALT_LEN] = hex_decode(&file.salt)
+            .and_then(|v| v.try_into().ok())
+            .ok_or_else(corrupt)?;
+        let nonce = hex_decode(&file.nonce).filter(|n| n.len() == 24).ok_or_else(corrupt)?;
+        let ciphertext = hex_decode(&file.ciphertext).ok_or_else(corrupt)?;
+
+        let key = derive_key(master, &salt, &params)?;
+        let cipher = XChaCha20Poly1305::new(&key.into());
+        let plaintext = cipher
+            .decrypt(XNonce::from_slice(&nonce), ciphertext.as_ref())
+            .map_err(|_| rust_i18n::t!("vault.wrong_password").to_string())?;
+        let plaintext = String::from_utf8(plaintext).map_err(|e| e.to_string())?;
+        let contents = toml::from_str(&plaintext).map_err(|e| e.to_string())?;
+        Ok(Self { path, params, salt, key, contents })
+    }
+
+    fn save(&self) -> Result<(), String> {
+        let plaintext = toml::to_string(&self.contents).map_err(|e| e.to_string())?;
+        let cipher = XChaCha20Poly1305::new(&self.key.into());
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = cipher
+            .encrypt(&nonce, plaintext.as_bytes())
+            .map_err(|_| rust_i18n::t!("vault.encrypt_failed").to_string())?;
+        let file = VaultFile {
+            version: VAULT_VERSION,
+            kdf: "argon2id".into(),
+            m_cost: self.params.m_cost(),
+            t_cost: self.params.t_cost(),
+            p_cost: self.params.p_cost(),
+            salt: hex_encode(&self.salt),
+            nonce: hex_encode(&nonce),
+            ciphertext: hex_encode(&ciphertext),
+        };
+        let content = toml::to_string_pretty(&file).map_err(|e| e.to_string())?;
+        write_private(&self.path, &content)
+    }
+}
+
+fn derive_key(master: &str, salt: &[u8], params: &Params) -> Result<[u8; KEY_LEN], String> {
+    let mut key = [0u8; KEY_LEN];
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params.clone())
+        .hash_password_into(master.as_bytes(), salt, &mut key)
+        .map_err(|e| e.to_string())?;
+    Ok(key)
+}
+
+fn corrupt() -> String {
+    rust_i18n::t!("vault.corrupt").into()
+}
+
+/// Like `write_atomic`, but the temporary file is created readable by the
+/// owner only, so the vault never sits at its final path with wider
+/// permissions.
+fn write_private(path: &Path, content: &str) -> Result<(), String> {
+    use std::io::Write;
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    let tmp = path.with_extension("toml.tmp");
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+        options.mode(0o600);
+        // `mode` only applies to new files; a stale temp file keeps its own
+        if tmp.exists() {
+            fs::set_permissions(&tmp, fs::Permissions::from_mode(0o600)).map_err(|e| e.to_string())?;
+        }
+    }
+    let mut file = options.open(&tmp).map_err(|e| e.to_string())?;
+    file.write_all(content.as_bytes()).and_then(|_| file.sync_all()).map_err(|e| e.to_string())?;
+    fs::rename(&tmp, path).map_err(|e| e.to_string())
+}
+
+fn hex_encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn hex_decode(s: &str) -> Option<Vec<u8>> {
+    if !s.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(s.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// Where a credential ended up after `CredentialStore::store`.
+#[derive(Clone, Copy, PartialEq)]
+pub enum StoreLocation {
+    Vault,
+    SecretService,
+}
+
+/// Front door for credential lookups: the unlocked vault first, then the
+/// Secret Service.
+pub struct CredentialStore {
+    vault: Option<Vault>,
+    secret_service: bool,
+}
+
+impl Default for CredentialStore {
+    fn default() -> Self {
+        Self { vault: None, secret_service: secret_service::available() }
+    }
+}
+
+impl CredentialStore {
+    pub fn vault_exists(&self) -> bool {
+        self.vault.is_some() || vault_path().exists()
+    }
+
+    pub fn is_unlocked(&self) -> bool {
+        self.vault.is_some()
+    }
+
+    pub fn secret_service_available(&self) -> bool {
+        self.secret_service
+    }
+
+    pub fn create_vault(&mut self, master: &str) -> Result<(), String> {
+        if master.is_empty() {
+            return Err(rust_i18n::t!("vault.empty_password").into());
+        }
+        if vault_path().exists() {
+            return Err(rust_i18n::t!("vault.exists").into());
+        }
+        self.vault = Some(Vault::create(vault_path(), master, Params::default())?);
+        Ok(())
+    }
+
+    pub fn unlock(&mut self, master: &str) -> Result<(), String> {
+        self.vault = Some(Vault::open(vault_path(), master)?);
+        Ok(())
+    }
+
+    pub fn lock(&mut self) {
+        self.vault = None;
+    }
+
+    pub fn lookup(&self, key: &str) -> Option<String> {
+        if let Some(vault) = &self.vault {
+            if let Some(secret) = vault.contents.entries.get(key) {
+                return Some(secret.clone());
+            }
+        }
+        if self.secret_service {
+            return secret_service::lookup(key);
+        }
+        None
+    }
+
+    pub fn store(&mut self, key: &str, secret: &str) -> Result<StoreLocation, String> {
+        if let Some(vault) = &mut self.vault {
+            vault.contents.entries.insert(key.to_string(), secret.to_string());
+            vault.save()?;
+            return Ok(StoreLocation::Vault);
+        }
+        if vault_path().exists() {
+            return Err(rust_i18n::t!("vault.locked").into());
+        }
+        if self.secret_service {
+            secret_service::store(key, secret)?;
+            return Ok(StoreLocation::SecretService);
+        }
+        Err(rust_i18n::t!("vault.no_storage").into())
+    }
+
+    /// Forget the secret of a deleted connection, unless one of the
+    /// `remaining` connections shares its user@host:port key (duplicates do).
+    pub fn release(&mut self, key: &str, remaining: &[super::ssh::SshConnection]) {
+        if !remaining.iter().any(|c| c.credential_key() == key) {
+            self.remove(key);
+        }
+    }
+
+    fn remove(&mut self, key: &str) {
+        if let Some(vault) = &mut self.vault {
+            if vault.contents.entries.remove(key).is_some() {
+                let _ = vault.save();
+            }
+        }
+        if self.secret_service {
+            secret_service::clear(key);
+        }
+    }
+}
+
+/// Minimal Secret Service client built on libsecret's `secret-tool`.
+mod secret_service {
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+
+    const SERVICE: &str = "termix";
+
+    pub fn available() -> bool {
+        cfg!(target_os = "linux")
+            && std::env::var_os("DBUS_SESSION_BUS_ADDRESS").is_some()
+            && std::env::var_os("PATH")
+                .map(|paths| std::env::split_paths(&paths).any(|p| p.join("secret-tool").is_file()))
+                .unwrap_or(false)
+    }
+
+    pub fn lookup(key: &str) -> Option<String> {
+        let output = Command::new("secret-tool")
+            .args(["lookup", "service", SERVICE, "account", key])
+            .stderr(Stdio::null())
+            .output()
+            .ok()?;
+        if !output.status.success() || output.stdout.is_empty() {
+            return None;
+        }
+        String::from_utf8(output.stdout).ok()
+    }
+
+    pub fn store(key: &str, secret: &str) -> Result<(), String> {
+        let mut child = Command::new("secret-tool")
+            .args(["store", &format!("--label=TermiX: {}", key), "service", SERVICE, "account", key])
+            .stdin(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| e.to_string())?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(secret.as_bytes()).map_err(|e| e.to_string())?;
+        }
+        let output = child.wait_with_output().map_err(|e| e.to_string())?;
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+        }
+    }
+
+    pub fn clear(key: &str) {
+        let _ = Command::new("secret-tool")
+            .args(["clear", "service", SERVICE, "account", key])
+            .stderr(Stdio::null())
+            .status();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ssh::SshConnection;
+
+    /// Cheap KDF settings; the defaults take seconds in debug builds.
+    fn params() -> Params {
+        Params::new(1024, 1, 1, None).unwrap()
+    }
+
+    fn scratch(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("termix-vault-{}-{name}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir.join("vault.toml")
+    }
+
+    #[test]
+    fn create_store_and_unlock() {
+        let path = scratch("unlock");
+        let mut vault = Vault::create(path.clone(), "correct horse", params()).unwrap();
+        vault.contents.entries.insert("ssh:me@web:22".into(), "s3cret".into());
+        vault.save().unwrap();
+        let first = fs::read_to_string(&path).unwrap();
+        assert!(!first.contains("s3cret"));
+
+        // Saving again encrypts with a fresh nonce; both versions open
+        vault.contents.entries.insert("ssh:me@db:22".into(), "other".into());
+        vault.save().unwrap();
+        assert_ne!(fs::read_to_string(&path).unwrap(), first);
+        let reopened = Vault::open(path.clone(), "correct horse").unwrap();
+        assert_eq!(reopened.contents.entries["ssh:me@web:22"], "s3cret");
+        assert_eq!(reopened.contents.entries["ssh:me@db:22"], "other");
+        assert_eq!(reopened.params.m_cost(), 1024);
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn wrong_password_and_tampering_are_rejected() {
+        let path = scratch("wrong");
+        Vault::create(path.clone(), "correct horse", params()).unwrap();
+        assert_eq!(Vault::open(path.clone(), "battery staple").err(), Some(rust_i18n::t!("vault.wrong_password").to_string()));
+
+        let content = fs::read_to_string(&path).unwrap().replace("version = 1", "version = 9");
+        fs::write(&path, content).unwrap();
+        assert!(Vault::open(path.clone(), "correct horse").is_err());
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn vault_file_is_private() {
+        use std::os::unix::fs::PermissionsExt;
+        let path = scratch("mode");
+        Vault::create(path.clone(), "correct horse", params()).unwrap();
+        assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+        assert!(!path.with_extension("toml.tmp").exists());
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn shared_secrets_outlive_duplicates() {
+        let path = scratch("release");
+        let vault = Vault::create(path.clone(), "correct horse", params()).unwrap();
+        let mut store = CredentialStore { vault: Some(vault), secret_service: false };
+        let conn = SshConnection { name: "web".into(), host: "web".into(), username: "me".into(), ..Default::default() };
+        let copy = SshConnection { name: "web (copy)".into(), ..conn.clone() };
+        store.vault.as_mut().unwrap().contents.entries.insert(conn.credential_key(), "s3cret".into());
+
+        store.release(&conn.credential_key(), std::slice::from_ref(&copy));
+        assert_eq!(store.lookup(&conn.credential_key()).as_deref(), Some("s3cret"));
+        store.release(&copy.credential_key(), &[]);
+        assert_eq!(store.lookup(&conn.credential_key()), None);
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+    }
+}
@@ -0,0 +1,89 @@
+//! Small fuzzy matcher shared by the search boxes (SSH manager, palette, history).
+
+/// Score `candidate` against `query` as a case-insensitive subsequence match.
+///
+/// Returns `None` when the query characters do not all appear in order.
+/// Consecutive matches, matches at word starts and exact prefixes rank higher;
+/// an empty query matches everything with score 0.
+pub fn score(query: &str, candidate: &str) -> Option<i64> {
+    let query: Vec<char> = query.trim().to_lowercase().chars().collect();
+    if query.is_empty() {
+        return Some(0);
+    }
+    let chars: Vec<char> = candidate.to_lowercase().chars().collect();
+
+    let mut score = 0i64;
+    let mut qi = 0;
+    let mut last_match: Option<usize> = None;
+    for (ci, &c) in chars.iter().enumerate() {
+        if qi == query.len() {
+            break;
+        }
+        if c != query[qi] {
+            continue;
+        }
+        score += 1;
+        match last_match {
+            Some(prev) if prev + 1 == ci => score += 5,
+            Some(prev) => score -= (ci - prev - 1).min(5) as i64,
+            None => score -= ci.min(10) as i64,
+        }
+        if ci == 0 || !chars[ci - 1].is_alphanumeric() {
+            score += 8;
+        }
+        last_match = Some(ci);
+        qi += 1;
+    }
+    if qi < query.len() {
+        return None;
+    }
+    if chars.starts_with(&query) {
+        score += 10;
+    }
+    Some(score)
+}
+
+/// Best score of `query` over several fields of one item.
+pub fn best_score<'a>(query: &str, fields: impl IntoIterator<Item = &'a str>) -> Option<i64> {
+    fields.into_iter().filter_map(|f| score(query, f)).max()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn subsequences_match_regardless_of_case() {
+        assert!(score("gco", "git checkout").is_some());
+        assert_eq!(score("SSH", "open ssh manager"), score("ssh", "Open SSH Manager"));
+        assert_eq!(score("", "anything"), Some(0));
+        assert_eq!(score("  ", "anything"), Some(0));
+    }
+
+    #[test]
+    fn non_matches_are_none() {
+        assert_eq!(score("xyz", "git checkout"), None);
+        assert_eq!(score("kcehc", "checkout"), None); // right letters, wrong order
+        assert_eq!(score("checkouts", "checkout"), None);
+        assert_eq!(score("a", ""), None);
+    }
+
+    #[test]
+    fn word_starts_and_runs_rank_higher() {
+        // Word starts beat the same letters inside words
+        assert!(score("nt", "new tab").unwrap() > score("nt", "conte nt").unwrap());
+        assert!(score("sm", "ssh manager").unwrap() > score("sm", "cosmos").unwrap());
+        // Contiguous letters beat scattered ones
+        assert!(score("tab", "tab close").unwrap() > score("tab", "t a b").unwrap());
+        // Prefixes beat the same run later on
+        assert!(score("split", "split right").unwrap() > score("split", "close split").unwrap());
+    }
+
+    #[test]
+    fn best_score_takes_the_best_field() {
+        let fields = ["Close tab", "Ctrl+W", "closes the current tab"];
+        assert_eq!(best_score("close", fields), fields.iter().filter_map(|f| score("close", f)).max());
+        assert_eq!(best_score("zz", fields), None);
+        assert_eq!(best_score("w", ["xyz", "Ctrl+W"]), score("w", "Ctrl+W"));
+    }
+}
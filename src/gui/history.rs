@@ -0,0 +1,252 @@
+//! Command history in the window: the reverse search over the terminal
+//! (Ctrl+R) and the history panel with its filters.
+
+use crate::config::history::{Filter, History, HistoryEntry, Status};
+use eframe::egui;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Rows shown below the search field.
+const VISIBLE_ROWS: usize = 12;
+
+pub(super) struct HistoryView {
+    history: History,
+    // Reverse search overlay
+    searching: bool,
+    query: String,
+    selected: usize,
+    focus: bool, // one-shot: focus the search field
+    // Panel
+    pub panel_open: bool,
+    filter: Filter,
+    here_only: bool, // filter by the focused terminal's directory
+    pub status: String,
+}
+
+impl HistoryView {
+    pub fn load() -> Self {
+        HistoryView {
+            history: History::load(),
+            searching: false,
+            query: String::new(),
+            selected: 0,
+            focus: false,
+            panel_open: false,
+            filter: Filter::default(),
+            here_only: false,
+            status: String::new(),
+        }
+    }
+
+    pub fn record(&mut self, entry: HistoryEntry) {
+        if let Err(e) = self.history.record(entry) {
+            self.status = format!("{}: {}", crate::config::history::history_path().display(), e);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.status = match self.history.clear() {
+            Ok(()) => rust_i18n::t!("history.cleared").to_string(),
+            Err(e) => e.to_string(),
+        };
+    }
+
+    /// (host, command) of every entry, newest first.
+    pub fn commands(&self) -> impl Iterator<Item = (Option<String>, String)> + '_ {
+        self.history.entries().iter().rev().map(|e| (e.host.clone(), e.command.clone()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.history.entries().len()
+    }
+
+    pub fn is_searching(&self) -> bool {
+        self.searching
+    }
+
+    pub fn toggle_search(&mut self) {
+        self.searching = !self.searching;
+        self.query.clear();
+        self.selected = 0;
+        self.focus = self.searching;
+    }
+
+    /// Draw the reverse search over the window for the commands run on
+    /// `host` (None = local shells) and return the one picked with Enter or
+    /// a click, to be typed into the terminal. Esc closes it.
+    pub fn search_ui(&mut self, ctx: &egui::Context, host: Option<String>) -> Option<String> {
+        if !self.searching {
+            return None;
+        }
+        let filter = Filter { query: self.query.clone(), host: Some(host.clone()), ..Default::default() };
+        let found = self.history.search(&filter);
+        let (up, down, enter, escape) = ctx.input_mut(|i| {
+            (
+                i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowUp),
+                i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowDown),
+                i.consume_key(egui::Modifiers::NONE, egui::Key::Enter),
+                i.consume_key(egui::Modifiers::NONE, egui::Key::Escape),
+            )
+        });
+        if escape {
+            self.toggle_search();
+            return None;
+        }
+        // Up goes back in time, like pressing Ctrl+R again in bash
+        if up {
+            self.selected += 1;
+        }
+        if down {
+            self.selected = self.selected.saturating_sub(1);
+        }
+        self.selected = self.selected.min(found.len().saturating_sub(1));
+
+        let mut picked = enter.then_some(self.selected);
+        let title = match &host {
+            Some(host) => rust_i18n::t!("history.search_on", host = host),
+            None => rust_i18n::t!("history.search_local"),
+        };
+        egui::Area::new(egui::Id::new("history_search"))
+            .anchor(egui::Align2::CENTER_TOP, egui::vec2(0.0, 60.0))
+            .order(egui::Order::Foreground)
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.set_width(560.0);
+                    ui.label(egui::RichText::new(title).small().color(egui::Color32::GRAY));
+                    let response = ui.add(
+                        egui::TextEdit::singleline(&mut self.query)
+                            .hint_text(rust_i18n::t!("history.search_hint"))
+                            .desired_width(f32::INFINITY),
+                    );
+                    if std::mem::take(&mut self.focus) {
+                        response.request_focus();
+                    }
+                    if response.changed() {
+                        self.selected = 0;
+                    }
+                    ui.separator();
+                    if found.is_empty() {
+                        ui.label(egui::RichText::new(rust_i18n::t!("history.no_match")).color(egui::Color32::GRAY));
+                    }
+                    let first = self.selected.saturating_sub(VISIBLE_ROWS - 1);
+                    for (idx, entry) in found.iter().enumerate().skip(first).take(VISIBLE_ROWS) {
+                        ui.horizontal(|ui| {
+                            let text = egui::RichText::new(&entry.command).monospace();
+                            if ui.selectable_label(idx == self.selected, text).clicked() {
+                                picked = Some(idx);
+                            }
+                            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                                entry_details(ui, entry);
+                            });
+                        });
+                    }
+                });
+            });
+
+        let command = found.get(picked?)?.command.clone();
+        self.toggle_search();
+        Some(command)
+    }
+
+    /// The history panel; `cwd` is the focused terminal's directory for the
+    /// "only here" filter. Returns a clicked command, to be typed into the
+    /// terminal.
+    pub fn panel_ui(&mut self, ui: &mut egui::Ui, cwd: Option<String>, recording: bool) -> Option<String> {
+        let mut picked = None;
+        ui.heading(rust_i18n::t!("history.title").as_ref());
+        if !recording {
+            ui.label(egui::RichText::new(rust_i18n::t!("history.disabled")).small().color(egui::Color32::YELLOW));
+        }
+        ui.add(
+            egui::TextEdit::singleline(&mut self.filter.query)
+                .hint_text(rust_i18n::t!("history.search_hint"))
+                .desired_width(f32::INFINITY),
+        );
+        ui.horizontal_wrapped(|ui| {
+            let host_label = |host: &Option<Option<String>>| match host {
+                None => rust_i18n::t!("history.all_hosts").to_string(),
+                Some(None) => rust_i18n::t!("history.local").to_string(),
+                Some(Some(name)) => name.clone(),
+            };
+            egui::ComboBox::from_id_source("history_host")
+                .selected_text(host_label(&self.filter.host))
+                .show_ui(ui, |ui| {
+                    let mut hosts = vec![None, Some(None)];
+                    hosts.extend(self.history.hosts().into_iter().map(|h| Some(Some(h.to_string()))));
+                    for host in hosts {
+                        let label = host_label(&host);
+                        ui.selectable_value(&mut self.filter.host, host, label);
+                    }
+                });
+            egui::ComboBox::from_id_source("history_status")
+                .selected_text(status_label(self.filter.status))
+                .show_ui(ui, |ui| {
+                    for status in [Status::Any, Status::Succeeded, Status::Failed] {
+                        ui.selectable_value(&mut self.filter.status, status, status_label(status));
+                    }
+                });
+            ui.checkbox(&mut self.here_only, rust_i18n::t!("history.here_only"));
+        });
+        self.filter.cwd = if self.here_only { cwd } else { None };
+        ui.separator();
+
+        let found = self.history.search(&self.filter);
+        ui.label(egui::RichText::new(rust_i18n::t!("history.count", count = found.len())).small().color(egui::Color32::GRAY));
+        egui::ScrollArea::vertical().auto_shrink([false; 2]).show_rows(ui, 36.0, found.len(), |ui, rows| {
+            for entry in &found[rows] {
+                let response = ui
+                    .add(egui::Label::new(egui::RichText::new(&entry.command).monospace()).truncate(true).sense(egui::Sense::click()))
+                    .on_hover_text(rust_i18n::t!("history.insert_hint"));
+                if response.clicked() {
+                    picked = Some(entry.command.clone());
+                }
+                ui.horizontal(|ui| {
+                    entry_details(ui, entry);
+                    if let Some(cwd) = &entry.cwd {
+                        ui.label(egui::RichText::new(cwd).small().color(egui::Color32::GRAY));
+                    }
+                });
+            }
+        });
+        picked
+    }
+}
+
+fn status_label(status: Status) -> String {
+    match status {
+        Status::Any => rust_i18n::t!("history.any_status"),
+        Status::Succeeded => rust_i18n::t!("history.succeeded"),
+        Status::Failed => rust_i18n::t!("history.failed"),
+    }
+    .into()
+}
+
+/// Exit code, host and age of `entry`, small.
+fn entry_details(ui: &mut egui::Ui, entry: &HistoryEntry) {
+    match entry.exit_code {
+        Some(0) => {
+            ui.label(egui::RichText::new("✔").small().color(egui::Color32::GREEN));
+        }
+        Some(code) => {
+            ui.label(egui::RichText::new(format!("✖ {}", code)).small().color(egui::Color32::LIGHT_RED))
+                .on_hover_text(rust_i18n::t!("history.exit_code", code = code));
+        }
+        None => {}
+    }
+    if let Some(host) = &entry.host {
+        ui.label(egui::RichText::new(format!("🔗 {}", host)).small().color(egui::Color32::LIGHT_BLUE));
+    }
+    ui.label(egui::RichText::new(age(entry.time)).small().color(egui::Color32::GRAY));
+}
+
+/// How long ago `time` (seconds since the epoch) was, coarsely.
+fn age(time: u64) -> String {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    let secs = now.saturating_sub(time);
+    match secs {
+        0..=59 => rust_i18n::t!("history.just_now"),
+        60..=3599 => rust_i18n::t!("history.minutes_ago", n = secs / 60),
+        3600..=86399 => rust_i18n::t!("history.hours_ago", n = secs / 3600),
+        _ => rust_i18n::t!("history.days_ago", n = secs / 86400),
+    }
+    .into()
+}
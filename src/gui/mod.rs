@@ -2,18 +2,59 @@
 use eframe::{egui, App, Frame, CreationContext};
 #[cfg(feature = "gui")]
 use std::{
-    io::{Read, Write},
-    sync::mpsc::{self, Receiver, Sender},
+    sync::mpsc,
     thread,
-    time::Duration,
 };
 #[cfg(feature = "gui")]
-use portable_pty::{CommandBuilder, NativePtySystem, PtySize, PtySystem, MasterPty};
-#[cfg(feature = "gui")]
-use vt100::Parser as VtParser;
+use portable_pty::{NativePtySystem, PtySize, PtySystem};
 #[cfg(feature = "gui")]
 use pulldown_cmark::{Parser as MdParser, Event, Tag, HeadingLevel};
 use crate::config::{AppSettings, Rgba, load_settings, save_settings};
+#[cfg(feature = "gui")]
+use crate::cli::Options;
+#[cfg(feature = "gui")]
+use crate::config::vault::{CredentialStore, StoreLocation};
+#[cfg(feature = "gui")]
+use crate::config::layout::{self, Layout, LayoutNode, LayoutOrientation, LayoutSsh, LayoutTab};
+#[cfg(feature = "gui")]
+use crate::config::profile::{self, Profile};
+#[cfg(feature = "gui")]
+use crate::config::keybindings::{self, Action, KeyChord, Keymap};
+#[cfg(feature = "gui")]
+use crate::config::session::{self, Session, SessionMarkdown};
+#[cfg(feature = "gui")]
+use crate::config::history::HistoryEntry;
+#[cfg(feature = "gui")]
+use crate::config::ssh::{self, SshConnection};
+#[cfg(all(feature = "gui", unix))]
+use crate::server;
+#[cfg(feature = "gui")]
+use crate::pty::{self, PtyProcess};
+#[cfg(feature = "gui")]
+use crate::term::{input::{Key, Modifiers}, view::{Input, Outcome, View}, Point, ShellEvent};
+#[cfg(feature = "gui")]
+use crate::term::complete::{self, Sources};
+#[cfg(feature = "gui")]
+use std::rc::Rc;
+
+#[cfg(feature = "gui")]
+mod history;
+#[cfg(feature = "gui")]
+mod multi_exec;
+#[cfg(feature = "gui")]
+mod palette;
+#[cfg(all(feature = "gui", unix))]
+mod remote;
+#[cfg(feature = "gui")]
+mod snippets;
+#[cfg(feature = "gui")]
+use history::HistoryView;
+#[cfg(feature = "gui")]
+use multi_exec::MultiExec;
+#[cfg(feature = "gui")]
+use palette::{Command, Entry, Palette};
+#[cfg(feature = "gui")]
+use snippets::SnippetsView;
 
 #[cfg(feature = "gui")]
 #[derive(Clone, Copy, PartialEq)]
@@ -175,15 +216,16 @@ impl CursorShape {
             _ => CursorShape::Block,
         }
     }
-    fn name(&self) -> &str {
+    fn name(&self) -> String {
         match self {
-            CursorShape::Block => "Block █",
-            CursorShape::Underline => "Unterstrich _",
-            CursorShape::VerticalBar => "Strich |",
-            CursorShape::DoubleUnderscore => "Doppelunterstrich ‗",
-            CursorShape::Box => "Kasten ▯",
-            CursorShape::Cross => "Kreuz ╳",
+            CursorShape::Block => rust_i18n::t!("cursor_shapes.block"),
+            CursorShape::Underline => rust_i18n::t!("cursor_shapes.underline"),
+            CursorShape::VerticalBar => rust_i18n::t!("cursor_shapes.vertical_bar"),
+            CursorShape::DoubleUnderscore => rust_i18n::t!("cursor_shapes.double_underscore"),
+            CursorShape::Box => rust_i18n::t!("cursor_shapes.box"),
+            CursorShape::Cross => rust_i18n::t!("cursor_shapes.cross"),
         }
+        .to_string()
     }
     
     fn all() -> Vec<CursorShape> {
@@ -235,37 +277,205 @@ pub struct GuiApp {
     // Sidebar state
     sidebar_collapsed: bool,
     // Rename dialogs
-    terminal_rename_dialog: Option<(usize, String)>, // (tab_index, new_name)
+    terminal_rename_dialog: Option<(PaneId, usize, String)>, // (pane, tab_index, new_name)
     markdown_rename_dialog: Option<(usize, String)>, // (tab_index, new_name)
     // SSH connection request (from SshManager UI)
     pending_ssh_connection: Option<SshConnection>,
+    ssh_tab_title: Option<String>, // from `--title`, for the tab of `--ssh`
+    // SSH view: connection list or multi-exec
+    ssh_multi_exec_view: bool,
+    multi_exec: MultiExec,
     // SSH password prompt
     ssh_password_prompt: Option<(SshConnection, String)>, // (connection, password_input)
+    ssh_remember_password: bool,
+    // Saved SSH credentials (vault / Secret Service)
+    credentials: CredentialStore,
+    vault_unlock_prompt: Option<(Option<SshConnection>, String)>, // (connection to resume, master_password)
+    vault_unlock_declined: bool, // user chose to continue without the vault this session
+    vault_master_input: String,
+    vault_master_confirm: String,
+    vault_status: String,
     // Terminal settings
     scrollback_lines: usize,
+    restore_session: bool,
+    session_scrollback_lines: usize,
+    use_server: bool,
+    // Terminal profiles; new tabs use `default_profile` (empty = plain shell)
+    profiles: Vec<Profile>,
+    default_profile: String,
+    // Keyboard shortcuts; `recording_action` waits for the chord to bind
+    keymap: Keymap,
+    pass_through_keys: bool,
+    recording_action: Option<Action>,
+    keys_status: String,
+    palette: Palette,
+    // Command history (recorded only while `record_history` is on)
+    record_history: bool,
+    history: HistoryView,
+    completion: Rc<Sources>, // shared with the terminals while they draw
+    snippets: SnippetsView,
     // Drag state for terminal tabs
-    dragging_terminal_tab: Option<usize>,
+    dragging_terminal_tab: Option<(PaneId, usize)>,
+    tab_rects: Vec<(PaneId, usize, egui::Rect)>, // this frame, for tab drops
     // Split view panes
     split_panes: Vec<SplitPane>,
+    split_layout: Option<SplitNode>, // present while `split_panes` is non-empty
+    zoomed_pane: Option<PaneId>,
+    pane_rects: Vec<(PaneId, egui::Rect)>, // last frame, for directional focus
     active_pane: usize,
+    main_focused: bool, // main terminal has focus (otherwise `active_pane`)
+    // Named layouts menu
+    layout_name_input: String,
+    layout_status: String,
+    // Background server: session list (None = not running), refreshed while the menu is open
+    #[cfg(unix)]
+    server_sessions: Option<Vec<server::client::SessionInfo>>,
+    #[cfg(unix)]
+    server_checked: f64,
+    #[cfg(unix)]
+    server_status: String,
     // Language preference
     current_language: String,
+    // Socket for `termix msg`
+    #[cfg(unix)]
+    remote: Option<crate::ipc::Listener>,
 }
 #[cfg(feature = "gui")]
 #[allow(private_interfaces)]
 pub struct SplitPane {
-    #[allow(dead_code)]
-    pub orientation: SplitOrientation,
     pub(crate) terminals: Vec<TerminalTab>,
-    #[allow(dead_code)]
-    pub size: f32, // relative size (0.0..1.0)
+    pub active_tab: usize,
 }
 
 #[cfg(feature = "gui")]
 #[derive(Clone, Copy, PartialEq)]
 pub enum SplitOrientation {
-    Horizontal,
-    Vertical,
+    Horizontal, // children side by side
+    Vertical,   // children stacked
+}
+
+/// A leaf of the split layout.
+#[cfg(feature = "gui")]
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum PaneId {
+    Main,         // active tab of `GuiApp.terminals`
+    Split(usize), // index into `GuiApp.split_panes`
+}
+
+/// Recursive split layout. Containers divide their rectangle between two
+/// children; `size` is the share of the first one and is changed by
+/// dragging the divider.
+#[cfg(feature = "gui")]
+enum SplitNode {
+    Leaf(PaneId),
+    Container {
+        orientation: SplitOrientation,
+        size: f32, // relative size of `first` (0.0..1.0)
+        first: Box<SplitNode>,
+        second: Box<SplitNode>,
+    },
+}
+
+#[cfg(feature = "gui")]
+impl SplitNode {
+    /// Replace the leaf `target` by a container holding `target` and `new`.
+    fn split(&mut self, target: PaneId, orientation: SplitOrientation, new: PaneId) -> bool {
+        match self {
+            SplitNode::Leaf(id) if *id == target => {
+                *self = SplitNode::Container {
+                    orientation,
+                    size: 0.5,
+                    first: Box::new(SplitNode::Leaf(target)),
+                    second: Box::new(SplitNode::Leaf(new)),
+                };
+                true
+            }
+            SplitNode::Leaf(_) => false,
+            SplitNode::Container { first, second, .. } => {
+                first.split(target, orientation, new) || second.split(target, orientation, new)
+            }
+        }
+    }
+
+    /// Remove the leaf `target`; its parent container collapses into the sibling.
+    fn remove(&mut self, target: PaneId) -> bool {
+        let SplitNode::Container { first, second, .. } = self else {
+            return false;
+        };
+        let sibling = if matches!(**first, SplitNode::Leaf(id) if id == target) {
+            std::mem::replace(&mut **second, SplitNode::Leaf(PaneId::Main))
+        } else if matches!(**second, SplitNode::Leaf(id) if id == target) {
+            std::mem::replace(&mut **first, SplitNode::Leaf(PaneId::Main))
+        } else {
+            return first.remove(target) || second.remove(target);
+        };
+        *self = sibling;
+        true
+    }
+
+    /// Leaves in layout order (left-to-right, top-to-bottom).
+    fn leaves(&self, out: &mut Vec<PaneId>) {
+        match self {
+            SplitNode::Leaf(id) => out.push(*id),
+            SplitNode::Container { first, second, .. } => {
+                first.leaves(out);
+                second.leaves(out);
+            }
+        }
+    }
+
+    fn map_leaves(&mut self, f: &mut impl FnMut(PaneId) -> PaneId) {
+        match self {
+            SplitNode::Leaf(id) => *id = f(*id),
+            SplitNode::Container { first, second, .. } => {
+                first.map_leaves(f);
+                second.map_leaves(f);
+            }
+        }
+    }
+
+    /// Shift split indices down after `split_panes[removed]` was deleted.
+    fn renumber_after(&mut self, removed: usize) {
+        match self {
+            SplitNode::Leaf(PaneId::Split(idx)) if *idx > removed => *idx -= 1,
+            SplitNode::Leaf(_) => {}
+            SplitNode::Container { first, second, .. } => {
+                first.renumber_after(removed);
+                second.renumber_after(removed);
+            }
+        }
+    }
+}
+
+/// Per-pane actions from the pane header, applied after rendering.
+#[cfg(feature = "gui")]
+#[derive(Clone, Copy)]
+enum PaneAction {
+    Close,
+    Zoom,
+    Swap,
+    MoveTab,
+    Tab(TabAction),
+}
+
+/// Clicks in a pane's tab strip.
+#[cfg(feature = "gui")]
+#[derive(Clone, Copy)]
+enum TabAction {
+    Select(usize),
+    Rename(usize),
+    Close(usize),
+    ToggleBroadcast(usize),
+    StartDrag(usize),
+}
+
+/// Where a dragged tab would land if released now.
+#[cfg(feature = "gui")]
+#[derive(Clone, Copy, PartialEq)]
+enum TabDropTarget {
+    Tab(PaneId, usize),                 // takes this tab's position
+    Pane(PaneId),                       // appended to the pane
+    NewSplit(PaneId, SplitOrientation), // new pane beside/below it
 }
 
 #[cfg(feature = "gui")]
@@ -283,22 +493,14 @@ struct MarkdownTab {
 #[cfg(feature = "gui")]
 impl Default for GuiApp {
     fn default() -> Self {
-        let mut terminals = Vec::new();
-        if let Ok(term) = TerminalView::new(2000) {
-            terminals.push(TerminalTab {
-                name: "Terminal 1".to_string(),
-                terminal: term,
-            });
-        }
-        
         let markdown_editors = vec![MarkdownTab {
-            name: "Dokument 1".to_string(),
+            name: format!("{} 1", rust_i18n::t!("markdown.document")),
             editor: MarkdownEditor::default(),
         }];
         
         Self {
             selected: 0,
-            terminals,
+            terminals: Vec::new(),
             active_terminal_tab: 0,
             markdown_editors,
             active_markdown_tab: 0,
@@ -317,12 +519,51 @@ impl Default for GuiApp {
             terminal_rename_dialog: None,
             markdown_rename_dialog: None,
             pending_ssh_connection: None,
+            ssh_tab_title: None,
+            ssh_multi_exec_view: false,
+            multi_exec: MultiExec::default(),
             ssh_password_prompt: None,
+            ssh_remember_password: false,
+            credentials: CredentialStore::default(),
+            vault_unlock_prompt: None,
+            vault_unlock_declined: false,
+            vault_master_input: String::new(),
+            vault_master_confirm: String::new(),
+            vault_status: String::new(),
             scrollback_lines: 2000,
+            restore_session: false,
+            session_scrollback_lines: 500,
+            use_server: false,
+            profiles: Vec::new(),
+            default_profile: String::new(),
+            keymap: Keymap::default(),
+            pass_through_keys: false,
+            recording_action: None,
+            keys_status: String::new(),
+            palette: Palette::load(),
+            record_history: false,
+            history: HistoryView::load(),
+            completion: Rc::default(),
+            snippets: SnippetsView::load(),
             dragging_terminal_tab: None,
+            tab_rects: Vec::new(),
             split_panes: Vec::new(),
+            split_layout: None,
+            zoomed_pane: None,
+            pane_rects: Vec::new(),
             active_pane: 0,
+            main_focused: true,
+            layout_name_input: String::new(),
+            layout_status: String::new(),
+            #[cfg(unix)]
+            server_sessions: None,
+            #[cfg(unix)]
+            server_checked: f64::NEG_INFINITY,
+            #[cfg(unix)]
+            server_status: String::new(),
             current_language: "de".to_string(),
+            #[cfg(unix)]
+            remote: None,
         }
     }
 }
@@ -400,17 +641,23 @@ impl GuiApp {
 
         // Terminal settings
         self.scrollback_lines = settings.scrollback_lines.clamp(100, 100000);
+        self.restore_session = settings.restore_session;
+        self.session_scrollback_lines = settings.session_scrollback_lines.min(self.scrollback_lines);
+        self.use_server = settings.use_server;
+        self.profiles = settings.profiles.clone();
+        self.default_profile = settings.default_profile.clone();
+        self.keymap = Keymap::new(&settings.keybindings);
+        self.pass_through_keys = settings.pass_through_keys;
+        self.record_history = settings.record_history;
         
         // Language
         self.current_language = settings.language.clone();
         rust_i18n::set_locale(&self.current_language);
 
         // Propagate to existing terminals
+        let style = self.terminal_style();
         for t in &mut self.terminals {
-            t.terminal.text_color = self.terminal_text_color;
-            t.terminal.cursor_color = self.cursor_color;
-            t.terminal.cursor_shape = self.cursor_shape;
-            t.terminal.cursor_blinking = self.cursor_blinking;
+            t.terminal.set_style(style);
         }
     }
 
@@ -428,6 +675,14 @@ impl GuiApp {
             custom_font_path: self.custom_font_info.clone(),
             sidebar_collapsed: self.sidebar_collapsed,
             scrollback_lines: self.scrollback_lines,
+            restore_session: self.restore_session,
+            session_scrollback_lines: self.session_scrollback_lines,
+            use_server: self.use_server,
+            default_profile: self.default_profile.clone(),
+            profiles: self.profiles.clone(),
+            keybindings: self.keymap.overrides(),
+            pass_through_keys: self.pass_through_keys,
+            record_history: self.record_history,
             language: self.current_language.clone(),
         }
     }
@@ -436,201 +691,1611 @@ impl GuiApp {
         save_settings(&self.to_settings());
     }
 
-    pub fn new(cc: &CreationContext<'_>) -> Self {
+    fn terminal_style(&self) -> TerminalStyle {
+        TerminalStyle {
+            text_color: self.terminal_text_color,
+            cursor_color: self.cursor_color,
+            cursor_shape: self.cursor_shape,
+            cursor_blinking: self.cursor_blinking,
+        }
+    }
+
+    /// Completion sources: the built-in and user specs (`completions/` in
+    /// the config directory) and the saved history.
+    fn load_completion(&self) -> Rc<Sources> {
+        let (mut sources, errors) = Sources::load(&crate::config::config_dir().join("completions"));
+        for e in errors {
+            eprintln!("{}: {}", rust_i18n::t!("terminal.spec_failed"), e);
+        }
+        sources.extend_history(self.history.commands());
+        Rc::new(sources)
+    }
+
+    fn profile(&self, name: &str) -> Option<&Profile> {
+        profile::find(&self.profiles, name)
+    }
+
+    /// The profile new tabs start with; `None` for a plain shell.
+    fn default_profile(&self) -> Option<&Profile> {
+        Some(self.default_profile.as_str()).filter(|n| !n.is_empty()).and_then(|n| self.profile(n))
+    }
+
+    /// The profile an SSH connection refers to.
+    fn ssh_profile(&self, conn: &SshConnection) -> Option<&Profile> {
+        conn.profile.as_deref().and_then(|name| self.profile(name))
+    }
+
+    pub fn new(cc: &CreationContext<'_>, launch: Launch) -> Self {
+        let Launch { options, program } = launch;
         let mut app = GuiApp::default();
-        let settings = load_settings();
+        if let Ok(term) = TerminalView::new(2000) {
+            app.terminals.push(TerminalTab { name: "Terminal 1".to_string(), terminal: term });
+        }
+        let mut settings = load_settings();
+        options.apply(&mut settings);
         app.apply_settings(&settings, &cc.egui_ctx);
+        app.completion = app.load_completion();
+        // Tabs asked for on the command line replace the restored ones
+        let explicit = options.layout.is_some() || options.opens_terminal();
+        let pick_up_server = !explicit && app.use_server;
+        let mut restored = false;
+        if let Some(name) = &options.layout {
+            match layout::load_layout(name) {
+                Ok(layout) => app.apply_layout(&layout),
+                Err(e) => {
+                    eprintln!("{}: {}", rust_i18n::t!("layouts.load_failed"), e);
+                    app.layout_status = format!("❌ {}", e);
+                }
+            }
+        } else if !explicit && app.restore_session {
+            if let Some(session) = session::load_session() {
+                app.apply_session(&session);
+                restored = true;
+            }
+        }
+        #[cfg(unix)]
+        if pick_up_server {
+            app.attach_detached_sessions(!restored);
+        }
+        #[cfg(not(unix))]
+        let _ = (pick_up_server, restored);
+        #[cfg(unix)]
+        app.listen_for_remote(&cc.egui_ctx);
+        if options.opens_terminal() {
+            let replace = options.layout.is_none();
+            app.open_startup_tab(options, program, replace);
+        } else if !explicit && !restored && !pick_up_server && app.default_profile().is_some() {
+            // The default tab was started before the settings were known
+            app.open_startup_tab(options, None, true);
+        }
         app
     }
 
+    /// The tab asked for with `-e`, `--cwd`, `--title`, `--ssh` or
+    /// `--profile`, else one with the default profile. It takes the place
+    /// of the default tab, or joins the tabs of an opened layout.
+    fn open_startup_tab(&mut self, options: Options, program: Option<TerminalView>, replace: bool) {
+        if replace {
+            self.terminals.clear();
+            self.active_terminal_tab = 0;
+        }
+        if let Some(conn) = options.ssh_connection {
+            // Connects on the first frame, asking for a password if needed
+            self.ssh_tab_title = options.title;
+            self.pending_ssh_connection = Some(conn);
+            self.selected = 1;
+            return;
+        }
+        let profile = options.profile_config.or_else(|| self.default_profile().cloned());
+        let name = options
+            .title
+            .or_else(|| {
+                let program = std::path::Path::new(options.command.first()?).file_name()?;
+                Some(program.to_string_lossy().into_owned())
+            })
+            .or_else(|| Some(profile.as_ref()?.name.clone()))
+            .unwrap_or_else(|| "Terminal 1".to_string());
+        let term = match program {
+            Some(term) => term,
+            None => match self.local_terminal(&name, profile.as_ref(), None, options.cwd.as_deref()) {
+                Ok(term) => term,
+                Err(e) => {
+                    eprintln!("{}: {}", rust_i18n::t!("terminal.start_failed"), e);
+                    return;
+                }
+            },
+        };
+        let tab = self.styled_tab(&name, term);
+        self.terminals.push(tab);
+        self.active_terminal_tab = self.terminals.len() - 1;
+    }
+
+    /// Run the actions whose chords were pressed. Their key events are taken
+    /// out of the input, so the terminal never sees them; everything else,
+    /// and every chord while `pass_through_keys` is on, reaches the terminal.
     fn handle_keyboard_shortcuts(&mut self, ctx: &egui::Context) {
-        ctx.input(|i| {
-            // Ctrl+T: New Terminal Tab
-            if i.modifiers.ctrl && i.key_pressed(egui::Key::T)
-                && self.selected == 0 { // Only in Terminal view
-                    if let Ok(mut term) = TerminalView::new(self.scrollback_lines) {
-                        term.text_color = self.terminal_text_color;
-                        term.cursor_color = self.cursor_color;
-                        term.cursor_shape = self.cursor_shape;
-                        term.cursor_blinking = self.cursor_blinking;
-                        self.terminals.push(TerminalTab {
-                            name: format!("Terminal {}", self.terminals.len() + 1),
-                            terminal: term,
-                        });
-                        self.active_terminal_tab = self.terminals.len() - 1;
+        if self.recording_action.is_some() {
+            return;
+        }
+        let mut triggered = Vec::new();
+        ctx.input_mut(|i| {
+            let shift = i.modifiers.shift;
+            i.events.retain(|event| {
+                let (chord, pasted) = match event {
+                    egui::Event::Key { key, pressed: true, modifiers, .. } => (key_chord(*key, *modifiers), None),
+                    // egui reports these chords as clipboard events instead of keys
+                    egui::Event::Copy => (KeyChord::new("C", true, false, shift), None),
+                    egui::Event::Cut => (KeyChord::new("X", true, false, shift), None),
+                    egui::Event::Paste(text) => (KeyChord::new("V", true, false, shift), Some(text.clone())),
+                    _ => return true,
+                };
+                let action = chord
+                    .and_then(|chord| self.keymap.action(&chord))
+                    .filter(|a| !self.pass_through_keys || *a == Action::PassThrough)
+                    .filter(|a| self.action_applies(*a));
+                match action {
+                    Some(action) => {
+                        triggered.push((action, pasted));
+                        false
                     }
+                    None => true,
                 }
+            });
+        });
+        for (action, pasted) in triggered {
+            self.run_action(action, pasted, ctx);
+        }
+    }
 
-            // Ctrl+W: Close active tab
-            if i.modifiers.ctrl && i.key_pressed(egui::Key::W) {
-                match self.selected {
-                    0 => { // Terminal
-                        if self.terminals.len() > 1 {
-                            self.terminals.remove(self.active_terminal_tab);
-                            if self.active_terminal_tab >= self.terminals.len() {
-                                self.active_terminal_tab = self.terminals.len().saturating_sub(1);
-                            }
-                        }
+    /// Whether `action` does something in the current view; chords of other
+    /// actions stay with the view (e.g. the markdown editor's clipboard).
+    fn action_applies(&self, action: Action) -> bool {
+        match action {
+            Action::CloseTab | Action::NextTab | Action::PreviousTab => matches!(self.selected, 0 | 2),
+            Action::FontLarger | Action::FontSmaller | Action::FontReset | Action::PassThrough => true,
+            // Otherwise Ctrl+R stays the shell's own reverse search
+            Action::HistorySearch => self.selected == 0 && self.record_history,
+            Action::ZoomPane | Action::FocusLeft | Action::FocusRight | Action::FocusUp | Action::FocusDown => {
+                self.selected == 0 && self.split_layout.is_some()
+            }
+            _ => self.selected == 0,
+        }
+    }
+
+    /// `pasted` is the clipboard text when the chord arrived as a paste
+    /// event; egui offers no other way to read the clipboard.
+    fn run_action(&mut self, action: Action, pasted: Option<String>, ctx: &egui::Context) {
+        match action {
+            Action::NewTab => self.new_terminal_tab(),
+            Action::CloseTab => match self.selected {
+                0 => { // Terminal: close the tab of the focused pane
+                    let pane = self.focused_pane();
+                    if let Some((_, active)) = self.pane_tabs_mut(pane) {
+                        let idx = *active;
+                        self.close_tab(pane, idx);
                     }
-                    2 => { // Markdown
-                        if self.markdown_editors.len() > 1 {
-                            self.markdown_editors.remove(self.active_markdown_tab);
-                            if self.active_markdown_tab >= self.markdown_editors.len() {
-                                self.active_markdown_tab = self.markdown_editors.len().saturating_sub(1);
-                            }
+                }
+                _ => { // Markdown
+                    if self.markdown_editors.len() > 1 {
+                        self.markdown_editors.remove(self.active_markdown_tab);
+                        if self.active_markdown_tab >= self.markdown_editors.len() {
+                            self.active_markdown_tab = self.markdown_editors.len().saturating_sub(1);
                         }
                     }
-                    _ => {}
                 }
-            }
-
-            // Ctrl+Tab: Next tab
-            if i.modifiers.ctrl && i.key_pressed(egui::Key::Tab) && !i.modifiers.shift {
+            },
+            Action::NextTab | Action::PreviousTab => {
+                let forward = action == Action::NextTab;
+                let step = |active: usize, len: usize| {
+                    if forward {
+                        (active + 1) % len
+                    } else if active == 0 {
+                        len - 1
+                    } else {
+                        active - 1
+                    }
+                };
                 match self.selected {
                     0 => {
-                        if !self.terminals.is_empty() {
-                            self.active_terminal_tab = (self.active_terminal_tab + 1) % self.terminals.len();
+                        if let Some((tabs, active)) = self.pane_tabs_mut(self.focused_pane()) {
+                            if !tabs.is_empty() {
+                                *active = step(*active, tabs.len());
+                            }
                         }
                     }
-                    2 => {
+                    _ => {
                         if !self.markdown_editors.is_empty() {
-                            self.active_markdown_tab = (self.active_markdown_tab + 1) % self.markdown_editors.len();
+                            self.active_markdown_tab = step(self.active_markdown_tab, self.markdown_editors.len());
                         }
                     }
-                    _ => {}
                 }
             }
-
-            // Ctrl+Shift+Tab: Previous tab
-            if i.modifiers.ctrl && i.modifiers.shift && i.key_pressed(egui::Key::Tab) {
-                match self.selected {
-                    0 => {
-                        if !self.terminals.is_empty() {
-                            self.active_terminal_tab = if self.active_terminal_tab == 0 {
-                                self.terminals.len() - 1
-                            } else {
-                                self.active_terminal_tab - 1
-                            };
-                        }
-                    }
-                    2 => {
-                        if !self.markdown_editors.is_empty() {
-                            self.active_markdown_tab = if self.active_markdown_tab == 0 {
-                                self.markdown_editors.len() - 1
-                            } else {
-                                self.active_markdown_tab - 1
-                            };
-                        }
+            Action::SplitHorizontal => self.create_split(SplitOrientation::Horizontal),
+            Action::SplitVertical => self.create_split(SplitOrientation::Vertical),
+            Action::ZoomPane => {
+                let pane = self.focused_pane();
+                self.zoomed_pane = if self.zoomed_pane == Some(pane) { None } else { Some(pane) };
+            }
+            Action::FocusLeft => self.focus_direction(egui::vec2(-1.0, 0.0)),
+            Action::FocusRight => self.focus_direction(egui::vec2(1.0, 0.0)),
+            Action::FocusUp => self.focus_direction(egui::vec2(0.0, -1.0)),
+            Action::FocusDown => self.focus_direction(egui::vec2(0.0, 1.0)),
+            Action::FocusPane(n) => {
+                let idx = usize::from(n.max(1)) - 1;
+                if idx < self.split_panes.len() {
+                    self.set_focus(PaneId::Split(idx));
+                }
+            }
+            Action::ToggleBroadcast => {
+                if let Some(term) = self.focused_terminal_mut() {
+                    term.view.broadcast = !term.view.broadcast;
+                }
+            }
+            Action::Copy => {
+                if let Some(term) = self.focused_terminal_mut() {
+                    if let Some(text) = term.view.session.term.selected_text() {
+                        term.view.session.term.selection = None;
+                        ctx.output_mut(|o| o.copied_text = text);
                     }
-                    _ => {}
                 }
             }
-
-            // Ctrl+Plus/Equals: Increase font size
-            if i.modifiers.ctrl && i.key_pressed(egui::Key::Plus) {
-                self.font_scale = (self.font_scale + 0.1).min(3.0);
+            Action::Paste => {
+                if let (Some(text), Some(term)) = (pasted, self.focused_terminal_mut()) {
+                    term.view.paste(&text);
+                }
+            }
+            Action::Search => {
+                if let Some(term) = self.focused_terminal_mut() {
+                    term.toggle_search();
+                }
+            }
+            Action::FontLarger | Action::FontSmaller | Action::FontReset => {
+                self.font_scale = match action {
+                    Action::FontLarger => (self.font_scale + 0.1).min(3.0),
+                    Action::FontSmaller => (self.font_scale - 0.1).max(0.5),
+                    _ => 1.0,
+                };
                 self.save_settings();
             }
-
-            // Ctrl+Minus: Decrease font size
-            if i.modifiers.ctrl && i.key_pressed(egui::Key::Minus) {
-                self.font_scale = (self.font_scale - 0.1).max(0.5);
+            Action::PassThrough => {
+                self.pass_through_keys = !self.pass_through_keys;
                 self.save_settings();
             }
+            Action::CommandPalette => self.palette.toggle(),
+            Action::HistorySearch => self.history.toggle_search(),
+            Action::InsertSnippet => {
+                if self.sidebar_collapsed {
+                    self.sidebar_collapsed = false;
+                    self.save_settings();
+                }
+                self.snippets.focus_search();
+            }
+        }
+    }
+
+    /// Whether the palette, the history search or the snippets take the
+    /// keyboard.
+    fn overlay_open(&self) -> bool {
+        self.palette.is_open() || self.history.is_searching() || self.snippets.takes_keys()
+    }
+
+    /// Everything the command palette offers, in the order shown before
+    /// anything is typed or used.
+    fn palette_entries(&self) -> Vec<Entry> {
+        // Paste needs a clipboard event, which a palette entry cannot supply
+        let mut entries: Vec<Entry> = keybindings::ACTIONS
+            .iter()
+            .copied()
+            .filter(|a| !matches!(a, Action::Paste | Action::CommandPalette))
+            .map(|a| Entry::new(Command::Action(a), format!("action:{}", a.id()), a.name()).with_keys(self.keymap.chord(a).map(KeyChord::label)))
+            .collect();
+        let views = [
+            rust_i18n::t!("menu.terminal"),
+            rust_i18n::t!("menu.ssh"),
+            rust_i18n::t!("menu.markdown"),
+            rust_i18n::t!("menu.settings"),
+            rust_i18n::t!("menu.about"),
+        ];
+        for (idx, view) in views.iter().enumerate() {
+            entries.push(Entry::new(Command::View(idx), format!("view:{}", idx), rust_i18n::t!("palette.go_to", view = view)));
+        }
+        entries.push(Entry::new(Command::ToggleSettings, "settings", rust_i18n::t!("palette.toggle_settings")));
+        entries.push(Entry::new(Command::ToggleSidebar, "sidebar", rust_i18n::t!("palette.toggle_sidebar")));
+        for theme in Theme::all() {
+            let name = theme.name().to_string();
+            entries.push(Entry::new(Command::Theme(name.clone()), format!("theme:{}", name), rust_i18n::t!("palette.theme", theme = name)));
+        }
+        for conn in &self.ssh_manager.connections {
+            let target = format!("{}@{}", conn.username, conn.host);
+            let label = rust_i18n::t!("palette.connect", name = conn.name, target = target);
+            entries.push(Entry::new(Command::Connect(conn.name.clone()), format!("ssh:{}", conn.name), label));
+        }
+        let plain = rust_i18n::t!("profile.plain_shell").to_string();
+        entries.push(Entry::new(Command::Profile(None), "profile:", rust_i18n::t!("palette.new_tab_with", profile = plain)));
+        for p in &self.profiles {
+            let label = rust_i18n::t!("palette.new_tab_with", profile = p.name);
+            entries.push(Entry::new(Command::Profile(Some(p.name.clone())), format!("profile:{}", p.name), label));
+        }
+        for name in self.palette.layouts() {
+            let label = rust_i18n::t!("palette.layout", layout = name);
+            entries.push(Entry::new(Command::Layout(name.clone()), format!("layout:{}", name), label));
+        }
+        let host = self.focused_host();
+        for snippet in self.snippets.offered(host.as_deref()) {
+            let label = rust_i18n::t!("palette.snippet", name = snippet.name);
+            entries.push(Entry::new(Command::Snippet(snippet.name.clone()), format!("snippet:{}", snippet.name), label));
+        }
+        entries.push(Entry::new(Command::NewMarkdown, "markdown:new", rust_i18n::t!("palette.new_markdown")));
+        entries.push(Entry::new(Command::OpenMarkdown, "markdown:open", rust_i18n::t!("palette.open_markdown")));
+        entries
+    }
 
-            // Ctrl+0: Reset font size
-            if i.modifiers.ctrl && i.key_pressed(egui::Key::Num0) {
-                self.font_scale = 1.0;
+    fn run_command(&mut self, command: Command, ctx: &egui::Context) {
+        match command {
+            Command::Action(action) => {
+                // Terminal actions switch to the terminal first
+                if !self.action_applies(action) {
+                    self.selected = 0;
+                }
+                if self.action_applies(action) {
+                    self.run_action(action, None, ctx);
+                }
+            }
+            Command::View(idx) => self.selected = idx,
+            Command::ToggleSettings => self.selected = if self.selected == 3 { 0 } else { 3 },
+            Command::ToggleSidebar => {
+                self.sidebar_collapsed = !self.sidebar_collapsed;
                 self.save_settings();
             }
-
-            // Ctrl+H: Horizontal split
-            if i.modifiers.ctrl && i.key_pressed(egui::Key::H)
-                && self.selected == 0 { // Only in Terminal view
-                    self.create_split(SplitOrientation::Horizontal);
+            Command::Theme(name) => {
+                self.current_theme = Theme::from_name(&name);
+                self.current_theme.apply(ctx);
+                self.save_settings();
+            }
+            Command::Connect(name) => {
+                if let Some(conn) = self.ssh_manager.connections.iter().find(|c| c.name == name) {
+                    self.pending_ssh_connection = Some(conn.clone());
                 }
-
-            // Ctrl+V: Vertical split (when Shift is pressed to avoid paste conflict)
-            if i.modifiers.ctrl && i.modifiers.shift && i.key_pressed(egui::Key::V)
-                && self.selected == 0 { // Only in Terminal view
-                    self.create_split(SplitOrientation::Vertical);
+            }
+            Command::Profile(name) => {
+                self.selected = 0;
+                let profile = name.and_then(|n| self.profile(&n).cloned());
+                self.new_profile_tab(profile);
+            }
+            Command::Layout(name) => {
+                self.selected = 0;
+                match layout::load_layout(&name) {
+                    Ok(layout) => {
+                        self.apply_layout(&layout);
+                        self.layout_status.clear();
+                    }
+                    Err(e) => self.layout_status = format!("❌ {}", e),
+                }
+            }
+            Command::Snippet(name) => {
+                self.selected = 0;
+                if let Some(command) = self.snippets.start(&name) {
+                    self.insert_snippet(&command);
+                }
+            }
+            Command::NewMarkdown => {
+                self.markdown_editors.push(MarkdownTab {
+                    name: format!("{} {}", rust_i18n::t!("markdown.document"), self.markdown_editors.len() + 1),
+                    editor: MarkdownEditor::default(),
+                });
+                self.active_markdown_tab = self.markdown_editors.len() - 1;
+                self.selected = 2;
+            }
+            Command::OpenMarkdown => {
+                let Some(path) = rfd::FileDialog::new().add_filter("Markdown", &["md", "markdown"]).pick_file() else {
+                    return;
+                };
+                if let Ok(content) = std::fs::read_to_string(&path) {
+                    let name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+                    self.markdown_editors.push(MarkdownTab { name, editor: MarkdownEditor { content, file_path: Some(path) } });
+                    self.active_markdown_tab = self.markdown_editors.len() - 1;
+                    self.selected = 2;
                 }
+            }
+        }
+    }
 
-            // Ctrl+1-9: Switch between split panes
-            for (idx, key) in [egui::Key::Num1, egui::Key::Num2, egui::Key::Num3, 
-                              egui::Key::Num4, egui::Key::Num5, egui::Key::Num6,
-                              egui::Key::Num7, egui::Key::Num8, egui::Key::Num9].iter().enumerate() {
-                if i.modifiers.ctrl && i.key_pressed(*key)
-                    && idx < self.split_panes.len() {
-                        self.active_pane = idx;
+    /// Settings section listing every action with its chord. Clicking a chord
+    /// records the next one pressed (see `record_chord`).
+    fn keybindings_ui(&mut self, ui: &mut egui::Ui) {
+        ui.label(egui::RichText::new(rust_i18n::t!("keys.title")).strong());
+        ui.label(egui::RichText::new(rust_i18n::t!("keys.help")).small().color(egui::Color32::GRAY));
+        ui.add_space(6.0);
+        let toggle = rust_i18n::t!("keys.pass_through_toggle", keys = self.keymap.label(Action::PassThrough));
+        if ui.checkbox(&mut self.pass_through_keys, toggle.as_ref()).changed() {
+            self.save_settings();
+        }
+        ui.add_space(6.0);
+        if self.recording_action.is_some() {
+            self.record_chord(ui.ctx());
+        }
+        let conflicts = self.keymap.conflicts();
+        let mut changed = false;
+        egui::Grid::new("keybindings").striped(true).show(ui, |ui| {
+            for action in keybindings::ACTIONS.iter().copied() {
+                ui.label(action.name());
+                let recording = self.recording_action == Some(action);
+                let text = if recording { rust_i18n::t!("keys.press_keys").to_string() } else { self.keymap.label(action) };
+                let conflicting = conflicts.iter().any(|(_, actions)| actions.contains(&action));
+                let text = if conflicting { egui::RichText::new(text).color(egui::Color32::RED) } else { egui::RichText::new(text) };
+                if ui.selectable_label(recording, text).clicked() {
+                    self.recording_action = if recording { None } else { Some(action) };
+                    self.keys_status.clear();
+                }
+                ui.horizontal(|ui| {
+                    if ui.small_button("×").on_hover_text(rust_i18n::t!("keys.unbind")).clicked() {
+                        self.keymap.set(action, None);
+                        changed = true;
+                    }
+                    if ui.small_button("↺").on_hover_text(rust_i18n::t!("keys.reset")).clicked() {
+                        self.keymap.set(action, action.default_chord());
+                        changed = true;
                     }
+                });
+                ui.end_row();
             }
         });
+        if ui.button(rust_i18n::t!("keys.reset_all")).clicked() {
+            self.keymap = Keymap::default();
+            changed = true;
+        }
+        for (chord, actions) in &conflicts {
+            let names: Vec<String> = actions.iter().map(|a| a.name()).collect();
+            ui.colored_label(egui::Color32::RED, rust_i18n::t!("keys.conflict", chord = chord.label(), first = &names[0], second = &names[1]).as_ref());
+        }
+        if !self.keys_status.is_empty() {
+            ui.colored_label(egui::Color32::YELLOW, &self.keys_status);
+        }
+        if changed {
+            self.keys_status.clear();
+            self.save_settings();
+        }
     }
 
-    fn create_split(&mut self, orientation: SplitOrientation) {
-        if let Ok(mut term) = TerminalView::new(self.scrollback_lines) {
-            term.text_color = self.terminal_text_color;
-            term.cursor_color = self.cursor_color;
-            term.cursor_shape = self.cursor_shape;
-            term.cursor_blinking = self.cursor_blinking;
-            
-            let pane = SplitPane {
-                orientation,
-                terminals: vec![TerminalTab {
-                    name: format!("Split {}", self.split_panes.len() + 1),
-                    terminal: term,
-                }],
-                size: 0.5,
+    /// Take the next chord pressed for `recording_action`. A chord that is
+    /// already bound to another action is refused; one that programs in the
+    /// terminal use is taken with a warning.
+    fn record_chord(&mut self, ctx: &egui::Context) {
+        let Some(action) = self.recording_action else {
+            return;
+        };
+        let pressed = ctx.input_mut(|i| {
+            let shift = i.modifiers.shift;
+            let pos = i.events.iter().position(|e| match e {
+                egui::Event::Key { key, pressed: true, .. } => key_chord(*key, egui::Modifiers::NONE).is_some(),
+                egui::Event::Copy | egui::Event::Cut | egui::Event::Paste(_) => true,
+                _ => false,
+            })?;
+            let chord = match i.events.remove(pos) {
+                egui::Event::Key { key: egui::Key::Escape, modifiers, .. } if modifiers.is_none() => None,
+                egui::Event::Key { key, modifiers, .. } => key_chord(key, modifiers),
+                egui::Event::Copy => KeyChord::new("C", true, false, shift),
+                egui::Event::Cut => KeyChord::new("X", true, false, shift),
+                _ => KeyChord::new("V", true, false, shift),
             };
-            self.split_panes.push(pane);
-            self.active_pane = self.split_panes.len() - 1;
+            Some(chord)
+        });
+        let Some(chord) = pressed else {
+            return;
+        };
+        self.recording_action = None;
+        let Some(chord) = chord else {
+            return; // Esc
+        };
+        match self.keymap.action(&chord) {
+            Some(other) if other != action => {
+                self.keys_status = rust_i18n::t!("keys.used_by", chord = chord.label(), action = other.name()).into();
+            }
+            _ => {
+                self.keys_status = if chord.shadows_terminal() {
+                    rust_i18n::t!("keys.shadows_terminal", chord = chord.label()).into()
+                } else {
+                    String::new()
+                };
+                self.keymap.set(action, Some(chord));
+                self.save_settings();
+            }
         }
     }
-}
 
-#[cfg(feature = "gui")]
-impl App for GuiApp {
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut Frame) {
-        // Handle global keyboard shortcuts
-        self.handle_keyboard_shortcuts(ctx);
+    fn focused_terminal_mut(&mut self) -> Option<&mut TerminalView> {
+        let (tabs, active) = self.pane_tabs_mut(self.focused_pane())?;
+        tabs.get_mut(*active).map(|t| &mut t.terminal)
+    }
 
-        // Apply selected theme
-        self.current_theme.apply(ctx);
-        ctx.set_pixels_per_point(self.font_scale);
+    /// SSH connection of the focused terminal; None for local shells.
+    fn focused_host(&self) -> Option<String> {
+        let (tabs, active) = self.pane_tabs(self.focused_pane())?;
+        tabs.get(active)?.terminal.ssh.as_ref().map(|c| c.name.clone())
+    }
 
-        // Avoid per-frame font reconfiguration for performance.
-        // Fonts are applied in apply_settings() and when changed via the Settings UI.
-        if let FontMode::Custom(info) = &self.font_mode {
-            // Keep a reference to silence warnings; no per-frame font ops.
-            let _ = info;
+    /// Type a filled-in snippet into the focused terminal, without running it.
+    fn insert_snippet(&mut self, command: &str) {
+        if let Some(term) = self.focused_terminal_mut() {
+            term.view.insert(command);
         }
+    }
 
-        egui::TopBottomPanel::top("top").show(ctx, |ui| {
-            ui.horizontal_wrapped(|ui| {
-                ui.heading("TermiX");
-                ui.label(egui::RichText::new(" • GUI").color(egui::Color32::LIGHT_BLUE));
-                ui.separator();
-                ui.label(format!("{}:", rust_i18n::t!("common.mode")));
-                ui.label(match self.selected {
-                    0 => rust_i18n::t!("menu.terminal").to_string(),
-                    1 => rust_i18n::t!("menu.ssh").to_string(),
-                    2 => rust_i18n::t!("menu.markdown").to_string(),
-                    3 => rust_i18n::t!("menu.settings").to_string(),
-                    4 => rust_i18n::t!("menu.about").to_string(),
-                    5 => rust_i18n::t!("menu.exit").to_string(),
-                    _ => "?".to_string()
-                });
-            });
-        });
+    /// Tab list and active index of `pane`.
+    fn pane_tabs_mut(&mut self, pane: PaneId) -> Option<(&mut Vec<TerminalTab>, &mut usize)> {
+        match pane {
+            PaneId::Main => Some((&mut self.terminals, &mut self.active_terminal_tab)),
+            PaneId::Split(idx) => self.split_panes.get_mut(idx).map(|p| (&mut p.terminals, &mut p.active_tab)),
+        }
+    }
 
-        let sidebar_width = if self.sidebar_collapsed { 60.0 } else { 220.0 };
-        
-        egui::SidePanel::left("menu")
-            .resizable(false)
-            .exact_width(sidebar_width)  // Use exact_width instead of default_width
-            .show(ctx, |ui| {
-            // Toggle button at top
+    /// Open a new local terminal with the default profile as a tab of the
+    /// focused pane.
+    fn new_terminal_tab(&mut self) {
+        self.new_profile_tab(self.default_profile().cloned());
+    }
+
+    /// Like `new_terminal_tab`, with `profile` (`None` = plain shell). The
+    /// tab is named after the profile.
+    fn new_profile_tab(&mut self, profile: Option<Profile>) {
+        let pane = self.focused_pane();
+        let name = match &profile {
+            Some(profile) => profile.name.clone(),
+            None => format!("Terminal {}", self.pane_tabs(pane).map_or(0, |(tabs, _)| tabs.len()) + 1),
+        };
+        if let Ok(term) = self.local_terminal(&name, profile.as_ref(), None, None) {
+            let tab = self.styled_tab(&name, term);
+            if let Some((tabs, active)) = self.pane_tabs_mut(pane) {
+                tabs.push(tab);
+                *active = tabs.len() - 1;
+            }
+        }
+    }
+
+    /// Dropdown next to the new-tab button: open a tab with any profile, or
+    /// pick (★) the one new tabs start with.
+    fn profile_menu(&mut self, ui: &mut egui::Ui) {
+        let default = self.default_profile().map(|p| p.name.clone());
+        let mut open = None;
+        let mut make_default = None;
+        let make_default_hint = rust_i18n::t!("profile.make_default", keys = self.keymap.label(Action::NewTab));
+        ui.menu_button("▾", |ui| {
+            for profile in std::iter::once(None).chain(self.profiles.iter().map(Some)) {
+                let name = profile.map(|p| p.name.clone());
+                ui.horizontal(|ui| {
+                    let star = if name == default { "★" } else { "☆" };
+                    if ui.small_button(star).on_hover_text(make_default_hint.as_ref()).clicked() {
+                        make_default = Some(name.clone().unwrap_or_default());
+                    }
+                    let label = name.clone().unwrap_or_else(|| rust_i18n::t!("profile.plain_shell").to_string());
+                    if ui.button(label).clicked() {
+                        open = Some(profile.cloned());
+                        ui.close_menu();
+                    }
+                });
+            }
+            if self.profiles.is_empty() {
+                ui.separator();
+                ui.colored_label(egui::Color32::GRAY, rust_i18n::t!("profile.none_hint").as_ref());
+            }
+        })
+        .response
+        .on_hover_text(rust_i18n::t!("profile.menu"));
+        if let Some(name) = make_default {
+            self.default_profile = name;
+            self.save_settings();
+        }
+        if let Some(profile) = open {
+            self.new_profile_tab(profile);
+        }
+    }
+
+    /// Open every server session no window is attached to as a tab of the
+    /// main pane; with `replace` they take the place of its tabs.
+    #[cfg(unix)]
+    fn attach_detached_sessions(&mut self, replace: bool) {
+        if let Err(e) = server::client::ensure_running() {
+            eprintln!("{}: {}", rust_i18n::t!("server.unreachable"), e);
+            return;
+        }
+        let attached: Vec<u64> = self.all_terminals_mut().iter().filter_map(|t| t.server_id()).collect();
+        let sessions = server::client::list_sessions().unwrap_or_default();
+        let mut tabs: Vec<TerminalTab> = sessions
+            .iter()
+            .filter(|s| s.clients == 0 && !attached.contains(&s.id))
+            .filter_map(|s| self.server_tab(s.id, &s.title))
+            .collect();
+        if !replace {
+            self.terminals.append(&mut tabs);
+            return;
+        }
+        if tabs.is_empty() {
+            match self.local_terminal("Terminal 1", self.default_profile(), None, None) {
+                Ok(term) => tabs.push(self.styled_tab("Terminal 1", term)),
+                Err(_) => return,
+            }
+        }
+        self.terminals = tabs;
+        self.active_terminal_tab = 0;
+    }
+
+    #[cfg(unix)]
+    fn server_tab(&self, id: u64, title: &str) -> Option<TerminalTab> {
+        let attachment = server::client::Attachment::attach(id, 25, 80).ok()?;
+        let term = TerminalView::attach_server(attachment, self.scrollback_lines).ok()?;
+        Some(self.styled_tab(title, term))
+    }
+
+    /// Toolbar menu of the background server and its sessions.
+    #[cfg(unix)]
+    fn server_menu(&mut self, ui: &mut egui::Ui) {
+        ui.menu_button(format!("🖧 {} ▾", rust_i18n::t!("server.title")), |ui| {
+            let now = ui.input(|i| i.time);
+            if now - self.server_checked > 1.0 {
+                self.server_checked = now;
+                self.server_sessions = server::client::list_sessions().ok();
+            }
+            let Some(sessions) = self.server_sessions.clone() else {
+                ui.colored_label(egui::Color32::GRAY, format!("○ {}", rust_i18n::t!("server.not_running")));
+                if ui.button(format!("▶ {}", rust_i18n::t!("server.start"))).clicked() {
+                    self.server_status = match server::client::ensure_running() {
+                        Ok(()) => String::new(),
+                        Err(e) => format!("❌ {}", e),
+                    };
+                    self.server_checked = f64::NEG_INFINITY;
+                }
+                if !self.server_status.is_empty() {
+                    ui.label(&self.server_status);
+                }
+                return;
+            };
+            ui.horizontal(|ui| {
+                ui.colored_label(egui::Color32::GREEN, format!("● {}", rust_i18n::t!("server.running")));
+                if ui.small_button(format!("⏹ {}", rust_i18n::t!("server.stop"))).on_hover_text(rust_i18n::t!("server.stop_hint")).clicked() {
+                    if let Err(e) = server::client::shutdown() {
+                        self.server_status = format!("❌ {}", e);
+                    }
+                    self.server_checked = f64::NEG_INFINITY;
+                }
+            });
+            ui.separator();
+            if sessions.is_empty() {
+                ui.colored_label(egui::Color32::GRAY, rust_i18n::t!("server.no_sessions").as_ref());
+            }
+            let attached: Vec<u64> = self.all_terminals_mut().iter().filter_map(|t| t.server_id()).collect();
+            for s in &sessions {
+                ui.horizontal(|ui| {
+                    let label = format!("#{} {}", s.id, s.title);
+                    let open = ui
+                        .add_enabled(!attached.contains(&s.id), egui::Button::new(label))
+                        .on_hover_text(rust_i18n::t!("server.attach"));
+                    if open.clicked() {
+                        if let Some(tab) = self.server_tab(s.id, &s.title) {
+                            let pane = self.focused_pane();
+                            let len = self.pane_tabs(pane).map_or(0, |(tabs, _)| tabs.len());
+                            self.insert_tab(pane, len, tab);
+                        }
+                        ui.close_menu();
+                    }
+                    if s.clients > 0 {
+                        ui.colored_label(egui::Color32::GRAY, format!("({} {})", s.clients, rust_i18n::t!("server.clients")));
+                    }
+                    if ui.small_button("🗑").on_hover_text(rust_i18n::t!("server.kill")).clicked() {
+                        if let Err(e) = server::client::kill_session(s.id) {
+                            self.server_status = format!("❌ {}", e);
+                        }
+                        self.server_checked = f64::NEG_INFINITY;
+                    }
+                });
+            }
+            ui.separator();
+            let can_detach = self.focused_terminal_mut().is_some_and(|t| t.server_id().is_some());
+            if ui.add_enabled(can_detach, egui::Button::new(format!("⏏ {}", rust_i18n::t!("server.detach")))).clicked() {
+                self.detach_active_tab();
+                self.server_checked = f64::NEG_INFINITY;
+                ui.close_menu();
+            }
+            if !self.server_status.is_empty() {
+                ui.label(&self.server_status);
+            }
+        });
+    }
+
+    /// Close the focused tab but keep its shell running on the server.
+    #[cfg(unix)]
+    fn detach_active_tab(&mut self) {
+        let pane = self.focused_pane();
+        let Some((tabs, active)) = self.pane_tabs_mut(pane) else {
+            return;
+        };
+        let (idx, len) = (*active, tabs.len());
+        if let Some(tab) = tabs.get_mut(idx) {
+            tab.terminal.detach_server();
+        }
+        // The main pane never goes without a tab
+        if len <= 1 && self.split_layout.is_none() {
+            self.new_terminal_tab();
+        }
+        self.close_tab(pane, idx);
+    }
+
+    /// Start a shell, or the program of `profile`, on the background server
+    /// when that is enabled.
+    fn local_terminal(
+        &self,
+        title: &str,
+        profile: Option<&Profile>,
+        command: Option<&str>,
+        cwd: Option<&std::path::Path>,
+    ) -> anyhow::Result<TerminalView> {
+        #[cfg(unix)]
+        if self.use_server {
+            server::client::ensure_running()?;
+            let launch = with_env(profile, &self.window_env());
+            let attachment = server::client::Attachment::spawn(title, command, cwd, launch.as_ref(), 25, 80)?;
+            let scrollback = profile.and_then(|p| p.scrollback_lines).unwrap_or(self.scrollback_lines);
+            let mut term = TerminalView::attach_server(attachment, scrollback)?;
+            term.command = command.map(str::to_string);
+            term.start_cwd = cwd.map(std::path::Path::to_path_buf);
+            term.profile = profile.cloned();
+            return Ok(term);
+        }
+        #[cfg(not(unix))]
+        let _ = title;
+        TerminalView::spawn(self.scrollback_lines, profile, &self.window_env(), command, cwd)
+    }
+
+    /// Variables for programs started in this window, so `termix msg` in
+    /// them reaches it.
+    fn window_env(&self) -> Vec<(String, String)> {
+        #[cfg(unix)]
+        if let Some(listener) = &self.remote {
+            return vec![(crate::ipc::SOCKET_ENV.to_string(), listener.path().display().to_string())];
+        }
+        Vec::new()
+    }
+
+    /// Remove tab `idx` from `pane`, keeping the active index in range.
+    fn take_tab(&mut self, pane: PaneId, idx: usize) -> Option<TerminalTab> {
+        let (tabs, active) = self.pane_tabs_mut(pane)?;
+        if idx >= tabs.len() {
+            return None;
+        }
+        let tab = tabs.remove(idx);
+        if *active > idx || *active >= tabs.len() {
+            *active = active.saturating_sub(1);
+        }
+        Some(tab)
+    }
+
+    fn insert_tab(&mut self, pane: PaneId, idx: usize, tab: TerminalTab) {
+        if let Some((tabs, active)) = self.pane_tabs_mut(pane) {
+            let idx = idx.min(tabs.len());
+            tabs.insert(idx, tab);
+            *active = idx;
+        }
+    }
+
+    /// Close a tab; a split pane whose last tab goes is closed as well. The
+    /// main terminal keeps its last tab while it is the only pane.
+    fn close_tab(&mut self, pane: PaneId, idx: usize) {
+        let len = self.pane_tabs_mut(pane).map_or(0, |(tabs, _)| tabs.len());
+        if len <= 1 && self.split_layout.is_none() {
+            return;
+        }
+        self.take_tab(pane, idx);
+        self.close_pane_if_empty(pane);
+    }
+
+    fn close_pane_if_empty(&mut self, pane: PaneId) {
+        if self.pane_tabs_mut(pane).is_some_and(|(tabs, _)| tabs.is_empty()) {
+            self.close_pane(pane);
+        }
+    }
+
+    fn apply_tab_action(&mut self, pane: PaneId, action: TabAction) {
+        match action {
+            TabAction::Select(idx) => {
+                if let Some((_, active)) = self.pane_tabs_mut(pane) {
+                    *active = idx;
+                }
+                self.set_focus(pane);
+            }
+            TabAction::Rename(idx) => {
+                if let Some(tab) = self.pane_tabs_mut(pane).and_then(|(tabs, _)| tabs.get(idx)) {
+                    self.terminal_rename_dialog = Some((pane, idx, tab.name.clone()));
+                }
+            }
+            TabAction::Close(idx) => self.close_tab(pane, idx),
+            TabAction::ToggleBroadcast(idx) => {
+                if let Some(tab) = self.pane_tabs_mut(pane).and_then(|(tabs, _)| tabs.get_mut(idx)) {
+                    tab.terminal.view.broadcast = !tab.terminal.view.broadcast;
+                }
+            }
+            TabAction::StartDrag(idx) => self.dragging_terminal_tab = Some((pane, idx)),
+        }
+    }
+
+    /// Drop target under `pos`: a tab, or a pane body. The right and bottom
+    /// quarter of a pane open a new split instead.
+    fn tab_drop_target(&self, pos: egui::Pos2) -> Option<TabDropTarget> {
+        if let Some((pane, idx, _)) = self.tab_rects.iter().find(|(_, _, r)| r.contains(pos)) {
+            return Some(TabDropTarget::Tab(*pane, *idx));
+        }
+        let (pane, rect) = self.pane_rects.iter().find(|(_, r)| r.contains(pos))?;
+        Some(if pos.x > rect.right() - rect.width() * 0.25 {
+            TabDropTarget::NewSplit(*pane, SplitOrientation::Horizontal)
+        } else if pos.y > rect.bottom() - rect.height() * 0.25 {
+            TabDropTarget::NewSplit(*pane, SplitOrientation::Vertical)
+        } else {
+            TabDropTarget::Pane(*pane)
+        })
+    }
+
+    /// Show the drop preview while a tab is dragged and move it on release.
+    fn handle_tab_drag(&mut self, ui: &egui::Ui) {
+        let Some((from, idx)) = self.dragging_terminal_tab else {
+            return;
+        };
+        let (pos, released, down) = ui.input(|i| (i.pointer.hover_pos(), i.pointer.any_released(), i.pointer.any_down()));
+        let target = pos.and_then(|pos| self.tab_drop_target(pos));
+
+        let painter = ui.ctx().layer_painter(egui::LayerId::new(egui::Order::Foreground, egui::Id::new("tab_drop_preview")));
+        let highlight = egui::Color32::from_rgba_unmultiplied(0, 200, 120, 50);
+        let preview = match target {
+            Some(TabDropTarget::Tab(pane, i)) => self.tab_rects.iter().find(|(p, j, _)| *p == pane && *j == i).map(|(_, _, r)| *r),
+            Some(TabDropTarget::Pane(pane)) => self.pane_rects.iter().find(|(p, _)| *p == pane).map(|(_, r)| *r),
+            Some(TabDropTarget::NewSplit(pane, orientation)) => self.pane_rects.iter().find(|(p, _)| *p == pane).map(|(_, r)| {
+                match orientation {
+                    SplitOrientation::Horizontal => egui::Rect::from_min_max(egui::pos2(r.center().x, r.top()), r.max),
+                    SplitOrientation::Vertical => egui::Rect::from_min_max(egui::pos2(r.left(), r.center().y), r.max),
+                }
+            }),
+            None => None,
+        };
+        if let Some(rect) = preview {
+            painter.rect_filled(rect, 4.0, highlight);
+        }
+        if let (Some(pos), Some(tab)) = (pos, self.pane_tabs_mut(from).and_then(|(tabs, _)| tabs.get(idx))) {
+            painter.text(pos + egui::vec2(12.0, 12.0), egui::Align2::LEFT_TOP, &tab.name,
+                egui::TextStyle::Body.resolve(ui.style()), ui.visuals().strong_text_color());
+        }
+        ui.ctx().request_repaint();
+
+        if released || !down {
+            self.dragging_terminal_tab = None;
+            if let Some(target) = target {
+                self.drop_tab(from, idx, target);
+            }
+        }
+    }
+
+    fn drop_tab(&mut self, from: PaneId, idx: usize, target: TabDropTarget) {
+        let source_len = self.pane_tabs_mut(from).map_or(0, |(tabs, _)| tabs.len());
+        match target {
+            TabDropTarget::Tab(pane, to) if pane == from => {
+                // Reorder within the strip
+                if to != idx {
+                    if let Some(tab) = self.take_tab(from, idx) {
+                        self.insert_tab(from, to, tab);
+                    }
+                }
+            }
+            TabDropTarget::Pane(pane) if pane == from => {}
+            TabDropTarget::NewSplit(pane, _) if pane == from && source_len <= 1 => {}
+            TabDropTarget::Tab(pane, to) => {
+                if let Some(tab) = self.take_tab(from, idx) {
+                    self.insert_tab(pane, to, tab);
+                    self.set_focus(pane);
+                    self.close_pane_if_empty(from);
+                }
+            }
+            TabDropTarget::Pane(pane) => {
+                if let Some(tab) = self.take_tab(from, idx) {
+                    self.insert_tab(pane, usize::MAX, tab);
+                    self.set_focus(pane);
+                    self.close_pane_if_empty(from);
+                }
+            }
+            TabDropTarget::NewSplit(pane, orientation) => {
+                if let Some(tab) = self.take_tab(from, idx) {
+                    self.add_pane(pane, orientation, tab);
+                    self.close_pane_if_empty(from);
+                }
+            }
+        }
+    }
+
+    fn all_terminals_mut(&mut self) -> Vec<&mut TerminalView> {
+        self.terminals
+            .iter_mut()
+            .map(|t| &mut t.terminal)
+            .chain(self.split_panes.iter_mut().flat_map(|p| p.terminals.iter_mut().map(|t| &mut t.terminal)))
+            .collect()
+    }
+
+    /// Save the commands the terminals ran, while recording is on, and
+    /// offer them as suggestions.
+    fn collect_history(&mut self) {
+        let entries: Vec<HistoryEntry> = self
+            .all_terminals_mut()
+            .into_iter()
+            .flat_map(|t| std::mem::take(&mut t.history_out))
+            .collect();
+        for entry in entries {
+            // Suggestions learn from this session's commands either way
+            if !entry.command.starts_with(' ') {
+                Rc::make_mut(&mut self.completion).add_history(entry.host.as_deref(), &entry.command);
+            }
+            if self.record_history {
+                self.history.record(entry);
+            }
+        }
+    }
+
+    /// Mirror input typed into a broadcasting terminal to the rest of the group.
+    fn flush_broadcast(&mut self) {
+        let mut terminals = self.all_terminals_mut();
+        let outgoing: Vec<(usize, Vec<u8>)> = terminals
+            .iter_mut()
+            .enumerate()
+            .flat_map(|(idx, t)| std::mem::take(&mut t.view.broadcast_out).into_iter().map(move |b| (idx, b)))
+            .collect();
+        for (origin, bytes) in outgoing {
+            for (idx, t) in terminals.iter_mut().enumerate() {
+                if idx != origin && t.view.broadcast {
+                    t.view.write_raw(&bytes);
+                }
+            }
+        }
+    }
+
+    fn create_split(&mut self, orientation: SplitOrientation) {
+        let name = format!("Split {}", self.split_panes.len() + 1);
+        if let Ok(term) = self.local_terminal(&name, self.default_profile(), None, None) {
+            // Split whichever pane currently has focus
+            let tab = self.styled_tab(&name, term);
+            self.add_pane(self.focused_pane(), orientation, tab);
+        }
+    }
+
+    /// Put `tab` into a new pane next to `target` and focus it.
+    fn add_pane(&mut self, target: PaneId, orientation: SplitOrientation, tab: TerminalTab) {
+        self.split_panes.push(SplitPane { terminals: vec![tab], active_tab: 0 });
+        let new = PaneId::Split(self.split_panes.len() - 1);
+        self.split_layout
+            .get_or_insert(SplitNode::Leaf(PaneId::Main))
+            .split(target, orientation, new);
+        self.zoomed_pane = None;
+        self.set_focus(new);
+    }
+
+    fn focused_pane(&self) -> PaneId {
+        if self.main_focused || self.split_panes.is_empty() {
+            PaneId::Main
+        } else {
+            PaneId::Split(self.active_pane)
+        }
+    }
+
+    fn set_focus(&mut self, pane: PaneId) {
+        match pane {
+            PaneId::Main => self.main_focused = true,
+            PaneId::Split(idx) => {
+                self.active_pane = idx;
+                self.main_focused = false;
+            }
+        }
+    }
+
+    /// Close a pane with all of its tabs and collapse its container. When the
+    /// main pane goes, the first remaining pane takes over its tab list.
+    fn close_pane(&mut self, pane: PaneId) {
+        let Some(layout) = &mut self.split_layout else {
+            return;
+        };
+        let removed = match pane {
+            PaneId::Split(idx) if idx < self.split_panes.len() => {
+                self.split_panes.remove(idx);
+                layout.remove(pane);
+                idx
+            }
+            PaneId::Split(_) => return,
+            PaneId::Main => {
+                layout.remove(PaneId::Main);
+                let mut leaves = Vec::new();
+                layout.leaves(&mut leaves);
+                let Some(PaneId::Split(idx)) = leaves.first().copied() else {
+                    return;
+                };
+                self.terminals = self.split_panes.remove(idx).terminals;
+                self.active_terminal_tab = 0;
+                layout.map_leaves(&mut |p| if p == PaneId::Split(idx) { PaneId::Main } else { p });
+                idx
+            }
+        };
+        layout.renumber_after(removed);
+        self.zoomed_pane = None;
+        if self.split_panes.is_empty() {
+            self.split_layout = None;
+            self.active_pane = 0;
+            self.main_focused = true;
+        } else if pane == PaneId::Main || self.active_pane == removed {
+            self.active_pane = removed.min(self.split_panes.len() - 1);
+            self.main_focused = true;
+        } else if self.active_pane > removed {
+            self.active_pane -= 1;
+        }
+    }
+
+    /// Exchange the position of `pane` with the next pane in layout order.
+    fn swap_pane(&mut self, pane: PaneId) {
+        let Some(layout) = &mut self.split_layout else {
+            return;
+        };
+        let mut leaves = Vec::new();
+        layout.leaves(&mut leaves);
+        let Some(pos) = leaves.iter().position(|p| *p == pane) else {
+            return;
+        };
+        let other = leaves[(pos + 1) % leaves.len()];
+        layout.map_leaves(&mut |p| {
+            if p == pane {
+                other
+            } else if p == other {
+                pane
+            } else {
+                p
+            }
+        });
+    }
+
+    /// Move the tab shown in `pane` into a new pane beside it.
+    fn move_tab_to_new_pane(&mut self, pane: PaneId) {
+        let Some((tabs, active)) = self.pane_tabs_mut(pane) else {
+            return;
+        };
+        if tabs.len() < 2 {
+            return;
+        }
+        let idx = *active;
+        if let Some(tab) = self.take_tab(pane, idx) {
+            self.add_pane(pane, SplitOrientation::Horizontal, tab);
+        }
+    }
+
+    fn pane_tabs(&self, pane: PaneId) -> Option<(&[TerminalTab], usize)> {
+        match pane {
+            PaneId::Main => Some((&self.terminals, self.active_terminal_tab)),
+            PaneId::Split(idx) => self.split_panes.get(idx).map(|p| (p.terminals.as_slice(), p.active_tab)),
+        }
+    }
+
+    /// Snapshot of the current tabs and splits as a named layout.
+    fn capture_layout(&self, name: &str) -> Layout {
+        Layout::new(name.trim(), self.capture_terminals(false))
+    }
+
+    /// Layout tree of all panes. For a `session` it also keeps scrollback
+    /// and the server sessions behind the tabs.
+    fn capture_terminals(&self, session: bool) -> LayoutNode {
+        match &self.split_layout {
+            Some(node) => self.capture_node(node, session),
+            None => self.capture_pane(PaneId::Main, session),
+        }
+    }
+
+    fn capture_node(&self, node: &SplitNode, session: bool) -> LayoutNode {
+        match node {
+            SplitNode::Leaf(pane) => self.capture_pane(*pane, session),
+            SplitNode::Container { orientation, size, first, second } => LayoutNode::Split {
+                orientation: match orientation {
+                    SplitOrientation::Horizontal => LayoutOrientation::Horizontal,
+                    SplitOrientation::Vertical => LayoutOrientation::Vertical,
+                },
+                size: *size,
+                first: Box::new(self.capture_node(first, session)),
+                second: Box::new(self.capture_node(second, session)),
+            },
+        }
+    }
+
+    fn capture_pane(&self, pane: PaneId, session: bool) -> LayoutNode {
+        let scrollback = if session { self.session_scrollback_lines } else { 0 };
+        let (tabs, active) = self.pane_tabs(pane).unwrap_or((&[], 0));
+        let tabs = tabs
+            .iter()
+            .map(|tab| {
+                let term = &tab.terminal;
+                LayoutTab {
+                    title: tab.name.clone(),
+                    command: term.command.clone(),
+                    cwd: if term.ssh.is_some() { None } else { term.current_cwd().map(|d| layout::shorten_home(&d)) },
+                    ssh: term.ssh.as_ref().map(|c| LayoutSsh {
+                        name: c.name.clone(),
+                        host: c.host.clone(),
+                        port: c.port,
+                        username: c.username.clone(),
+                        identity_file: c.identity_file.clone(),
+                    }),
+                    scrollback: Some(term.scrollback_tail(scrollback)).filter(|s| scrollback > 0 && !s.is_empty()),
+                    server: if session { term.server_id() } else { None },
+                    profile: term.profile.as_ref().map(|p| p.name.clone()),
+                }
+            })
+            .collect();
+        LayoutNode::Pane { active, tabs }
+    }
+
+    /// Replace all terminals and splits with `layout`. The first pane in
+    /// layout order becomes the main terminal.
+    fn apply_layout(&mut self, layout: &Layout) {
+        self.apply_layout_node(&layout.root, false);
+    }
+
+    /// `restore`: session restore, where SSH tabs come back disconnected.
+    fn apply_layout_node(&mut self, node: &LayoutNode, restore: bool) {
+        let mut panes = Vec::new();
+        let mut root = self.build_layout_node(node, restore, &mut panes);
+        root.map_leaves(&mut |p| match p {
+            PaneId::Split(0) => PaneId::Main,
+            PaneId::Split(idx) => PaneId::Split(idx - 1),
+            PaneId::Main => PaneId::Main,
+        });
+        let mut panes = panes.into_iter();
+        let (terminals, active) = panes.next().unwrap_or_default();
+        self.terminals = terminals;
+        self.active_terminal_tab = active;
+        self.split_panes = panes.map(|(terminals, active_tab)| SplitPane { terminals, active_tab }).collect();
+        self.split_layout = if self.split_panes.is_empty() { None } else { Some(root) };
+        self.zoomed_pane = None;
+        self.active_pane = 0;
+        self.main_focused = true;
+        self.selected = 0;
+    }
+
+    /// Open the tabs of `node`; leaves refer to `panes` as `PaneId::Split(index)`.
+    fn build_layout_node(&mut self, node: &LayoutNode, restore: bool, panes: &mut Vec<(Vec<TerminalTab>, usize)>) -> SplitNode {
+        match node {
+            LayoutNode::Pane { active, tabs } => {
+                let mut opened: Vec<TerminalTab> = tabs.iter().filter_map(|tab| self.open_layout_tab(tab, restore)).collect();
+                if opened.is_empty() {
+                    opened.extend(self.open_layout_tab(&LayoutTab { title: "Terminal 1".into(), ..Default::default() }, false));
+                }
+                let active = (*active).min(opened.len().saturating_sub(1));
+                panes.push((opened, active));
+                SplitNode::Leaf(PaneId::Split(panes.len() - 1))
+            }
+            LayoutNode::Split { orientation, size, first, second } => SplitNode::Container {
+                orientation: match orientation {
+                    LayoutOrientation::Horizontal => SplitOrientation::Horizontal,
+                    LayoutOrientation::Vertical => SplitOrientation::Vertical,
+                },
+                size: size.clamp(0.1, 0.9),
+                first: Box::new(self.build_layout_node(first, restore, panes)),
+                second: Box::new(self.build_layout_node(second, restore, panes)),
+            },
+        }
+    }
+
+    /// Start one layout tab. SSH tabs connect with the stored credentials;
+    /// without them the system `ssh` client is started in a local shell so
+    /// it can ask interactively.
+    fn open_layout_tab(&mut self, tab: &LayoutTab, restore: bool) -> Option<TerminalTab> {
+        let mut term = match &tab.ssh {
+            Some(ssh) => {
+                let mut conn = SshConnection {
+                    name: ssh.name.clone(),
+                    host: ssh.host.clone(),
+                    port: ssh.port,
+                    username: ssh.username.clone(),
+                    identity_file: ssh.identity_file.clone(),
+                    password: String::new(),
+                    group: String::new(),
+                    tags: Vec::new(),
+                    favorite: false,
+                    color: None,
+                    profile: tab.profile.clone(),
+                };
+                let profile = self.ssh_profile(&conn);
+                if restore {
+                    let mut term = TerminalView::disconnected(&conn, self.scrollback_lines).ok()?;
+                    term.profile = profile.cloned();
+                    term
+                } else {
+                    conn.password = self.credentials.lookup(&conn.credential_key()).unwrap_or_default();
+                    match TerminalView::new_ssh(&conn, profile, self.scrollback_lines) {
+                        Ok(term) => term,
+                        Err(_) => {
+                            let mut command = format!("ssh -p {} {}@{}", conn.port, conn.username, conn.host);
+                            if let Some(key) = &conn.identity_file {
+                                command.push_str(&format!(" -i '{}'", key));
+                            }
+                            let mut term = TerminalView::spawn(self.scrollback_lines, None, &self.window_env(), Some(&command), None).ok()?;
+                            term.command = None;
+                            term.ssh = Some(SshConnection { password: String::new(), ..conn });
+                            term
+                        }
+                    }
+                }
+            }
+            None => {
+                let profile = tab.profile.as_deref().and_then(|name| self.profile(name));
+                #[cfg(unix)]
+                let attached = tab
+                    .server
+                    .filter(|_| restore)
+                    .and_then(|id| server::client::Attachment::attach(id, 25, 80).ok())
+                    .and_then(|a| TerminalView::attach_server(a, self.scrollback_lines).ok());
+                #[cfg(not(unix))]
+                let attached: Option<TerminalView> = None;
+                match attached {
+                    // The server replays the output itself
+                    Some(mut term) => {
+                        term.profile = profile.cloned();
+                        return Some(self.styled_tab(&tab.title, term));
+                    }
+                    None => {
+                        let cwd = tab.cwd.as_deref().map(layout::expand_home);
+                        self.local_terminal(&tab.title, profile, tab.command.as_deref(), cwd.as_deref()).ok()?
+                    }
+                }
+            }
+        };
+        if let Some(text) = &tab.scrollback {
+            term.restore_scrollback(text);
+        }
+        Some(self.styled_tab(&tab.title, term))
+    }
+
+    fn styled_tab(&self, name: &str, mut term: TerminalView) -> TerminalTab {
+        term.set_style(self.terminal_style());
+        TerminalTab { name: name.to_string(), terminal: term }
+    }
+
+    /// Write (or, when disabled, remove) the session file.
+    fn save_session(&self) {
+        if !self.restore_session {
+            session::clear_session();
+            return;
+        }
+        let mut session = Session::new(self.capture_terminals(true));
+        session.selected_view = self.selected;
+        session.focused_pane = self.leaf_order().iter().position(|p| *p == self.focused_pane()).unwrap_or(0);
+        session.active_markdown = self.active_markdown_tab;
+        session.markdown = self
+            .markdown_editors
+            .iter()
+            .map(|tab| {
+                let editor = &tab.editor;
+                let on_disk = editor.file_path.as_ref().and_then(|p| std::fs::read_to_string(p).ok());
+                SessionMarkdown {
+                    name: tab.name.clone(),
+                    path: editor.file_path.as_ref().map(|p| p.display().to_string()),
+                    content: (on_disk.as_deref() != Some(editor.content.as_str())).then(|| editor.content.clone()),
+                }
+            })
+            .collect();
+        if let Err(e) = session::save_session(&session) {
+            eprintln!("{}: {}", rust_i18n::t!("session.save_failed"), e);
+        }
+    }
+
+    fn apply_session(&mut self, session: &Session) {
+        self.apply_layout_node(&session.terminals, true);
+        if let Some(pane) = self.leaf_order().get(session.focused_pane) {
+            self.set_focus(*pane);
+        }
+        let markdown: Vec<MarkdownTab> = session
+            .markdown
+            .iter()
+            .filter_map(|doc| {
+                let file_path = doc.path.as_ref().map(std::path::PathBuf::from);
+                let content = match (&doc.content, &file_path) {
+                    (Some(content), _) => content.clone(),
+                    (None, Some(path)) => std::fs::read_to_string(path).ok()?,
+                    (None, None) => String::new(),
+                };
+                Some(MarkdownTab { name: doc.name.clone(), editor: MarkdownEditor { content, file_path } })
+            })
+            .collect();
+        if !markdown.is_empty() {
+            self.markdown_editors = markdown;
+            self.active_markdown_tab = session.active_markdown.min(self.markdown_editors.len() - 1);
+        }
+        // Never come back to the exit view
+        self.selected = if session.selected_view < 5 { session.selected_view } else { 0 };
+    }
+
+    fn leaf_order(&self) -> Vec<PaneId> {
+        let mut leaves = Vec::new();
+        match &self.split_layout {
+            Some(layout) => layout.leaves(&mut leaves),
+            None => leaves.push(PaneId::Main),
+        }
+        leaves
+    }
+
+    /// Reconnect restored SSH tabs whose button was pressed, keeping their
+    /// scrollback. Uses the saved credentials.
+    fn handle_reconnects(&mut self) {
+        let scrollback = self.scrollback_lines;
+        let style = self.terminal_style();
+        let (credentials, profiles) = (&self.credentials, &self.profiles);
+        let tabs = self.terminals.iter_mut().chain(self.split_panes.iter_mut().flat_map(|p| p.terminals.iter_mut()));
+        for tab in tabs.filter(|t| t.terminal.reconnect_requested) {
+            tab.terminal.reconnect_requested = false;
+            let Some(mut conn) = tab.terminal.ssh.clone() else {
+                continue;
+            };
+            conn.password = credentials.lookup(&conn.credential_key()).unwrap_or_default();
+            let profile = conn.profile.as_deref().and_then(|name| profile::find(profiles, name));
+            match TerminalView::new_ssh(&conn, profile, scrollback) {
+                Ok(mut term) => {
+                    term.set_style(style);
+                    term.restore_scrollback(&tab.terminal.scrollback_tail(scrollback));
+                    tab.terminal = term;
+                }
+                Err(e) => {
+                    let message = format!("\r\n\x1b[31m❌ {}\x1b[0m\r\n{}\r\n", e, rust_i18n::t!("session.reconnect_hint"));
+                    tab.terminal.view.session.term.process(message.as_bytes());
+                }
+            }
+        }
+    }
+
+    /// Focus the nearest pane in direction `dir`, based on last frame's layout.
+    fn focus_direction(&mut self, dir: egui::Vec2) {
+        let current = self.focused_pane();
+        let Some(from) = self.pane_rects.iter().find(|(p, _)| *p == current).map(|(_, r)| *r) else {
+            return;
+        };
+        let target = self
+            .pane_rects
+            .iter()
+            .filter(|(p, _)| *p != current)
+            .filter_map(|(p, r)| {
+                let offset = r.center() - from.center();
+                let along = offset.x * dir.x + offset.y * dir.y;
+                if along <= 0.0 {
+                    return None;
+                }
+                let across = (offset - dir * along).length();
+                Some((*p, along + 2.0 * across))
+            })
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(p, _)| p);
+        if let Some(pane) = target {
+            self.set_focus(pane);
+        }
+    }
+
+    /// Lay out `node` inside `rect`, recursing into containers. Each pane gets
+    /// its own child `Ui`, so terminals size their PTY to the pane.
+    fn show_split_node(
+        &mut self,
+        ui: &mut egui::Ui,
+        node: &mut SplitNode,
+        rect: egui::Rect,
+        id: egui::Id,
+        focus: &mut Option<PaneId>,
+        action: &mut Option<(PaneId, PaneAction)>,
+    ) {
+        match node {
+            SplitNode::Container { orientation, size, first, second } => {
+                const HANDLE: f32 = 6.0;
+                let horizontal = *orientation == SplitOrientation::Horizontal;
+                let total = (if horizontal { rect.width() } else { rect.height() } - HANDLE).max(0.0);
+                let first_len = (total * *size).round();
+                let (first_rect, handle_rect, second_rect) = if horizontal {
+                    let x = rect.left() + first_len;
+                    (
+                        egui::Rect::from_min_max(rect.min, egui::pos2(x, rect.bottom())),
+                        egui::Rect::from_min_max(egui::pos2(x, rect.top()), egui::pos2(x + HANDLE, rect.bottom())),
+                        egui::Rect::from_min_max(egui::pos2(x + HANDLE, rect.top()), rect.max),
+                    )
+                } else {
+                    let y = rect.top() + first_len;
+                    (
+                        egui::Rect::from_min_max(rect.min, egui::pos2(rect.right(), y)),
+                        egui::Rect::from_min_max(egui::pos2(rect.left(), y), egui::pos2(rect.right(), y + HANDLE)),
+                        egui::Rect::from_min_max(egui::pos2(rect.left(), y + HANDLE), rect.max),
+                    )
+                };
+
+                // Draggable divider
+                let handle = ui.interact(handle_rect, id.with("handle"), egui::Sense::drag());
+                if handle.dragged() && total > 0.0 {
+                    let delta = if horizontal { handle.drag_delta().x } else { handle.drag_delta().y };
+                    *size = (*size + delta / total).clamp(0.1, 0.9);
+                }
+                let hot = handle.hovered() || handle.dragged();
+                if hot {
+                    ui.ctx().set_cursor_icon(if horizontal {
+                        egui::CursorIcon::ResizeHorizontal
+                    } else {
+                        egui::CursorIcon::ResizeVertical
+                    });
+                }
+                let color = if hot {
+                    ui.visuals().widgets.active.bg_stroke.color
+                } else {
+                    ui.visuals().widgets.noninteractive.bg_stroke.color
+                };
+                ui.painter().rect_filled(handle_rect.shrink(1.0), 2.0, color);
+
+                self.show_split_node(ui, first, first_rect, id.with(0), focus, action);
+                self.show_split_node(ui, second, second_rect, id.with(1), focus, action);
+            }
+            SplitNode::Leaf(pane) => {
+                let pane = *pane;
+                let active = match pane {
+                    PaneId::Main => self.main_focused,
+                    PaneId::Split(idx) => !self.main_focused && idx == self.active_pane,
+                };
+                let marker = if active { "●" } else { "○" };
+                let zoomed = self.zoomed_pane == Some(pane);
+                self.pane_rects.push((pane, rect));
+                let style = self.terminal_style();
+                let typing = active && !self.overlay_open(); // the palette takes the keys while open
+                let mut child = ui.child_ui_with_id_source(rect, egui::Layout::top_down(egui::Align::Min), id);
+                child.set_clip_rect(rect.intersect(ui.clip_rect()));
+                let mut tab_rects = Vec::new();
+                let keymap = self.keymap.clone(); // the tabs borrow `self`
+                let completion = Rc::clone(&self.completion);
+                let Some((tabs, active_tab)) = self.pane_tabs_mut(pane) else {
+                    return;
+                };
+                let can_move_tab = tabs.len() > 1;
+                child.horizontal_wrapped(|ui| {
+                    ui.label(marker);
+                    if let Some(tab_action) = tab_strip(ui, pane, tabs, *active_tab, true, &keymap, &mut tab_rects) {
+                        *action = Some((pane, PaneAction::Tab(tab_action)));
+                    }
+                    ui.separator();
+                    let zoom_icon = if zoomed { "🗗" } else { "⛶" };
+                    if ui.small_button(zoom_icon).on_hover_text(rust_i18n::t!("terminal.pane_zoom", keys = keymap.label(Action::ZoomPane))).clicked() {
+                        *action = Some((pane, PaneAction::Zoom));
+                    }
+                    if !zoomed && ui.small_button("⇄").on_hover_text(rust_i18n::t!("terminal.pane_swap")).clicked() {
+                        *action = Some((pane, PaneAction::Swap));
+                    }
+                    if can_move_tab && ui.small_button("⧉").on_hover_text(rust_i18n::t!("terminal.pane_move_tab")).clicked() {
+                        *action = Some((pane, PaneAction::MoveTab));
+                    }
+                    if ui.small_button("×").on_hover_text(rust_i18n::t!("terminal.pane_close")).clicked() {
+                        *action = Some((pane, PaneAction::Close));
+                    }
+                });
+                let Some(term) = tabs.get_mut(*active_tab).map(|t| &mut t.terminal) else {
+                    child.colored_label(egui::Color32::RED, rust_i18n::t!("terminal.no_terminal").as_ref());
+                    return;
+                };
+                term.set_style(style);
+                if term.ui_with_activity(&mut child, typing, &completion) {
+                    *focus = Some(pane);
+                }
+                self.tab_rects.extend(tab_rects);
+            }
+        }
+    }
+
+    /// Open an SSH tab for `conn`; on success optionally remember its secret.
+    fn connect_ssh(&mut self, conn: SshConnection, remember: bool) {
+        match TerminalView::new_ssh(&conn, self.ssh_profile(&conn), self.scrollback_lines) {
+            Ok(mut term) => {
+                term.set_style(self.terminal_style());
+                self.terminals.push(TerminalTab {
+                    name: self.ssh_tab_title.take().unwrap_or_else(|| format!("SSH: {}", conn.name)),
+                    terminal: term,
+                });
+                self.active_terminal_tab = self.terminals.len() - 1;
+                self.selected = 0; // Switch to Terminal view
+                self.ssh_manager.status_message = format!("✓ {}", rust_i18n::t!("ssh.connected", name = conn.name));
+                if remember && !conn.password.is_empty() {
+                    match self.credentials.store(&conn.credential_key(), &conn.password) {
+                        Ok(StoreLocation::Vault) => {
+                            self.ssh_manager.status_message.push_str(&format!(" • {}", rust_i18n::t!("vault.saved_in_vault")));
+                        }
+                        Ok(StoreLocation::SecretService) => {
+                            self.ssh_manager.status_message.push_str(&format!(" • {}", rust_i18n::t!("vault.saved_in_secret_service")));
+                        }
+                        Err(e) => {
+                            self.ssh_manager.status_message.push_str(&format!(" • {}: {}", rust_i18n::t!("vault.save_failed"), e));
+                        }
+                    }
+                }
+            }
+            Err(e) => {
+                // Show error in SSH manager status
+                self.ssh_manager.status_message = format!("❌ {}: {}", rust_i18n::t!("common.error"), e);
+                // Encrypted key without stored passphrase: ask for it
+                if conn.identity_file.is_some() && conn.password.is_empty() {
+                    self.ssh_remember_password = false;
+                    self.ssh_password_prompt = Some((conn, String::new()));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(feature = "gui")]
+impl App for GuiApp {
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        self.save_session();
+        // Server shells outlive the window
+        #[cfg(unix)]
+        for term in self.all_terminals_mut() {
+            term.detach_server();
+        }
+    }
+
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut Frame) {
+        // Handle global keyboard shortcuts
+        self.handle_keyboard_shortcuts(ctx);
+        if self.palette.is_open() {
+            let entries = self.palette_entries();
+            if let Some(command) = self.palette.ui(ctx, entries) {
+                self.run_command(command, ctx);
+            }
+        }
+        if self.history.is_searching() {
+            let host = self.focused_host();
+            if let Some(command) = self.history.search_ui(ctx, host) {
+                if let Some(term) = self.focused_terminal_mut() {
+                    term.view.insert(&command);
+                }
+            }
+        }
+        self.snippets.editor_ui(ctx);
+        if let Some(command) = self.snippets.prompt_ui(ctx) {
+            self.selected = 0;
+            self.insert_snippet(&command);
+        }
+        #[cfg(unix)]
+        self.handle_remote_requests();
+
+        // Apply selected theme
+        self.current_theme.apply(ctx);
+        ctx.set_pixels_per_point(self.font_scale);
+
+        // Avoid per-frame font reconfiguration for performance.
+        // Fonts are applied in apply_settings() and when changed via the Settings UI.
+        if let FontMode::Custom(info) = &self.font_mode {
+            // Keep a reference to silence warnings; no per-frame font ops.
+            let _ = info;
+        }
+
+        egui::TopBottomPanel::top("top").show(ctx, |ui| {
+            ui.horizontal_wrapped(|ui| {
+                ui.heading("TermiX");
+                ui.label(egui::RichText::new(" • GUI").color(egui::Color32::LIGHT_BLUE));
+                ui.separator();
+                ui.label(format!("{}:", rust_i18n::t!("common.mode")));
+                ui.label(match self.selected {
+                    0 => rust_i18n::t!("menu.terminal").to_string(),
+                    1 => rust_i18n::t!("menu.ssh").to_string(),
+                    2 => rust_i18n::t!("menu.markdown").to_string(),
+                    3 => rust_i18n::t!("menu.settings").to_string(),
+                    4 => rust_i18n::t!("menu.about").to_string(),
+                    5 => rust_i18n::t!("menu.exit").to_string(),
+                    _ => "?".to_string()
+                });
+                ui.separator();
+                let hint = rust_i18n::t!("palette.open", keys = self.keymap.label(Action::CommandPalette));
+                if ui.selectable_label(self.palette.is_open(), "🔎").on_hover_text(hint).clicked() {
+                    self.palette.toggle();
+                }
+            });
+        });
+
+        let sidebar_width = if self.sidebar_collapsed { 60.0 } else { 220.0 };
+        
+        egui::SidePanel::left("menu")
+            .resizable(false)
+            .exact_width(sidebar_width)  // Use exact_width instead of default_width
+            .show(ctx, |ui| {
+            // Toggle button at top
             ui.horizontal(|ui| {
                 let toggle_icon = if self.sidebar_collapsed { "☰" } else { "◀" };
                 if ui.button(egui::RichText::new(toggle_icon).size(20.0)).clicked() {
@@ -638,7 +2303,7 @@ impl App for GuiApp {
                     self.save_settings();
                 }
                 if !self.sidebar_collapsed {
-                    ui.heading(rust_i18n::t!("sidebar.main_menu").as_ref());
+                    ui.heading(rust_i18n::t!("sidebar.menu").as_ref());
                 }
             });
             ui.separator();
@@ -670,11 +2335,25 @@ impl App for GuiApp {
                 }
                 
                 if self.selected == *idx && !self.sidebar_collapsed {
-                    ui.colored_label(egui::Color32::from_gray(140), "◀ aktiv");
+                    ui.colored_label(egui::Color32::from_gray(140), format!("◀ {}", rust_i18n::t!("common.active")));
                 }
                 
                 ui.separator();
             }
+
+            // Snippets, typed into the focused terminal
+            if self.sidebar_collapsed {
+                let hint = rust_i18n::t!("snippets.open", keys = self.keymap.label(Action::InsertSnippet));
+                if self.snippets.collapsed_ui(ui, &hint) {
+                    self.run_action(Action::InsertSnippet, None, ctx);
+                }
+            } else {
+                let host = self.focused_host();
+                if let Some(command) = self.snippets.sidebar_ui(ui, host.as_deref()) {
+                    self.selected = 0;
+                    self.insert_snippet(&command);
+                }
+            }
             
             // Bottom items (fixed at bottom)
             ui.with_layout(egui::Layout::bottom_up(egui::Align::Center), |ui| {
@@ -703,7 +2382,7 @@ impl App for GuiApp {
                     self.selected = 5;
                 }
                 if self.selected == 5 && !self.sidebar_collapsed {
-                    ui.colored_label(egui::Color32::from_gray(140), rust_i18n::t!("sidebar.active").as_ref());
+                    ui.colored_label(egui::Color32::from_gray(140), rust_i18n::t!("common.active").as_ref());
                 }
                 
                 ui.separator();
@@ -725,205 +2404,266 @@ impl App for GuiApp {
                     self.selected = 4;
                 }
                 if self.selected == 4 && !self.sidebar_collapsed {
-                    ui.colored_label(egui::Color32::from_gray(140), "◀ aktiv");
+                    ui.colored_label(egui::Color32::from_gray(140), format!("◀ {}", rust_i18n::t!("common.active")));
                 }
             });
         });
 
+        // Command history next to the terminals
+        if self.selected == 0 && self.history.panel_open {
+            let cwd = self.focused_terminal_mut().and_then(|t| t.history_cwd());
+            let recording = self.record_history;
+            let picked = egui::SidePanel::right("history")
+                .resizable(true)
+                .default_width(320.0)
+                .show(ctx, |ui| self.history.panel_ui(ui, cwd, recording))
+                .inner;
+            if let (Some(command), Some(term)) = (picked, self.focused_terminal_mut()) {
+                term.view.insert(&command);
+            }
+        }
+
         egui::CentralPanel::default().show(ctx, |ui| {
             ui.spacing_mut().item_spacing = egui::vec2(8.0, 8.0);
             match self.selected {
                 0 => {
                     ui.heading(rust_i18n::t!("menu.terminal").as_ref());
                     
-                    // Tab bar for terminals
+                    // Tab bar for terminals. With splits, every pane shows its
+                    // own strip and the toolbar acts on the focused pane.
+                    self.tab_rects.clear();
+                    self.pane_rects.clear();
                     ui.horizontal(|ui| {
-                        let mut to_close = None;
-                        let mut to_rename = None;
-                        let mut hovered_tab: Option<usize> = None;
-                        let mut pending_reorder: Option<(usize, usize)> = None; // (from, to)
-                        for (idx, tab) in self.terminals.iter().enumerate() {
-                            let selected = idx == self.active_terminal_tab;
-                            ui.group(|ui| {
-                                // Tab label with drag + ctrl-click-to-close
-                                let label = egui::SelectableLabel::new(selected, &tab.name);
-                                let response = ui.add(label).on_hover_text(rust_i18n::t!("terminal.ctrl_click_to_close"));
-                                if response.hovered() {
-                                    hovered_tab = Some(idx);
-                                }
-                                // Select on click (unless ctrl for close)
-                                let ctrl_down = ui.input(|i| i.modifiers.ctrl);
-                                if response.clicked() {
-                                    if ctrl_down {
-                                        to_close = Some(idx);
-                                    } else {
-                                        self.active_terminal_tab = idx;
-                                    }
-                                }
-                                // Start drag
-                                if response.drag_started() {
-                                    self.dragging_terminal_tab = Some(idx);
+                        if self.split_layout.is_none() {
+                            let mut tab_rects = Vec::new();
+                            if let Some(tab_action) = tab_strip(ui, PaneId::Main, &self.terminals, self.active_terminal_tab, false, &self.keymap, &mut tab_rects) {
+                                self.apply_tab_action(PaneId::Main, tab_action);
+                            }
+                            self.tab_rects.extend(tab_rects);
+                        }
+                        if ui.button(format!("➕ {}", rust_i18n::t!("terminal.new")))
+                            .on_hover_text(self.keymap.label(Action::NewTab))
+                            .clicked() {
+                            self.new_terminal_tab();
+                        }
+                        self.profile_menu(ui);
+                        // Tab overview dropdown (focused pane)
+                        let focused = self.focused_pane();
+                        ui.menu_button(format!("{} ▾", rust_i18n::t!("terminal.tabs")), |ui| {
+                            let Some((tabs, active)) = self.pane_tabs_mut(focused) else {
+                                return;
+                            };
+                            for (i, tab) in tabs.iter().enumerate() {
+                                if ui.selectable_label(i == *active, &tab.name).clicked() {
+                                    *active = i;
+                                    ui.close_menu();
                                 }
-                                // Reorder on drop over another tab (defer mutation until after loop)
-                                if response.hovered() {
-                                    if let Some(drag_idx) = self.dragging_terminal_tab {
-                                        if ui.input(|i| i.pointer.any_released()) {
-                                            if drag_idx != idx {
-                                                let insert_at = if drag_idx < idx { idx - 1 } else { idx };
-                                                pending_reorder = Some((drag_idx, insert_at));
-                                            }
-                                            self.dragging_terminal_tab = None;
-                                        }
+                            }
+                            let several = tabs.len() > 1;
+                            ui.separator();
+                            if ui.add_enabled(several, egui::Button::new(rust_i18n::t!("terminal.pane_move_tab"))).clicked() {
+                                self.move_tab_to_new_pane(focused);
+                                ui.close_menu();
+                            }
+                            if ui.button(rust_i18n::t!("terminal.close_all_but_active")).clicked() {
+                                if let Some((tabs, active)) = self.pane_tabs_mut(focused) {
+                                    if *active < tabs.len() {
+                                        let keep_tab = tabs.remove(*active);
+                                        tabs.clear();
+                                        tabs.push(keep_tab);
+                                        *active = 0;
                                     }
                                 }
-                                ui.horizontal(|ui| {
-                                    if ui.small_button("✏").on_hover_text(rust_i18n::t!("common.rename")).clicked() {
-                                        to_rename = Some(idx);
-                                    }
-                                    if self.terminals.len() > 1
-                                        && ui.small_button("×").on_hover_text(rust_i18n::t!("terminal.shortcuts.close")).clicked() {
-                                            to_close = Some(idx);
-                                        }
-                                });
-                            });
-                        }
-                        if let Some((from, to)) = pending_reorder {
-                            if from < self.terminals.len() {
-                                let mut to_idx = to.min(self.terminals.len().saturating_sub(1));
-                                let item = self.terminals.remove(from);
-                                if from < to_idx { to_idx = to_idx.saturating_sub(1); }
-                                self.terminals.insert(to_idx, item);
-                                self.active_terminal_tab = to_idx;
+                                ui.close_menu();
                             }
-                        }
-                        if ui.button(format!("➕ {}", rust_i18n::t!("terminal.new_terminal")))
-                            .on_hover_text(rust_i18n::t!("terminal.shortcuts.new"))
-                            .clicked() {
-                            if let Ok(mut term) = TerminalView::new(self.scrollback_lines) {
-                                term.text_color = self.terminal_text_color;
-                                term.cursor_color = self.cursor_color;
-                                term.cursor_shape = self.cursor_shape;
-                                term.cursor_blinking = self.cursor_blinking;
-                                self.terminals.push(TerminalTab {
-                                    name: format!("Terminal {}", self.terminals.len() + 1),
-                                    terminal: term,
-                                });
-                                self.active_terminal_tab = self.terminals.len() - 1;
+                        });
+                        
+                        // Named layouts
+                        ui.menu_button(format!("🗂 {} ▾", rust_i18n::t!("layouts.title")), |ui| {
+                            let names = layout::list_layouts();
+                            if names.is_empty() {
+                                ui.colored_label(egui::Color32::GRAY, rust_i18n::t!("layouts.none").as_ref());
                             }
-                        }
-                        // Tab overview dropdown
-                        ui.menu_button("Tabs ▾", |ui| {
-                            for (i, tab) in self.terminals.iter().enumerate() {
-                                let selected = i == self.active_terminal_tab;
-                                if ui.selectable_label(selected, &tab.name).clicked() {
-                                    self.active_terminal_tab = i;
-                                    ui.close_menu();
-                                }
+                            for name in names {
+                                ui.horizontal(|ui| {
+                                    if ui.button(&name).on_hover_text(rust_i18n::t!("layouts.open")).clicked() {
+                                        match layout::load_layout(&name) {
+                                            Ok(layout) => {
+                                                self.apply_layout(&layout);
+                                                self.layout_status.clear();
+                                            }
+                                            Err(e) => self.layout_status = format!("❌ {}", e),
+                                        }
+                                        ui.close_menu();
+                                    }
+                                    if ui.small_button("🗑").on_hover_text(rust_i18n::t!("common.delete")).clicked() {
+                                        if let Err(e) = layout::delete_layout(&name) {
+                                            self.layout_status = format!("❌ {}", e);
+                                        }
+                                    }
+                                });
                             }
                             ui.separator();
-                            if ui.button(rust_i18n::t!("terminal.close_all_except_current")).clicked() {
-                                let keep = self.active_terminal_tab;
-                                if keep < self.terminals.len() {
-                                    let keep_tab = self.terminals.remove(keep);
-                                    self.terminals.clear();
-                                    self.terminals.push(keep_tab);
-                                    self.active_terminal_tab = 0;
+                            ui.horizontal(|ui| {
+                                ui.add(egui::TextEdit::singleline(&mut self.layout_name_input)
+                                    .hint_text(rust_i18n::t!("layouts.name_hint"))
+                                    .desired_width(140.0));
+                                if ui.add_enabled(!self.layout_name_input.trim().is_empty(), egui::Button::new(format!("💾 {}", rust_i18n::t!("layouts.save")))).clicked() {
+                                    let layout = self.capture_layout(&self.layout_name_input);
+                                    self.layout_status = match layout::save_layout(&layout) {
+                                        Ok(()) => format!("✓ {}: {}", rust_i18n::t!("layouts.saved"), layout.name),
+                                        Err(e) => format!("❌ {}", e),
+                                    };
                                 }
-                                ui.close_menu();
+                            });
+                            ui.horizontal(|ui| {
+                                if ui.button(format!("📥 {}", rust_i18n::t!("layouts.import"))).clicked() {
+                                    if let Some(path) = rfd::FileDialog::new().add_filter("TOML", &["toml"]).pick_file() {
+                                        self.layout_status = match layout::import_layout(&path).and_then(|l| layout::save_layout(&l).map(|_| l)) {
+                                            Ok(l) => format!("✓ {}: {}", rust_i18n::t!("layouts.saved"), l.name),
+                                            Err(e) => format!("❌ {}", e),
+                                        };
+                                    }
+                                }
+                                if ui.button(format!("📤 {}", rust_i18n::t!("layouts.export"))).clicked() {
+                                    let name = if self.layout_name_input.trim().is_empty() { "layout".to_string() } else { self.layout_name_input.trim().to_string() };
+                                    if let Some(path) = rfd::FileDialog::new().add_filter("TOML", &["toml"]).set_file_name(format!("{}.toml", name)).save_file() {
+                                        let layout = self.capture_layout(&name);
+                                        self.layout_status = match layout::export_layout(&layout, &path) {
+                                            Ok(()) => format!("✓ {}", path.display()),
+                                            Err(e) => format!("❌ {}", e),
+                                        };
+                                    }
+                                }
+                            });
+                            if !self.layout_status.is_empty() {
+                                ui.label(&self.layout_status);
                             }
                         });
-                        
+                        #[cfg(unix)]
+                        self.server_menu(ui);
+
                         // Split buttons
                         if ui.button(format!("⬌ {}", rust_i18n::t!("terminal.split_horizontal")))
-                            .on_hover_text(rust_i18n::t!("terminal.shortcuts.split_h"))
+                            .on_hover_text(self.keymap.label(Action::SplitHorizontal))
                             .clicked() {
                             self.create_split(SplitOrientation::Horizontal);
                         }
                         if ui.button(format!("⬍ {}", rust_i18n::t!("terminal.split_vertical")))
-                            .on_hover_text(rust_i18n::t!("terminal.shortcuts.split_v"))
+                            .on_hover_text(self.keymap.label(Action::SplitVertical))
                             .clicked() {
                             self.create_split(SplitOrientation::Vertical);
                         }
+
+                        // Broadcast group
+                        let members = self.all_terminals_mut().iter().filter(|t| t.view.broadcast).count();
+                        let label = if members > 0 {
+                            egui::RichText::new(format!("📡 {} ({}) ▾", rust_i18n::t!("terminal.broadcast_active"), members)).color(egui::Color32::from_rgb(255, 140, 0))
+                        } else {
+                            egui::RichText::new(format!("📡 {} ▾", rust_i18n::t!("terminal.broadcast_active")))
+                        };
+                        ui.menu_button(label, |ui| {
+                            if ui.button(rust_i18n::t!("terminal.broadcast_all")).clicked() {
+                                for t in self.all_terminals_mut() {
+                                    t.view.broadcast = true;
+                                }
+                                ui.close_menu();
+                            }
+                            if ui.button(rust_i18n::t!("terminal.broadcast_none")).clicked() {
+                                for t in self.all_terminals_mut() {
+                                    t.view.broadcast = false;
+                                }
+                                ui.close_menu();
+                            }
+                        });
                         
-                        ui.label(egui::RichText::new(rust_i18n::t!("terminal.shortcuts_info")).small().color(egui::Color32::GRAY));
-                        
-                        if let Some(idx) = to_rename {
-                            self.terminal_rename_dialog = Some((idx, self.terminals[idx].name.clone()));
+                        let hint = rust_i18n::t!("history.panel_toggle", keys = self.keymap.label(Action::HistorySearch));
+                        if ui.selectable_label(self.history.panel_open, "🕘").on_hover_text(hint).clicked() {
+                            self.history.panel_open = !self.history.panel_open;
                         }
-                        
-                        if let Some(idx) = to_close {
-                            self.terminals.remove(idx);
-                            if self.active_terminal_tab >= self.terminals.len() {
-                                self.active_terminal_tab = self.terminals.len().saturating_sub(1);
+
+                        let info = rust_i18n::t!(
+                            "terminal.shortcuts_info",
+                            close = self.keymap.label(Action::CloseTab),
+                            next = self.keymap.label(Action::NextTab),
+                            pane = self.keymap.label(Action::FocusPane(1)),
+                            left = self.keymap.label(Action::FocusLeft)
+                        );
+                        ui.label(egui::RichText::new(info).small().color(egui::Color32::GRAY));
+                        if self.pass_through_keys {
+                            let label = egui::RichText::new(format!("⌨ {}", rust_i18n::t!("keys.pass_through_active"))).color(egui::Color32::YELLOW);
+                            if ui.selectable_label(true, label).on_hover_text(self.keymap.label(Action::PassThrough)).clicked() {
+                                self.pass_through_keys = false;
+                                self.save_settings();
                             }
                         }
                     });
                     
                     ui.separator();
                     
-                    // Render split panes if any exist
-                    if !self.split_panes.is_empty() {
-                        ui.horizontal(|ui| {
-                            // Show main terminal area
-                            ui.vertical(|ui| {
-                                ui.label("Haupt-Terminal");
-                                if let Some(tab) = self.terminals.get_mut(self.active_terminal_tab) {
-                                    tab.terminal.text_color = self.terminal_text_color;
-                                    tab.terminal.cursor_color = self.cursor_color;
-                                    tab.terminal.cursor_shape = self.cursor_shape;
-                                    tab.terminal.cursor_blinking = self.cursor_blinking;
-                                    // Main terminal is considered active when split view is shown alongside
-                                    tab.terminal.ui_with_activity(ui, /*active*/ true);
-                                }
-                            });
-                            
-                            ui.separator();
-                            
-                            // Show split panes
-                            let panes_len = self.split_panes.len();
-                            for (idx, pane) in self.split_panes.iter_mut().enumerate() {
-                                let is_active = idx == self.active_pane;
-                                ui.vertical(|ui| {
-                                    ui.horizontal(|ui| {
-                                        ui.label(format!("Split {} {}", idx + 1, 
-                                            if is_active { "●" } else { "○" }));
-                                        if ui.small_button("×").clicked() {
-                                            // Mark for removal
-                                        }
-                                    });
-                                    if let Some(term_tab) = pane.terminals.get_mut(0) {
-                                        term_tab.terminal.text_color = self.terminal_text_color;
-                                        term_tab.terminal.cursor_color = self.cursor_color;
-                                        term_tab.terminal.cursor_shape = self.cursor_shape;
-                                        term_tab.terminal.cursor_blinking = self.cursor_blinking;
-                                        term_tab.terminal.ui_with_activity(ui, /*active*/ is_active);
-                                    }
-                                });
-                                if idx < panes_len - 1 {
-                                    ui.separator();
-                                }
+                    // Render the split layout if any panes exist
+                    if let Some(mut layout) = self.split_layout.take() {
+                        let rect = ui.available_rect_before_wrap();
+                        let mut focus = None;
+                        let mut action = None;
+                        // Leaving a zoomed pane (e.g. via Ctrl+1-9) restores the layout
+                        if self.zoomed_pane.is_some_and(|p| p != self.focused_pane()) {
+                            self.zoomed_pane = None;
+                        }
+                        let id = ui.id().with("split_layout");
+                        match self.zoomed_pane {
+                            Some(pane) => {
+                                let mut leaf = SplitNode::Leaf(pane);
+                                self.show_split_node(ui, &mut leaf, rect, id, &mut focus, &mut action);
                             }
-                        });
+                            None => self.show_split_node(ui, &mut layout, rect, id, &mut focus, &mut action),
+                        }
+                        ui.allocate_rect(rect, egui::Sense::hover());
+                        self.split_layout = Some(layout);
+                        if let Some(pane) = focus {
+                            self.set_focus(pane);
+                        }
+                        match action {
+                            Some((pane, PaneAction::Close)) => self.close_pane(pane),
+                            Some((pane, PaneAction::Zoom)) => {
+                                self.zoomed_pane = if self.zoomed_pane == Some(pane) { None } else { Some(pane) };
+                                self.set_focus(pane);
+                            }
+                            Some((pane, PaneAction::Swap)) => self.swap_pane(pane),
+                            Some((pane, PaneAction::MoveTab)) => self.move_tab_to_new_pane(pane),
+                            Some((pane, PaneAction::Tab(tab_action))) => self.apply_tab_action(pane, tab_action),
+                            None => {}
+                        }
                     } else {
                         // Active terminal (no splits)
+                        self.pane_rects.push((PaneId::Main, ui.available_rect_before_wrap()));
+                        let style = self.terminal_style();
+                        let typing = !self.overlay_open();
                         if let Some(tab) = self.terminals.get_mut(self.active_terminal_tab) {
                             // Ensure terminal respects current settings if changed elsewhere
-                            tab.terminal.text_color = self.terminal_text_color;
-                            tab.terminal.cursor_color = self.cursor_color;
-                            tab.terminal.cursor_shape = self.cursor_shape;
-                            tab.terminal.cursor_blinking = self.cursor_blinking;
-                            tab.terminal.ui_with_activity(ui, /*active*/ true);
+                            tab.terminal.set_style(style);
+                            tab.terminal.ui_with_activity(ui, typing, &self.completion);
                         } else {
-                            ui.colored_label(egui::Color32::RED, "Kein Terminal verfügbar.");
+                            ui.colored_label(egui::Color32::RED, rust_i18n::t!("terminal.no_terminal").as_ref());
                         }
                     }
+                    self.handle_tab_drag(ui);
                 }
                 1 => {
                     ui.heading(rust_i18n::t!("menu.ssh").as_ref());
                     // Apply SSH text color only within this panel
                     let old = ui.visuals_mut().override_text_color;
                     ui.visuals_mut().override_text_color = Some(self.ssh_text_color);
-                    self.ssh_manager.ui(ui, &mut self.pending_ssh_connection);
+                    ui.horizontal(|ui| {
+                        ui.selectable_value(&mut self.ssh_multi_exec_view, false, format!("🔗 {}", rust_i18n::t!("multi_exec.connections")));
+                        ui.selectable_value(&mut self.ssh_multi_exec_view, true, format!("⚡ {}", rust_i18n::t!("multi_exec.title")));
+                    });
+                    ui.separator();
+                    if self.ssh_multi_exec_view {
+                        self.multi_exec.ui(ui, &self.ssh_manager.connections, &self.credentials);
+                    } else {
+                        self.ssh_manager.ui(ui, &mut self.pending_ssh_connection, &mut self.credentials, &self.profiles);
+                    }
                     ui.visuals_mut().override_text_color = old;
                 }
                 2 => {
@@ -944,7 +2684,7 @@ impl App for GuiApp {
                                         to_rename = Some(idx);
                                     }
                                     if self.markdown_editors.len() > 1
-                                        && ui.small_button("×").on_hover_text(rust_i18n::t!("terminal.shortcuts.close")).clicked() {
+                                        && ui.small_button("×").on_hover_text(self.keymap.label(Action::CloseTab)).clicked() {
                                             to_close = Some(idx);
                                         }
                                 });
@@ -957,7 +2697,12 @@ impl App for GuiApp {
                             });
                             self.active_markdown_tab = self.markdown_editors.len() - 1;
                         }
-                        ui.label(egui::RichText::new(rust_i18n::t!("markdown.shortcuts_info")).small().color(egui::Color32::GRAY));
+                        let info = rust_i18n::t!(
+                            "markdown.shortcuts_info",
+                            close = self.keymap.label(Action::CloseTab),
+                            next = self.keymap.label(Action::NextTab)
+                        );
+                        ui.label(egui::RichText::new(info).small().color(egui::Color32::GRAY));
                         
                         if let Some(idx) = to_rename {
                             self.markdown_rename_dialog = Some((idx, self.markdown_editors[idx].name.clone()));
@@ -1005,16 +2750,16 @@ impl App for GuiApp {
                     
                     // Theme preview
                     ui.group(|ui| {
-                        ui.label(egui::RichText::new("Theme Vorschau:").strong());
+                        ui.label(egui::RichText::new(rust_i18n::t!("settings.theme_preview")).strong());
                         ui.separator();
                         match self.current_theme {
-                            Theme::Dark => ui.label("Standard dunkles Theme mit neutralen Farben"),
-                            Theme::Light => ui.label("Helles Theme für bessere Lesbarkeit bei Tageslicht"),
-                            Theme::Dracula => ui.label("Beliebtes dunkles Theme mit lila Akzenten"),
-                            Theme::Monokai => ui.label("Klassisches Entwickler-Theme mit warmen Farben"),
-                            Theme::SolarizedDark => ui.label("Wissenschaftlich optimiertes dunkles Theme"),
-                            Theme::Nord => ui.label("Arktisch inspiriertes Theme mit kalten Farbtönen"),
-                            Theme::GruvboxDark => ui.label("Retro-inspiriertes warmes dunkles Theme"),
+                            Theme::Dark => ui.label(rust_i18n::t!("themes.dark").as_ref()),
+                            Theme::Light => ui.label(rust_i18n::t!("themes.light").as_ref()),
+                            Theme::Dracula => ui.label(rust_i18n::t!("themes.dracula").as_ref()),
+                            Theme::Monokai => ui.label(rust_i18n::t!("themes.monokai").as_ref()),
+                            Theme::SolarizedDark => ui.label(rust_i18n::t!("themes.solarized").as_ref()),
+                            Theme::Nord => ui.label(rust_i18n::t!("themes.nord").as_ref()),
+                            Theme::GruvboxDark => ui.label(rust_i18n::t!("themes.gruvbox").as_ref()),
                         };
                     });
                     
@@ -1024,7 +2769,7 @@ impl App for GuiApp {
 
                     // Per-view text colors
                     ui.group(|ui| {
-                        ui.label(egui::RichText::new(rust_i18n::t!("settings.colors_per_area")).strong());
+                        ui.label(egui::RichText::new(rust_i18n::t!("settings.colors_per_view")).strong());
                         ui.add_space(6.0);
                         ui.horizontal(|ui| {
                             ui.label(format!("{}:", rust_i18n::t!("settings.terminal_text_color")));
@@ -1032,8 +2777,9 @@ impl App for GuiApp {
                             if ui.color_edit_button_srgba(&mut c).changed() {
                                 self.terminal_text_color = c;
                                 // Apply to all terminal tabs
+                                let style = self.terminal_style();
                                 for t in &mut self.terminals {
-                                    t.terminal.text_color = c;
+                                    t.terminal.set_style(style);
                                 }
                                 self.save_settings();
                             }
@@ -1113,7 +2859,7 @@ impl App for GuiApp {
 
                     // Global font selection
                     ui.group(|ui| {
-                        ui.label(egui::RichText::new(rust_i18n::t!("settings.font_global")).strong());
+                        ui.label(egui::RichText::new(rust_i18n::t!("settings.font_settings")).strong());
                         ui.add_space(6.0);
                         egui::ComboBox::from_id_source("font_mode")
                             .selected_text(match self.font_mode { 
@@ -1157,7 +2903,7 @@ impl App for GuiApp {
                                 }
                                 if let Some(info) = &self.custom_font_info {
                                     ui.label(format!("{}: {}", rust_i18n::t!("fonts.active"), info));
-                                    if ui.button(rust_i18n::t!("common.reset")).clicked() {
+                                    if ui.button(rust_i18n::t!("fonts.reset")).clicked() {
                                         self.custom_font_info = None;
                                         self.font_mode = FontMode::Default;
                                         self.save_settings();
@@ -1169,14 +2915,20 @@ impl App for GuiApp {
                     
                     // Font scale
                     ui.horizontal(|ui| {
-                        ui.label(egui::RichText::new("📏 Schriftgröße:").strong());
+                        ui.label(egui::RichText::new(format!("📏 {}", rust_i18n::t!("settings.font_scale"))).strong());
                         let mut tmp = self.font_scale;
-                        if ui.add(egui::Slider::new(&mut tmp, 0.75..=2.0).text("Skalierung")).changed() {
+                        if ui.add(egui::Slider::new(&mut tmp, 0.75..=2.0).text(rust_i18n::t!("settings.scale"))).changed() {
                             self.font_scale = tmp;
                             self.save_settings();
                         }
                     });
-                    ui.label(egui::RichText::new("Strg+Plus/Minus: Zoom | Strg+0: Reset").small().color(egui::Color32::GRAY));
+                    let zoom = rust_i18n::t!(
+                        "settings.shortcuts_zoom",
+                        larger = self.keymap.label(Action::FontLarger),
+                        smaller = self.keymap.label(Action::FontSmaller),
+                        reset = self.keymap.label(Action::FontReset)
+                    );
+                    ui.label(egui::RichText::new(zoom).small().color(egui::Color32::GRAY));
                     
                     ui.add_space(15.0);
                     ui.separator();
@@ -1184,23 +2936,74 @@ impl App for GuiApp {
 
                     // Terminal settings
                     ui.group(|ui| {
-                        ui.label(egui::RichText::new("Terminal Einstellungen:").strong());
+                        ui.label(egui::RichText::new(rust_i18n::t!("settings.terminal_settings")).strong());
                         ui.add_space(6.0);
                         ui.horizontal(|ui| {
-                            ui.label("Scrollback-Zeilen:");
+                            ui.label(rust_i18n::t!("settings.scrollback_lines").as_ref());
                             let mut tmp = self.scrollback_lines as i32;
-                            if ui.add(egui::Slider::new(&mut tmp, 100..=10000).text("Zeilen")).changed() {
+                            if ui.add(egui::Slider::new(&mut tmp, 100..=10000).text(rust_i18n::t!("settings.lines"))).changed() {
                                 self.scrollback_lines = tmp as usize;
                                 self.save_settings();
                             }
                         });
-                        ui.label(egui::RichText::new("Anzahl der Zeilen, die im Terminal gespeichert werden").small().color(egui::Color32::GRAY));
+                        ui.label(egui::RichText::new(rust_i18n::t!("settings.scrollback_help")).small().color(egui::Color32::GRAY));
+                        ui.add_space(6.0);
+                        if ui.checkbox(&mut self.restore_session, rust_i18n::t!("session.restore")).changed() {
+                            self.save_settings();
+                        }
+                        ui.add_enabled_ui(self.restore_session, |ui| {
+                            ui.horizontal(|ui| {
+                                ui.label(rust_i18n::t!("session.scrollback").as_ref());
+                                let max = self.scrollback_lines.min(5000);
+                                if ui.add(egui::Slider::new(&mut self.session_scrollback_lines, 0..=max)).changed() {
+                                    self.save_settings();
+                                }
+                            });
+                        });
+                        ui.label(egui::RichText::new(rust_i18n::t!("session.restore_help")).small().color(egui::Color32::GRAY));
+                        #[cfg(unix)]
+                        {
+                            ui.add_space(6.0);
+                            if ui.checkbox(&mut self.use_server, rust_i18n::t!("server.use_server")).changed() {
+                                self.save_settings();
+                            }
+                            ui.label(egui::RichText::new(rust_i18n::t!("server.use_server_help")).small().color(egui::Color32::GRAY));
+                        }
+                        ui.add_space(6.0);
+                        if ui.checkbox(&mut self.record_history, rust_i18n::t!("history.record")).changed() {
+                            self.save_settings();
+                        }
+                        let help = rust_i18n::t!(
+                            "history.record_help",
+                            path = crate::config::history::history_path().display(),
+                            keys = self.keymap.label(Action::HistorySearch)
+                        );
+                        ui.label(egui::RichText::new(help).small().color(egui::Color32::GRAY));
+                        ui.horizontal(|ui| {
+                            let clear = egui::Button::new(rust_i18n::t!("history.clear", count = self.history.len()));
+                            if ui.add_enabled(self.history.len() > 0, clear).clicked() {
+                                self.history.clear();
+                                Rc::make_mut(&mut self.completion).clear_history();
+                            }
+                            if !self.history.status.is_empty() {
+                                ui.label(&self.history.status);
+                            }
+                        });
                     });
                     
                     ui.add_space(15.0);
                     ui.separator();
                     ui.add_space(10.0);
                     
+                    // Keyboard shortcuts
+                    ui.group(|ui| {
+                        self.keybindings_ui(ui);
+                    });
+
+                    ui.add_space(15.0);
+                    ui.separator();
+                    ui.add_space(10.0);
+
                     // Language selection
                     ui.group(|ui| {
                         ui.label(egui::RichText::new(rust_i18n::t!("settings.language")).strong());
@@ -1308,39 +3111,41 @@ impl App for GuiApp {
 
                     // Import/Export
                     ui.group(|ui| {
-                        ui.label(egui::RichText::new("Import/Export:").strong());
+                        ui.label(egui::RichText::new(rust_i18n::t!("settings.import_export")).strong());
                         ui.add_space(6.0);
                         ui.horizontal(|ui| {
-                            if ui.button("📤 Einstellungen exportieren").clicked() {
+                            let path = "termix_settings_export.toml";
+                            if ui.button(format!("📤 {}", rust_i18n::t!("settings.export_settings"))).clicked() {
                                 let settings = self.to_settings();
-                                if let Err(e) = crate::config::export_settings(&settings, std::path::Path::new("termix_settings_export.toml")) {
-                                    eprintln!("Export failed: {}", e);
+                                if let Err(e) = crate::config::export_settings(&settings, std::path::Path::new(path)) {
+                                    eprintln!("{}: {}", rust_i18n::t!("settings.export_failed"), e);
                                 } else {
-                                    println!("Settings exported to termix_settings_export.toml");
+                                    println!("{}", rust_i18n::t!("settings.exported", path = path));
                                 }
                             }
-                            if ui.button("📥 Einstellungen importieren").clicked() {
-                                match crate::config::import_settings(std::path::Path::new("termix_settings_export.toml")) {
+                            if ui.button(format!("📥 {}", rust_i18n::t!("settings.import_settings"))).clicked() {
+                                match crate::config::import_settings(std::path::Path::new(path)) {
                                     Ok(settings) => {
                                         self.apply_settings(&settings, ui.ctx());
-                                        println!("Settings imported successfully");
+                                        println!("{}", rust_i18n::t!("settings.imported", path = path));
                                     }
-                                    Err(e) => eprintln!("Import failed: {}", e),
+                                    Err(e) => eprintln!("{}: {}", rust_i18n::t!("settings.import_failed"), e),
                                 }
                             }
                         });
                         ui.horizontal(|ui| {
-                            if ui.button("📤 SSH-Verbindungen exportieren").clicked() {
-                                if let Err(e) = self.ssh_manager.export(std::path::Path::new("ssh_export.toml")) {
-                                    eprintln!("SSH export failed: {}", e);
+                            let path = "ssh_export.toml";
+                            if ui.button(format!("📤 {}", rust_i18n::t!("settings.export_ssh"))).clicked() {
+                                if let Err(e) = self.ssh_manager.export(std::path::Path::new(path)) {
+                                    eprintln!("{}: {}", rust_i18n::t!("settings.export_failed"), e);
                                 } else {
-                                    println!("SSH connections exported to ssh_export.toml");
+                                    println!("{}", rust_i18n::t!("settings.exported", path = path));
                                 }
                             }
-                            if ui.button("📥 SSH-Verbindungen importieren").clicked() {
-                                match self.ssh_manager.import(std::path::Path::new("ssh_export.toml")) {
-                                    Ok(()) => println!("SSH connections imported successfully"),
-                                    Err(e) => eprintln!("SSH import failed: {}", e),
+                            if ui.button(format!("📥 {}", rust_i18n::t!("settings.import_ssh"))).clicked() {
+                                match self.ssh_manager.import(std::path::Path::new(path)) {
+                                    Ok(()) => println!("{}", rust_i18n::t!("settings.imported", path = path)),
+                                    Err(e) => eprintln!("{}: {}", rust_i18n::t!("settings.import_failed"), e),
                                 }
                             }
                         });
@@ -1350,13 +3155,74 @@ impl App for GuiApp {
                     ui.separator();
                     ui.add_space(10.0);
                     
+                    // Credential vault
+                    ui.group(|ui| {
+                        ui.label(egui::RichText::new(rust_i18n::t!("vault.title")).strong());
+                        ui.add_space(6.0);
+                        if self.credentials.is_unlocked() {
+                            ui.colored_label(egui::Color32::GREEN, rust_i18n::t!("vault.status_unlocked").as_ref());
+                            if ui.button(format!("🔒 {}", rust_i18n::t!("vault.lock"))).clicked() {
+                                self.credentials.lock();
+                                self.vault_status.clear();
+                            }
+                        } else if self.credentials.vault_exists() {
+                            ui.colored_label(egui::Color32::YELLOW, rust_i18n::t!("vault.status_locked").as_ref());
+                            ui.horizontal(|ui| {
+                                ui.label(rust_i18n::t!("vault.master_password").as_ref());
+                                ui.add(egui::TextEdit::singleline(&mut self.vault_master_input).password(true));
+                                if ui.button(format!("🔓 {}", rust_i18n::t!("vault.unlock"))).clicked() {
+                                    match self.credentials.unlock(&self.vault_master_input) {
+                                        Ok(()) => self.vault_status.clear(),
+                                        Err(e) => self.vault_status = e,
+                                    }
+                                    self.vault_master_input.clear();
+                                }
+                            });
+                        } else {
+                            ui.colored_label(egui::Color32::GRAY, rust_i18n::t!("vault.status_none").as_ref());
+                            ui.horizontal(|ui| {
+                                ui.label(rust_i18n::t!("vault.master_password").as_ref());
+                                ui.add(egui::TextEdit::singleline(&mut self.vault_master_input).password(true));
+                            });
+                            ui.horizontal(|ui| {
+                                ui.label(rust_i18n::t!("vault.confirm_password").as_ref());
+                                ui.add(egui::TextEdit::singleline(&mut self.vault_master_confirm).password(true));
+                            });
+                            if ui.button(format!("🔐 {}", rust_i18n::t!("vault.create"))).clicked() {
+                                if self.vault_master_input != self.vault_master_confirm {
+                                    self.vault_status = rust_i18n::t!("vault.mismatch").to_string();
+                                } else {
+                                    match self.credentials.create_vault(&self.vault_master_input) {
+                                        Ok(()) => self.vault_status.clear(),
+                                        Err(e) => self.vault_status = e,
+                                    }
+                                    self.vault_master_input.clear();
+                                    self.vault_master_confirm.clear();
+                                }
+                            }
+                        }
+                        if !self.vault_status.is_empty() {
+                            ui.colored_label(egui::Color32::LIGHT_RED, &self.vault_status);
+                        }
+                        let secret_service = if self.credentials.secret_service_available() {
+                            rust_i18n::t!("vault.secret_service_available")
+                        } else {
+                            rust_i18n::t!("vault.secret_service_unavailable")
+                        };
+                        ui.label(egui::RichText::new(secret_service).small().color(egui::Color32::GRAY));
+                    });
+
+                    ui.add_space(15.0);
+                    ui.separator();
+                    ui.add_space(10.0);
+
                     // System info
                     ui.group(|ui| {
-                        ui.label(egui::RichText::new("System-Information:").strong());
+                        ui.label(egui::RichText::new(rust_i18n::t!("settings.system_info")).strong());
                         ui.separator();
-                        ui.label(format!("🖥️ Plattform: {}", std::env::consts::OS));
-                        ui.label(format!("🦀 Rust Version: {}", rustc_version_runtime::version()));
-                        ui.label("📦 TermiX Version: 0.1.0".to_string());
+                        ui.label(format!("🖥️ {} {}", rust_i18n::t!("settings.platform"), std::env::consts::OS));
+                        ui.label(format!("🦀 {} {}", rust_i18n::t!("settings.rust_version"), rustc_version_runtime::version()));
+                        ui.label(format!("📦 {} {}", rust_i18n::t!("settings.termix_version"), env!("CARGO_PKG_VERSION")));
                     });
                 }
                 4 => {
@@ -1367,19 +3233,19 @@ impl App for GuiApp {
                     ui.add_space(10.0);
                     ui.separator();
                     ui.heading(format!("{}:", rust_i18n::t!("about.features")));
-                    ui.label(format!("✓ {}", rust_i18n::t!("about.feature_multi_tab")));
-                    ui.label(format!("✓ {}", rust_i18n::t!("about.feature_suggestions")));
-                    ui.label(format!("✓ {}", rust_i18n::t!("about.feature_ssh")));
-                    ui.label(format!("✓ {}", rust_i18n::t!("about.feature_cross_platform")));
-                    ui.label(format!("✓ {}", rust_i18n::t!("about.feature_dual_interface")));
+                    ui.label(format!("✓ {}", rust_i18n::t!("about.feature_1")));
+                    ui.label(format!("✓ {}", rust_i18n::t!("about.feature_2")));
+                    ui.label(format!("✓ {}", rust_i18n::t!("about.feature_3")));
+                    ui.label(format!("✓ {}", rust_i18n::t!("about.feature_4")));
+                    ui.label(format!("✓ {}", rust_i18n::t!("about.feature_5")));
                     ui.add_space(10.0);
                     ui.separator();
                     ui.heading(format!("{}:", rust_i18n::t!("about.technology")));
-                    ui.label("• TUI: Crossterm");
-                    ui.label("• GUI: egui/eframe");
-                    ui.label("• Terminal: portable-pty + vt100");
-                    ui.label("• SSH: ssh2");
-                    ui.label("• Markdown: pulldown-cmark");
+                    ui.label(format!("• {}", rust_i18n::t!("about.tech_1")));
+                    ui.label(format!("• {}", rust_i18n::t!("about.tech_2")));
+                    ui.label(format!("• {}", rust_i18n::t!("about.tech_3")));
+                    ui.label(format!("• {}", rust_i18n::t!("about.tech_4")));
+                    ui.label(format!("• {}", rust_i18n::t!("about.tech_5")));
                     ui.add_space(10.0);
                     ui.separator();
                     ui.heading(format!("{}:", rust_i18n::t!("about.support")));
@@ -1387,7 +3253,7 @@ impl App for GuiApp {
                     ui.add_space(5.0);
                     
                     // Buy Me a Coffee styled button
-                    let button_text = egui::RichText::new("☕ Buy me a coffee")
+                    let button_text = egui::RichText::new(format!("☕ {}", rust_i18n::t!("about.buy_coffee")))
                         .color(egui::Color32::BLACK)
                         .size(16.0);
                     
@@ -1396,13 +3262,13 @@ impl App for GuiApp {
                         .stroke(egui::Stroke::new(2.0, egui::Color32::BLACK))
                         .rounding(egui::Rounding::same(8.0));
                     
-                    if ui.add_sized([200.0, 40.0], button).on_hover_text(rust_i18n::t!("about.support_link")).clicked() {
+                    if ui.add_sized([200.0, 40.0], button).on_hover_text(rust_i18n::t!("about.buy_coffee_hint")).clicked() {
                         let _ = open::that("https://buymeacoffee.com/janquillfeldt");
                     }
                 }
                 5 => {
                     ui.heading(rust_i18n::t!("menu.exit").as_ref());
-                    ui.label(rust_i18n::t!("exit.description").as_ref());
+                    ui.label(rust_i18n::t!("exit.message").as_ref());
                     if ui.button(rust_i18n::t!("exit.close_window").as_ref()).clicked() {
                         ui.ctx().send_viewport_cmd(egui::ViewportCommand::Close);
                     }
@@ -1411,9 +3277,14 @@ impl App for GuiApp {
             }
         });
 
+        // Deliver keystrokes typed into broadcasting terminals
+        self.flush_broadcast();
+        self.collect_history();
+        self.handle_reconnects();
+
         // Terminal rename dialog
         let mut close_rename_dialog = false;
-        if let Some((idx, ref mut new_name)) = self.terminal_rename_dialog {
+        if let Some((pane, idx, ref mut new_name)) = self.terminal_rename_dialog {
             egui::Window::new(rust_i18n::t!("dialogs.rename_terminal").as_ref())
                 .collapsible(false)
                 .resizable(false)
@@ -1425,8 +3296,12 @@ impl App for GuiApp {
                     ui.separator();
                     ui.horizontal(|ui| {
                         if ui.button(format!("✓ {}", rust_i18n::t!("common.save"))).clicked() {
-                            if idx < self.terminals.len() {
-                                self.terminals[idx].name = new_name.clone();
+                            let tabs = match pane {
+                                PaneId::Main => Some(&mut self.terminals),
+                                PaneId::Split(i) => self.split_panes.get_mut(i).map(|p| &mut p.terminals),
+                            };
+                            if let Some(tab) = tabs.and_then(|tabs| tabs.get_mut(idx)) {
+                                tab.name = new_name.clone();
                             }
                             close_rename_dialog = true;
                         }
@@ -1470,57 +3345,108 @@ impl App for GuiApp {
         }
 
         // Handle pending SSH connection request
-        if let Some(ssh_conn) = self.pending_ssh_connection.take() {
-            // If no password stored, show prompt dialog
+        if let Some(mut ssh_conn) = self.pending_ssh_connection.take() {
             if ssh_conn.password.is_empty() {
+                if let Some(secret) = self.credentials.lookup(&ssh_conn.credential_key()) {
+                    ssh_conn.password = secret;
+                }
+            }
+            if ssh_conn.password.is_empty()
+                && self.credentials.vault_exists()
+                && !self.credentials.is_unlocked()
+                && !self.vault_unlock_declined
+            {
+                // Saved credential may be in the locked vault: ask once per session
+                self.vault_unlock_prompt = Some((Some(ssh_conn), String::new()));
+            } else if ssh_conn.password.is_empty() && ssh_conn.identity_file.is_none() {
+                // If no password stored, show prompt dialog
+                self.ssh_remember_password = false;
                 self.ssh_password_prompt = Some((ssh_conn, String::new()));
             } else {
-                // Try to create SSH terminal
-                match TerminalView::new_ssh(&ssh_conn, self.scrollback_lines) {
-                    Ok(mut term) => {
-                        term.text_color = self.terminal_text_color;
-                        term.cursor_color = self.cursor_color;
-                        term.cursor_shape = self.cursor_shape;
-                        term.cursor_blinking = self.cursor_blinking;
-                        self.terminals.push(TerminalTab {
-                            name: format!("SSH: {}", ssh_conn.name),
-                            terminal: term,
-                        });
-                        self.active_terminal_tab = self.terminals.len() - 1;
-                        self.selected = 0; // Switch to Terminal view
-                        self.ssh_manager.status_message = format!("✓ Verbunden mit {}", ssh_conn.name);
-                    }
-                    Err(e) => {
-                        // Show error in SSH manager status
-                        self.ssh_manager.status_message = format!("❌ Fehler: {}", e);
-                    }
-                }
+                self.connect_ssh(ssh_conn, false);
             }
         }
 
+        // Vault unlock dialog
+        let mut close_vault_prompt = false;
+        let mut resume_connection = None;
+        if let Some((ref mut conn, ref mut master)) = self.vault_unlock_prompt {
+            egui::Window::new(rust_i18n::t!("vault.unlock_title").as_ref())
+                .collapsible(false)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    ui.label(rust_i18n::t!("vault.unlock_hint").as_ref());
+                    ui.separator();
+                    let mut submitted = false;
+                    ui.horizontal(|ui| {
+                        ui.label(rust_i18n::t!("vault.master_password").as_ref());
+                        let response = ui.add(egui::TextEdit::singleline(master).password(true));
+                        submitted = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                    });
+                    if !self.vault_status.is_empty() {
+                        ui.colored_label(egui::Color32::LIGHT_RED, &self.vault_status);
+                    }
+                    ui.separator();
+                    ui.horizontal(|ui| {
+                        if ui.button(format!("🔓 {}", rust_i18n::t!("vault.unlock"))).clicked() || submitted {
+                            match self.credentials.unlock(master) {
+                                Ok(()) => {
+                                    self.vault_status.clear();
+                                    resume_connection = conn.take();
+                                    close_vault_prompt = true;
+                                }
+                                Err(e) => self.vault_status = e,
+                            }
+                        }
+                        if ui.button(format!("✗ {}", rust_i18n::t!("vault.skip"))).clicked() {
+                            self.vault_unlock_declined = true;
+                            self.vault_status.clear();
+                            resume_connection = conn.take();
+                            close_vault_prompt = true;
+                        }
+                    });
+                });
+        }
+        if close_vault_prompt {
+            self.vault_unlock_prompt = None;
+        }
+        if resume_connection.is_some() {
+            self.pending_ssh_connection = resume_connection;
+        }
+
         // SSH password prompt dialog
         let mut close_password_prompt = false;
         let mut attempt_connection = None;
         if let Some((ref conn, ref mut password)) = self.ssh_password_prompt {
-            egui::Window::new("SSH Passwort erforderlich")
+            let title = if conn.identity_file.is_some() {
+                rust_i18n::t!("ssh.passphrase_required")
+            } else {
+                rust_i18n::t!("ssh.password_required")
+            };
+            egui::Window::new(title.as_ref())
                 .collapsible(false)
                 .resizable(false)
                 .show(ctx, |ui| {
-                    ui.label(format!("Verbindung zu: {}@{}:{}", conn.username, conn.host, conn.port));
+                    ui.label(format!("{} {}@{}:{}", rust_i18n::t!("ssh.connect_to"), conn.username, conn.host, conn.port));
                     ui.separator();
                     ui.horizontal(|ui| {
-                        ui.label("Passwort:");
+                        if conn.identity_file.is_some() {
+                            ui.label(rust_i18n::t!("ssh.passphrase").as_ref());
+                        } else {
+                            ui.label(rust_i18n::t!("ssh.password").as_ref());
+                        }
                         ui.add(egui::TextEdit::singleline(password).password(true));
                     });
+                    ui.checkbox(&mut self.ssh_remember_password, rust_i18n::t!("ssh.remember_password").as_ref());
                     ui.separator();
                     ui.horizontal(|ui| {
-                        if ui.button("✓ Verbinden").clicked() {
+                        if ui.button(format!("✓ {}", rust_i18n::t!("ssh.connect"))).clicked() {
                             let mut conn_with_pwd = conn.clone();
                             conn_with_pwd.password = password.clone();
                             attempt_connection = Some(conn_with_pwd);
                             close_password_prompt = true;
                         }
-                        if ui.button("✗ Abbrechen").clicked() {
+                        if ui.button(format!("✗ {}", rust_i18n::t!("common.cancel"))).clicked() {
                             close_password_prompt = true;
                         }
                     });
@@ -1531,30 +3457,35 @@ impl App for GuiApp {
         }
         if let Some(conn) = attempt_connection {
             // Try SSH connection with provided password
-            match TerminalView::new_ssh(&conn, self.scrollback_lines) {
-                Ok(mut term) => {
-                    term.text_color = self.terminal_text_color;
-                    term.cursor_color = self.cursor_color;
-                    term.cursor_shape = self.cursor_shape;
-                    term.cursor_blinking = self.cursor_blinking;
-                    self.terminals.push(TerminalTab {
-                        name: format!("SSH: {}", conn.name),
-                        terminal: term,
-                    });
-                    self.active_terminal_tab = self.terminals.len() - 1;
-                    self.selected = 0;
-                    self.ssh_manager.status_message = format!("✓ Verbunden mit {}", conn.name);
-                }
-                Err(e) => {
-                    self.ssh_manager.status_message = format!("❌ Fehler: {}", e);
-                }
-            }
+            self.connect_ssh(conn, self.ssh_remember_password);
         }
     }
 }
 
+/// What the window opens with, from the command line. The program given
+/// with `-e` is started right away, so one that cannot start fails the
+/// launch before a window shows up.
+#[cfg(feature = "gui")]
+pub struct Launch {
+    options: Options,
+    program: Option<TerminalView>,
+}
+
+#[cfg(feature = "gui")]
+impl Launch {
+    pub fn new(options: Options) -> anyhow::Result<Self> {
+        let settings = load_settings();
+        let profile = options.profile_config.as_ref().or_else(|| settings.default_profile());
+        let program = match options.command.as_slice() {
+            [] => None,
+            argv => Some(TerminalView::spawn_program(argv, options.cwd.as_deref(), profile, settings.scrollback_lines)?),
+        };
+        Ok(Launch { options, program })
+    }
+}
+
 #[cfg(feature = "gui")]
-pub fn run_gui() -> eframe::Result<()> {
+pub fn run_gui(launch: Launch) -> eframe::Result<()> {
     let options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
             .with_inner_size([900.0, 600.0])
@@ -1565,34 +3496,53 @@ pub fn run_gui() -> eframe::Result<()> {
     eframe::run_native(
         "TermiX",
         options,
-        Box::new(|cc| Box::new(GuiApp::new(cc))),
+        Box::new(|cc| Box::new(GuiApp::new(cc, launch))),
     )
 }
 
 // ===================== Embedded PTY Terminal =====================
+/// Dark blue-black, unless a profile sets another background.
+#[cfg(feature = "gui")]
+const TERMINAL_BACKGROUND: egui::Color32 = egui::Color32::from_rgb(20, 20, 30);
+
+/// Colors and cursor from the settings, before the profile of a terminal
+/// overrides them.
+#[cfg(feature = "gui")]
+#[derive(Clone, Copy)]
+struct TerminalStyle {
+    text_color: egui::Color32,
+    cursor_color: egui::Color32,
+    cursor_shape: CursorShape,
+    cursor_blinking: bool,
+}
+
 #[cfg(feature = "gui")]
 struct TerminalView {
-    rx: Receiver<Vec<u8>>,     // from reader thread
-    writer: Sender<Vec<u8>>,   // to writer thread
-    parser: VtParser,
-    cols: u16,
-    rows: u16,
-    master: Box<dyn MasterPty + Send>,
-    // Command suggestion
-    input_buffer: String,
-    suggestions: Vec<String>,
-    selected_suggestion: usize,
-    show_suggestions: bool,
+    id: u64, // unique per window, names the tab for `termix msg`
+    view: View, // session, input, selection and search; painted here
+    focus_search: bool, // one-shot: focus the search field
     // Appearance
     text_color: egui::Color32,
+    background_color: egui::Color32,
+    font_scale: f32, // of the text grid, from the profile
     cursor_color: egui::Color32,
     cursor_shape: CursorShape,
     cursor_blinking: bool,
     cursor_visible: bool, // for blink state
     last_blink_time: f64,
     last_paint_time: f64,
-    // Cache of plain text lines (including scrollback) for fast virtualized rendering
-    cached_plain_lines: Vec<String>,
+    // Commands for the history: the running one and those waiting to be saved
+    pending_command: Option<HistoryEntry>,
+    history_out: Vec<HistoryEntry>,
+    // How the terminal was started (for layouts)
+    profile: Option<Profile>,
+    command: Option<String>,
+    start_cwd: Option<std::path::PathBuf>,
+    ssh: Option<SshConnection>, // without password
+    disconnected: bool,         // restored SSH tab, not connected yet
+    reconnect_requested: bool,
+    #[cfg(unix)]
+    server: Option<server::client::AttachWriter>, // shell runs on the background server
     // Performance metrics (debug build only)
     #[cfg(debug_assertions)]
     frame_times: std::collections::VecDeque<f64>,
@@ -1600,264 +3550,310 @@ struct TerminalView {
     last_repaint_reason: String,
 }
 
-// Common shell commands for suggestions
+/// Closing a server-backed tab ends its shell, like for a local one; use
+/// `detach_server` to keep it running.
+#[cfg(all(feature = "gui", unix))]
+impl Drop for TerminalView {
+    fn drop(&mut self) {
+        if let Some(server) = self.server.take() {
+            let _ = server.kill();
+            server.detach();
+        }
+    }
+}
+
+/// `profile` (or a plain shell's) with `env` added on top; the profile
+/// itself stays as the tab remembers it.
 #[cfg(feature = "gui")]
-const COMMON_COMMANDS: &[&str] = &[
-    "ls", "ls -la", "ls -lh", "cd", "pwd", "cat", "echo", "mkdir", "rmdir", "rm", "rm -rf",
-    "cp", "mv", "touch", "grep", "find", "chmod", "chown", "ps", "ps aux", "top", "htop",
-    "kill", "killall", "df", "df -h", "du", "du -sh", "free", "free -h", "uname", "uname -a",
-    "whoami", "which", "whereis", "man", "history", "clear", "exit", "sudo", "apt update",
-    "apt install", "apt upgrade", "systemctl status", "systemctl start", "systemctl stop",
-    "journalctl", "wget", "curl", "ssh", "scp", "tar", "tar -xzf", "tar -czf", "zip", "unzip",
-    "git status", "git add", "git commit", "git push", "git pull", "git log", "git clone",
-    "docker ps", "docker run", "docker build", "docker-compose up", "npm install", "npm start",
-    "cargo build", "cargo run", "python", "python3", "node", "vim", "nano", "less", "more",
-];
+fn with_env(profile: Option<&Profile>, env: &[(String, String)]) -> Option<Profile> {
+    if env.is_empty() {
+        return profile.cloned();
+    }
+    let mut profile = profile.cloned().unwrap_or_default();
+    profile.env.extend(env.iter().cloned());
+    Some(profile)
+}
 
 #[cfg(feature = "gui")]
 impl TerminalView {
     fn new(scrollback: usize) -> anyhow::Result<Self> {
+        Self::spawn(scrollback, None, &[], None, None)
+    }
+
+    /// Start the default shell (or the program of `profile`) in `cwd` with
+    /// `env` added and type `command` into it.
+    fn spawn(
+        scrollback: usize,
+        profile: Option<&Profile>,
+        env: &[(String, String)],
+        command: Option<&str>,
+        cwd: Option<&std::path::Path>,
+    ) -> anyhow::Result<Self> {
+        let (initial_rows, initial_cols) = (25u16, 80u16);
+        let pty = match with_env(profile, env).as_ref() {
+            Some(profile) => pty::spawn_profile(profile, &[], initial_rows, initial_cols, cwd)?,
+            None => pty::spawn_shell(initial_rows, initial_cols, cwd)?,
+        };
+
+        let scrollback = profile.and_then(|p| p.scrollback_lines).unwrap_or(scrollback);
+        let mut term = Self::from_pty(pty, initial_rows, initial_cols, scrollback);
+        term.profile = profile.cloned();
+        term.command = command.map(str::to_string);
+        term.start_cwd = cwd.map(std::path::Path::to_path_buf);
+        if let Some(command) = command.filter(|c| !c.trim().is_empty()) {
+            term.view.write_raw(format!("{}\r", command).as_bytes());
+        }
+        Ok(term)
+    }
+
+    /// Run `argv` instead of a shell (`termix -e`), with the environment
+    /// of `profile`.
+    fn spawn_program(argv: &[String], cwd: Option<&std::path::Path>, profile: Option<&Profile>, scrollback: usize) -> anyhow::Result<Self> {
+        let (rows, cols) = (25u16, 80u16);
+        let pty = match profile {
+            Some(profile) => pty::spawn_profile(profile, argv, rows, cols, cwd)?,
+            None => pty::spawn_command(argv, rows, cols, cwd)?,
+        };
+        let scrollback = profile.and_then(|p| p.scrollback_lines).unwrap_or(scrollback);
+        let mut term = Self::from_pty(pty, rows, cols, scrollback);
+        term.profile = profile.cloned();
+        Ok(term)
+    }
+
+    fn new_ssh(conn: &SshConnection, profile: Option<&Profile>, scrollback: usize) -> anyhow::Result<Self> {
+        let (rows, cols) = (24u16, 80u16);
+        let pty = crate::ssh::spawn_shell(conn, profile, rows, cols)?;
+        let scrollback = profile.and_then(|p| p.scrollback_lines).unwrap_or(scrollback);
+        let mut term = Self::from_pty(pty, rows, cols, scrollback);
+        term.ssh = Some(SshConnection { password: String::new(), ..conn.clone() });
+        term.profile = profile.cloned();
+        Ok(term)
+    }
+
+    /// Placeholder for a restored SSH tab: shows its scrollback and offers
+    /// to reconnect; input goes nowhere until then.
+    fn disconnected(conn: &SshConnection, scrollback: usize) -> anyhow::Result<Self> {
+        let (to_writer_tx, _) = mpsc::channel::<Vec<u8>>();
+        let (_, from_reader_rx) = mpsc::channel::<Vec<u8>>();
+        let pair = NativePtySystem::default().openpty(PtySize { rows: 24, cols: 80, pixel_width: 0, pixel_height: 0 })?;
+        let pty = PtyProcess { rx: from_reader_rx, writer: to_writer_tx, master: pair.master, child_pid: None };
+        let mut term = Self::from_pty(pty, 24, 80, scrollback);
+        term.ssh = Some(SshConnection { password: String::new(), ..conn.clone() });
+        term.disconnected = true;
+        Ok(term)
+    }
+
+    /// Terminal showing a session of the background server.
+    #[cfg(unix)]
+    fn attach_server(attachment: server::client::Attachment, scrollback: usize) -> anyhow::Result<Self> {
         let (to_writer_tx, to_writer_rx) = mpsc::channel::<Vec<u8>>();
         let (from_reader_tx, from_reader_rx) = mpsc::channel::<Vec<u8>>();
+        let child_pid = attachment.pid;
+        let (mut reader, writer) = attachment.split()?;
 
-        // Open PTY
-        let pty_system = NativePtySystem::default();
-        let initial_cols = 80u16;
-        let initial_rows = 25u16;
-        let pair = pty_system.openpty(PtySize {
-            rows: initial_rows,
-            cols: initial_cols,
-            pixel_width: 0,
-            pixel_height: 0,
-        })?;
-
-        // Spawn shell
-        #[cfg(target_os = "windows")]
-        let mut cmd = CommandBuilder::new("cmd.exe");
-        #[cfg(not(target_os = "windows"))]
-        let cmd = CommandBuilder::new(std::env::var("SHELL").unwrap_or_else(|_| "/bin/sh".into()));
-
-        let _child = pair.slave.spawn_command(cmd)?;
-
-        let mut writer = pair.master.take_writer()?;
-        let mut reader = pair.master.try_clone_reader()?;
-        let master: Box<dyn MasterPty + Send> = pair.master;
-
-        // Writer thread: send bytes to PTY
-        let writer_thread = thread::spawn(move || {
+        // Writer thread
+        let input = writer.clone();
+        thread::spawn(move || {
             while let Ok(buf) = to_writer_rx.recv() {
-                let _ = writer.write_all(&buf);
-                let _ = writer.flush();
+                if input.input(&buf).is_err() {
+                    break;
+                }
             }
         });
-        // Detach thread
-        let _ = writer_thread.thread().id();
 
-        // Reader thread: read bytes from PTY and forward
+        // Reader thread
         thread::spawn(move || {
-            let mut buf = [0u8; 4096];
-            loop {
-                match reader.read(&mut buf) {
-                    Ok(0) => {
-                        let _ = from_reader_tx.send(b"\n[PTY closed]\n".to_vec());
-                        break;
-                    }
-                    Ok(n) => {
-                        let _ = from_reader_tx.send(buf[..n].to_vec());
-                    }
-                    Err(_) => {
-                        // reduce busy loop on error
-                        thread::sleep(Duration::from_millis(10));
+            let closed: &[u8] = loop {
+                match reader.next_output() {
+                    Ok(Some(data)) => {
+                        let _ = from_reader_tx.send(data);
                     }
+                    Ok(None) => break b"\n[PTY closed]\n",
+                    Err(_) => break b"\n[Server connection closed]\n",
                 }
-            }
+            };
+            let _ = from_reader_tx.send(closed.to_vec());
         });
 
-        Ok(Self {
-            rx: from_reader_rx,
-            writer: to_writer_tx,
-            parser: VtParser::new(initial_rows, initial_cols, scrollback),
-            cols: initial_cols,
-            rows: initial_rows,
-            master,
-            input_buffer: String::new(),
-            suggestions: Vec::new(),
-            selected_suggestion: 0,
-            show_suggestions: false,
+        // Dummy PTY master like for SSH; resizes go to the server
+        let pair = NativePtySystem::default().openpty(PtySize { rows: 25, cols: 80, pixel_width: 0, pixel_height: 0 })?;
+        let pty = PtyProcess { rx: from_reader_rx, writer: to_writer_tx, master: pair.master, child_pid };
+        let mut term = Self::from_pty(pty, 25, 80, scrollback);
+        term.server = Some(writer);
+        Ok(term)
+    }
+
+    /// Apply `style`, then the colors and text size of the profile.
+    fn set_style(&mut self, style: TerminalStyle) {
+        let profile = self.profile.as_ref();
+        let color = |c: Option<Rgba>, fallback| c.map(GuiApp::color_from_rgba).unwrap_or(fallback);
+        self.text_color = color(profile.and_then(|p| p.text_color), style.text_color);
+        self.cursor_color = color(profile.and_then(|p| p.cursor_color), style.cursor_color);
+        self.background_color = color(profile.and_then(|p| p.background_color), TERMINAL_BACKGROUND);
+        self.font_scale = profile.and_then(|p| p.font_scale).unwrap_or(1.0);
+        self.cursor_shape = style.cursor_shape;
+        self.cursor_blinking = style.cursor_blinking;
+    }
+
+    #[cfg(unix)]
+    fn server_id(&self) -> Option<u64> {
+        self.server.as_ref().map(|s| s.id)
+    }
+
+    #[cfg(not(unix))]
+    fn server_id(&self) -> Option<u64> {
+        None
+    }
+
+    /// Let go of the server session without ending its shell.
+    #[cfg(unix)]
+    fn detach_server(&mut self) {
+        if let Some(server) = self.server.take() {
+            server.detach();
+        }
+    }
+
+    fn from_pty(pty: PtyProcess, rows: u16, cols: u16, scrollback: usize) -> Self {
+        static NEXT_ID: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(1);
+        Self {
+            id: NEXT_ID.fetch_add(1, std::sync::atomic::Ordering::Relaxed),
+            view: View::new(crate::term::Session::new(pty, rows, cols, scrollback)),
+            focus_search: false,
             text_color: egui::Color32::from_rgb(220, 220, 220),
+            background_color: TERMINAL_BACKGROUND,
+            font_scale: 1.0,
             cursor_color: egui::Color32::from_rgb(0, 255, 0),
             cursor_shape: CursorShape::Block,
             cursor_blinking: false,
             cursor_visible: true,
             last_blink_time: 0.0,
             last_paint_time: 0.0,
-            cached_plain_lines: Vec::new(),
+            pending_command: None,
+            history_out: Vec::new(),
+            profile: None,
+            command: None,
+            start_cwd: None,
+            ssh: None,
+            disconnected: false,
+            reconnect_requested: false,
+            #[cfg(unix)]
+            server: None,
             #[cfg(debug_assertions)]
             frame_times: std::collections::VecDeque::new(),
             #[cfg(debug_assertions)]
             last_repaint_reason: String::new(),
-        })
+        }
     }
 
-    fn new_ssh(conn: &SshConnection, scrollback: usize) -> anyhow::Result<Self> {
-        use ssh2::Session;
-        use std::net::TcpStream;
-
-        let (to_writer_tx, to_writer_rx) = mpsc::channel::<Vec<u8>>();
-        let (from_reader_tx, from_reader_rx) = mpsc::channel::<Vec<u8>>();
+    /// Feed saved output into the screen, followed by a separator line.
+    fn restore_scrollback(&mut self, text: &str) {
+        self.view.session.term.restore(text, &rust_i18n::t!("session.restored"));
+    }
 
-        // Connect to SSH server
-        let tcp = TcpStream::connect(format!("{}:{}", conn.host, conn.port))
-            .map_err(|e| anyhow::anyhow!("Verbindung zu {}:{} fehlgeschlagen: {}", conn.host, conn.port, e))?;
-        
-        let mut sess = Session::new()?;
-        sess.set_tcp_stream(tcp);
-        sess.handshake()
-            .map_err(|e| anyhow::anyhow!("SSH-Handshake fehlgeschlagen: {}", e))?;
+    /// Last `lines` lines of output, without trailing blank lines.
+    fn scrollback_tail(&self, lines: usize) -> String {
+        self.view.session.term.tail(lines)
+    }
 
-        // Authenticate
-        if conn.password.is_empty() {
-            anyhow::bail!("Kein Passwort angegeben. Bitte Passwort in den Verbindungseinstellungen speichern.");
-        }
-        
-        sess.userauth_password(&conn.username, &conn.password)
-            .map_err(|e| anyhow::anyhow!("Authentifizierung fehlgeschlagen: {}", e))?;
-        
-        if !sess.authenticated() {
-            anyhow::bail!("Authentifizierung fehlgeschlagen: Falsches Passwort oder Benutzer nicht berechtigt");
+    fn resize(&mut self, cols: u16, rows: u16) {
+        let (rows, cols) = (rows.max(5), cols.max(10));
+        #[cfg(unix)]
+        if let Some(server) = &self.server {
+            if self.view.session.term.resize(rows, cols) {
+                let _ = server.resize(rows, cols);
+            }
+            return;
         }
+        self.view.session.resize(rows, cols);
+    }
 
-        // Open channel and request PTY
-        let mut channel = sess.channel_session()
-            .map_err(|e| anyhow::anyhow!("Kanal-Erstellung fehlgeschlagen: {}", e))?;
-        channel.request_pty("xterm", None, Some((80, 24, 0, 0)))
-            .map_err(|e| anyhow::anyhow!("PTY-Anfrage fehlgeschlagen: {}", e))?;
-        channel.shell()
-            .map_err(|e| anyhow::anyhow!("Shell-Start fehlgeschlagen: {}", e))?;
-
-        let initial_cols = 80u16;
-        let initial_rows = 24u16;
+    /// Working directory of the shell, falling back to where it was started.
+    fn current_cwd(&self) -> Option<std::path::PathBuf> {
+        self.view.session.pty.cwd().or_else(|| self.start_cwd.clone())
+    }
 
-        // Split channel for read/write
-        let read_channel = channel.stream(0);
-        let mut write_channel = channel.stream(0);
+    /// Directory to note with a command: what the shell reports (OSC 7), or
+    /// for local shells where it runs.
+    fn history_cwd(&self) -> Option<String> {
+        let cwd = match self.view.session.term.reported_cwd() {
+            Some(cwd) => Some(cwd.to_path_buf()),
+            None if self.ssh.is_none() => self.current_cwd(),
+            None => None,
+        };
+        cwd.map(|cwd| cwd.display().to_string())
+    }
 
-        // Writer thread
-        thread::spawn(move || {
-            while let Ok(buf) = to_writer_rx.recv() {
-                let _ = write_channel.write_all(&buf);
-                let _ = write_channel.flush();
-            }
-        });
+    fn history_entry(&self, command: &str) -> HistoryEntry {
+        HistoryEntry::new(command, self.ssh.as_ref().map(|c| c.name.clone()), self.history_cwd())
+    }
 
-        // Reader thread
-        thread::spawn(move || {
-            let mut buf = [0u8; 4096];
-            let mut read_stream = read_channel;
-            loop {
-                match read_stream.read(&mut buf) {
-                    Ok(0) => {
-                        let _ = from_reader_tx.send(b"\n[SSH connection closed]\n".to_vec());
-                        break;
+    /// Queue the commands the shell reported through its prompt marks.
+    fn take_shell_events(&mut self) {
+        for event in self.view.session.term.take_shell_events() {
+            match event {
+                ShellEvent::Command(command) => {
+                    let entry = self.history_entry(&command);
+                    // A command without `D` (e.g. the shell exited) still counts
+                    if let Some(unfinished) = self.pending_command.replace(entry) {
+                        self.history_out.push(unfinished);
                     }
-                    Ok(n) => {
-                        let _ = from_reader_tx.send(buf[..n].to_vec());
-                    }
-                    Err(_) => {
-                        thread::sleep(Duration::from_millis(10));
+                }
+                ShellEvent::Finished(code) => {
+                    if let Some(mut entry) = self.pending_command.take() {
+                        entry.exit_code = code;
+                        self.history_out.push(entry);
                     }
                 }
+                ShellEvent::Cwd(_) => {}
             }
-        });
-
-        // Create a dummy PTY master that does nothing (SSH channel handles I/O via threads)
-        let pty_system = NativePtySystem::default();
-        let pair = pty_system.openpty(PtySize {
-            rows: initial_rows,
-            cols: initial_cols,
-            pixel_width: 0,
-            pixel_height: 0,
-        })?;
-        let master: Box<dyn MasterPty + Send> = pair.master;
-
-        Ok(Self {
-            rx: from_reader_rx,
-            writer: to_writer_tx,
-            parser: VtParser::new(initial_rows, initial_cols, scrollback),
-            cols: initial_cols,
-            rows: initial_rows,
-            master, // Dummy; resize will be ignored for SSH
-            input_buffer: String::new(),
-            suggestions: Vec::new(),
-            selected_suggestion: 0,
-            show_suggestions: false,
-            text_color: egui::Color32::from_rgb(220, 220, 220),
-            cursor_color: egui::Color32::from_rgb(0, 255, 0),
-            cursor_shape: CursorShape::Block,
-            cursor_blinking: false,
-            cursor_visible: true,
-            last_blink_time: 0.0,
-            last_paint_time: 0.0,
-            cached_plain_lines: Vec::new(),
-            #[cfg(debug_assertions)]
-            frame_times: std::collections::VecDeque::new(),
-            #[cfg(debug_assertions)]
-            last_repaint_reason: String::new(),
-        })
-    }
-
-    fn resize(&mut self, cols: u16, rows: u16) {
-        if cols == self.cols && rows == self.rows { return; }
-        self.cols = cols.max(10);
-        self.rows = rows.max(5);
-        self.parser.set_size(self.rows, self.cols);
-        let _ = self.master.resize(PtySize { rows: self.rows, cols: self.cols, pixel_width: 0, pixel_height: 0 });
+        }
     }
 
-    fn send_str(&mut self, s: &str) {
-        let _ = self.writer.send(s.as_bytes().to_vec());
+    /// Without prompt marks, queue the `line` typed before Enter, as far as
+    /// it was tracked; only while the local shell itself reads it, so a
+    /// password typed for `sudo` never ends up in the history.
+    fn note_typed_command(&mut self, line: Option<&str>) {
+        if self.view.session.term.shell_integration() || self.profile.as_ref().is_some_and(|p| p.command.is_some()) {
+            return;
+        }
+        let Some(line) = line.filter(|line| !line.trim().is_empty()) else {
+            return;
+        };
+        if self.shell_at_prompt() {
+            let entry = self.history_entry(line);
+            self.history_out.push(entry);
+        }
     }
 
-    fn send_bytes(&mut self, b: &[u8]) {
-        let _ = self.writer.send(b.to_vec());
+    /// Whether the shell is in the foreground of its terminal; only known
+    /// for local shells on Unix.
+    fn shell_at_prompt(&self) -> bool {
+        #[cfg(unix)]
+        {
+            let pty = &self.view.session.pty;
+            if let (Some(pid), Some(fd)) = (pty.child_pid, pty.master.as_raw_fd()) {
+                let group = crate::terminal::foreground_process_group(fd);
+                return group.is_some() && group == crate::terminal::process_group(pid);
+            }
+        }
+        false
     }
 
-    fn update_suggestions(&mut self) {
-        if self.input_buffer.is_empty() {
-            self.show_suggestions = false;
-            self.suggestions.clear();
-            return;
-        }
-        
-        // Find matching commands
-        self.suggestions = COMMON_COMMANDS
-            .iter()
-            .filter(|cmd| cmd.starts_with(&self.input_buffer))
-            .take(5)  // Show max 5 suggestions
-            .map(|s| s.to_string())
-            .collect();
-        
-        self.show_suggestions = !self.suggestions.is_empty();
-        self.selected_suggestion = 0;
+    /// Open the search bar with its field focused, or close it.
+    fn toggle_search(&mut self) {
+        self.focus_search = self.view.toggle_search();
     }
 
-    fn ui_with_activity(&mut self, ui: &mut egui::Ui, active: bool) {
+    /// Render the terminal; keyboard input is only consumed when `active`.
+    /// Returns true when the terminal was clicked (used for focus changes).
+    fn ui_with_activity(&mut self, ui: &mut egui::Ui, active: bool, completion: &Sources) -> bool {
         #[cfg(debug_assertions)]
         let frame_start = ui.input(|i| i.time);
         
-        // Drain incoming bytes and update VT parser
-        let mut processed_bytes = false;
-        for chunk in self.rx.try_iter() { 
-            self.parser.process(&chunk); 
-            processed_bytes = true;
-        }
+        // Drain incoming bytes into the terminal
+        let processed_bytes = self.view.poll().output;
+        self.take_shell_events();
         if processed_bytes {
-            // Update cached plain-text lines (includes scrollback + current screen)
-            let screen = self.parser.screen();
-            let full = screen.contents();
-            // Limit memory spikes by reserving approximately current size
-            self.cached_plain_lines.clear();
-            self.cached_plain_lines.extend(full.lines().map(|s| s.to_string()));
             #[cfg(debug_assertions)]
             {
                 self.last_repaint_reason = "new data".to_string();
@@ -1912,22 +3908,82 @@ impl TerminalView {
         }
 
         // Create a visually distinct terminal frame
+        let border = if self.view.broadcast {
+            egui::Color32::from_rgb(255, 140, 0)  // Orange border: part of the broadcast group
+        } else {
+            egui::Color32::from_rgb(0, 200, 120)  // Green border
+        };
         let frame = egui::Frame::default()
-            .fill(egui::Color32::from_rgb(20, 20, 30))  // Dark blue-black background
-            .stroke(egui::Stroke::new(2.0, egui::Color32::from_rgb(0, 200, 120)))  // Green border
+            .fill(self.background_color)
+            .stroke(egui::Stroke::new(2.0, border))
             .inner_margin(egui::Margin::same(10.0))
             .rounding(egui::Rounding::same(5.0));
         
-        frame.show(ui, |ui| {
-            // Jump buttons
+        let mut search_focused = false;
+        let frame_response = frame.show(ui, |ui| {
+            // Jump buttons and search
             ui.horizontal(|ui| {
-                if ui.small_button("⤒").on_hover_text("Zum Anfang (Home)").clicked() {
-                    self.send_bytes(b"\x1b[H");
+                if ui.small_button("⤒").on_hover_text(rust_i18n::t!("terminal.scroll_top")).clicked() {
+                    self.view.send_bytes(b"\x1b[H");
+                }
+                if ui.small_button("⤓").on_hover_text(rust_i18n::t!("terminal.scroll_bottom")).clicked() {
+                    self.view.send_bytes(b"\x1b[F");
                 }
-                if ui.small_button("⤓").on_hover_text("Zum Ende (End)").clicked() {
-                    self.send_bytes(b"\x1b[F");
+                ui.separator();
+                if ui.selectable_label(self.view.search.is_some(), "🔍").on_hover_text(rust_i18n::t!("terminal.search")).clicked() {
+                    self.toggle_search();
+                }
+                if let Some(query) = &mut self.view.search {
+                    let response = ui.add(
+                        egui::TextEdit::singleline(query)
+                            .hint_text(rust_i18n::t!("terminal.search_hint"))
+                            .desired_width(160.0),
+                    );
+                    if std::mem::take(&mut self.focus_search) {
+                        response.request_focus();
+                    }
+                    // Enter in the field jumps to a match instead of reaching the shell
+                    let submitted = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                    search_focused = response.has_focus() || response.lost_focus();
+                    if response.changed() {
+                        self.view.update_matches();
+                    }
+                    if ui.small_button("⬆").on_hover_text(rust_i18n::t!("terminal.search_previous")).clicked() || submitted {
+                        self.view.jump_to_match(true);
+                    }
+                    if ui.small_button("⬇").on_hover_text(rust_i18n::t!("terminal.search_next")).clicked() {
+                        self.view.jump_to_match(false);
+                    }
+                    ui.label(match self.view.current_match {
+                        Some(i) => format!("{}/{}", i + 1, self.view.matches.len()),
+                        None => rust_i18n::t!("terminal.search_matches", count = self.view.matches.len()).to_string(),
+                    });
+                }
+                if let Some(program) = self.view.session.pty.foreground_process() {
+                    ui.separator();
+                    ui.label(format!("▶ {}", program)).on_hover_text(rust_i18n::t!("terminal.running"));
+                }
+                if self.disconnected {
+                    ui.separator();
+                    ui.colored_label(egui::Color32::YELLOW, format!("🔌 {}", rust_i18n::t!("session.disconnected")));
+                    if ui.small_button(format!("🔄 {}", rust_i18n::t!("session.reconnect"))).clicked() {
+                        self.reconnect_requested = true;
+                    }
+                }
+                if self.view.broadcast {
+                    ui.separator();
+                    ui.colored_label(border, format!("📡 {}", rust_i18n::t!("terminal.broadcast_active")));
+                    if ui.small_button("✕").on_hover_text(rust_i18n::t!("terminal.broadcast_exclude")).clicked() {
+                        self.view.broadcast = false;
+                    }
                 }
             });
+            // Text size of the profile, for the grid only
+            if self.font_scale != 1.0 {
+                if let Some(font) = ui.style_mut().text_styles.get_mut(&egui::TextStyle::Monospace) {
+                    font.size *= self.font_scale;
+                }
+            }
             // Estimate character cell size and compute rows/cols
             let char_w = ui.fonts(|f| f.glyph_width(&egui::TextStyle::Monospace.resolve(ui.style()), 'W'));
             let char_h = ui.text_style_height(&egui::TextStyle::Monospace);
@@ -1938,33 +3994,60 @@ impl TerminalView {
                 self.resize(cols, rows);
             }
 
-            // Render VT screen with ANSI colors using vertically virtualized rows
+            // Render scrollback and screen with ANSI colors using vertically virtualized rows
             use egui::text::LayoutJob;
-            let screen = self.parser.screen();
+            let term = &mut self.view.session.term;
             // Cursor position from vt100 (1-based columns -> we use saturating_sub(1))
-            let (cursor_row, cursor_col) = screen.cursor_position();
+            let (cursor_row, cursor_col) = term.screen().cursor_position();
             let display_col: u16 = cursor_col.saturating_sub(1);
 
-            let rows = self.rows as usize;
-            let cols = self.cols as usize;
+            let rows = term.rows() as usize;
+            let cols = term.cols() as usize;
             let font_id = egui::TextStyle::Monospace.resolve(ui.style());
             let row_height = char_h.max(1.0);
-            // Total rows including scrollback based on cached plain lines
-            let total_rows = self.cached_plain_lines.len().max(rows);
-            // Boundary index where current screen starts in the absolute buffer
-            let boundary = total_rows.saturating_sub(rows);
+            // Scrollback rows come first, the current screen starts at the boundary
+            let boundary = term.history_len();
+            let total_rows = boundary + rows;
+
+            // Highlighted columns of an absolute line: search matches and the selection
+            let selection = term.selection;
+            let selection_bg = ui.visuals().selection.bg_fill;
+            let current = self.view.current_match.map(|i| self.view.matches[i]);
+            let matches = &self.view.matches;
+            let marks = |line: usize| -> Vec<(std::ops::Range<usize>, egui::Color32)> {
+                let mut marks: Vec<_> = matches
+                    .iter()
+                    .filter(|m| m.line == line)
+                    .map(|m| (m.start..m.end, if Some(*m) == current { egui::Color32::from_rgb(200, 120, 0) } else { egui::Color32::from_rgb(110, 90, 0) }))
+                    .collect();
+                marks.extend(selection.and_then(|s| s.columns(line, cols)).map(|r| (r, selection_bg)));
+                marks
+            };
 
-            egui::ScrollArea::vertical()
+            let pointer = ui.input(|i| i.pointer.interact_pos());
+            let mut hovered: Option<Point> = None;
+            let mut scroll_area = egui::ScrollArea::vertical()
                 .auto_shrink([false, false])
-                .show_rows(ui, row_height, total_rows, |ui, row_range| {
-                    for row in row_range {
-                        // Render scrolled-back history as plain text
-                        if row < boundary {
-                            let line = self.cached_plain_lines.get(row).map(|s| s.as_str()).unwrap_or("");
-                            ui.label(egui::RichText::new(line).monospace().color(self.text_color));
-                            continue;
-                        }
-                        let row = row - boundary; // screen row index (0..rows-1)
+                .stick_to_bottom(true)
+                .drag_to_scroll(false);
+            if let Some(line) = self.view.scroll_to_line.take() {
+                let pitch = row_height + ui.spacing().item_spacing.y;
+                scroll_area = scroll_area.vertical_scroll_offset(line.saturating_sub(rows / 2) as f32 * pitch);
+            }
+            scroll_area.show_rows(ui, row_height, total_rows, |ui, row_range| {
+                    for line in row_range {
+                        let marks = marks(line);
+                        let response = if line < boundary {
+                            // Scrolled-back history as plain text
+                            let text = term.lines().get(line).map(String::as_str).unwrap_or("");
+                            if marks.is_empty() {
+                                ui.label(egui::RichText::new(text).monospace().color(self.text_color))
+                            } else {
+                                ui.label(marked_line(text, &marks, &font_id, self.text_color))
+                            }
+                        } else {
+                        let screen = term.screen();
+                        let row = line - boundary; // screen row index (0..rows-1)
                         let mut job = LayoutJob::default();
                         // Run-length encode same styled cells to reduce allocations
                         let mut run_text = String::with_capacity(cols);
@@ -2014,12 +4097,15 @@ impl TerminalView {
                                 continue;
                             }
 
-                            // Effective colors with inverse
+                            // Effective colors with inverse and highlights
                             let mut eff_fg = fg;
                             let mut eff_bg = bg;
                             if inverse {
                                 std::mem::swap(&mut eff_fg, eff_bg.get_or_insert(self.text_color));
                             }
+                            if let Some((_, mark)) = marks.iter().find(|(r, _)| r.contains(&col)) {
+                                eff_bg = Some(*mark);
+                            }
 
                             // Extend current run or flush and start new
                             if run_active && eff_fg == run_fg && eff_bg == run_bg {
@@ -2036,118 +4122,69 @@ impl TerminalView {
                         }
                         // flush last run for the row
                         flush_run(&mut job, &font_id, &mut run_text, run_fg, run_bg, &mut run_active);
-                        ui.label(job);
+                        ui.label(job)
+                        };
+                        // Cell under the mouse, for selecting
+                        if let Some(pos) = pointer.filter(|p| response.rect.y_range().contains(p.y) && ui.max_rect().x_range().contains(p.x)) {
+                            let col = ((pos.x - response.rect.left()) / char_w).max(0.0) as usize;
+                            hovered = Some(Point::new(line, col.min(cols.saturating_sub(1))));
+                        }
                     }
                 });
 
-        // Always handle keyboard input when Terminal is the active panel
-        ui.input(|i| {
-            for ev in &i.events {
-                match ev {
-                    egui::Event::Text(t) => { 
-                        if !t.is_empty() {
-                            // Track input for suggestions
-                            for ch in t.chars() {
-                                if ch.is_alphanumeric() || ch == '-' || ch == '_' || ch == '/' || ch == '.' {
-                                    self.input_buffer.push(ch);
-                                    self.update_suggestions();
-                                }
-                            }
-                            self.send_str(t); 
-                        } 
-                    }
-                    egui::Event::Key { key, pressed: true, modifiers, .. } => {
-                        match key {
-                            egui::Key::PageUp => {
-                                // Send typical PageUp escape sequence
-                                self.send_bytes(b"\x1b[5~");
-                            }
-                            egui::Key::PageDown => {
-                                // Send typical PageDown escape sequence
-                                self.send_bytes(b"\x1b[6~");
-                            }
-                            egui::Key::Enter => {
-                                self.input_buffer.clear();
-                                self.show_suggestions = false;
-                                self.send_bytes(b"\r");
-                            }
-                            egui::Key::Backspace => {
-                                self.input_buffer.pop();
-                                self.update_suggestions();
-                                self.send_bytes(&[0x7f]);
-                            }
-                            egui::Key::Tab => {
-                                // Auto-complete with selected suggestion
-                                if self.show_suggestions && !self.suggestions.is_empty() {
-                                    let suggestion = self.suggestions[self.selected_suggestion].clone();
-                                    let to_complete = suggestion[self.input_buffer.len()..].to_string();
-                                    self.send_str(&to_complete);
-                                    self.input_buffer = suggestion;
-                                    self.show_suggestions = false;
-                                } else {
-                                    self.send_bytes(b"\t");
-                                }
-                            }
-                            egui::Key::ArrowUp => {
-                                if self.show_suggestions && !self.suggestions.is_empty() {
-                                    self.selected_suggestion = self.selected_suggestion.saturating_sub(1);
-                                } else {
-                                    self.send_bytes(b"\x1b[A");
-                                }
-                            }
-                            egui::Key::ArrowDown => {
-                                if self.show_suggestions && !self.suggestions.is_empty() {
-                                    self.selected_suggestion = (self.selected_suggestion + 1).min(self.suggestions.len() - 1);
-                                } else {
-                                    self.send_bytes(b"\x1b[B");
-                                }
-                            }
-                            egui::Key::ArrowRight => self.send_bytes(b"\x1b[C"),
-                            egui::Key::ArrowLeft => self.send_bytes(b"\x1b[D"),
-                            egui::Key::Escape => {
-                                self.show_suggestions = false;
-                            }
-                            egui::Key::C if modifiers.ctrl => {
-                                self.input_buffer.clear();
-                                self.show_suggestions = false;
-                                self.send_bytes(&[0x03]);
-                            }
-                            egui::Key::D if modifiers.ctrl => self.send_bytes(&[0x04]),
-                            _ => {}
-                        }
-                    }
-                    egui::Event::Scroll(delta) => {
-                        // Simple mousewheel to arrow mapping when suggestions closed
-                        if !self.show_suggestions {
-                            if delta.y > 0.0 { self.send_bytes(b"\x1b[A"); }
-                            if delta.y < 0.0 { self.send_bytes(b"\x1b[B"); }
-                        }
-                    }
-                    _ => {}
+            // Drag to select; a click in the terminal drops the selection
+            let (pressed, down) = ui.input(|i| (i.pointer.primary_pressed(), i.pointer.primary_down()));
+            self.view.drag(pressed, down, hovered);
+
+        // Handle keyboard input only for the focused terminal
+        if active && !search_focused {
+            let events = ui.input(|i| i.events.clone());
+            for event in &events {
+                let outcome = match terminal_input(event) {
+                    Some(input) => self.view.input(input),
+                    None => continue,
+                };
+                match outcome {
+                    Outcome::Copied(text) => ui.ctx().output_mut(|o| o.copied_text = text),
+                    Outcome::Submitted(line) => self.note_typed_command(line.as_deref()),
+                    Outcome::None => {}
                 }
             }
-        });
+        }
         
         ui.separator();
         
         // Show command suggestions
-        if self.show_suggestions && !self.suggestions.is_empty() {
+        if self.view.suggestions.is_stale() {
+            let cwd = if self.ssh.is_none() { self.current_cwd() } else { None };
+            let ctx = complete::Context { host: self.ssh.as_ref().map(|c| c.name.as_str()), cwd: cwd.as_deref() };
+            self.view.suggestions.refresh(completion, &ctx);
+        }
+        if self.view.suggestions.is_open() {
             ui.horizontal(|ui| {
-                ui.colored_label(egui::Color32::YELLOW, "💡 Vorschläge:");
-                for (idx, suggestion) in self.suggestions.iter().enumerate() {
-                    let selected = idx == self.selected_suggestion;
+                ui.colored_label(egui::Color32::YELLOW, format!("💡 {}", rust_i18n::t!("terminal.suggestions")));
+                let items = self.view.suggestions.items();
+                for (idx, suggestion) in items.iter().enumerate() {
+                    let selected = idx == self.view.suggestions.selected();
                     let color = if selected { egui::Color32::GREEN } else { egui::Color32::LIGHT_GRAY };
-                    ui.colored_label(color, suggestion);
-                    if idx < self.suggestions.len() - 1 {
+                    let label = match suggestion.kind {
+                        complete::Kind::History => format!("🕘 {}", suggestion.label),
+                        _ => suggestion.label.clone(),
+                    };
+                    ui.colored_label(color, label);
+                    if idx < items.len() - 1 {
                         ui.label("|");
                     }
                 }
-                ui.label("(Tab = Vervollständigen, ↑↓ = Auswählen, Esc = Schließen)");
+                ui.label(rust_i18n::t!("terminal.suggestions_help").as_ref());
             });
+        } else if self.view.session.term.selection.is_some() {
+            ui.colored_label(egui::Color32::LIGHT_BLUE, format!("📋 {}", rust_i18n::t!("terminal.selection_hint")));
         } else {
-            ui.colored_label(egui::Color32::GREEN, "⌨️ Terminal aktiv - Befehle werden direkt verarbeitet (Tab für Vorschläge)");
+            ui.colored_label(egui::Color32::GREEN, format!("⌨️ {}", rust_i18n::t!("terminal.terminal_active")));
         }
         }); // Close frame
+        let clicked = frame_response.response.contains_pointer() && ui.input(|i| i.pointer.primary_pressed());
         
         // Mark paint time for throttling
         self.last_paint_time = ui.input(|i| i.time);
@@ -2172,80 +4209,188 @@ impl TerminalView {
             ui.horizontal(|ui| {
                 ui.colored_label(egui::Color32::LIGHT_BLUE, format!("🔍 FPS: {:.1}", fps));
                 ui.separator();
-                ui.colored_label(egui::Color32::LIGHT_BLUE, format!("Lines: {} (cached) | {} rows", self.cached_plain_lines.len(), self.rows));
+                ui.colored_label(egui::Color32::LIGHT_BLUE, format!("Lines: {} | {} rows", self.view.session.term.history_len() + self.view.session.term.rows() as usize, self.view.session.term.rows()));
                 ui.separator();
                 ui.colored_label(egui::Color32::LIGHT_BLUE, format!("Repaint: {}", self.last_repaint_reason));
             });
         }
+
+        clicked
     }
 
     // (intentionally no simple wrapper to avoid unused warnings)
 }
 
+/// Tab strip of one pane: select, Ctrl+click/× to close, rename, broadcast
+/// toggle and drag handle. Tab rectangles are collected for drop detection.
+/// `close_last` allows closing the only tab (which closes a split pane).
+#[cfg(feature = "gui")]
+fn tab_strip(
+    ui: &mut egui::Ui,
+    pane: PaneId,
+    tabs: &[TerminalTab],
+    active: usize,
+    close_last: bool,
+    keymap: &Keymap,
+    tab_rects: &mut Vec<(PaneId, usize, egui::Rect)>,
+) -> Option<TabAction> {
+    let mut action = None;
+    for (idx, tab) in tabs.iter().enumerate() {
+        let group = ui.group(|ui| {
+            // Tab label with drag + ctrl-click-to-close
+            let response = ui
+                .add(egui::SelectableLabel::new(idx == active, &tab.name))
+                .interact(egui::Sense::click_and_drag())
+                .on_hover_text(rust_i18n::t!("terminal.ctrl_click_to_close"));
+            if response.clicked() {
+                let ctrl_down = ui.input(|i| i.modifiers.ctrl);
+                action = Some(if ctrl_down { TabAction::Close(idx) } else { TabAction::Select(idx) });
+            }
+            if response.drag_started() {
+                action = Some(TabAction::StartDrag(idx));
+            }
+            ui.horizontal(|ui| {
+                if ui.small_button("✏").on_hover_text(rust_i18n::t!("common.rename")).clicked() {
+                    action = Some(TabAction::Rename(idx));
+                }
+                if ui.selectable_label(tab.terminal.view.broadcast, "📡")
+                    .on_hover_text(rust_i18n::t!("terminal.broadcast_toggle", keys = keymap.label(Action::ToggleBroadcast)))
+                    .clicked() {
+                        action = Some(TabAction::ToggleBroadcast(idx));
+                    }
+                if (tabs.len() > 1 || close_last)
+                    && ui.small_button("×").on_hover_text(keymap.label(Action::CloseTab)).clicked() {
+                        action = Some(TabAction::Close(idx));
+                    }
+            });
+        });
+        tab_rects.push((pane, idx, group.response.rect));
+    }
+    action
+}
+
 // Map vt100 colors to egui::Color32
 #[cfg(feature = "gui")]
 fn vt_color_to_egui(c: vt100::Color, default: egui::Color32) -> egui::Color32 {
-    use vt100::Color;
-    match c {
-        Color::Default => default,
-        Color::Rgb(r, g, b) => egui::Color32::from_rgb(r, g, b),
-        // Fallback: 256-color indexed palette
-        other => {
-            if let Some(idx) = color_to_index(other) {
-                ansi256_to_rgb(idx).unwrap_or(default)
-            } else {
-                default
-            }
+    crate::term::color::to_rgb(c).map_or(default, |(r, g, b)| egui::Color32::from_rgb(r, g, b))
+}
+
+/// The terminal's part of an egui event; app shortcuts are taken out before.
+#[cfg(feature = "gui")]
+fn terminal_input(event: &egui::Event) -> Option<Input<'_>> {
+    Some(match event {
+        egui::Event::Text(text) => Input::Text(text),
+        // egui turns Ctrl+C and Ctrl+X into Copy and Cut
+        egui::Event::Copy => Input::Copy,
+        egui::Event::Cut => Input::Key(Key::Char('x'), Modifiers::CTRL),
+        egui::Event::Paste(text) => Input::Paste(text),
+        egui::Event::Key { key, pressed: true, modifiers, .. } => {
+            let mods = Modifiers { ctrl: modifiers.ctrl, alt: modifiers.alt, shift: modifiers.shift };
+            let key = match key {
+                egui::Key::Enter => Key::Enter,
+                egui::Key::Backspace => Key::Backspace,
+                egui::Key::Tab => Key::Tab,
+                egui::Key::Escape => Key::Esc,
+                egui::Key::D if modifiers.ctrl => Key::Char('d'),
+                key => terminal_key(*key)?,
+            };
+            Input::Key(key, mods)
         }
-    }
+        egui::Event::Scroll(delta) => Input::Scroll(delta.y),
+        _ => return None,
+    })
 }
 
+/// Keys the terminal forwards as they are; the rest are mapped in
+/// `terminal_input` or belong to app shortcuts.
 #[cfg(feature = "gui")]
-fn color_to_index(c: vt100::Color) -> Option<u8> {
-    match c {
-        vt100::Color::Idx(i) => Some(i),
-        _ => None,
-    }
+fn terminal_key(key: egui::Key) -> Option<Key> {
+    use egui::Key as K;
+    Some(match key {
+        K::ArrowUp => Key::Up,
+        K::ArrowDown => Key::Down,
+        K::ArrowLeft => Key::Left,
+        K::ArrowRight => Key::Right,
+        K::Home => Key::Home,
+        K::End => Key::End,
+        K::PageUp => Key::PageUp,
+        K::PageDown => Key::PageDown,
+        K::Insert => Key::Insert,
+        K::Delete => Key::Delete,
+        K::F1 => Key::F(1),
+        K::F2 => Key::F(2),
+        K::F3 => Key::F(3),
+        K::F4 => Key::F(4),
+        K::F5 => Key::F(5),
+        K::F6 => Key::F(6),
+        K::F7 => Key::F(7),
+        K::F8 => Key::F(8),
+        K::F9 => Key::F(9),
+        K::F10 => Key::F(10),
+        K::F11 => Key::F(11),
+        K::F12 => Key::F(12),
+        _ => return None,
+    })
 }
 
+/// Chord of a key press, for matching against the keymap.
 #[cfg(feature = "gui")]
-fn ansi256_to_rgb(i: u8) -> Option<egui::Color32> {
-    let idx = i as u16;
-    if idx <= 15 {
-        let (r, g, b) = match idx {
-            0 => (0, 0, 0),
-            1 => (128, 0, 0),
-            2 => (0, 128, 0),
-            3 => (128, 128, 0),
-            4 => (0, 0, 128),
-            5 => (128, 0, 128),
-            6 => (0, 128, 128),
-            7 => (192, 192, 192),
-            8 => (128, 128, 128),
-            9 => (255, 0, 0),
-            10 => (0, 255, 0),
-            11 => (255, 255, 0),
-            12 => (0, 0, 255),
-            13 => (255, 0, 255),
-            14 => (0, 255, 255),
-            15 => (255, 255, 255),
-            _ => (255, 255, 255),
-        };
-        return Some(egui::Color32::from_rgb(r, g, b));
-    }
-    if (16..=231).contains(&idx) {
-        let n = idx - 16;
-        let r = n / 36;
-        let g = (n % 36) / 6;
-        let b = n % 6;
-        let conv = |v: u16| -> u8 { [0, 95, 135, 175, 215, 255][v as usize] };
-        return Some(egui::Color32::from_rgb(conv(r), conv(g), conv(b)));
-    }
-    if (232..=255).contains(&idx) {
-        let l = (8 + (idx - 232) * 10) as u8;
-        return Some(egui::Color32::from_rgb(l, l, l));
+fn key_chord(key: egui::Key, modifiers: egui::Modifiers) -> Option<KeyChord> {
+    use egui::Key as K;
+    let name = match key {
+        K::A => "A", K::B => "B", K::C => "C", K::D => "D", K::E => "E", K::F => "F", K::G => "G",
+        K::H => "H", K::I => "I", K::J => "J", K::K => "K", K::L => "L", K::M => "M", K::N => "N",
+        K::O => "O", K::P => "P", K::Q => "Q", K::R => "R", K::S => "S", K::T => "T", K::U => "U",
+        K::V => "V", K::W => "W", K::X => "X", K::Y => "Y", K::Z => "Z",
+        K::Num0 => "0", K::Num1 => "1", K::Num2 => "2", K::Num3 => "3", K::Num4 => "4",
+        K::Num5 => "5", K::Num6 => "6", K::Num7 => "7", K::Num8 => "8", K::Num9 => "9",
+        K::F1 => "F1", K::F2 => "F2", K::F3 => "F3", K::F4 => "F4", K::F5 => "F5", K::F6 => "F6",
+        K::F7 => "F7", K::F8 => "F8", K::F9 => "F9", K::F10 => "F10", K::F11 => "F11", K::F12 => "F12",
+        K::Tab => "Tab",
+        K::Enter => "Enter",
+        K::Space => "Space",
+        K::Backspace => "Backspace",
+        K::Escape => "Escape",
+        K::Insert => "Insert",
+        K::Delete => "Delete",
+        K::Home => "Home",
+        K::End => "End",
+        K::PageUp => "PageUp",
+        K::PageDown => "PageDown",
+        K::ArrowLeft => "Left",
+        K::ArrowRight => "Right",
+        K::ArrowUp => "Up",
+        K::ArrowDown => "Down",
+        K::Plus => "Plus",
+        K::Minus => "Minus",
+        K::Equals => "Equals",
+        K::Comma => "Comma",
+        K::Period => "Period",
+        K::Slash => "Slash",
+        _ => return None,
+    };
+    KeyChord::new(name, modifiers.ctrl, modifiers.alt, modifiers.shift)
+}
+
+/// Plain history line with `marks` (column ranges) on a colored background.
+#[cfg(feature = "gui")]
+fn marked_line(text: &str, marks: &[(std::ops::Range<usize>, egui::Color32)], font_id: &egui::FontId, color: egui::Color32) -> egui::text::LayoutJob {
+    let mark_at = |col: usize| marks.iter().find(|(r, _)| r.contains(&col)).map(|(_, bg)| *bg);
+    let width = text.chars().count().max(marks.iter().map(|(r, _)| r.end).max().unwrap_or(0));
+    let mut chars = text.chars();
+    let mut job = egui::text::LayoutJob::default();
+    let mut col = 0;
+    while col < width {
+        let bg = mark_at(col);
+        let end = (col + 1..width).find(|c| mark_at(*c) != bg).unwrap_or(width);
+        let run: String = (col..end).map(|_| chars.next().unwrap_or(' ')).collect();
+        let mut format = egui::TextFormat { font_id: font_id.clone(), color, ..Default::default() };
+        #[allow(deprecated)]
+        if let Some(bg) = bg { format.background = bg; }
+        job.append(&run, 0.0, format);
+        col = end;
     }
-    None
+    job
 }
 
 // ===================== Markdown Editor =====================
@@ -2260,7 +4405,7 @@ struct MarkdownEditor {
 impl MarkdownEditor {
     fn ui(&mut self, ui: &mut egui::Ui) {
         ui.horizontal(|ui| {
-            if ui.button("📂 Öffnen").clicked() {
+            if ui.button(format!("📂 {}", rust_i18n::t!("markdown.open"))).clicked() {
                 if let Some(path) = rfd::FileDialog::new().add_filter("Markdown", &["md", "markdown"]).pick_file() {
                     if let Ok(content) = std::fs::read_to_string(&path) {
                         self.content = content;
@@ -2268,7 +4413,7 @@ impl MarkdownEditor {
                     }
                 }
             }
-            if ui.button("💾 Speichern").clicked() {
+            if ui.button(format!("💾 {}", rust_i18n::t!("markdown.save"))).clicked() {
                 if let Some(path) = &self.file_path {
                     let _ = std::fs::write(path, &self.content);
                 } else if let Some(path) = rfd::FileDialog::new().add_filter("Markdown", &["md"]).save_file() {
@@ -2276,7 +4421,7 @@ impl MarkdownEditor {
                     self.file_path = Some(path);
                 }
             }
-            if ui.button("📄 Neu").clicked() {
+            if ui.button(format!("📄 {}", rust_i18n::t!("markdown.new"))).clicked() {
                 self.content.clear();
                 self.file_path = None;
             }
@@ -2284,21 +4429,21 @@ impl MarkdownEditor {
             if let Some(path) = &self.file_path {
                 ui.label(format!("📝 {}", path.display()));
             } else {
-                ui.label("📝 Ungespeichert");
+                ui.label(format!("📝 {}", rust_i18n::t!("markdown.unsaved")));
             }
         });
         ui.separator();
 
         // Split editor and preview
         egui::TopBottomPanel::bottom("preview").resizable(true).default_height(ui.available_height() * 0.5).show_inside(ui, |ui| {
-            ui.heading("Vorschau");
+            ui.heading(rust_i18n::t!("markdown.preview").as_ref());
             egui::ScrollArea::vertical().show(ui, |ui| {
                 render_markdown(ui, &self.content);
             });
         });
 
         egui::CentralPanel::default().show_inside(ui, |ui| {
-            ui.heading("Editor");
+            ui.heading(rust_i18n::t!("markdown.editor").as_ref());
             egui::ScrollArea::vertical().show(ui, |ui| {
                 ui.add(egui::TextEdit::multiline(&mut self.content).font(egui::TextStyle::Monospace).desired_width(f32::INFINITY).desired_rows(30));
             });
@@ -2351,90 +4496,155 @@ fn render_markdown(ui: &mut egui::Ui, text: &str) {
 
 // ===================== SSH Manager =====================
 #[cfg(feature = "gui")]
-use serde::{Deserialize, Serialize};
-
-#[cfg(feature = "gui")]
-#[derive(Clone, Debug, Serialize, Deserialize)]
-struct SshConnection {
-    name: String,
-    host: String,
-    port: u16,
-    username: String,
-    #[serde(skip)]
-    password: String, // Not saved to disk for security
-}
-
-#[cfg(feature = "gui")]
-impl Default for SshConnection {
-    fn default() -> Self {
-        Self {
-            name: String::new(),
-            host: String::new(),
-            port: 22,
-            username: String::new(),
-            password: String::new(),
-        }
-    }
-}
-
-#[cfg(feature = "gui")]
-#[derive(Default, Serialize, Deserialize)]
+#[derive(Default)]
 struct SshManager {
     connections: Vec<SshConnection>,
-    #[serde(skip)]
     new_connection: SshConnection,
-    #[serde(skip)]
     show_add_dialog: bool,
-    #[serde(skip)]
     status_message: String,
-    #[serde(skip)]
-    rename_dialog: Option<(usize, String)>, // (connection_index, new_name)
+    editing: Option<usize>, // connection being edited in the dialog; None = new
+    tags_input: String,
+    remember_new_password: bool,
+    search: String,
+    load_error: Option<String>, // the file could not be read; saving would overwrite it
+}
+
+#[cfg(feature = "gui")]
+#[derive(Clone, Copy)]
+enum ConnectionAction {
+    Connect,
+    ToggleFavorite,
+    Edit,
+    Duplicate,
+    MoveUp,
+    MoveDown,
+    Delete,
 }
 
 #[cfg(feature = "gui")]
 impl SshManager {
     fn load_or_default() -> Self {
-        let config_path = std::path::PathBuf::from("ssh_connections.toml");
-        if let Ok(content) = std::fs::read_to_string(&config_path) {
-            if let Ok(manager) = toml::from_str::<SshManager>(&content) {
-                return manager;
-            }
+        match ssh::load_connections() {
+            Ok((connections, legacy)) => Self {
+                connections,
+                status_message: legacy
+                    .map(|path| rust_i18n::t!("ssh.legacy_imported", path = path.display()).to_string())
+                    .unwrap_or_default(),
+                ..Self::default()
+            },
+            Err(e) => Self { status_message: format!("❌ {}", e), load_error: Some(e), ..Self::default() },
         }
-        Self::default()
     }
 
-    fn save(&self) {
-        let config_path = std::path::PathBuf::from("ssh_connections.toml");
-        if let Ok(content) = toml::to_string_pretty(self) {
-            let _ = std::fs::write(config_path, content);
+    /// Write the connections; refused while the file on disk could not be
+    /// read, so its contents are not replaced by the (partial) list here.
+    fn save(&mut self) -> bool {
+        let result = match &self.load_error {
+            Some(e) => Err(rust_i18n::t!("ssh.not_saved", error = e).to_string()),
+            None => ssh::save_connections(&self.connections),
+        };
+        if let Err(e) = result {
+            self.status_message = format!("❌ {}: {}", rust_i18n::t!("ssh.save_failed"), e);
+            return false;
         }
+        true
     }
 
     fn export(&self, path: &std::path::Path) -> Result<(), String> {
-        if let Ok(content) = toml::to_string_pretty(self) {
-            std::fs::write(path, content).map_err(|e| e.to_string())
-        } else {
-            Err("Failed to serialize SSH connections".to_string())
-        }
+        ssh::export_connections(&self.connections, path)
     }
 
     fn import(&mut self, path: &std::path::Path) -> Result<(), String> {
-        let content = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
-        let manager: SshManager = toml::from_str(&content).map_err(|e| e.to_string())?;
-        self.connections = manager.connections;
+        self.connections = ssh::import_connections(path)?;
         Ok(())
     }
 
-    fn ui(&mut self, ui: &mut egui::Ui, pending_connection: &mut Option<SshConnection>) {
+    fn groups(&self) -> Vec<String> {
+        let mut groups: Vec<String> = Vec::new();
+        for conn in &self.connections {
+            if !conn.group.is_empty() && !groups.contains(&conn.group) {
+                groups.push(conn.group.clone());
+            }
+        }
+        groups.sort();
+        groups
+    }
+
+    fn open_dialog(&mut self, editing: Option<usize>, conn: SshConnection) {
+        self.tags_input = conn.tags.join(", ");
+        self.new_connection = conn;
+        self.editing = editing;
+        self.remember_new_password = false;
+        self.show_add_dialog = true;
+    }
+
+    /// Index of the neighbouring connection in the same group, for reordering.
+    fn neighbour_in_group(&self, idx: usize, up: bool) -> Option<usize> {
+        let group = &self.connections[idx].group;
+        if up {
+            (0..idx).rev().find(|&i| &self.connections[i].group == group)
+        } else {
+            (idx + 1..self.connections.len()).find(|&i| &self.connections[i].group == group)
+        }
+    }
+
+    fn connection_row(ui: &mut egui::Ui, conn: &SshConnection, action: &mut Option<ConnectionAction>) {
+        ui.group(|ui| {
+            ui.horizontal(|ui| {
+                let color = conn.color.map(GuiApp::color_from_rgba).unwrap_or(egui::Color32::from_rgb(100, 200, 255));
+                ui.colored_label(color, "▌");
+                ui.colored_label(color, &conn.name);
+                ui.separator();
+                ui.label(format!("{}@{}:{}", conn.username, conn.host, conn.port));
+                if conn.identity_file.is_some() {
+                    ui.label("🔑");
+                }
+                for tag in &conn.tags {
+                    ui.label(egui::RichText::new(format!("#{}", tag)).small().background_color(egui::Color32::from_gray(50)));
+                }
+            });
+
+            ui.horizontal(|ui| {
+                if ui.button(format!("🔌 {}", rust_i18n::t!("ssh.connect"))).clicked() {
+                    *action = Some(ConnectionAction::Connect);
+                }
+                let star = if conn.favorite { "★" } else { "☆" };
+                if ui.button(star).on_hover_text(rust_i18n::t!("ssh.favorite")).clicked() {
+                    *action = Some(ConnectionAction::ToggleFavorite);
+                }
+                if ui.button(format!("✏ {}", rust_i18n::t!("ssh.edit"))).clicked() {
+                    *action = Some(ConnectionAction::Edit);
+                }
+                if ui.button(format!("⎘ {}", rust_i18n::t!("ssh.duplicate"))).clicked() {
+                    *action = Some(ConnectionAction::Duplicate);
+                }
+                if ui.small_button("⬆").on_hover_text(rust_i18n::t!("ssh.move_up")).clicked() {
+                    *action = Some(ConnectionAction::MoveUp);
+                }
+                if ui.small_button("⬇").on_hover_text(rust_i18n::t!("ssh.move_down")).clicked() {
+                    *action = Some(ConnectionAction::MoveDown);
+                }
+                if ui.button(format!("🗑 {}", rust_i18n::t!("ssh.delete"))).clicked() {
+                    *action = Some(ConnectionAction::Delete);
+                }
+            });
+        });
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui, pending_connection: &mut Option<SshConnection>, credentials: &mut CredentialStore, profiles: &[Profile]) {
         // Add connection button
         ui.horizontal(|ui| {
-            if ui.button("➕ Neue SSH Verbindung").clicked() {
-                self.show_add_dialog = true;
-                self.new_connection = SshConnection::default();
+            if ui.button(format!("➕ {}", rust_i18n::t!("ssh.new_connection"))).clicked() {
+                self.open_dialog(None, SshConnection::default());
+            }
+            if ui.button(format!("💾 {}", rust_i18n::t!("ssh.save"))).clicked() && self.save() {
+                self.status_message = rust_i18n::t!("ssh.saved").to_string();
             }
-            if ui.button("💾 Speichern").clicked() {
-                self.save();
-                self.status_message = "Verbindungen gespeichert!".into();
+            ui.separator();
+            ui.label("🔍");
+            ui.add(egui::TextEdit::singleline(&mut self.search).hint_text(rust_i18n::t!("ssh.search_hint")));
+            if !self.search.is_empty() && ui.small_button("×").clicked() {
+                self.search.clear();
             }
         });
 
@@ -2444,129 +4654,343 @@ impl SshManager {
 
         ui.separator();
 
-        // Add connection dialog
+        // Add / edit connection dialog
         if self.show_add_dialog {
-            egui::Window::new("Neue SSH Verbindung")
+            let title = if self.editing.is_some() {
+                rust_i18n::t!("ssh.edit_connection")
+            } else {
+                rust_i18n::t!("ssh.new_connection")
+            };
+            let groups = self.groups();
+            egui::Window::new(title.as_ref())
                 .collapsible(false)
                 .resizable(false)
                 .show(ui.ctx(), |ui| {
                     ui.horizontal(|ui| {
-                        ui.label("Name:");
+                        ui.label(rust_i18n::t!("ssh.name").as_ref());
                         ui.text_edit_singleline(&mut self.new_connection.name);
                     });
                     ui.horizontal(|ui| {
-                        ui.label("Host:");
+                        ui.label(rust_i18n::t!("ssh.host").as_ref());
                         ui.text_edit_singleline(&mut self.new_connection.host);
                     });
                     ui.horizontal(|ui| {
-                        ui.label("Port:");
+                        ui.label(rust_i18n::t!("ssh.port").as_ref());
                         ui.add(egui::DragValue::new(&mut self.new_connection.port).clamp_range(1..=65535));
                     });
                     ui.horizontal(|ui| {
-                        ui.label("Benutzer:");
+                        ui.label(rust_i18n::t!("ssh.username").as_ref());
                         ui.text_edit_singleline(&mut self.new_connection.username);
                     });
                     ui.horizontal(|ui| {
-                        ui.label("Passwort:");
+                        ui.label(rust_i18n::t!("ssh.identity_file").as_ref());
+                        let mut identity = self.new_connection.identity_file.clone().unwrap_or_default();
+                        if ui.text_edit_singleline(&mut identity).changed() {
+                            self.new_connection.identity_file = (!identity.trim().is_empty()).then(|| identity.trim().to_string());
+                        }
+                        if ui.button("📂").clicked() {
+                            if let Some(path) = rfd::FileDialog::new().pick_file() {
+                                self.new_connection.identity_file = Some(path.display().to_string());
+                            }
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        if self.new_connection.identity_file.is_some() {
+                            ui.label(rust_i18n::t!("ssh.passphrase").as_ref());
+                        } else {
+                            ui.label(rust_i18n::t!("ssh.password").as_ref());
+                        }
                         ui.add(egui::TextEdit::singleline(&mut self.new_connection.password).password(true));
                     });
+                    ui.checkbox(&mut self.remember_new_password, rust_i18n::t!("ssh.remember_password").as_ref());
 
                     ui.separator();
                     ui.horizontal(|ui| {
-                        if ui.button("✓ Hinzufügen").clicked() {
-                            self.connections.push(self.new_connection.clone());
-                            self.show_add_dialog = false;
-                            self.status_message = format!("Verbindung '{}' hinzugefügt", self.new_connection.name);
+                        ui.label(rust_i18n::t!("ssh.group").as_ref());
+                        ui.text_edit_singleline(&mut self.new_connection.group);
+                        if !groups.is_empty() {
+                            egui::ComboBox::from_id_source("ssh_group_picker")
+                                .selected_text("▾")
+                                .show_ui(ui, |ui| {
+                                    for group in &groups {
+                                        if ui.selectable_label(&self.new_connection.group == group, group).clicked() {
+                                            self.new_connection.group = group.clone();
+                                        }
+                                    }
+                                });
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label(rust_i18n::t!("ssh.tags").as_ref());
+                        ui.add(egui::TextEdit::singleline(&mut self.tags_input).hint_text("prod, web, db"));
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label(rust_i18n::t!("ssh.profile").as_ref());
+                        let none = rust_i18n::t!("ssh.no_profile").to_string();
+                        egui::ComboBox::from_id_source("ssh_profile_picker")
+                            .selected_text(self.new_connection.profile.clone().unwrap_or_else(|| none.clone()))
+                            .show_ui(ui, |ui| {
+                                ui.selectable_value(&mut self.new_connection.profile, None, none);
+                                for profile in profiles {
+                                    ui.selectable_value(&mut self.new_connection.profile, Some(profile.name.clone()), &profile.name);
+                                }
+                            });
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label(rust_i18n::t!("ssh.label_color").as_ref());
+                        let mut has_color = self.new_connection.color.is_some();
+                        if ui.checkbox(&mut has_color, "").changed() {
+                            self.new_connection.color = has_color.then_some(Rgba { r: 100, g: 200, b: 255, a: 255 });
+                        }
+                        if let Some(rgba) = self.new_connection.color {
+                            let mut c = GuiApp::color_from_rgba(rgba);
+                            if ui.color_edit_button_srgba(&mut c).changed() {
+                                self.new_connection.color = Some(GuiApp::color_to_rgba(c));
+                            }
+                        }
+                        ui.checkbox(&mut self.new_connection.favorite, format!("★ {}", rust_i18n::t!("ssh.favorite")));
+                    });
+
+                    ui.separator();
+                    ui.horizontal(|ui| {
+                        let confirm = format!("✓ {}", if self.editing.is_some() { rust_i18n::t!("ssh.save") } else { rust_i18n::t!("ssh.add") });
+                        if ui.button(confirm).clicked() {
+                            self.new_connection.group = self.new_connection.group.trim().to_string();
+                            self.new_connection.tags = self.tags_input
+                                .split(',')
+                                .map(|t| t.trim().trim_start_matches('#').to_string())
+                                .filter(|t| !t.is_empty())
+                                .collect();
+                            if let Err(e) = self.new_connection.validate() {
+                                self.status_message = format!("❌ {}", e);
+                            } else {
+                                match self.editing.filter(|&idx| idx < self.connections.len()) {
+                                    Some(idx) => {
+                                        // Credentials are keyed by user@host:port; follow edits of those
+                                        let old_key = self.connections[idx].credential_key();
+                                        if old_key != self.new_connection.credential_key() && self.new_connection.password.is_empty() {
+                                            if let Some(secret) = credentials.lookup(&old_key) {
+                                                self.new_connection.password = secret;
+                                                self.remember_new_password = true;
+                                            }
+                                        }
+                                        self.connections[idx] = self.new_connection.clone();
+                                        self.status_message = rust_i18n::t!("ssh.updated", name = self.new_connection.name).to_string();
+                                    }
+                                    None => {
+                                        self.connections.push(self.new_connection.clone());
+                                        self.status_message = rust_i18n::t!("ssh.added", name = self.new_connection.name).to_string();
+                                    }
+                                }
+                                self.show_add_dialog = false;
+                                if self.remember_new_password && !self.new_connection.password.is_empty() {
+                                    if let Err(e) = credentials.store(&self.new_connection.credential_key(), &self.new_connection.password) {
+                                        self.status_message = format!("{} • {}: {}", self.status_message, rust_i18n::t!("vault.save_failed"), e);
+                                    }
+                                }
+                                self.save();
+                            }
                         }
-                        if ui.button("✗ Abbrechen").clicked() {
+                        if ui.button(format!("✗ {}", rust_i18n::t!("ssh.cancel"))).clicked() {
                             self.show_add_dialog = false;
                         }
                     });
                 });
         }
 
-        // List connections
-        ui.heading("Gespeicherte Verbindungen:");
-        ui.separator();
+        let mut action: Option<(usize, ConnectionAction)> = None;
+
+        egui::ScrollArea::vertical().auto_shrink([false, false]).show(ui, |ui| {
+            if !self.search.trim().is_empty() {
+                // Search: flat list ranked by match quality
+                let mut hits: Vec<(usize, i64)> = self.connections
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(idx, conn)| conn.search_score(&self.search).map(|score| (idx, score)))
+                    .collect();
+                hits.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+                ui.heading(format!("{} ({})", rust_i18n::t!("ssh.search_results"), hits.len()));
+                ui.separator();
+                for (idx, _) in hits {
+                    let mut row_action = None;
+                    Self::connection_row(ui, &self.connections[idx], &mut row_action);
+                    if let Some(a) = row_action {
+                        action = Some((idx, a));
+                    }
+                }
+                return;
+            }
 
-        let mut to_remove = None;
-        let mut to_connect = None;
-        let mut to_rename = None;
+            // Favorites
+            if self.connections.iter().any(|c| c.favorite) {
+                ui.heading(format!("★ {}", rust_i18n::t!("ssh.favorites")));
+                for (idx, conn) in self.connections.iter().enumerate().filter(|(_, c)| c.favorite) {
+                    let mut row_action = None;
+                    Self::connection_row(ui, conn, &mut row_action);
+                    if let Some(a) = row_action {
+                        action = Some((idx, a));
+                    }
+                }
+                ui.separator();
+            }
 
-        for (idx, conn) in self.connections.iter().enumerate() {
-            ui.group(|ui| {
-                ui.horizontal(|ui| {
-                    ui.colored_label(egui::Color32::from_rgb(100, 200, 255), &conn.name);
-                    ui.separator();
-                    ui.label(format!("{}@{}:{}", conn.username, conn.host, conn.port));
-                });
+            // List connections by group
+            ui.heading(rust_i18n::t!("ssh.saved_connections").as_ref());
+            ui.separator();
 
-                ui.horizontal(|ui| {
-                    if ui.button("🔌 Verbinden").clicked() {
-                        to_connect = Some(idx);
-                    }
-                    if ui.button("✏ Umbenennen").clicked() {
-                        to_rename = Some(idx);
-                    }
-                    if ui.button("🗑 Löschen").clicked() {
-                        to_remove = Some(idx);
+            let mut sections: Vec<(String, String)> = self.groups().into_iter().map(|g| (g.clone(), g)).collect();
+            sections.push((String::new(), rust_i18n::t!("ssh.ungrouped").to_string()));
+            for (group, title) in sections {
+                let members: Vec<usize> = (0..self.connections.len()).filter(|&i| self.connections[i].group == group).collect();
+                if members.is_empty() {
+                    continue;
+                }
+                egui::CollapsingHeader::new(format!("📁 {} ({})", title, members.len()))
+                    .id_source(("ssh_group", &group))
+                    .default_open(true)
+                    .show(ui, |ui| {
+                        for idx in members {
+                            let mut row_action = None;
+                            Self::connection_row(ui, &self.connections[idx], &mut row_action);
+                            if let Some(a) = row_action {
+                                action = Some((idx, a));
+                            }
+                        }
+                    });
+            }
+        });
+
+        if let Some((idx, action)) = action {
+            match action {
+                ConnectionAction::Connect => {
+                    let conn = self.connections[idx].clone();
+                    self.status_message = rust_i18n::t!("ssh.connecting", target = format!("{}@{}:{}", conn.username, conn.host, conn.port)).to_string();
+                    *pending_connection = Some(conn);
+                }
+                ConnectionAction::ToggleFavorite => {
+                    self.connections[idx].favorite = !self.connections[idx].favorite;
+                    self.save();
+                }
+                ConnectionAction::Edit => {
+                    let conn = self.connections[idx].clone();
+                    self.open_dialog(Some(idx), conn);
+                }
+                ConnectionAction::Duplicate => {
+                    let mut copy = self.connections[idx].clone();
+                    copy.name = format!("{} ({})", copy.name, rust_i18n::t!("ssh.copy_suffix"));
+                    copy.favorite = false;
+                    self.connections.insert(idx + 1, copy);
+                    self.save();
+                }
+                ConnectionAction::MoveUp | ConnectionAction::MoveDown => {
+                    if let Some(other) = self.neighbour_in_group(idx, matches!(action, ConnectionAction::MoveUp)) {
+                        self.connections.swap(idx, other);
+                        self.save();
                     }
-                });
-            });
-            ui.separator();
+                }
+                ConnectionAction::Delete => {
+                    let removed = self.connections.remove(idx);
+                    credentials.release(&removed.credential_key(), &self.connections);
+                    self.status_message = rust_i18n::t!("ssh.deleted", name = removed.name).to_string();
+                    self.save();
+                }
+            }
         }
 
-        if let Some(idx) = to_rename {
-            self.rename_dialog = Some((idx, self.connections[idx].name.clone()));
+        if self.connections.is_empty() {
+            ui.colored_label(egui::Color32::GRAY, rust_i18n::t!("ssh.no_connections").as_ref());
         }
+    }
+}
 
-        if let Some(idx) = to_remove {
-            let removed = self.connections.remove(idx);
-            self.status_message = format!("Verbindung '{}' gelöscht", removed.name);
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    /// Tab on channels instead of a shell.
+    fn tab(name: &str) -> TerminalTab {
+        let (_, rx) = mpsc::channel();
+        let (writer, _) = mpsc::channel();
+        let pair = NativePtySystem::default()
+            .openpty(PtySize { rows: 5, cols: 20, pixel_width: 0, pixel_height: 0 })
+            .unwrap();
+        let pty = PtyProcess { rx, writer, master: pair.master, child_pid: None };
+        TerminalTab { name: name.into(), terminal: TerminalView::from_pty(pty, 5, 20, 100) }
+    }
 
-        if let Some(idx) = to_connect {
-            let conn = self.connections[idx].clone();
-            self.status_message = format!("Verbinde zu {}@{}:{}...", conn.username, conn.host, conn.port);
-            *pending_connection = Some(conn);
+    fn app(tabs: &[&str]) -> GuiApp {
+        GuiApp {
+            terminals: tabs.iter().map(|name| tab(name)).collect(),
+            active_terminal_tab: tabs.len().saturating_sub(1),
+            ..Default::default()
         }
+    }
 
-        if self.connections.is_empty() {
-            ui.colored_label(egui::Color32::GRAY, "Keine SSH Verbindungen gespeichert. Klicke auf '➕ Neue SSH Verbindung' um eine hinzuzufügen.");
-        }
+    fn leaves(node: &SplitNode) -> Vec<PaneId> {
+        let mut out = Vec::new();
+        node.leaves(&mut out);
+        out
+    }
 
-        // Rename dialog
-        let mut close_rename_dialog = false;
-        let mut new_status_message = None;
-        if let Some((idx, ref mut new_name)) = self.rename_dialog {
-            egui::Window::new("SSH Verbindung umbenennen")
-                .collapsible(false)
-                .resizable(false)
-                .show(ui.ctx(), |ui| {
-                    ui.horizontal(|ui| {
-                        ui.label("Neuer Name:");
-                        ui.text_edit_singleline(new_name);
-                    });
-                    ui.separator();
-                    ui.horizontal(|ui| {
-                        if ui.button("✓ Speichern").clicked() {
-                            if idx < self.connections.len() {
-                                self.connections[idx].name = new_name.clone();
-                                new_status_message = Some(format!("Verbindung umbenannt zu '{}'", new_name));
-                            }
-                            close_rename_dialog = true;
-                        }
-                        if ui.button("✗ Abbrechen").clicked() {
-                            close_rename_dialog = true;
-                        }
-                    });
-                });
-        }
-        if close_rename_dialog {
-            self.rename_dialog = None;
-        }
-        if let Some(msg) = new_status_message {
-            self.status_message = msg;
-        }
+    fn names(app: &GuiApp, pane: PaneId) -> Vec<String> {
+        app.pane_tabs(pane).map_or(Vec::new(), |(tabs, _)| tabs.iter().map(|t| t.name.clone()).collect())
+    }
+
+    #[test]
+    fn nested_splits_collapse_and_renumber() {
+        use PaneId::{Main, Split};
+        let mut layout = SplitNode::Leaf(Main);
+        assert!(layout.split(Main, SplitOrientation::Horizontal, Split(0)));
+        assert!(layout.split(Split(0), SplitOrientation::Vertical, Split(1)));
+        assert!(layout.split(Main, SplitOrientation::Vertical, Split(2)));
+        assert!(!layout.split(Split(7), SplitOrientation::Vertical, Split(3)));
+        assert_eq!(leaves(&layout), [Main, Split(2), Split(0), Split(1)]);
+
+        assert!(layout.remove(Split(0)));
+        layout.renumber_after(0);
+        assert_eq!(leaves(&layout), [Main, Split(1), Split(0)]);
+        assert!(layout.remove(Main));
+        assert_eq!(leaves(&layout), [Split(1), Split(0)]);
+        assert!(!layout.remove(Split(5)));
+    }
+
+    #[test]
+    fn closing_panes_keeps_focus_on_a_neighbour() {
+        let mut app = app(&["main"]);
+        app.add_pane(PaneId::Main, SplitOrientation::Horizontal, tab("a"));
+        app.add_pane(PaneId::Split(0), SplitOrientation::Vertical, tab("b"));
+        assert_eq!(app.focused_pane(), PaneId::Split(1));
+
+        app.close_pane(PaneId::Split(0));
+        assert_eq!(app.split_layout.as_ref().map(leaves), Some(vec![PaneId::Main, PaneId::Split(0)]));
+        assert_eq!(app.focused_pane(), PaneId::Split(0));
+        assert_eq!(names(&app, PaneId::Split(0)), ["b"]);
+
+        // The last split takes over the main pane's place and tabs
+        app.close_pane(PaneId::Main);
+        assert!(app.split_layout.is_none() && app.split_panes.is_empty());
+        assert_eq!(app.focused_pane(), PaneId::Main);
+        assert_eq!(names(&app, PaneId::Main), ["b"]);
+    }
+
+    #[test]
+    fn tabs_move_to_new_panes_and_panes_swap() {
+        let mut app = app(&["one", "two"]);
+        app.move_tab_to_new_pane(PaneId::Main);
+        assert_eq!(names(&app, PaneId::Main), ["one"]);
+        assert_eq!(names(&app, PaneId::Split(0)), ["two"]);
+        assert_eq!(app.focused_pane(), PaneId::Split(0));
+
+        // A pane's only tab stays where it is
+        app.move_tab_to_new_pane(PaneId::Split(0));
+        assert_eq!(app.split_panes.len(), 1);
+
+        app.add_pane(PaneId::Split(0), SplitOrientation::Vertical, tab("three"));
+        app.swap_pane(PaneId::Main);
+        assert_eq!(app.split_layout.as_ref().map(leaves), Some(vec![PaneId::Split(0), PaneId::Main, PaneId::Split(1)]));
+        // The last pane swaps with the first
+        app.swap_pane(PaneId::Split(1));
+        assert_eq!(app.split_layout.as_ref().map(leaves), Some(vec![PaneId::Split(1), PaneId::Main, PaneId::Split(0)]));
     }
 }
@@ -0,0 +1,583 @@
+//! Multi-exec: run one command on many saved SSH hosts in parallel and
+//! compare the results.
+
+use crate::config::ssh::SshConnection;
+use crate::config::vault::CredentialStore;
+use crate::ssh::{open_session_until, remaining};
+use eframe::egui;
+use serde::Serialize;
+use std::collections::{BTreeSet, VecDeque};
+use std::io::{ErrorKind, Read};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Hosts contacted at the same time; the rest wait for a free worker.
+const WORKERS: usize = 8;
+
+#[derive(Clone, Serialize)]
+struct HostRun {
+    name: String,
+    target: String,
+    #[serde(skip)]
+    running: bool,
+    exit_code: Option<i32>,
+    duration_ms: Option<u128>,
+    stdout: String,
+    stderr: String,
+    error: Option<String>,
+}
+
+impl HostRun {
+    /// Fingerprint used to group hosts that produced the same result.
+    fn outcome(&self) -> (Option<i32>, &str, Option<&str>) {
+        (self.exit_code, self.stdout.as_str(), self.error.as_deref())
+    }
+}
+
+struct ExecResult {
+    exit_code: i32,
+    stdout: String,
+    stderr: String,
+}
+
+/// Run `command` on `conn`, giving up when `timeout` has passed since the
+/// start, connecting included.
+fn run_remote(conn: &SshConnection, command: &str, timeout: Duration) -> anyhow::Result<ExecResult> {
+    let deadline = Instant::now() + timeout;
+    let sess = open_session_until(conn, deadline)?;
+    let mut channel = sess.channel_session()?;
+    channel.exec(command)?;
+
+    // Drain stdout and stderr together: a command that fills the stderr
+    // window while we wait on stdout would otherwise never finish
+    sess.set_blocking(false);
+    let (mut stdout, mut stderr) = (Vec::new(), Vec::new());
+    let mut buf = [0u8; 8192];
+    loop {
+        let mut got_data = false;
+        for (mut stream, sink) in [(channel.stream(0), &mut stdout), (channel.stderr(), &mut stderr)] {
+            match stream.read(&mut buf) {
+                Ok(n) => {
+                    sink.extend_from_slice(&buf[..n]);
+                    got_data |= n > 0;
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+        if !got_data {
+            if channel.eof() {
+                break;
+            }
+            remaining(deadline)?;
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+    sess.set_blocking(true);
+    sess.set_timeout(remaining(deadline)?.as_millis().clamp(1, u32::MAX as u128) as u32);
+    channel.wait_close()?;
+    Ok(ExecResult {
+        exit_code: channel.exit_status()?,
+        stdout: String::from_utf8_lossy(&stdout).into_owned(),
+        stderr: String::from_utf8_lossy(&stderr).into_owned(),
+    })
+}
+
+pub(super) struct MultiExec {
+    selected: BTreeSet<String>, // names of the chosen connections
+    command: String,
+    fallback_password: String,
+    timeout_secs: u32,
+    runs: Vec<HostRun>,
+    rx: Option<Receiver<(usize, HostRun)>>,
+    started: Option<Instant>,
+    diff_base: Option<usize>,
+    diff_with: Option<usize>,
+    diff: Option<DiffCache>,
+    generation: u64, // bumped whenever a run changes
+    status_message: String,
+}
+
+/// Diff of two runs' output, kept until the pair or their results change.
+struct DiffCache {
+    key: (usize, usize, u64),
+    lines: Vec<(DiffTag, String)>,
+}
+
+impl Default for MultiExec {
+    fn default() -> Self {
+        Self {
+            selected: BTreeSet::new(),
+            command: String::new(),
+            fallback_password: String::new(),
+            timeout_secs: 30,
+            runs: Vec::new(),
+            rx: None,
+            started: None,
+            diff_base: None,
+            diff_with: None,
+            diff: None,
+            generation: 0,
+            status_message: String::new(),
+        }
+    }
+}
+
+impl MultiExec {
+    fn is_running(&self) -> bool {
+        self.runs.iter().any(|r| r.running)
+    }
+
+    /// Forget hosts deleted or renamed in the SSH manager, so a selection
+    /// never carries over to another connection.
+    fn forget_missing(&mut self, connections: &[SshConnection]) {
+        self.selected.retain(|name| connections.iter().any(|c| c.name == *name));
+    }
+
+    fn set_selected(&mut self, connections: &[SshConnection], select: impl Fn(&SshConnection) -> bool) {
+        self.selected = connections.iter().filter(|c| select(c)).map(|c| c.name.clone()).collect();
+    }
+
+    fn chosen<'a>(&'a self, connections: &'a [SshConnection]) -> impl Iterator<Item = &'a SshConnection> {
+        connections.iter().filter(|c| self.selected.contains(&c.name))
+    }
+
+    fn start(&mut self, connections: &[SshConnection], credentials: &CredentialStore) {
+        let (tx, rx) = mpsc::channel();
+        self.runs.clear();
+        self.diff_base = None;
+        self.diff_with = None;
+        self.generation += 1;
+        let mut jobs = VecDeque::new();
+        let timeout = Duration::from_secs(self.timeout_secs.max(1) as u64);
+        let chosen: Vec<SshConnection> = self.chosen(connections).cloned().collect();
+        for mut conn in chosen {
+            if conn.password.is_empty() {
+                conn.password = credentials
+                    .lookup(&conn.credential_key())
+                    .unwrap_or_else(|| self.fallback_password.clone());
+            }
+            let idx = self.runs.len();
+            let run = HostRun {
+                name: conn.name.clone(),
+                target: format!("{}@{}:{}", conn.username, conn.host, conn.port),
+                running: true,
+                exit_code: None,
+                duration_ms: None,
+                stdout: String::new(),
+                stderr: String::new(),
+                error: None,
+            };
+            self.runs.push(run.clone());
+            jobs.push_back((idx, conn, run));
+        }
+        let workers = jobs.len().min(WORKERS);
+        let jobs = Arc::new(Mutex::new(jobs));
+        for _ in 0..workers {
+            let (jobs, tx) = (Arc::clone(&jobs), tx.clone());
+            let command = self.command.clone();
+            thread::spawn(move || loop {
+                let Some((idx, conn, mut run)) = jobs.lock().unwrap().pop_front() else {
+                    break;
+                };
+                let begin = Instant::now();
+                match run_remote(&conn, &command, timeout) {
+                    Ok(result) => {
+                        run.exit_code = Some(result.exit_code);
+                        run.stdout = result.stdout;
+                        run.stderr = result.stderr;
+                    }
+                    Err(e) => run.error = Some(e.to_string()),
+                }
+                run.running = false;
+                run.duration_ms = Some(begin.elapsed().as_millis());
+                if tx.send((idx, run)).is_err() {
+                    break;
+                }
+            });
+        }
+        self.rx = Some(rx);
+        self.started = Some(Instant::now());
+    }
+
+    fn poll(&mut self) {
+        if let Some(rx) = &self.rx {
+            for (idx, run) in rx.try_iter() {
+                if let Some(slot) = self.runs.get_mut(idx) {
+                    *slot = run;
+                    self.generation += 1;
+                }
+            }
+        }
+        if !self.is_running() {
+            self.rx = None;
+        }
+    }
+
+    /// Variant name per run: hosts with identical results share a name.
+    fn variants(&self) -> Vec<String> {
+        let mut seen: Vec<(Option<i32>, &str, Option<&str>)> = Vec::new();
+        self.runs
+            .iter()
+            .map(|run| {
+                let key = run.outcome();
+                let pos = seen.iter().position(|k| *k == key).unwrap_or_else(|| {
+                    seen.push(key);
+                    seen.len() - 1
+                });
+                variant_name(pos)
+            })
+            .collect()
+    }
+
+    fn export_json(&self, path: &std::path::Path) -> Result<(), String> {
+        let content = serde_json::to_string_pretty(&self.runs).map_err(|e| e.to_string())?;
+        std::fs::write(path, content).map_err(|e| e.to_string())
+    }
+
+    fn export_csv(&self, path: &std::path::Path) -> Result<(), String> {
+        let field = csv_field;
+        let mut out = String::from("name,target,exit_code,duration_ms,stdout,stderr,error\n");
+        for run in &self.runs {
+            out.push_str(&[
+                field(&run.name),
+                field(&run.target),
+                run.exit_code.map(|c| c.to_string()).unwrap_or_default(),
+                run.duration_ms.map(|d| d.to_string()).unwrap_or_default(),
+                field(&run.stdout),
+                field(&run.stderr),
+                field(run.error.as_deref().unwrap_or("")),
+            ].join(","));
+            out.push('\n');
+        }
+        std::fs::write(path, out).map_err(|e| e.to_string())
+    }
+
+    pub(super) fn ui(&mut self, ui: &mut egui::Ui, connections: &[SshConnection], credentials: &CredentialStore) {
+        self.poll();
+        if self.is_running() {
+            ui.ctx().request_repaint_after(Duration::from_millis(100));
+        }
+        self.forget_missing(connections);
+
+        // Host selection
+        ui.group(|ui| {
+            ui.horizontal(|ui| {
+                ui.label(egui::RichText::new(rust_i18n::t!("multi_exec.hosts")).strong());
+                if ui.small_button(rust_i18n::t!("multi_exec.select_all")).clicked() {
+                    self.set_selected(connections, |_| true);
+                }
+                if ui.small_button(rust_i18n::t!("multi_exec.select_none")).clicked() {
+                    self.selected.clear();
+                }
+                let mut groups: Vec<&str> = connections.iter().map(|c| c.group.as_str()).filter(|g| !g.is_empty()).collect();
+                groups.sort();
+                groups.dedup();
+                ui.menu_button(format!("📁 {} ▾", rust_i18n::t!("multi_exec.select_group")), |ui| {
+                    for group in groups {
+                        if ui.button(group).clicked() {
+                            self.set_selected(connections, |conn| conn.group == group);
+                            ui.close_menu();
+                        }
+                    }
+                    if ui.button(format!("★ {}", rust_i18n::t!("ssh.favorites"))).clicked() {
+                        self.set_selected(connections, |conn| conn.favorite);
+                        ui.close_menu();
+                    }
+                });
+            });
+            egui::ScrollArea::vertical().id_source("multi_exec_hosts").max_height(120.0).show(ui, |ui| {
+                ui.horizontal_wrapped(|ui| {
+                    for conn in connections {
+                        let mut selected = self.selected.contains(&conn.name);
+                        if ui.checkbox(&mut selected, format!("{} ({})", conn.name, conn.host)).changed() {
+                            match selected {
+                                true => self.selected.insert(conn.name.clone()),
+                                false => self.selected.remove(&conn.name),
+                            };
+                        }
+                    }
+                });
+            });
+            if connections.is_empty() {
+                ui.colored_label(egui::Color32::GRAY, rust_i18n::t!("multi_exec.no_connections").as_ref());
+            }
+        });
+
+        // Command
+        let count = self.chosen(connections).count();
+        ui.horizontal(|ui| {
+            ui.label("$");
+            let response = ui.add(
+                egui::TextEdit::singleline(&mut self.command)
+                    .font(egui::TextStyle::Monospace)
+                    .desired_width(ui.available_width() - 260.0)
+                    .hint_text("uptime"),
+            );
+            let submitted = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+            ui.label(rust_i18n::t!("multi_exec.timeout").as_ref());
+            ui.add(egui::DragValue::new(&mut self.timeout_secs).clamp_range(1..=3600).suffix(" s"));
+            let can_run = count > 0 && !self.command.trim().is_empty() && !self.is_running();
+            if ui.add_enabled(can_run, egui::Button::new(format!("▶ {} ({})", rust_i18n::t!("multi_exec.run"), count))).clicked()
+                || (submitted && can_run)
+            {
+                self.start(connections, credentials);
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label(rust_i18n::t!("multi_exec.fallback_password").as_ref());
+            ui.add(egui::TextEdit::singleline(&mut self.fallback_password).password(true).desired_width(160.0));
+        });
+
+        if self.runs.is_empty() {
+            return;
+        }
+        ui.separator();
+
+        // Summary + export
+        let done = self.runs.iter().filter(|r| !r.running).count();
+        let ok = self.runs.iter().filter(|r| r.exit_code == Some(0)).count();
+        ui.horizontal(|ui| {
+            let elapsed = self.started.map(|s| s.elapsed().as_secs_f32()).unwrap_or(0.0);
+            ui.label(format!("{}/{} • ✓ {} • ✗ {} • {:.1}s", done, self.runs.len(), ok, done - ok, elapsed));
+            ui.separator();
+            if ui.add_enabled(!self.is_running(), egui::Button::new("📤 JSON")).clicked() {
+                if let Some(path) = rfd::FileDialog::new().add_filter("JSON", &["json"]).set_file_name("multi-exec.json").save_file() {
+                    self.status_message = match self.export_json(&path) {
+                        Ok(()) => format!("✓ {}", path.display()),
+                        Err(e) => format!("❌ {}", e),
+                    };
+                }
+            }
+            if ui.add_enabled(!self.is_running(), egui::Button::new("📤 CSV")).clicked() {
+                if let Some(path) = rfd::FileDialog::new().add_filter("CSV", &["csv"]).set_file_name("multi-exec.csv").save_file() {
+                    self.status_message = match self.export_csv(&path) {
+                        Ok(()) => format!("✓ {}", path.display()),
+                        Err(e) => format!("❌ {}", e),
+                    };
+                }
+            }
+            if !self.status_message.is_empty() {
+                ui.label(&self.status_message);
+            }
+        });
+
+        // Results table
+        let variants = self.variants();
+        let distinct = variants.iter().collect::<std::collections::HashSet<_>>().len();
+        if distinct > 1 {
+            ui.colored_label(egui::Color32::YELLOW, format!("⚠ {} {}", distinct, rust_i18n::t!("multi_exec.variants")));
+        }
+        egui::ScrollArea::vertical().id_source("multi_exec_results").auto_shrink([false, false]).show(ui, |ui| {
+            egui::Grid::new("multi_exec_grid").striped(true).num_columns(6).show(ui, |ui| {
+                ui.strong("Host");
+                ui.strong(rust_i18n::t!("multi_exec.exit_code").as_ref());
+                ui.strong(rust_i18n::t!("multi_exec.duration").as_ref());
+                ui.strong(rust_i18n::t!("multi_exec.variant").as_ref());
+                ui.strong(rust_i18n::t!("multi_exec.output").as_ref());
+                ui.strong(rust_i18n::t!("multi_exec.diff").as_ref());
+                ui.end_row();
+                for (idx, run) in self.runs.iter().enumerate() {
+                    ui.label(&run.name).on_hover_text(&run.target);
+                    if run.running {
+                        ui.spinner();
+                    } else if let Some(err) = &run.error {
+                        ui.colored_label(egui::Color32::LIGHT_RED, "✗").on_hover_text(err);
+                    } else {
+                        let code = run.exit_code.unwrap_or(-1);
+                        let color = if code == 0 { egui::Color32::GREEN } else { egui::Color32::LIGHT_RED };
+                        ui.colored_label(color, code.to_string());
+                    }
+                    ui.label(run.duration_ms.map(|d| format!("{} ms", d)).unwrap_or_default());
+                    ui.label(&variants[idx]);
+                    let preview = run.error.clone().unwrap_or_else(|| {
+                        let text = if run.stdout.is_empty() { &run.stderr } else { &run.stdout };
+                        text.lines().next().unwrap_or("").chars().take(80).collect()
+                    });
+                    ui.monospace(preview).on_hover_ui(|ui| {
+                        ui.monospace(&run.stdout);
+                        if !run.stderr.is_empty() {
+                            ui.colored_label(egui::Color32::LIGHT_RED, &run.stderr);
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        if ui.selectable_label(self.diff_base == Some(idx), rust_i18n::t!("multi_exec.base")).clicked() {
+                            self.diff_base = Some(idx);
+                        }
+                        if ui.selectable_label(self.diff_with == Some(idx), rust_i18n::t!("multi_exec.compare")).clicked() {
+                            self.diff_with = Some(idx);
+                        }
+                    });
+                    ui.end_row();
+                }
+            });
+
+            // Line diff between two hosts
+            if let (Some(a), Some(b)) = (self.diff_base, self.diff_with) {
+                if let (Some(base), Some(other)) = (self.runs.get(a), self.runs.get(b)) {
+                    let key = (a, b, self.generation);
+                    if self.diff.as_ref().is_none_or(|d| d.key != key) {
+                        let lines = diff_lines(&base.stdout, &other.stdout).into_iter().map(|(tag, l)| (tag, l.to_string())).collect();
+                        self.diff = Some(DiffCache { key, lines });
+                    }
+                    ui.separator();
+                    ui.label(egui::RichText::new(format!("{} ⇄ {}", base.name, other.name)).strong());
+                    for (tag, line) in self.diff.iter().flat_map(|d| &d.lines) {
+                        let (prefix, color) = match tag {
+                            DiffTag::Same => (' ', egui::Color32::GRAY),
+                            DiffTag::Removed => ('-', egui::Color32::LIGHT_RED),
+                            DiffTag::Added => ('+', egui::Color32::LIGHT_GREEN),
+                        };
+                        ui.colored_label(color, egui::RichText::new(format!("{} {}", prefix, line)).monospace());
+                    }
+                }
+            }
+        });
+    }
+}
+
+/// Spreadsheet-style name for the variant at `pos`: A … Z, AA, AB, …
+fn variant_name(mut pos: usize) -> String {
+    let mut name = Vec::new();
+    loop {
+        name.push(b'A' + (pos % 26) as u8);
+        if pos < 26 {
+            break;
+        }
+        pos = pos / 26 - 1;
+    }
+    name.reverse();
+    String::from_utf8(name).unwrap()
+}
+
+/// A CSV field, always quoted so commas and line breaks in output survive.
+fn csv_field(s: &str) -> String {
+    format!("\"{}\"", s.replace('"', "\"\""))
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum DiffTag {
+    Same,
+    Removed,
+    Added,
+}
+
+/// Line-based LCS diff; large outputs fall back to a plain before/after listing.
+fn diff_lines<'a>(a: &'a str, b: &'a str) -> Vec<(DiffTag, &'a str)> {
+    let a: Vec<&str> = a.lines().collect();
+    let b: Vec<&str> = b.lines().collect();
+    if a.len() * b.len() > 4_000_000 {
+        return a.iter().map(|l| (DiffTag::Removed, *l)).chain(b.iter().map(|l| (DiffTag::Added, *l))).collect();
+    }
+    let mut lcs = vec![vec![0u32; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] { lcs[i + 1][j + 1] + 1 } else { lcs[i + 1][j].max(lcs[i][j + 1]) };
+        }
+    }
+    let (mut i, mut j) = (0, 0);
+    let mut out = Vec::new();
+    while i < a.len() && j < b.len() {
+        if a[i] == b[j] {
+            out.push((DiffTag::Same, a[i]));
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            out.push((DiffTag::Removed, a[i]));
+            i += 1;
+        } else {
+            out.push((DiffTag::Added, b[j]));
+            j += 1;
+        }
+    }
+    out.extend(a[i..].iter().map(|l| (DiffTag::Removed, *l)));
+    out.extend(b[j..].iter().map(|l| (DiffTag::Added, *l)));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(name: &str, exit_code: Option<i32>, stdout: &str) -> HostRun {
+        HostRun {
+            name: name.into(),
+            target: format!("me@{}:22", name),
+            running: false,
+            exit_code,
+            duration_ms: Some(1),
+            stdout: stdout.into(),
+            stderr: String::new(),
+            error: None,
+        }
+    }
+
+    #[test]
+    fn diff_keeps_common_lines() {
+        use DiffTag::*;
+        assert_eq!(
+            diff_lines("a\nb\nc\n", "a\nx\nc\nd\n"),
+            [(Same, "a"), (Removed, "b"), (Added, "x"), (Same, "c"), (Added, "d")]
+        );
+        assert_eq!(diff_lines("same\n", "same\n"), [(Same, "same")]);
+        assert_eq!(diff_lines("", "new"), [(Added, "new")]);
+        assert!(diff_lines("", "").is_empty());
+    }
+
+    #[test]
+    fn identical_results_share_a_variant() {
+        let mut exec = MultiExec {
+            runs: vec![run("a", Some(0), "ok"), run("b", Some(1), "ok"), run("c", Some(0), "ok"), run("d", Some(0), "other")],
+            ..Default::default()
+        };
+        exec.runs.push(HostRun { error: Some("refused".into()), ..run("e", None, "") });
+        assert_eq!(exec.variants(), ["A", "B", "A", "C", "D"]);
+
+        exec.runs = (0..30).map(|i| run("h", Some(0), &i.to_string())).collect();
+        let variants = exec.variants();
+        assert_eq!((variants[25].as_str(), variants[26].as_str(), variants[29].as_str()), ("Z", "AA", "AD"));
+        assert_eq!(variant_name(26 + 26 * 26), "AAA");
+    }
+
+    fn connection(name: &str) -> SshConnection {
+        SshConnection {
+            name: name.into(),
+            host: format!("{name}.example.com"),
+            port: 22,
+            username: "me".into(),
+            identity_file: None,
+            password: String::new(),
+            group: String::new(),
+            tags: Vec::new(),
+            favorite: false,
+            color: None,
+            profile: None,
+        }
+    }
+
+    #[test]
+    fn selection_follows_connections_not_positions() {
+        let mut connections: Vec<SshConnection> = ["web", "db", "cache"].into_iter().map(connection).collect();
+        let mut exec = MultiExec::default();
+        exec.set_selected(&connections, |c| c.name != "web");
+
+        // Deleted in the SSH manager while the dialog is open
+        connections.remove(1);
+        exec.forget_missing(&connections);
+        let chosen: Vec<&str> = exec.chosen(&connections).map(|c| c.name.as_str()).collect();
+        assert_eq!(chosen, ["cache"]);
+
+        // A new connection under the old name is not picked up
+        connections.insert(0, connection("db"));
+        assert_eq!(exec.chosen(&connections).count(), 1);
+    }
+
+    #[test]
+    fn csv_fields_are_quoted() {
+        assert_eq!(csv_field("plain"), "\"plain\"");
+        assert_eq!(csv_field("say \"hi\", twice"), "\"say \"\"hi\"\", twice\"");
+        assert_eq!(csv_field("two\nlines"), "\"two\nlines\"");
+    }
+}
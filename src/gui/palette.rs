@@ -0,0 +1,192 @@
+//! Command palette: fuzzy search over everything the window can do, with
+//! the keys bound to each command and recently used commands first.
+
+use crate::config::{self, keybindings::Action};
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// How many commands count as recently used.
+const RECENT_LIMIT: usize = 20;
+/// Rows shown below the search field.
+const VISIBLE_ROWS: usize = 12;
+
+#[derive(Clone, PartialEq)]
+pub(super) enum Command {
+    Action(Action),
+    View(usize), // sidebar entry, as in `GuiApp::selected`
+    ToggleSettings,
+    ToggleSidebar,
+    Theme(String),
+    Connect(String),         // saved SSH connection, by name
+    Profile(Option<String>), // new tab with a profile; None = plain shell
+    Layout(String),
+    Snippet(String), // by name; asks for its placeholders first
+    NewMarkdown,
+    OpenMarkdown,
+}
+
+pub(super) struct Entry {
+    pub command: Command,
+    pub id: String, // stable across runs, for the recent list
+    pub label: String,
+    pub keys: Option<String>,
+}
+
+impl Entry {
+    pub fn new(command: Command, id: impl Into<String>, label: impl Into<String>) -> Self {
+        Entry { command, id: id.into(), label: label.into(), keys: None }
+    }
+
+    pub fn with_keys(mut self, keys: Option<String>) -> Self {
+        self.keys = keys;
+        self
+    }
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct RecentFile {
+    #[serde(default)]
+    recent: Vec<String>,
+}
+
+fn recent_path() -> PathBuf {
+    config::config_dir().join("palette.toml")
+}
+
+#[derive(Default)]
+pub(super) struct Palette {
+    open: bool,
+    query: String,
+    selected: usize,
+    focus: bool,         // one-shot: focus the search field
+    recent: Vec<String>, // entry ids, most recent first
+    layouts: Vec<String>, // saved layouts, listed when the palette opens
+}
+
+impl Palette {
+    pub fn load() -> Self {
+        let recent = std::fs::read_to_string(recent_path())
+            .ok()
+            .and_then(|content| toml::from_str::<RecentFile>(&content).ok())
+            .map(|file| file.recent)
+            .unwrap_or_default();
+        Palette { recent, ..Default::default() }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+        self.query.clear();
+        self.selected = 0;
+        self.focus = self.open;
+        self.layouts = if self.open { config::layout::list_layouts() } else { Vec::new() };
+    }
+
+    pub fn layouts(&self) -> &[String] {
+        &self.layouts
+    }
+
+    /// Entries matching the query, best first. Recently used ones lead while
+    /// the query is empty and get a bonus while typing.
+    fn rank(&self, entries: Vec<Entry>) -> Vec<Entry> {
+        let recency = |entry: &Entry| self.recent.iter().position(|id| *id == entry.id);
+        let mut ranked: Vec<(i64, Entry)> = entries
+            .into_iter()
+            .filter_map(|entry| {
+                let score = crate::fuzzy::score(&self.query, &entry.label)?;
+                let bonus = recency(&entry).map_or(0, |i| (RECENT_LIMIT - i) as i64);
+                let score = if self.query.trim().is_empty() { bonus } else { score + bonus };
+                Some((score, entry))
+            })
+            .collect();
+        // Stable, so equal scores keep the order the commands were listed in
+        ranked.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+        ranked.into_iter().map(|(_, entry)| entry).collect()
+    }
+
+    fn remember(&mut self, id: &str) {
+        self.recent.retain(|r| r != id);
+        self.recent.insert(0, id.to_string());
+        self.recent.truncate(RECENT_LIMIT);
+        let file = RecentFile { recent: self.recent.clone() };
+        if let Ok(content) = toml::to_string(&file) {
+            let _ = config::write_atomic(&recent_path(), &content);
+        }
+    }
+
+    /// Draw the palette over the window and return the command picked with
+    /// Enter or a click. Esc closes it.
+    pub fn ui(&mut self, ctx: &egui::Context, entries: Vec<Entry>) -> Option<Command> {
+        if !self.open {
+            return None;
+        }
+        let entries = self.rank(entries);
+        let (up, down, enter, escape) = ctx.input_mut(|i| {
+            (
+                i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowUp),
+                i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowDown),
+                i.consume_key(egui::Modifiers::NONE, egui::Key::Enter),
+                i.consume_key(egui::Modifiers::NONE, egui::Key::Escape),
+            )
+        });
+        if escape {
+            self.toggle();
+            return None;
+        }
+        if up {
+            self.selected = self.selected.saturating_sub(1);
+        }
+        if down {
+            self.selected += 1;
+        }
+        self.selected = self.selected.min(entries.len().saturating_sub(1));
+
+        let mut picked = enter.then_some(self.selected);
+        egui::Area::new(egui::Id::new("command_palette"))
+            .anchor(egui::Align2::CENTER_TOP, egui::vec2(0.0, 60.0))
+            .order(egui::Order::Foreground)
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.set_width(480.0);
+                    let response = ui.add(
+                        egui::TextEdit::singleline(&mut self.query)
+                            .hint_text(rust_i18n::t!("palette.hint"))
+                            .desired_width(f32::INFINITY),
+                    );
+                    if std::mem::take(&mut self.focus) {
+                        response.request_focus();
+                    }
+                    if response.changed() {
+                        self.selected = 0;
+                    }
+                    ui.separator();
+                    if entries.is_empty() {
+                        ui.label(egui::RichText::new(rust_i18n::t!("palette.no_match")).color(egui::Color32::GRAY));
+                    }
+                    // Keep the selection in view
+                    let first = self.selected.saturating_sub(VISIBLE_ROWS - 1);
+                    for (idx, entry) in entries.iter().enumerate().skip(first).take(VISIBLE_ROWS) {
+                        ui.horizontal(|ui| {
+                            if ui.selectable_label(idx == self.selected, &entry.label).clicked() {
+                                picked = Some(idx);
+                            }
+                            if let Some(keys) = &entry.keys {
+                                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                                    ui.label(egui::RichText::new(keys).small().color(egui::Color32::GRAY));
+                                });
+                            }
+                        });
+                    }
+                });
+            });
+
+        let entry = entries.into_iter().nth(picked?)?;
+        self.remember(&entry.id);
+        self.toggle();
+        Some(entry.command)
+    }
+}
@@ -0,0 +1,135 @@
+//! Requests from `termix msg`, answered between frames with the same
+//! operations the tab bar, splits and menus use.
+
+use super::{GuiApp, PaneId, SplitOrientation, TerminalView};
+use crate::ipc::{Listener, Request, Response, TabInfo};
+use eframe::egui;
+use std::path::Path;
+
+impl GuiApp {
+    /// Open the socket; shells started from now on get its path.
+    pub(super) fn listen_for_remote(&mut self, ctx: &egui::Context) {
+        let ctx = ctx.clone();
+        match Listener::bind(move || ctx.request_repaint()) {
+            Ok(listener) => self.remote = Some(listener),
+            Err(e) => eprintln!("{}: {}", rust_i18n::t!("ipc.listen_failed"), e),
+        }
+    }
+
+    pub(super) fn handle_remote_requests(&mut self) {
+        while let Some(pending) = self.remote.as_ref().and_then(Listener::try_recv) {
+            let response = self.remote_request(&pending.request).unwrap_or_else(Response::error);
+            pending.reply(response);
+        }
+    }
+
+    fn remote_request(&mut self, request: &Request) -> Result<Response, String> {
+        match request {
+            Request::List => Ok(Response { tabs: Some(self.tab_infos()), ..Response::ok() }),
+            Request::NewTab { title, cwd, command } => {
+                let pane = self.focused_pane();
+                let count = self.pane_tabs(pane).map_or(0, |(tabs, _)| tabs.len());
+                let name = title.clone().unwrap_or_else(|| format!("Terminal {}", count + 1));
+                let term = self.remote_terminal(&name, command.as_deref(), cwd.as_deref())?;
+                let id = term.id;
+                let tab = self.styled_tab(&name, term);
+                self.insert_tab(pane, count, tab);
+                self.selected = 0;
+                Ok(Response { tab: Some(id), ..Response::ok() })
+            }
+            Request::Split { vertical, cwd, command } => {
+                let name = format!("Split {}", self.split_panes.len() + 1);
+                let term = self.remote_terminal(&name, command.as_deref(), cwd.as_deref())?;
+                let id = term.id;
+                let orientation = if *vertical { SplitOrientation::Vertical } else { SplitOrientation::Horizontal };
+                let tab = self.styled_tab(&name, term);
+                self.add_pane(self.focused_pane(), orientation, tab);
+                self.selected = 0;
+                Ok(Response { tab: Some(id), ..Response::ok() })
+            }
+            Request::SendText { tab, text } => {
+                self.remote_tab(*tab)?.view.write_raw(text.as_bytes());
+                Ok(Response::ok())
+            }
+            Request::GetText { tab, lines } => {
+                let text = self.remote_tab(*tab)?.view.session.term.tail(lines.unwrap_or(usize::MAX));
+                Ok(Response { text: Some(text), ..Response::ok() })
+            }
+            Request::SetTitle { tab, title } => {
+                let (pane, idx) = self.find_tab(*tab)?;
+                if let Some(tab) = self.pane_tabs_mut(pane).and_then(|(tabs, _)| tabs.get_mut(idx)) {
+                    tab.name = title.clone();
+                }
+                Ok(Response::ok())
+            }
+            Request::FocusTab { tab } => {
+                let (pane, idx) = self.find_tab(Some(*tab))?;
+                self.apply_tab_action(pane, super::TabAction::Select(idx));
+                self.selected = 0;
+                Ok(Response::ok())
+            }
+            Request::CloseTab { tab } => {
+                let (pane, idx) = self.find_tab(*tab)?;
+                if self.split_layout.is_none() && self.terminals.len() <= 1 {
+                    return Err(rust_i18n::t!("ipc.last_tab").into());
+                }
+                self.close_tab(pane, idx);
+                Ok(Response::ok())
+            }
+        }
+    }
+
+    fn remote_terminal(&self, name: &str, command: Option<&str>, cwd: Option<&str>) -> Result<TerminalView, String> {
+        self.local_terminal(name, self.default_profile(), command, cwd.map(Path::new)).map_err(|e| e.to_string())
+    }
+
+    /// Main pane first, then the splits.
+    fn panes(&self) -> Vec<PaneId> {
+        std::iter::once(PaneId::Main).chain((0..self.split_panes.len()).map(PaneId::Split)).collect()
+    }
+
+    /// Pane and index of the tab with terminal `id`, or of the focused tab.
+    fn find_tab(&self, id: Option<u64>) -> Result<(PaneId, usize), String> {
+        let Some(id) = id else {
+            let pane = self.focused_pane();
+            return match self.pane_tabs(pane) {
+                Some((tabs, active)) if active < tabs.len() => Ok((pane, active)),
+                _ => Err(rust_i18n::t!("ipc.no_tab").into()),
+            };
+        };
+        self.panes()
+            .into_iter()
+            .find_map(|pane| {
+                let (tabs, _) = self.pane_tabs(pane)?;
+                tabs.iter().position(|t| t.terminal.id == id).map(|idx| (pane, idx))
+            })
+            .ok_or_else(|| rust_i18n::t!("ipc.unknown_tab", id = id).into())
+    }
+
+    fn remote_tab(&mut self, id: Option<u64>) -> Result<&mut TerminalView, String> {
+        let (pane, idx) = self.find_tab(id)?;
+        self.pane_tabs_mut(pane)
+            .and_then(|(tabs, _)| tabs.get_mut(idx))
+            .map(|tab| &mut tab.terminal)
+            .ok_or_else(|| rust_i18n::t!("ipc.no_tab").into())
+    }
+
+    fn tab_infos(&self) -> Vec<TabInfo> {
+        let focused = self.focused_pane();
+        let mut infos = Vec::new();
+        for (number, pane) in self.panes().into_iter().enumerate() {
+            let Some((tabs, active)) = self.pane_tabs(pane) else {
+                continue;
+            };
+            infos.extend(tabs.iter().enumerate().map(|(idx, tab)| TabInfo {
+                id: tab.terminal.id,
+                title: tab.name.clone(),
+                pane: number,
+                active: idx == active,
+                focused: idx == active && pane == focused,
+                cwd: tab.terminal.view.session.pty.cwd().map(|d| d.display().to_string()),
+            }));
+        }
+        infos
+    }
+}
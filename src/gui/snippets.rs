@@ -0,0 +1,340 @@
+//! Snippet library in the sidebar: search and tags, the editor, and the
+//! prompt for placeholders before a snippet is typed into the terminal.
+
+use crate::config::snippets::{Library, Placeholder, Snippet};
+use eframe::egui;
+use std::collections::HashMap;
+
+/// Height kept free below the list for the sidebar's bottom buttons.
+const BOTTOM_SPACE: f32 = 140.0;
+
+/// A snippet being edited; list fields are comma-separated text.
+#[derive(Default)]
+struct Draft {
+    index: Option<usize>, // None = new snippet
+    name: String,
+    command: String,
+    description: String,
+    tags: String,
+    hosts: String,
+    error: String,
+}
+
+impl Draft {
+    fn edit(index: usize, snippet: &Snippet) -> Self {
+        Draft {
+            index: Some(index),
+            name: snippet.name.clone(),
+            command: snippet.command.clone(),
+            description: snippet.description.clone(),
+            tags: snippet.tags.join(", "),
+            hosts: snippet.hosts.join(", "),
+            error: String::new(),
+        }
+    }
+
+    fn snippet(&self) -> Snippet {
+        let list = |text: &str| text.split(',').map(str::trim).filter(|s| !s.is_empty()).map(String::from).collect();
+        Snippet {
+            name: self.name.trim().to_string(),
+            command: self.command.clone(),
+            description: self.description.trim().to_string(),
+            tags: list(&self.tags),
+            hosts: list(&self.hosts),
+        }
+    }
+}
+
+/// Values asked for before inserting a snippet with placeholders.
+struct Prompt {
+    snippet: Snippet,
+    values: Vec<(Placeholder, String)>,
+    focus: bool, // one-shot: focus the first field
+}
+
+pub(super) struct SnippetsView {
+    library: Library,
+    query: String,
+    focus: bool, // one-shot: focus the search field
+    search_focused: bool,
+    editing: Option<Draft>,
+    prompt: Option<Prompt>,
+    status: String,
+}
+
+impl SnippetsView {
+    pub fn load() -> Self {
+        let (library, status) = match Library::load() {
+            Ok(library) => (library, String::new()),
+            Err(e) => (Library::default(), e),
+        };
+        SnippetsView {
+            library,
+            query: String::new(),
+            focus: false,
+            search_focused: false,
+            editing: None,
+            prompt: None,
+            status,
+        }
+    }
+
+    /// Snippets offered in a terminal on `host`, for the palette.
+    pub fn offered(&self, host: Option<&str>) -> impl Iterator<Item = &Snippet> + '_ {
+        let host = host.map(String::from);
+        self.library.snippets.iter().filter(move |s| s.applies_to(host.as_deref()))
+    }
+
+    /// Whether the search field, the editor or the prompt takes the keyboard.
+    pub fn takes_keys(&self) -> bool {
+        self.search_focused || self.editing.is_some() || self.prompt.is_some()
+    }
+
+    pub fn focus_search(&mut self) {
+        self.focus = true;
+    }
+
+    /// Insert the snippet called `name`: its command right away when it has
+    /// no placeholders, otherwise after the prompt (see `prompt_ui`).
+    pub fn start(&mut self, name: &str) -> Option<String> {
+        let snippet = self.library.find(name)?.clone();
+        let placeholders = snippet.placeholders();
+        if placeholders.is_empty() {
+            return Some(snippet.command);
+        }
+        let values = placeholders.into_iter().map(|p| (p.clone(), p.default)).collect();
+        self.prompt = Some(Prompt { snippet, values, focus: true });
+        None
+    }
+
+    /// The button standing in for the list while the sidebar is collapsed;
+    /// true when clicked.
+    pub fn collapsed_ui(&mut self, ui: &mut egui::Ui, hint: &str) -> bool {
+        self.search_focused = false;
+        let button = egui::Button::new(egui::RichText::new("📋").size(24.0));
+        ui.add_sized([50.0, 40.0], button).on_hover_text(hint).clicked()
+    }
+
+    /// The snippet list for the sidebar, filtered for `host` (None = local
+    /// shell). Returns a command to type into the terminal.
+    pub fn sidebar_ui(&mut self, ui: &mut egui::Ui, host: Option<&str>) -> Option<String> {
+        let mut picked = None;
+        ui.horizontal(|ui| {
+            ui.label(egui::RichText::new(format!("📋 {}", rust_i18n::t!("snippets.title"))).strong());
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                if ui.small_button("➕").on_hover_text(rust_i18n::t!("snippets.new")).clicked() {
+                    self.editing = Some(Draft::default());
+                }
+            });
+        });
+        let response = ui.add(
+            egui::TextEdit::singleline(&mut self.query)
+                .hint_text(rust_i18n::t!("snippets.search_hint"))
+                .desired_width(f32::INFINITY),
+        );
+        if std::mem::take(&mut self.focus) {
+            response.request_focus();
+        }
+        self.search_focused = response.has_focus();
+        let submitted = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+
+        // Tags toggle `#tag` in the query
+        let tags: Vec<String> = self.library.tags().into_iter().map(String::from).collect();
+        if !tags.is_empty() {
+            ui.horizontal_wrapped(|ui| {
+                for tag in tags {
+                    let word = format!("#{}", tag);
+                    let on = self.query.split_whitespace().any(|w| w.eq_ignore_ascii_case(&word));
+                    if ui.selectable_label(on, egui::RichText::new(&word).small()).clicked() {
+                        self.query = if on {
+                            self.query.split_whitespace().filter(|w| !w.eq_ignore_ascii_case(&word)).collect::<Vec<_>>().join(" ")
+                        } else {
+                            format!("{} {}", self.query.trim(), word).trim_start().to_string()
+                        };
+                    }
+                }
+            });
+        }
+
+        let found = self.library.search(&self.query, host);
+        if submitted {
+            if let Some(&idx) = found.first() {
+                let name = self.library.snippets[idx].name.clone();
+                picked = self.start(&name);
+            }
+        }
+        if self.library.snippets.is_empty() {
+            ui.label(egui::RichText::new(rust_i18n::t!("snippets.none")).small().color(egui::Color32::GRAY));
+        } else if found.is_empty() {
+            ui.label(egui::RichText::new(rust_i18n::t!("snippets.no_match")).small().color(egui::Color32::GRAY));
+        }
+        let mut start = None;
+        let mut delete = None;
+        egui::ScrollArea::vertical()
+            .id_source("snippets")
+            .max_height((ui.available_height() - BOTTOM_SPACE).max(60.0))
+            .show(ui, |ui| {
+                for idx in found {
+                    let snippet = &self.library.snippets[idx];
+                    ui.horizontal(|ui| {
+                        let mut hover = snippet.command.clone();
+                        if !snippet.description.is_empty() {
+                            hover = format!("{}\n\n{}", snippet.description, hover);
+                        }
+                        let label = egui::Label::new(&snippet.name).truncate(true).sense(egui::Sense::click());
+                        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                            if ui.small_button("🗑").on_hover_text(rust_i18n::t!("common.delete")).clicked() {
+                                delete = Some(idx);
+                            }
+                            if ui.small_button("✏").on_hover_text(rust_i18n::t!("snippets.edit")).clicked() {
+                                self.editing = Some(Draft::edit(idx, snippet));
+                            }
+                            ui.with_layout(egui::Layout::left_to_right(egui::Align::Center), |ui| {
+                                if ui.add(label).on_hover_text(hover).clicked() {
+                                    start = Some(snippet.name.clone());
+                                }
+                            });
+                        });
+                    });
+                }
+            });
+        if let Some(name) = start {
+            picked = self.start(&name);
+        }
+        if let Some(idx) = delete {
+            let mut library = self.library.clone();
+            library.snippets.remove(idx);
+            self.save(library);
+        }
+        if !self.status.is_empty() {
+            ui.label(egui::RichText::new(&self.status).small().color(egui::Color32::LIGHT_RED));
+        }
+        picked
+    }
+
+    /// The editor window, while a snippet is being edited.
+    pub fn editor_ui(&mut self, ctx: &egui::Context) {
+        let Some(draft) = &mut self.editing else {
+            return;
+        };
+        let title = match draft.index {
+            Some(_) => rust_i18n::t!("snippets.edit"),
+            None => rust_i18n::t!("snippets.new"),
+        };
+        let (mut save, mut close) = (false, false);
+        egui::Window::new(title.as_ref())
+            .collapsible(false)
+            .resizable(true)
+            .default_width(480.0)
+            .show(ctx, |ui| {
+                egui::Grid::new("snippet_editor").num_columns(2).spacing([8.0, 6.0]).show(ui, |ui| {
+                    ui.label(rust_i18n::t!("snippets.name").as_ref());
+                    ui.add(egui::TextEdit::singleline(&mut draft.name).desired_width(f32::INFINITY));
+                    ui.end_row();
+                    ui.label(rust_i18n::t!("snippets.command").as_ref());
+                    ui.add(
+                        egui::TextEdit::multiline(&mut draft.command)
+                            .code_editor()
+                            .desired_rows(3)
+                            .hint_text("kubectl -n {{namespace:default}} logs -f {{pod}}")
+                            .desired_width(f32::INFINITY),
+                    );
+                    ui.end_row();
+                    ui.label(rust_i18n::t!("snippets.description").as_ref());
+                    ui.add(egui::TextEdit::singleline(&mut draft.description).desired_width(f32::INFINITY));
+                    ui.end_row();
+                    ui.label(rust_i18n::t!("snippets.tags").as_ref());
+                    ui.add(egui::TextEdit::singleline(&mut draft.tags).hint_text("k8s, sql").desired_width(f32::INFINITY));
+                    ui.end_row();
+                    ui.label(rust_i18n::t!("snippets.hosts").as_ref());
+                    ui.add(egui::TextEdit::singleline(&mut draft.hosts).hint_text("bastion, prod-*, local").desired_width(f32::INFINITY));
+                    ui.end_row();
+                });
+                ui.label(egui::RichText::new(rust_i18n::t!("snippets.help")).small().color(egui::Color32::GRAY));
+                if !draft.error.is_empty() {
+                    ui.colored_label(egui::Color32::LIGHT_RED, &draft.error);
+                }
+                ui.separator();
+                ui.horizontal(|ui| {
+                    save = ui.button(format!("✓ {}", rust_i18n::t!("common.save"))).clicked();
+                    close = ui.button(format!("✗ {}", rust_i18n::t!("common.cancel"))).clicked();
+                });
+            });
+        if save {
+            let snippet = draft.snippet();
+            let mut library = self.library.clone();
+            match draft.index {
+                Some(idx) => library.snippets[idx] = snippet,
+                None => library.snippets.push(snippet),
+            }
+            if let Err(e) = library.validate() {
+                draft.error = e;
+                return;
+            }
+            close = self.save(library);
+        }
+        if close {
+            self.editing = None;
+        }
+    }
+
+    /// The placeholder prompt, while one is open. Returns the filled command
+    /// once confirmed.
+    pub fn prompt_ui(&mut self, ctx: &egui::Context) -> Option<String> {
+        let prompt = self.prompt.as_mut()?;
+        let escape = ctx.input_mut(|i| i.consume_key(egui::Modifiers::NONE, egui::Key::Escape));
+        let (mut insert, mut close) = (false, escape);
+        egui::Window::new(prompt.snippet.name.as_str())
+            .id(egui::Id::new("snippet_prompt"))
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                egui::Grid::new("snippet_values").num_columns(2).spacing([8.0, 6.0]).show(ui, |ui| {
+                    for (idx, (placeholder, value)) in prompt.values.iter_mut().enumerate() {
+                        ui.label(&placeholder.name);
+                        let response = ui.text_edit_singleline(value);
+                        if idx == 0 && std::mem::take(&mut prompt.focus) {
+                            response.request_focus();
+                        }
+                        insert |= response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                        ui.end_row();
+                    }
+                });
+                ui.separator();
+                ui.label(egui::RichText::new(prompt.snippet.fill(&values(&prompt.values))).monospace());
+                ui.separator();
+                ui.horizontal(|ui| {
+                    insert |= ui.button(format!("⏎ {}", rust_i18n::t!("snippets.insert"))).clicked();
+                    close |= ui.button(format!("✗ {}", rust_i18n::t!("common.cancel"))).clicked();
+                });
+            });
+        if close {
+            self.prompt = None;
+            return None;
+        }
+        if !insert {
+            return None;
+        }
+        let prompt = self.prompt.take()?;
+        Some(prompt.snippet.fill(&values(&prompt.values)))
+    }
+
+    /// Write `library` and keep it when that worked.
+    fn save(&mut self, library: Library) -> bool {
+        match library.save() {
+            Ok(()) => {
+                self.library = library;
+                self.status.clear();
+                true
+            }
+            Err(e) => {
+                self.status = e;
+                false
+            }
+        }
+    }
+}
+
+fn values(values: &[(Placeholder, String)]) -> HashMap<String, String> {
+    values.iter().map(|(p, v)| (p.name.clone(), v.clone())).collect()
+}
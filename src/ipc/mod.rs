@@ -0,0 +1,288 @@
+//! Remote control of a running window (`termix msg`). Every GUI window
+//! listens on its own Unix socket for requests, one JSON object per line,
+//! and answers each with one JSON line. Shells started by the window find
+//! it through `$TERMIX_SOCKET`; elsewhere the window started last answers.
+
+use serde::{Deserialize, Serialize};
+use std::fs::{self, Permissions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+/// Set for the shells of a window to the path of its socket.
+pub const SOCKET_ENV: &str = "TERMIX_SOCKET";
+
+/// How long a client waits for the window, which answers between frames.
+const REPLY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// `tab` is the id from `List`; without one the focused tab is meant.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "cmd", rename_all = "snake_case")]
+pub enum Request {
+    List,
+    NewTab { title: Option<String>, cwd: Option<String>, command: Option<String> },
+    Split {
+        #[serde(default)]
+        vertical: bool, // stacked instead of side by side
+        cwd: Option<String>,
+        command: Option<String>,
+    },
+    SendText { tab: Option<u64>, text: String },
+    GetText { tab: Option<u64>, lines: Option<usize> }, // all lines when not given
+    SetTitle { tab: Option<u64>, title: String },
+    FocusTab { tab: u64 },
+    CloseTab { tab: Option<u64> },
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Response {
+    pub ok: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tab: Option<u64>, // opened by `NewTab` and `Split`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tabs: Option<Vec<TabInfo>>,
+}
+
+impl Response {
+    pub fn ok() -> Self {
+        Response { ok: true, ..Default::default() }
+    }
+
+    pub fn error(message: String) -> Self {
+        Response { ok: false, error: Some(message), ..Default::default() }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TabInfo {
+    pub id: u64,
+    pub title: String,
+    pub pane: usize, // 0 = main pane, then the splits in order of creation
+    pub active: bool, // shown in its pane
+    pub focused: bool,
+    pub cwd: Option<String>,
+}
+
+/// Next to the server socket.
+fn socket_dir() -> PathBuf {
+    let path = crate::server::socket_path();
+    path.parent().map(Path::to_path_buf).unwrap_or_default()
+}
+
+/// A request waiting for the window to answer it.
+pub struct Pending {
+    pub request: Request,
+    reply: Sender<Response>,
+}
+
+impl Pending {
+    pub fn reply(self, response: Response) {
+        let _ = self.reply.send(response);
+    }
+}
+
+/// The socket of one window; removed again when dropped.
+pub struct Listener {
+    path: PathBuf,
+    rx: Receiver<Pending>,
+}
+
+impl Listener {
+    /// Listen on `gui-<pid>.sock`. `wake` runs for every request, so the
+    /// window can pick it up even while it is idle.
+    pub fn bind(wake: impl Fn() + Send + Sync + 'static) -> io::Result<Self> {
+        let dir = socket_dir();
+        fs::create_dir_all(&dir)?;
+        fs::set_permissions(&dir, Permissions::from_mode(0o700))?;
+        Self::bind_at(dir.join(format!("gui-{}.sock", std::process::id())), wake)
+    }
+
+    fn bind_at(path: PathBuf, wake: impl Fn() + Send + Sync + 'static) -> io::Result<Self> {
+        let _ = fs::remove_file(&path);
+        let listener = UnixListener::bind(&path)?;
+        fs::set_permissions(&path, Permissions::from_mode(0o600))?;
+
+        let (tx, rx) = mpsc::channel();
+        let wake: Arc<dyn Fn() + Send + Sync> = Arc::new(wake);
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let (tx, wake) = (tx.clone(), wake.clone());
+                thread::spawn(move || {
+                    let _ = handle_client(stream, &tx, &*wake);
+                });
+            }
+        });
+        Ok(Listener { path, rx })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn try_recv(&self) -> Option<Pending> {
+        self.rx.try_recv().ok()
+    }
+}
+
+impl Drop for Listener {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Answer requests until the client hangs up.
+fn handle_client(stream: UnixStream, tx: &Sender<Pending>, wake: &dyn Fn()) -> io::Result<()> {
+    let mut writer = stream.try_clone()?;
+    for line in BufReader::new(stream).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let response = match serde_json::from_str(&line) {
+            Ok(request) => {
+                let (reply, answer) = mpsc::channel();
+                if tx.send(Pending { request, reply }).is_err() {
+                    return Ok(());
+                }
+                wake();
+                answer
+                    .recv_timeout(REPLY_TIMEOUT)
+                    .unwrap_or_else(|_| Response::error(rust_i18n::t!("ipc.no_answer").into()))
+            }
+            Err(e) => Response::error(rust_i18n::t!("ipc.bad_request", error = e).into()),
+        };
+        serde_json::to_writer(&mut writer, &response)?;
+        writer.write_all(b"\n")?;
+    }
+    Ok(())
+}
+
+/// The window named by `$TERMIX_SOCKET`, else the one started last.
+fn connect() -> io::Result<UnixStream> {
+    if let Some(path) = std::env::var_os(SOCKET_ENV).filter(|p| !p.is_empty()) {
+        return UnixStream::connect(path);
+    }
+    connect_newest(&socket_dir())
+}
+
+/// The newest window socket in `dir` that accepts; sockets nobody listens
+/// on any more (a window that crashed) are removed on the way.
+fn connect_newest(dir: &Path) -> io::Result<UnixStream> {
+    let mut sockets: Vec<(SystemTime, PathBuf)> = fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            name.starts_with("gui-") && name.ends_with(".sock")
+        })
+        .filter_map(|path| Some((fs::metadata(&path).ok()?.modified().ok()?, path)))
+        .collect();
+    sockets.sort();
+    for (_, path) in sockets.iter().rev() {
+        match UnixStream::connect(path) {
+            Ok(stream) => return Ok(stream),
+            Err(e) if e.kind() == io::ErrorKind::ConnectionRefused => {
+                let _ = fs::remove_file(path);
+            }
+            Err(_) => {}
+        }
+    }
+    Err(io::Error::new(io::ErrorKind::NotFound, rust_i18n::t!("ipc.no_window").to_string()))
+}
+
+/// Send one request and wait for the answer.
+pub fn send(request: &Request) -> io::Result<Response> {
+    exchange(connect()?, request)
+}
+
+fn exchange(mut stream: UnixStream, request: &Request) -> io::Result<Response> {
+    serde_json::to_writer(&mut stream, request)?;
+    stream.write_all(b"\n")?;
+    let mut line = String::new();
+    BufReader::new(stream).read_line(&mut line)?;
+    Ok(serde_json::from_str(&line)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("termix-ipc-{}-{name}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn requests_travel_through_the_socket() {
+        let dir = scratch_dir("roundtrip");
+        let listener = Listener::bind_at(dir.join("gui-1.sock"), || {}).unwrap();
+
+        // The window answers between frames, here on this thread
+        let client = thread::spawn({
+            let path = listener.path().to_path_buf();
+            move || exchange(UnixStream::connect(path).unwrap(), &Request::GetText { tab: Some(2), lines: None })
+        });
+        let pending = loop {
+            match listener.try_recv() {
+                Some(pending) => break pending,
+                None => thread::sleep(Duration::from_millis(5)),
+            }
+        };
+        assert_eq!(pending.request, Request::GetText { tab: Some(2), lines: None });
+        pending.reply(Response { text: Some("$ ls".into()), ..Response::ok() });
+
+        let response = client.join().unwrap().unwrap();
+        assert!(response.ok);
+        assert_eq!(response.text.as_deref(), Some("$ ls"));
+        drop(listener);
+        assert!(!dir.join("gui-1.sock").exists());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn stale_sockets_are_removed() {
+        let dir = scratch_dir("stale");
+        let live = Listener::bind_at(dir.join("gui-1.sock"), || {}).unwrap();
+        // Newer, but its window is gone
+        thread::sleep(Duration::from_millis(20));
+        drop(UnixListener::bind(dir.join("gui-2.sock")).unwrap());
+
+        let stream = connect_newest(&dir).unwrap();
+        assert_eq!(stream.peer_addr().unwrap().as_pathname(), Some(live.path()));
+        assert!(!dir.join("gui-2.sock").exists());
+
+        drop((stream, live));
+        assert_eq!(connect_newest(&dir).unwrap_err().kind(), io::ErrorKind::NotFound);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn requests_are_tagged_by_cmd() {
+        let json = serde_json::to_string(&Request::FocusTab { tab: 3 }).unwrap();
+        assert_eq!(json, r#"{"cmd":"focus_tab","tab":3}"#);
+        let request: Request = serde_json::from_str(r#"{"cmd":"new_tab","title":"logs"}"#).unwrap();
+        assert_eq!(request, Request::NewTab { title: Some("logs".into()), cwd: None, command: None });
+        let request: Request = serde_json::from_str(r#"{"cmd":"split"}"#).unwrap();
+        assert_eq!(request, Request::Split { vertical: false, cwd: None, command: None });
+    }
+
+    #[test]
+    fn responses_leave_out_empty_fields() {
+        assert_eq!(serde_json::to_string(&Response::ok()).unwrap(), r#"{"ok":true}"#);
+        let response = Response { tab: Some(7), ..Response::ok() };
+        assert_eq!(serde_json::to_string(&response).unwrap(), r#"{"ok":true,"tab":7}"#);
+    }
+}
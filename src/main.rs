@@ -1,35 +1,188 @@
+mod cli;
 #[cfg(feature = "gui")]
 mod gui;
+// Remote control of GUI windows (`termix msg`)
+#[cfg(all(unix, feature = "gui"))]
+mod ipc;
+// Settings persistence is only consumed by the GUI so far
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
 mod config;
+#[cfg_attr(not(any(feature = "gui", feature = "ssh")), allow(dead_code))]
+mod fuzzy;
+#[cfg(any(feature = "tui", feature = "gui", feature = "ssh"))]
+mod pty;
+// The TUI build only attaches to a server, the GUI build can also run one
+#[cfg(unix)]
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+mod server;
+#[cfg(feature = "ssh")]
+mod ssh;
+// Colors and suggestions of the core are only drawn by the GUI
+#[cfg(any(feature = "tui", feature = "gui"))]
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+mod term;
+mod terminal;
 mod ui;
 
 // Initialize i18n support
 rust_i18n::i18n!("locales");
 
+use cli::{Command, Options};
 use ui::UI; // Immer verfügbar, auch wenn im GUI-Build nur als Fallback genutzt
 
-#[cfg(feature = "gui")]
 fn main() {
+    set_locale(&config::load_settings());
+    let (command, mut options) = match cli::parse(std::env::args().skip(1)) {
+        Ok(parsed) => parsed,
+        Err(e) => usage_error(&e),
+    };
+
+    // Hilfe und Version gehen auch mit kaputter '--config' oder unbekannten Namen
+    match command {
+        Command::Help => {
+            let mut settings = config::load_settings();
+            options.apply(&mut settings); // '--lang'
+            set_locale(&settings);
+            print!("{}", cli::help());
+            return;
+        }
+        Command::Version => {
+            println!("termix {}", env!("CARGO_PKG_VERSION"));
+            return;
+        }
+        _ => {}
+    }
+
+    // '--config' gilt für alles weitere, auch für den Server
+    if let Some(path) = options.config.clone() {
+        // Eine kaputte Datei würde sonst beim nächsten Speichern überschrieben
+        if path.exists() {
+            if let Err(e) = config::import_settings(&path) {
+                fail(&rust_i18n::t!("cli.config_invalid", path = path.display()), e);
+            }
+        }
+        config::use_settings_path(path);
+    }
+    let mut settings = config::load_settings();
+    options.apply(&mut settings);
+    set_locale(&settings);
+    if let Err(e) = options.check(&settings) {
+        usage_error(&e);
+    }
+
+    match command {
+        Command::Run => run(options),
+        Command::Help | Command::Version => {} // schon oben erledigt
+        #[cfg(all(unix, feature = "gui"))]
+        Command::Msg(request) => msg_command(request),
+        #[cfg(unix)]
+        command => server_command(command),
+    }
+}
+
+#[cfg(feature = "gui")]
+fn run(options: Options) {
     // '--tui' erzwingt Terminal-Modus selbst wenn GUI-Feature aktiv ist
-    let args: Vec<String> = std::env::args().collect();
-    if args.iter().any(|a| a == "--tui") {
-        run_tui();
+    if options.tui {
+        run_tui(&options);
         return;
     }
-
-    if let Err(e) = gui::run_gui() {
-        eprintln!("GUI konnte nicht gestartet werden: {}\nFalle zurück auf TUI...", e);
-        run_tui();
+    let launch = match gui::Launch::new(options.clone()) {
+        Ok(launch) => launch,
+        Err(e) => fail(&rust_i18n::t!("terminal.start_failed"), e),
+    };
+    if let Err(e) = gui::run_gui(launch) {
+        eprintln!("{}", rust_i18n::t!("cli.gui_failed", error = e));
+        run_tui(&options);
     }
 }
 
 #[cfg(not(feature = "gui"))]
-fn main() { run_tui(); }
+fn run(options: Options) {
+    run_tui(&options);
+}
+
+/// '--server', '--sessions' und '--attach [id]'
+#[cfg(unix)]
+fn server_command(command: Command) {
+    let result = match command {
+        Command::Server => {
+            #[cfg(feature = "gui")]
+            let result = server::run_server();
+            #[cfg(not(feature = "gui"))]
+            let result = Err(anyhow::anyhow!("{}", rust_i18n::t!("server.needs_gui")));
+            result
+        }
+        Command::Sessions => server::client::list_sessions().map_err(anyhow::Error::from).map(|sessions| {
+            for s in sessions {
+                println!("{:>4}  {:<24} pid {:<8} {} {}", s.id, s.title, s.pid.unwrap_or(0), s.clients, rust_i18n::t!("server.clients"));
+            }
+        }),
+        Command::Attach(id) => server::client::run_attached(id),
+        _ => Ok(()),
+    };
+    if let Err(e) = result {
+        fail(&rust_i18n::t!("common.error"), e);
+    }
+}
+
+/// 'termix msg': Anfrage an ein laufendes Fenster, Antwort auf stdout
+#[cfg(all(unix, feature = "gui"))]
+fn msg_command(mut request: ipc::Request) {
+    // Ohne Text wird gesendet, was auf stdin ankommt
+    if let ipc::Request::SendText { text, .. } = &mut request {
+        if text.is_empty() {
+            if let Err(e) = std::io::Read::read_to_string(&mut std::io::stdin(), text) {
+                fail(&rust_i18n::t!("common.error"), e);
+            }
+        }
+    }
+    let response = match ipc::send(&request) {
+        Ok(response) if response.ok => response,
+        Ok(response) => fail(&rust_i18n::t!("common.error"), response.error.unwrap_or_default()),
+        Err(e) => fail(&rust_i18n::t!("common.error"), e),
+    };
+    for tab in response.tabs.unwrap_or_default() {
+        let marker = if tab.focused { '*' } else { ' ' };
+        println!("{}{:>4}  {:>4}  {:<24} {}", marker, tab.id, tab.pane, tab.title, tab.cwd.unwrap_or_default());
+    }
+    if let Some(id) = response.tab {
+        println!("{}", id);
+    }
+    if let Some(text) = response.text {
+        println!("{}", text);
+    }
+}
 
-fn run_tui() {
+/// Sprache aus den Einstellungen (samt '--lang'), für TUI, GUI und Server
+fn set_locale(settings: &config::AppSettings) {
+    rust_i18n::set_locale(&settings.language);
+}
+
+/// Ungültige Argumente: Meldung, Hinweis auf '--help', Exit-Code 2
+fn usage_error(error: &str) -> ! {
+    eprintln!("termix: {}", error);
+    eprintln!("{}", rust_i18n::t!("cli.try_help"));
+    std::process::exit(cli::EXIT_USAGE);
+}
+
+/// Fehler zur Laufzeit: Meldung und Exit-Code 1
+fn fail(what: &str, error: impl std::fmt::Display) -> ! {
+    eprintln!("{}: {}", what, error);
+    std::process::exit(cli::EXIT_FAILURE);
+}
+
+fn run_tui(options: &Options) {
     let mut app = UI::new();
+    #[cfg(feature = "tui")]
+    if options.opens_terminal() {
+        if let Err(e) = app.open(options) {
+            fail(&rust_i18n::t!("terminal.start_failed"), e);
+        }
+    }
+    #[cfg(not(feature = "tui"))]
+    let _ = options;
     if let Err(e) = app.run() {
-        eprintln!("Fehler beim Starten der Anwendung: {}", e);
-        std::process::exit(1);
+        fail(&rust_i18n::t!("cli.startup_failed"), e);
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,134 @@
+//! Shells on a PTY for the terminal core: output arrives on a channel,
+//! ready to be fed into a `term::Session`.
+
+use crate::config::profile::Profile;
+use portable_pty::{CommandBuilder, MasterPty, NativePtySystem, PtySize, PtySystem};
+use std::io::{Read, Write};
+use std::path::Path;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+
+/// Sent as last output when the shell has exited; the channel closes after it.
+pub const CLOSED_MESSAGE: &[u8] = b"\n[PTY closed]\n";
+
+pub struct PtyProcess {
+    pub rx: Receiver<Vec<u8>>,  // from reader thread
+    pub writer: Sender<Vec<u8>>, // to writer thread
+    pub master: Box<dyn MasterPty + Send>,
+    pub child_pid: Option<u32>,
+}
+
+impl PtyProcess {
+    pub fn resize(&self, rows: u16, cols: u16) {
+        let _ = self.master.resize(PtySize { rows, cols, pixel_width: 0, pixel_height: 0 });
+    }
+
+    /// Current working directory of the shell, where the OS exposes it.
+    pub fn cwd(&self) -> Option<std::path::PathBuf> {
+        #[cfg(target_os = "linux")]
+        if let Some(pid) = self.child_pid {
+            return std::fs::read_link(format!("/proc/{}/cwd", pid)).ok();
+        }
+        None
+    }
+
+    /// Name of the program the shell runs in the foreground; `None` while
+    /// the shell waits at its prompt, and for remote shells.
+    pub fn foreground_process(&self) -> Option<String> {
+        #[cfg(unix)]
+        if let (Some(pid), Some(fd)) = (self.child_pid, self.master.as_raw_fd()) {
+            let group = crate::terminal::foreground_process_group(fd)?;
+            if crate::terminal::process_group(pid) != Some(group) {
+                return crate::terminal::process_name(group);
+            }
+        }
+        None
+    }
+}
+
+/// The user's shell.
+pub fn default_shell() -> CommandBuilder {
+    CommandBuilder::new(crate::terminal::default_shell())
+}
+
+/// `argv` (program and arguments), or the user's shell when it is empty,
+/// with `env` on top of the inherited environment.
+pub fn command(argv: &[String], env: &[(String, String)]) -> CommandBuilder {
+    let mut cmd = match argv {
+        [] => default_shell(),
+        argv => CommandBuilder::from_argv(argv.iter().map(Into::into).collect()),
+    };
+    for (key, value) in env {
+        cmd.env(key, value);
+    }
+    cmd
+}
+
+/// Start the default shell in `cwd` on a new PTY of the given size.
+pub fn spawn_shell(rows: u16, cols: u16, cwd: Option<&Path>) -> anyhow::Result<PtyProcess> {
+    spawn(default_shell(), rows, cols, cwd)
+}
+
+/// Start `argv` (program and arguments) instead of the shell, like `termix -e`.
+/// Without `cwd` it runs where TermiX was started, so relative paths in the
+/// arguments keep working.
+pub fn spawn_command(argv: &[String], rows: u16, cols: u16, cwd: Option<&Path>) -> anyhow::Result<PtyProcess> {
+    let cmd = command(argv, &[]);
+    let here = std::env::current_dir().ok();
+    spawn(cmd, rows, cols, cwd.or(here.as_deref()))
+}
+
+/// Start `argv`, or else the program of `profile` (its shell by default),
+/// with the profile's environment. `cwd` wins over its start directory.
+pub fn spawn_profile(profile: &Profile, argv: &[String], rows: u16, cols: u16, cwd: Option<&Path>) -> anyhow::Result<PtyProcess> {
+    let program = if argv.is_empty() { profile.program() } else { argv.to_vec() };
+    let start = cwd.map(Path::to_path_buf).or_else(|| profile.start_dir());
+    spawn(command(&program, &profile.environment()), rows, cols, start.as_deref())
+}
+
+fn spawn(mut cmd: CommandBuilder, rows: u16, cols: u16, cwd: Option<&Path>) -> anyhow::Result<PtyProcess> {
+    let (to_writer_tx, to_writer_rx) = mpsc::channel::<Vec<u8>>();
+    let (from_reader_tx, from_reader_rx) = mpsc::channel::<Vec<u8>>();
+
+    let pair = NativePtySystem::default().openpty(PtySize { rows, cols, pixel_width: 0, pixel_height: 0 })?;
+    if let Some(dir) = cwd.filter(|d| d.is_dir()) {
+        cmd.cwd(dir);
+    }
+    let child = pair.slave.spawn_command(cmd)?;
+    let child_pid = child.process_id();
+    // Only the shell may hold the slave, otherwise reads never end
+    drop(pair.slave);
+
+    let mut writer = pair.master.take_writer()?;
+    let mut reader = pair.master.try_clone_reader()?;
+
+    // Writer thread: send bytes to PTY
+    thread::spawn(move || {
+        while let Ok(buf) = to_writer_rx.recv() {
+            let _ = writer.write_all(&buf);
+            let _ = writer.flush();
+        }
+    });
+
+    // Reader thread: forward output until the shell is gone (EOF or EIO)
+    thread::spawn(move || {
+        let mut child = child;
+        let mut buf = [0u8; 4096];
+        loop {
+            match reader.read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => {
+                    if from_reader_tx.send(buf[..n].to_vec()).is_err() {
+                        break;
+                    }
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(_) => break,
+            }
+        }
+        let _ = from_reader_tx.send(CLOSED_MESSAGE.to_vec());
+        let _ = child.wait();
+    });
+
+    Ok(PtyProcess { rx: from_reader_rx, writer: to_writer_tx, master: pair.master, child_pid })
+}
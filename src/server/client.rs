@@ -0,0 +1,243 @@
+//! Client side of the server socket, used by the GUI and by `termix --attach`.
+
+use super::protocol::{read_frame, write_frame, Frame};
+pub use super::protocol::SessionInfo;
+use super::socket_path;
+use crate::config::profile::Profile;
+use std::io::{self, Read, Write};
+use std::os::unix::net::UnixStream;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// Ctrl+] detaches `termix --attach` from its session.
+const DETACH_KEY: u8 = 0x1d;
+
+pub fn is_running() -> bool {
+    UnixStream::connect(socket_path()).is_ok()
+}
+
+fn connect() -> io::Result<UnixStream> {
+    UnixStream::connect(socket_path()).map_err(|e| match e.kind() {
+        io::ErrorKind::NotFound | io::ErrorKind::ConnectionRefused => {
+            io::Error::new(e.kind(), rust_i18n::t!("server.none_running").to_string())
+        }
+        _ => e,
+    })
+}
+
+/// Start `termix --server` in the background unless it is running already.
+pub fn ensure_running() -> io::Result<()> {
+    use std::os::unix::process::CommandExt;
+    use std::process::{Command, Stdio};
+
+    if is_running() {
+        return Ok(());
+    }
+    let mut child = Command::new(std::env::current_exe()?)
+        .arg("--server")
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        // Own process group: Ctrl+C in the launching terminal must not reach it
+        .process_group(0)
+        .spawn()?;
+    thread::spawn(move || child.wait());
+    for _ in 0..40 {
+        thread::sleep(Duration::from_millis(50));
+        if is_running() {
+            return Ok(());
+        }
+    }
+    Err(io::Error::new(io::ErrorKind::TimedOut, rust_i18n::t!("server.start_failed").to_string()))
+}
+
+fn request(frame: &Frame) -> io::Result<Frame> {
+    let mut stream = connect()?;
+    write_frame(&mut stream, frame)?;
+    match read_frame(&mut stream)? {
+        Frame::Error(message) => Err(io::Error::other(message)),
+        reply => Ok(reply),
+    }
+}
+
+pub fn list_sessions() -> io::Result<Vec<SessionInfo>> {
+    match request(&Frame::List)? {
+        Frame::Sessions(sessions) => Ok(sessions),
+        _ => Err(unexpected()),
+    }
+}
+
+pub fn kill_session(id: u64) -> io::Result<()> {
+    request(&Frame::Kill { id }).map(|_| ())
+}
+
+/// Stop the server and every shell it owns.
+pub fn shutdown() -> io::Result<()> {
+    request(&Frame::Shutdown).map(|_| ())
+}
+
+fn unexpected() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, rust_i18n::t!("server.unexpected_reply").to_string())
+}
+
+/// A connection attached to one session.
+pub struct Attachment {
+    pub id: u64,
+    pub pid: Option<u32>,
+    stream: UnixStream,
+}
+
+impl Attachment {
+    /// Start a new shell on the server, typing `command` into it. With a
+    /// profile, its program and environment are used, and its start
+    /// directory unless `cwd` is given.
+    pub fn spawn(title: &str, command: Option<&str>, cwd: Option<&Path>, profile: Option<&Profile>, rows: u16, cols: u16) -> io::Result<Self> {
+        let cwd = cwd.map(Path::to_path_buf).or_else(|| profile.and_then(Profile::start_dir));
+        Self::open(&Frame::Spawn {
+            title: title.to_string(),
+            command: command.map(str::to_string),
+            cwd: cwd.map(|d| d.display().to_string()),
+            rows,
+            cols,
+            program: profile.map(Profile::program).unwrap_or_default(),
+            env: profile.map(Profile::environment).unwrap_or_default(),
+        })
+    }
+
+    pub fn attach(id: u64, rows: u16, cols: u16) -> io::Result<Self> {
+        Self::open(&Frame::Attach { id, rows, cols })
+    }
+
+    fn open(request: &Frame) -> io::Result<Self> {
+        let mut stream = connect()?;
+        write_frame(&mut stream, request)?;
+        match read_frame(&mut stream)? {
+            Frame::Attached { id, pid } => Ok(Self { id, pid, stream }),
+            Frame::Error(message) => Err(io::Error::other(message)),
+            _ => Err(unexpected()),
+        }
+    }
+
+    pub fn split(self) -> io::Result<(AttachReader, AttachWriter)> {
+        let reader = AttachReader(self.stream.try_clone()?);
+        Ok((reader, AttachWriter { id: self.id, stream: Arc::new(Mutex::new(self.stream)) }))
+    }
+}
+
+pub struct AttachReader(UnixStream);
+
+impl AttachReader {
+    /// Next chunk of output, starting with the session's history; `None`
+    /// once the shell has exited.
+    pub fn next_output(&mut self) -> io::Result<Option<Vec<u8>>> {
+        loop {
+            match read_frame(&mut self.0)? {
+                Frame::Output(data) => return Ok(Some(data)),
+                Frame::Exited => return Ok(None),
+                _ => continue,
+            }
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct AttachWriter {
+    pub id: u64,
+    stream: Arc<Mutex<UnixStream>>,
+}
+
+impl AttachWriter {
+    fn send(&self, frame: &Frame) -> io::Result<()> {
+        let mut stream = self.stream.lock().unwrap();
+        write_frame(&mut *stream, frame)
+    }
+
+    pub fn input(&self, data: &[u8]) -> io::Result<()> {
+        self.send(&Frame::Input(data.to_vec()))
+    }
+
+    pub fn resize(&self, rows: u16, cols: u16) -> io::Result<()> {
+        self.send(&Frame::Resize { rows, cols })
+    }
+
+    /// End the shell on the server.
+    pub fn kill(&self) -> io::Result<()> {
+        self.send(&Frame::Kill { id: self.id })
+    }
+
+    /// Drop the connection; the session keeps running.
+    pub fn detach(&self) {
+        let stream = self.stream.lock().unwrap();
+        let _ = stream.shutdown(std::net::Shutdown::Both);
+    }
+}
+
+/// `termix --attach [id]`: connect this terminal to a session (the first
+/// one, or a new shell if there is none) until it exits or Ctrl+] is pressed.
+pub fn run_attached(id: Option<u64>) -> anyhow::Result<()> {
+    use crate::terminal;
+
+    let (cols, rows) = terminal::size().unwrap_or((80, 24));
+    let first = if id.is_none() { list_sessions()?.first().map(|s| s.id) } else { None };
+    let attachment = match id.or(first) {
+        Some(id) => Attachment::attach(id, rows, cols)?,
+        None => {
+            let settings = crate::config::load_settings();
+            Attachment::spawn("Terminal", None, None, settings.default_profile(), rows, cols)?
+        }
+    };
+    let (mut reader, writer) = attachment.split()?;
+
+    let raw_mode = terminal::RawMode::enable()?;
+
+    // Resize thread: forward size changes of this terminal
+    let (w, resizes) = (writer.clone(), terminal::resize_events()?);
+    thread::spawn(move || {
+        for (cols, rows) in resizes {
+            if w.resize(rows, cols).is_err() {
+                break;
+            }
+        }
+    });
+
+    // Input thread: stdin to the session, Ctrl+] detaches
+    let w = writer.clone();
+    thread::spawn(move || {
+        let mut stdin = io::stdin();
+        let mut buf = [0u8; 1024];
+        while let Ok(n) = stdin.read(&mut buf) {
+            if n == 0 {
+                break;
+            }
+            if let Some(pos) = buf[..n].iter().position(|b| *b == DETACH_KEY) {
+                let _ = w.input(&buf[..pos]);
+                break;
+            }
+            if w.input(&buf[..n]).is_err() {
+                break;
+            }
+        }
+        w.detach();
+    });
+
+    let mut stdout = io::stdout();
+    let exited = loop {
+        match reader.next_output() {
+            Ok(Some(data)) => {
+                let _ = stdout.write_all(&data);
+                let _ = stdout.flush();
+            }
+            Ok(None) => break true,
+            Err(_) => break false,
+        }
+    };
+    drop(raw_mode);
+    if exited {
+        println!("\r\n[{}]", rust_i18n::t!("server.session_ended", id = writer.id));
+    } else {
+        println!("\r\n[{}]", rust_i18n::t!("server.session_detached", id = writer.id));
+    }
+    Ok(())
+}
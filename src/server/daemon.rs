@@ -0,0 +1,336 @@
+//! The server process: one PTY per session, raw output history for clients
+//! that attach later, and a thread per connection plus a writer thread per
+//! attached client.
+
+use super::protocol::{read_frame, write_frame, Frame, SessionInfo};
+use super::socket_path;
+use portable_pty::{Child, CommandBuilder, MasterPty, NativePtySystem, PtySize, PtySystem};
+use std::collections::BTreeMap;
+use std::fs::{self, Permissions};
+use std::io::{self, Read, Write};
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// Raw output kept per session and replayed to clients when they attach.
+const HISTORY_BYTES: usize = 1024 * 1024;
+
+/// Frames queued per attached client; one that falls this far behind is
+/// disconnected instead of holding up the session.
+const CLIENT_QUEUE: usize = 1024;
+
+type Sessions = Arc<Mutex<BTreeMap<u64, Arc<Session>>>>;
+
+/// Separate counters, so session ids stay small and dense for `--attach`.
+struct Ids {
+    sessions: AtomicU64,
+    clients: AtomicU64,
+}
+
+impl Ids {
+    fn next_session(&self) -> u64 {
+        self.sessions.fetch_add(1, Ordering::Relaxed)
+    }
+
+    fn next_client(&self) -> u64 {
+        self.clients.fetch_add(1, Ordering::Relaxed)
+    }
+}
+
+struct Session {
+    title: String,
+    pid: Option<u32>,
+    master: Mutex<Box<dyn MasterPty + Send>>,
+    writer: Mutex<Box<dyn Write + Send>>,
+    child: Mutex<Box<dyn Child + Send + Sync>>,
+    io: Mutex<SessionIo>,
+}
+
+/// Output side of a session; history and client list change together so
+/// an attaching client neither misses nor duplicates output.
+#[derive(Default)]
+struct SessionIo {
+    history: Vec<u8>,
+    clients: Vec<Client>,
+    exited: bool,
+}
+
+/// An attached client: frames go through its queue to its writer thread,
+/// so a slow reader only ever blocks itself.
+struct Client {
+    id: u64,
+    queue: SyncSender<Frame>,
+    stream: UnixStream,
+}
+
+impl Client {
+    fn attach(id: u64, stream: &UnixStream) -> io::Result<Self> {
+        let (queue, frames) = mpsc::sync_channel::<Frame>(CLIENT_QUEUE);
+        let mut writer = stream.try_clone()?;
+        thread::spawn(move || {
+            for frame in frames {
+                if write_frame(&mut writer, &frame).is_err() {
+                    break;
+                }
+            }
+            // Also ends the connection's reader loop
+            let _ = writer.shutdown(std::net::Shutdown::Both);
+        });
+        Ok(Client { id, queue, stream: stream.try_clone()? })
+    }
+
+    /// Queue `frame`; false (and the connection closed) when the client
+    /// has gone or fallen too far behind.
+    fn send(&self, frame: Frame) -> bool {
+        match self.queue.try_send(frame) {
+            Ok(()) => true,
+            Err(TrySendError::Full(_)) | Err(TrySendError::Disconnected(_)) => {
+                let _ = self.stream.shutdown(std::net::Shutdown::Both);
+                false
+            }
+        }
+    }
+}
+
+impl Session {
+    fn resize(&self, rows: u16, cols: u16) {
+        let size = PtySize { rows: rows.max(5), cols: cols.max(10), pixel_width: 0, pixel_height: 0 };
+        let _ = self.master.lock().unwrap().resize(size);
+    }
+
+    fn input(&self, data: &[u8]) {
+        let mut writer = self.writer.lock().unwrap();
+        let _ = writer.write_all(data);
+        let _ = writer.flush();
+    }
+
+    fn kill(&self) {
+        let _ = self.child.lock().unwrap().kill();
+    }
+}
+
+impl SessionIo {
+    fn output(&mut self, data: &[u8]) {
+        self.history.extend_from_slice(data);
+        if self.history.len() > HISTORY_BYTES {
+            // Keep the newest part, starting at a line boundary
+            let cut = self.history.len() - HISTORY_BYTES;
+            let cut = self.history[cut..].iter().position(|b| *b == b'\n').map_or(cut, |i| cut + i + 1);
+            self.history.drain(..cut);
+        }
+        self.clients.retain(|client| client.send(Frame::Output(data.to_vec())));
+    }
+}
+
+/// `termix --server`: serve sessions on the socket until a client sends
+/// `Shutdown`.
+pub fn run_server() -> anyhow::Result<()> {
+    let path = socket_path();
+    if UnixStream::connect(&path).is_ok() {
+        anyhow::bail!("{}", rust_i18n::t!("server.already_running", path = path.display()));
+    }
+    // The socket's own directory (see `socket_path`), never the config dir
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+        fs::set_permissions(dir, Permissions::from_mode(0o700))?;
+    }
+    // Left over from a server that did not shut down cleanly
+    let _ = fs::remove_file(&path);
+    let listener = UnixListener::bind(&path)?;
+    fs::set_permissions(&path, Permissions::from_mode(0o600))?;
+    println!("{}", rust_i18n::t!("server.listening", path = path.display()));
+
+    let sessions: Sessions = Arc::default();
+    let ids = Arc::new(Ids { sessions: AtomicU64::new(1), clients: AtomicU64::new(1) });
+    for stream in listener.incoming().flatten() {
+        let (sessions, ids) = (sessions.clone(), ids.clone());
+        thread::spawn(move || {
+            let _ = handle_client(stream, &sessions, &ids);
+        });
+    }
+    Ok(())
+}
+
+fn handle_client(mut stream: UnixStream, sessions: &Sessions, ids: &Ids) -> io::Result<()> {
+    let (id, session, rows, cols) = match read_frame(&mut stream)? {
+        Frame::List => {
+            let list = sessions
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|(id, s)| SessionInfo {
+                    id: *id,
+                    title: s.title.clone(),
+                    pid: s.pid,
+                    clients: s.io.lock().unwrap().clients.len() as u32,
+                })
+                .collect();
+            return write_frame(&mut stream, &Frame::Sessions(list));
+        }
+        Frame::Kill { id } => {
+            let reply = match sessions.lock().unwrap().get(&id) {
+                Some(session) => {
+                    session.kill();
+                    Frame::Ok
+                }
+                None => not_found(id),
+            };
+            return write_frame(&mut stream, &reply);
+        }
+        Frame::Shutdown => {
+            for session in sessions.lock().unwrap().values() {
+                session.kill();
+            }
+            let _ = write_frame(&mut stream, &Frame::Ok);
+            let _ = fs::remove_file(socket_path());
+            std::process::exit(0);
+        }
+        Frame::Spawn { title, command, cwd, rows, cols, program, env } => {
+            let mut cmd = crate::pty::command(&program, &env);
+            if let Some(dir) = cwd.filter(|d| std::path::Path::new(d).is_dir()) {
+                cmd.cwd(dir);
+            }
+            match spawn_session(sessions, ids, title, cmd, command.as_deref(), rows, cols) {
+                Ok((id, session)) => (id, session, rows, cols),
+                Err(e) => return write_frame(&mut stream, &Frame::Error(e.to_string())),
+            }
+        }
+        Frame::Attach { id, rows, cols } => match sessions.lock().unwrap().get(&id).cloned() {
+            Some(session) => (id, session, rows, cols),
+            None => return write_frame(&mut stream, &not_found(id)),
+        },
+        _ => return write_frame(&mut stream, &Frame::Error(rust_i18n::t!("server.unexpected_request").into())),
+    };
+
+    session.resize(rows, cols);
+    // Its writer thread gives up on a client that stops reading
+    stream.set_write_timeout(Some(Duration::from_secs(5)))?;
+    let client = Client::attach(ids.next_client(), &stream)?;
+    let client_id = client.id;
+    {
+        // Queued under the lock, so no output is missed or sent twice
+        let mut io = session.io.lock().unwrap();
+        client.send(Frame::Attached { id, pid: session.pid });
+        if !io.history.is_empty() {
+            client.send(Frame::Output(io.history.clone()));
+        }
+        if io.exited {
+            client.send(Frame::Exited);
+            return Ok(());
+        }
+        io.clients.push(client);
+    }
+
+    loop {
+        match read_frame(&mut stream) {
+            Ok(Frame::Input(data)) => session.input(&data),
+            Ok(Frame::Resize { rows, cols }) => session.resize(rows, cols),
+            Ok(Frame::Kill { .. }) => session.kill(),
+            Ok(_) => {}
+            Err(_) => break,
+        }
+    }
+    session.io.lock().unwrap().clients.retain(|c| c.id != client_id);
+    Ok(())
+}
+
+fn not_found(id: u64) -> Frame {
+    Frame::Error(rust_i18n::t!("server.session_not_found", id = id).into())
+}
+
+fn spawn_session(
+    sessions: &Sessions,
+    ids: &Ids,
+    title: String,
+    cmd: CommandBuilder,
+    command: Option<&str>,
+    rows: u16,
+    cols: u16,
+) -> anyhow::Result<(u64, Arc<Session>)> {
+    let pair = NativePtySystem::default().openpty(PtySize {
+        rows: rows.max(5),
+        cols: cols.max(10),
+        pixel_width: 0,
+        pixel_height: 0,
+    })?;
+    let child = pair.slave.spawn_command(cmd)?;
+    // Only the shell may hold the slave, otherwise we never see EOF
+    drop(pair.slave);
+
+    let reader = pair.master.try_clone_reader()?;
+    let mut writer = pair.master.take_writer()?;
+    if let Some(command) = command.filter(|c| !c.trim().is_empty()) {
+        writer.write_all(format!("{}\r", command).as_bytes())?;
+    }
+    let session = Arc::new(Session {
+        title,
+        pid: child.process_id(),
+        master: Mutex::new(pair.master),
+        writer: Mutex::new(writer),
+        child: Mutex::new(child),
+        io: Mutex::default(),
+    });
+    let id = ids.next_session();
+    sessions.lock().unwrap().insert(id, session.clone());
+
+    let (sessions, pumped) = (sessions.clone(), session.clone());
+    thread::spawn(move || pump_output(id, pumped, reader, &sessions));
+    Ok((id, session))
+}
+
+/// Reader thread of a session: record and fan out output until the shell
+/// exits, then drop the session.
+fn pump_output(id: u64, session: Arc<Session>, mut reader: Box<dyn Read + Send>, sessions: &Sessions) {
+    let mut buf = [0u8; 4096];
+    loop {
+        match reader.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => session.io.lock().unwrap().output(&buf[..n]),
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            // EIO once the shell is gone
+            Err(_) => break,
+        }
+    }
+    sessions.lock().unwrap().remove(&id);
+    let mut io = session.io.lock().unwrap();
+    io.exited = true;
+    // Dropping the queues lets the writers finish and close the connections
+    for client in io.clients.drain(..) {
+        client.send(Frame::Exited);
+    }
+    drop(io);
+    let _ = session.child.lock().unwrap().wait();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::protocol::read_frame;
+
+    #[test]
+    fn stalled_clients_do_not_block_output() {
+        let (server_side, mut client_side) = UnixStream::pair().unwrap();
+        server_side.set_write_timeout(Some(Duration::from_millis(50))).unwrap();
+        let mut io = SessionIo::default();
+        io.clients.push(Client::attach(1, &server_side).unwrap());
+
+        // Nobody reads: the queue fills up and the client is dropped, while
+        // every call returns right away
+        let mut chunk = vec![b'x'; 4095];
+        chunk.push(b'\n');
+        for _ in 0..CLIENT_QUEUE * 2 {
+            io.output(&chunk);
+        }
+        assert!(io.clients.is_empty());
+        assert!(io.history.len() <= HISTORY_BYTES && io.history.ends_with(b"x\n"));
+
+        // What was written arrives in order before the connection ends
+        while let Ok(frame) = read_frame(&mut client_side) {
+            assert_eq!(frame, Frame::Output(chunk.clone()));
+        }
+    }
+}
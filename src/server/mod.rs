@@ -0,0 +1,24 @@
+//! Background server (`termix --server`) that owns the shells and their
+//! output, so they survive GUI restarts. Clients talk to it over a Unix
+//! socket: they list sessions, spawn new ones and attach to them, possibly
+//! from several windows at once.
+
+pub mod client;
+mod protocol;
+#[cfg(feature = "gui")]
+mod daemon;
+
+#[cfg(feature = "gui")]
+pub use daemon::run_server;
+
+use std::path::PathBuf;
+
+/// `$XDG_RUNTIME_DIR/termix/server.sock`, else in a `run` directory next to
+/// the settings. Either directory holds only sockets, so the server can make
+/// it private.
+pub fn socket_path() -> PathBuf {
+    match std::env::var_os("XDG_RUNTIME_DIR").filter(|d| !d.is_empty()) {
+        Some(dir) => PathBuf::from(dir).join("termix").join("server.sock"),
+        None => crate::config::config_dir().join("run").join("server.sock"),
+    }
+}
@@ -0,0 +1,360 @@
+//! Wire format between the server and its clients: every message is a frame
+//! `tag: u8, len: u32 (BE), payload`. Integers are big endian, strings and
+//! byte blobs carry a `u32` length prefix.
+
+use std::io::{self, Read, Write};
+
+/// Upper bound for a single frame, protects against garbage on the socket.
+const MAX_FRAME: usize = 16 * 1024 * 1024;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Frame {
+    // Client -> server; the first frame of a connection is the request
+    List,
+    Spawn {
+        title: String,
+        command: Option<String>, // typed into the shell
+        cwd: Option<String>,
+        rows: u16,
+        cols: u16,
+        program: Vec<String>, // instead of the user's shell, when not empty
+        env: Vec<(String, String)>,
+    },
+    Attach { id: u64, rows: u16, cols: u16 },
+    Kill { id: u64 },
+    Shutdown,
+    // Client -> server, while attached
+    Input(Vec<u8>),
+    Resize { rows: u16, cols: u16 },
+    // Server -> client
+    Sessions(Vec<SessionInfo>),
+    Attached { id: u64, pid: Option<u32> },
+    Output(Vec<u8>),
+    Exited,
+    Ok,
+    Error(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionInfo {
+    pub id: u64,
+    pub title: String,
+    pub pid: Option<u32>,
+    pub clients: u32,
+}
+
+pub fn write_frame(w: &mut impl Write, frame: &Frame) -> io::Result<()> {
+    let mut p = Vec::new();
+    let tag = match frame {
+        Frame::List => 1,
+        Frame::Spawn { title, command, cwd, rows, cols, program, env } => {
+            put_str(&mut p, title);
+            put_opt_str(&mut p, command.as_deref());
+            put_opt_str(&mut p, cwd.as_deref());
+            p.extend_from_slice(&rows.to_be_bytes());
+            p.extend_from_slice(&cols.to_be_bytes());
+            // Appended later; older peers stop reading before them
+            p.extend_from_slice(&(program.len() as u32).to_be_bytes());
+            for arg in program {
+                put_str(&mut p, arg);
+            }
+            p.extend_from_slice(&(env.len() as u32).to_be_bytes());
+            for (key, value) in env {
+                put_str(&mut p, key);
+                put_str(&mut p, value);
+            }
+            2
+        }
+        Frame::Attach { id, rows, cols } => {
+            p.extend_from_slice(&id.to_be_bytes());
+            p.extend_from_slice(&rows.to_be_bytes());
+            p.extend_from_slice(&cols.to_be_bytes());
+            3
+        }
+        Frame::Kill { id } => {
+            p.extend_from_slice(&id.to_be_bytes());
+            4
+        }
+        Frame::Shutdown => 5,
+        Frame::Input(data) => {
+            p.extend_from_slice(data);
+            6
+        }
+        Frame::Resize { rows, cols } => {
+            p.extend_from_slice(&rows.to_be_bytes());
+            p.extend_from_slice(&cols.to_be_bytes());
+            7
+        }
+        Frame::Sessions(sessions) => {
+            p.extend_from_slice(&(sessions.len() as u32).to_be_bytes());
+            for s in sessions {
+                p.extend_from_slice(&s.id.to_be_bytes());
+                put_str(&mut p, &s.title);
+                p.extend_from_slice(&s.pid.unwrap_or(0).to_be_bytes());
+                p.extend_from_slice(&s.clients.to_be_bytes());
+            }
+            64
+        }
+        Frame::Attached { id, pid } => {
+            p.extend_from_slice(&id.to_be_bytes());
+            p.extend_from_slice(&pid.unwrap_or(0).to_be_bytes());
+            65
+        }
+        Frame::Output(data) => {
+            p.extend_from_slice(data);
+            66
+        }
+        Frame::Exited => 67,
+        Frame::Ok => 68,
+        Frame::Error(message) => {
+            put_str(&mut p, message);
+            69
+        }
+    };
+    // One write per frame, so frames from different threads never interleave
+    let mut buf = Vec::with_capacity(p.len() + 5);
+    buf.push(tag);
+    buf.extend_from_slice(&(p.len() as u32).to_be_bytes());
+    buf.extend_from_slice(&p);
+    w.write_all(&buf)?;
+    w.flush()
+}
+
+pub fn read_frame(r: &mut impl Read) -> io::Result<Frame> {
+    let mut head = [0u8; 5];
+    r.read_exact(&mut head)?;
+    let len = u32::from_be_bytes([head[1], head[2], head[3], head[4]]) as usize;
+    if len > MAX_FRAME {
+        return Err(invalid("frame too large"));
+    }
+    let mut payload = vec![0u8; len];
+    r.read_exact(&mut payload)?;
+    let mut p = Payload(&payload);
+    let frame = match head[0] {
+        1 => Frame::List,
+        2 => Frame::Spawn {
+            title: p.string()?,
+            command: p.opt_string()?,
+            cwd: p.opt_string()?,
+            rows: p.u16()?,
+            cols: p.u16()?,
+            program: p.strings()?,
+            env: p.pairs()?,
+        },
+        3 => Frame::Attach { id: p.u64()?, rows: p.u16()?, cols: p.u16()? },
+        4 => Frame::Kill { id: p.u64()? },
+        5 => Frame::Shutdown,
+        6 => Frame::Input(payload),
+        7 => Frame::Resize { rows: p.u16()?, cols: p.u16()? },
+        64 => {
+            let count = p.u32()?;
+            let mut sessions = Vec::new();
+            for _ in 0..count {
+                sessions.push(SessionInfo {
+                    id: p.u64()?,
+                    title: p.string()?,
+                    pid: Some(p.u32()?).filter(|pid| *pid != 0),
+                    clients: p.u32()?,
+                });
+            }
+            Frame::Sessions(sessions)
+        }
+        65 => Frame::Attached { id: p.u64()?, pid: Some(p.u32()?).filter(|pid| *pid != 0) },
+        66 => Frame::Output(payload),
+        67 => Frame::Exited,
+        68 => Frame::Ok,
+        69 => Frame::Error(p.string()?),
+        tag => return Err(invalid(&format!("unknown frame tag {}", tag))),
+    };
+    Ok(frame)
+}
+
+fn put_str(p: &mut Vec<u8>, s: &str) {
+    p.extend_from_slice(&(s.len() as u32).to_be_bytes());
+    p.extend_from_slice(s.as_bytes());
+}
+
+fn put_opt_str(p: &mut Vec<u8>, s: Option<&str>) {
+    match s {
+        Some(s) => {
+            p.push(1);
+            put_str(p, s);
+        }
+        None => p.push(0),
+    }
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+struct Payload<'a>(&'a [u8]);
+
+impl Payload<'_> {
+    fn take(&mut self, n: usize) -> io::Result<&[u8]> {
+        if self.0.len() < n {
+            return Err(invalid("truncated frame"));
+        }
+        let (head, rest) = self.0.split_at(n);
+        self.0 = rest;
+        Ok(head)
+    }
+
+    fn u16(&mut self) -> io::Result<u16> {
+        Ok(u16::from_be_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> io::Result<u32> {
+        Ok(u32::from_be_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> io::Result<u64> {
+        Ok(u64::from_be_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn string(&mut self) -> io::Result<String> {
+        let len = self.u32()? as usize;
+        String::from_utf8(self.take(len)?.to_vec()).map_err(|_| invalid("invalid UTF-8"))
+    }
+
+    /// Counted list of strings; frames from older peers end before it.
+    fn strings(&mut self) -> io::Result<Vec<String>> {
+        if self.0.is_empty() {
+            return Ok(Vec::new());
+        }
+        (0..self.u32()?).map(|_| self.string()).collect()
+    }
+
+    fn pairs(&mut self) -> io::Result<Vec<(String, String)>> {
+        if self.0.is_empty() {
+            return Ok(Vec::new());
+        }
+        (0..self.u32()?).map(|_| Ok((self.string()?, self.string()?))).collect()
+    }
+
+    fn opt_string(&mut self) -> io::Result<Option<String>> {
+        match self.take(1)?[0] {
+            0 => Ok(None),
+            _ => self.string().map(Some),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode(frame: &Frame) -> Vec<u8> {
+        let mut buf = Vec::new();
+        write_frame(&mut buf, frame).unwrap();
+        buf
+    }
+
+    fn frames() -> Vec<Frame> {
+        vec![
+            Frame::List,
+            Frame::Spawn {
+                title: "build ✓".into(),
+                command: Some("cargo build".into()),
+                cwd: None,
+                rows: 24,
+                cols: 80,
+                program: vec!["python3".into(), "-q".into()],
+                env: vec![("TERM".into(), "xterm-256color".into()), ("EMPTY".into(), String::new())],
+            },
+            Frame::Attach { id: u64::MAX, rows: 50, cols: 200 },
+            Frame::Kill { id: 3 },
+            Frame::Shutdown,
+            Frame::Input(b"ls\r".to_vec()),
+            Frame::Resize { rows: 1, cols: 2 },
+            Frame::Sessions(vec![
+                SessionInfo { id: 1, title: "zsh".into(), pid: Some(4242), clients: 2 },
+                SessionInfo { id: 2, title: String::new(), pid: None, clients: 0 },
+            ]),
+            Frame::Attached { id: 7, pid: None },
+            Frame::Output(vec![0, 0x1b, b'[', b'm', 0xff]),
+            Frame::Exited,
+            Frame::Ok,
+            Frame::Error("no such session".into()),
+        ]
+    }
+
+    #[test]
+    fn frames_round_trip() {
+        let mut stream = Vec::new();
+        for frame in frames() {
+            stream.extend(encode(&frame));
+        }
+        let mut reader = stream.as_slice();
+        for frame in frames() {
+            assert_eq!(read_frame(&mut reader).unwrap(), frame);
+        }
+        assert!(reader.is_empty());
+    }
+
+    #[test]
+    fn truncated_frames_are_errors() {
+        for frame in frames() {
+            let bytes = encode(&frame);
+            for len in 0..bytes.len() {
+                assert!(read_frame(&mut &bytes[..len]).is_err(), "{:?} cut at {}", frame, len);
+            }
+        }
+        // Header promises less than the frame needs
+        let mut short = encode(&Frame::Attach { id: 1, rows: 2, cols: 3 });
+        short.truncate(5 + 4);
+        short[1..5].copy_from_slice(&4u32.to_be_bytes());
+        assert_eq!(read_frame(&mut short.as_slice()).unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn garbage_is_rejected() {
+        let mut huge = vec![6];
+        huge.extend_from_slice(&(MAX_FRAME as u32 + 1).to_be_bytes());
+        assert_eq!(read_frame(&mut huge.as_slice()).unwrap_err().kind(), io::ErrorKind::InvalidData);
+        assert_eq!(read_frame(&mut [200, 0, 0, 0, 0].as_slice()).unwrap_err().kind(), io::ErrorKind::InvalidData);
+
+        let mut bad_utf8 = encode(&Frame::Error("ab".into()));
+        *bad_utf8.last_mut().unwrap() = 0xff;
+        assert!(read_frame(&mut bad_utf8.as_slice()).is_err());
+    }
+
+    #[test]
+    fn spawn_from_older_peers() {
+        // Before `program` and `env` were appended
+        let mut p = Vec::new();
+        put_str(&mut p, "old");
+        put_opt_str(&mut p, None);
+        put_opt_str(&mut p, Some("/tmp"));
+        p.extend_from_slice(&24u16.to_be_bytes());
+        p.extend_from_slice(&80u16.to_be_bytes());
+        let mut old = vec![2];
+        old.extend_from_slice(&(p.len() as u32).to_be_bytes());
+        old.extend_from_slice(&p);
+        let expected = Frame::Spawn {
+            title: "old".into(),
+            command: None,
+            cwd: Some("/tmp".into()),
+            rows: 24,
+            cols: 80,
+            program: Vec::new(),
+            env: Vec::new(),
+        };
+        assert_eq!(read_frame(&mut old.as_slice()).unwrap(), expected);
+
+        // An older peer reads the fields it knows and ignores the tail
+        let new = encode(&Frame::Spawn {
+            title: "old".into(),
+            command: None,
+            cwd: Some("/tmp".into()),
+            rows: 24,
+            cols: 80,
+            program: vec!["htop".into()],
+            env: vec![("A".into(), "1".into())],
+        });
+        let mut payload = Payload(&new[5..]);
+        assert_eq!(payload.string().unwrap(), "old");
+        assert_eq!((payload.opt_string().unwrap(), payload.opt_string().unwrap()), (None, Some("/tmp".into())));
+        assert_eq!((payload.u16().unwrap(), payload.u16().unwrap()), (24, 80));
+    }
+}
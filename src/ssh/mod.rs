@@ -0,0 +1,157 @@
+//! SSH client side shared by the GUI and the TUI: authenticate against a
+//! saved connection and run a remote shell behind the same `PtyProcess`
+//! interface as a local one.
+
+use crate::config::profile::Profile;
+use crate::config::ssh::SshConnection;
+use crate::pty::PtyProcess;
+use portable_pty::{NativePtySystem, PtySize, PtySystem};
+use ssh2::Session;
+use std::io::{Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Sent as last output when the remote shell has ended.
+pub const CLOSED_MESSAGE: &[u8] = b"\n[SSH connection closed]\n";
+
+/// Connect, handshake and authenticate against `conn`.
+pub fn open_session(conn: &SshConnection) -> anyhow::Result<Session> {
+    connect(conn, None)
+}
+
+/// Like `open_session`, but gives up once `deadline` has passed. The session
+/// is left with a timeout for the time remaining.
+#[cfg(feature = "gui")]
+pub fn open_session_until(conn: &SshConnection, deadline: Instant) -> anyhow::Result<Session> {
+    connect(conn, Some(deadline))
+}
+
+/// Time left until `deadline`, or an error once it has passed.
+pub fn remaining(deadline: Instant) -> anyhow::Result<Duration> {
+    match deadline.checked_duration_since(Instant::now()) {
+        Some(left) if !left.is_zero() => Ok(left),
+        _ => anyhow::bail!("{}", rust_i18n::t!("ssh.timed_out")),
+    }
+}
+
+/// Blocking libssh2 calls give up after the time left until `deadline`.
+fn limit(sess: &Session, deadline: Option<Instant>) -> anyhow::Result<()> {
+    if let Some(deadline) = deadline {
+        sess.set_timeout(remaining(deadline)?.as_millis().clamp(1, u32::MAX as u128) as u32);
+    }
+    Ok(())
+}
+
+fn connect(conn: &SshConnection, deadline: Option<Instant>) -> anyhow::Result<Session> {
+    // Connect to SSH server
+    let target = format!("{}:{}", conn.host, conn.port);
+    let failed = |e: std::io::Error| anyhow::anyhow!("{}: {}", rust_i18n::t!("ssh.tcp_failed", target = target), e);
+    let tcp = match deadline {
+        None => TcpStream::connect(&target).map_err(failed)?,
+        Some(deadline) => {
+            // Try each address the host resolves to, within the time left
+            let mut last = std::io::Error::new(std::io::ErrorKind::NotFound, rust_i18n::t!("ssh.no_address").to_string());
+            let mut connected = None;
+            for addr in (conn.host.as_str(), conn.port).to_socket_addrs().map_err(failed)? {
+                match TcpStream::connect_timeout(&addr, remaining(deadline)?) {
+                    Ok(tcp) => {
+                        connected = Some(tcp);
+                        break;
+                    }
+                    Err(e) => last = e,
+                }
+            }
+            connected.ok_or_else(|| failed(last))?
+        }
+    };
+
+    let mut sess = Session::new()?;
+    sess.set_tcp_stream(tcp);
+    limit(&sess, deadline)?;
+    sess.handshake()
+        .map_err(|e| anyhow::anyhow!("{}: {}", rust_i18n::t!("ssh.handshake_failed"), e))?;
+
+    // Authenticate: key file (password = optional passphrase) or password
+    limit(&sess, deadline)?;
+    if let Some(identity) = &conn.identity_file {
+        let passphrase = (!conn.password.is_empty()).then_some(conn.password.as_str());
+        sess.userauth_pubkey_file(&conn.username, None, std::path::Path::new(identity), passphrase)
+            .map_err(|e| anyhow::anyhow!("{}: {}", rust_i18n::t!("ssh.key_auth_failed"), e))?;
+    } else {
+        if conn.password.is_empty() {
+            anyhow::bail!("{}", rust_i18n::t!("ssh.no_password"));
+        }
+        sess.userauth_password(&conn.username, &conn.password)
+            .map_err(|e| anyhow::anyhow!("{}: {}", rust_i18n::t!("ssh.auth_failed"), e))?;
+    }
+
+    if !sess.authenticated() {
+        anyhow::bail!("{}", rust_i18n::t!("ssh.auth_rejected"));
+    }
+
+    Ok(sess)
+}
+
+/// Open an interactive shell on `conn` with a remote PTY of the given size.
+/// The returned process has no local child; its master is a placeholder,
+/// so resizes do not reach the server. A profile sets the terminal type and
+/// offers its variables, which servers only take when they accept them.
+pub fn spawn_shell(conn: &SshConnection, profile: Option<&Profile>, rows: u16, cols: u16) -> anyhow::Result<PtyProcess> {
+    let (to_writer_tx, to_writer_rx) = mpsc::channel::<Vec<u8>>();
+    let (from_reader_tx, from_reader_rx) = mpsc::channel::<Vec<u8>>();
+
+    let sess = open_session(conn)?;
+
+    // Open channel and request PTY
+    let mut channel = sess.channel_session()
+        .map_err(|e| anyhow::anyhow!("{}: {}", rust_i18n::t!("ssh.channel_failed"), e))?;
+    let env = profile.map(Profile::environment).unwrap_or_default();
+    for (key, value) in env.iter().filter(|(key, _)| key != "TERM") {
+        let _ = channel.setenv(key, value);
+    }
+    let term = env.iter().find(|(key, _)| key == "TERM").map_or("xterm", |(_, value)| value.as_str());
+    channel.request_pty(term, None, Some((cols as u32, rows as u32, 0, 0)))
+        .map_err(|e| anyhow::anyhow!("{}: {}", rust_i18n::t!("ssh.pty_failed"), e))?;
+    channel.shell()
+        .map_err(|e| anyhow::anyhow!("{}: {}", rust_i18n::t!("ssh.shell_failed"), e))?;
+
+    // Split channel for read/write
+    let read_channel = channel.stream(0);
+    let mut write_channel = channel.stream(0);
+
+    // Writer thread
+    thread::spawn(move || {
+        while let Ok(buf) = to_writer_rx.recv() {
+            let _ = write_channel.write_all(&buf);
+            let _ = write_channel.flush();
+        }
+    });
+
+    // Reader thread
+    thread::spawn(move || {
+        let mut buf = [0u8; 4096];
+        let mut read_stream = read_channel;
+        loop {
+            match read_stream.read(&mut buf) {
+                Ok(0) => {
+                    let _ = from_reader_tx.send(CLOSED_MESSAGE.to_vec());
+                    break;
+                }
+                Ok(n) => {
+                    if from_reader_tx.send(buf[..n].to_vec()).is_err() {
+                        break;
+                    }
+                }
+                Err(_) => {
+                    thread::sleep(Duration::from_millis(10));
+                }
+            }
+        }
+    });
+
+    // Dummy PTY master that does nothing (the SSH channel handles I/O via threads)
+    let pair = NativePtySystem::default().openpty(PtySize { rows, cols, pixel_width: 0, pixel_height: 0 })?;
+    Ok(PtyProcess { rx: from_reader_rx, writer: to_writer_tx, master: pair.master, child_pid: None })
+}
@@ -0,0 +1,80 @@
+//! xterm color palette for front-ends that paint RGB (the GUI); the TUI
+//! hands indexed colors to the host terminal as they are.
+
+pub type Rgb = (u8, u8, u8);
+
+/// RGB value of a cell color; `None` for the default color, which each
+/// front-end picks itself.
+pub fn to_rgb(color: vt100::Color) -> Option<Rgb> {
+    match color {
+        vt100::Color::Default => None,
+        vt100::Color::Idx(i) => Some(ansi256_to_rgb(i)),
+        vt100::Color::Rgb(r, g, b) => Some((r, g, b)),
+    }
+}
+
+/// The 16 system colors, the 6×6×6 cube and the grey ramp.
+pub fn ansi256_to_rgb(i: u8) -> Rgb {
+    const SYSTEM: [Rgb; 16] = [
+        (0, 0, 0),
+        (128, 0, 0),
+        (0, 128, 0),
+        (128, 128, 0),
+        (0, 0, 128),
+        (128, 0, 128),
+        (0, 128, 128),
+        (192, 192, 192),
+        (128, 128, 128),
+        (255, 0, 0),
+        (0, 255, 0),
+        (255, 255, 0),
+        (0, 0, 255),
+        (255, 0, 255),
+        (0, 255, 255),
+        (255, 255, 255),
+    ];
+    match i {
+        0..=15 => SYSTEM[i as usize],
+        16..=231 => {
+            let n = i - 16;
+            let level = |v: u8| [0, 95, 135, 175, 215, 255][v as usize];
+            (level(n / 36), level((n % 36) / 6), level(n % 6))
+        }
+        232..=255 => {
+            let l = 8 + (i - 232) * 10;
+            (l, l, l)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn system_colors() {
+        assert_eq!(ansi256_to_rgb(1), (128, 0, 0));
+        assert_eq!(ansi256_to_rgb(15), (255, 255, 255));
+    }
+
+    #[test]
+    fn color_cube() {
+        assert_eq!(ansi256_to_rgb(16), (0, 0, 0));
+        assert_eq!(ansi256_to_rgb(196), (255, 0, 0));
+        assert_eq!(ansi256_to_rgb(231), (255, 255, 255));
+        assert_eq!(ansi256_to_rgb(110), (135, 175, 215));
+    }
+
+    #[test]
+    fn grey_ramp() {
+        assert_eq!(ansi256_to_rgb(232), (8, 8, 8));
+        assert_eq!(ansi256_to_rgb(255), (238, 238, 238));
+    }
+
+    #[test]
+    fn cell_colors() {
+        assert_eq!(to_rgb(vt100::Color::Default), None);
+        assert_eq!(to_rgb(vt100::Color::Idx(2)), Some((0, 128, 0)));
+        assert_eq!(to_rgb(vt100::Color::Rgb(1, 2, 3)), Some((1, 2, 3)));
+    }
+}
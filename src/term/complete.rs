@@ -0,0 +1,444 @@
+//! Where command suggestions come from: executables on `$PATH`, file names
+//! in the shell's directory, the user's history and subcommand specs.
+//!
+//! A spec is a TOML file naming a command with its options and subcommands,
+//! which nest the same way:
+//!
+//! ```toml
+//! name = "git"
+//! options = ["--version"]
+//!
+//! [[subcommands]]
+//! name = "stash"
+//! subcommands = [{ name = "pop" }, { name = "list" }]
+//! ```
+//!
+//! Specs for git, cargo and docker are built in; a file in the spec
+//! directory replaces the built-in one of the same name.
+
+use serde::Deserialize;
+use std::cell::RefCell;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+const BUILTIN_SPECS: &[&str] = &[
+    include_str!("specs/git.toml"),
+    include_str!("specs/cargo.toml"),
+    include_str!("specs/docker.toml"),
+];
+
+/// History matches offered before the other sources.
+const HISTORY_FIRST: usize = 2;
+
+/// How long a `$PATH` scan is trusted; programs installed since show up
+/// after this.
+const RESCAN_AFTER: Duration = Duration::from_secs(60);
+
+/// File names offered from one directory, so a huge one does not stall
+/// typing.
+const DIR_MATCHES: usize = 500;
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Spec {
+    pub name: String,
+    #[serde(default)]
+    pub options: Vec<String>,
+    #[serde(default)]
+    pub subcommands: Vec<Spec>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    History,
+    Command,
+    Subcommand,
+    Option,
+    Directory,
+    File,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Suggestion {
+    pub line: String,  // the whole command line once taken
+    pub label: String, // what is shown: the completed word, or the history line
+    pub kind: Kind,
+}
+
+/// Where the line is typed.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Context<'a> {
+    pub host: Option<&'a str>, // SSH connection; None = local shell
+    pub cwd: Option<&'a Path>, // for file names; None where unknown
+}
+
+#[derive(Clone, Default)]
+pub struct Sources {
+    specs: Vec<Spec>,
+    executables: RefCell<Executables>,
+    history: Vec<(Option<String>, String)>, // (host, command), newest first
+}
+
+/// The programs on `$PATH`, scanned on first use and again when `$PATH`
+/// changes or the scan is older than `RESCAN_AFTER`.
+#[derive(Clone, Default)]
+struct Executables {
+    path: Option<OsString>,
+    scanned: Option<Instant>,
+    names: Vec<String>,
+}
+
+impl Executables {
+    fn is_stale(&self, path: &Option<OsString>, now: Instant) -> bool {
+        self.path != *path || self.scanned.is_none_or(|at| now.duration_since(at) >= RESCAN_AFTER)
+    }
+
+    fn names(&mut self) -> &[String] {
+        let (path, now) = (std::env::var_os("PATH"), Instant::now());
+        if self.is_stale(&path, now) {
+            *self = Executables { names: path_executables(path.as_deref()), path, scanned: Some(now) };
+        }
+        &self.names
+    }
+}
+
+impl Sources {
+    /// The built-in specs and those in `spec_dir` (`*.toml`), with the
+    /// files that could not be read as errors.
+    pub fn load(spec_dir: &Path) -> (Self, Vec<String>) {
+        let mut specs: Vec<Spec> = BUILTIN_SPECS.iter().filter_map(|spec| toml::from_str(spec).ok()).collect();
+        let mut errors = Vec::new();
+        let mut files: Vec<PathBuf> = std::fs::read_dir(spec_dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|e| e.path())
+            .filter(|p| p.extension().is_some_and(|e| e == "toml"))
+            .collect();
+        files.sort();
+        for path in files {
+            let spec = std::fs::read_to_string(&path)
+                .map_err(|e| e.to_string())
+                .and_then(|content| toml::from_str::<Spec>(&content).map_err(|e| e.to_string()));
+            match spec {
+                Ok(spec) => {
+                    specs.retain(|s| s.name != spec.name);
+                    specs.push(spec);
+                }
+                Err(e) => errors.push(format!("{}: {}", path.display(), e)),
+            }
+        }
+        (Sources { specs, ..Default::default() }, errors)
+    }
+
+    /// Note a command run on `host`, as the newest history entry.
+    pub fn add_history(&mut self, host: Option<&str>, command: &str) {
+        let command = command.trim();
+        if command.is_empty() {
+            return;
+        }
+        self.history.retain(|(h, c)| !(h.as_deref() == host && c == command));
+        self.history.insert(0, (host.map(String::from), command.to_string()));
+    }
+
+    /// Add older commands (newest first) behind the known ones.
+    pub fn extend_history(&mut self, older: impl IntoIterator<Item = (Option<String>, String)>) {
+        let mut known: std::collections::HashSet<(Option<String>, String)> = self.history.iter().cloned().collect();
+        for entry in older {
+            if known.insert(entry.clone()) {
+                self.history.push(entry);
+            }
+        }
+    }
+
+    pub fn clear_history(&mut self) {
+        self.history.clear();
+    }
+
+    /// Up to `limit` ways to continue `line`, the text typed so far: the
+    /// latest history matches, then completions of its last word.
+    pub fn complete(&self, line: &str, ctx: &Context, limit: usize) -> Vec<Suggestion> {
+        if line.trim().is_empty() {
+            return Vec::new();
+        }
+        let mut found: Vec<Suggestion> = self
+            .history
+            .iter()
+            .filter(|(host, command)| host.as_deref() == ctx.host && command.len() > line.len() && command.starts_with(line))
+            .take(HISTORY_FIRST.min(limit))
+            .map(|(_, command)| Suggestion { line: command.clone(), label: command.clone(), kind: Kind::History })
+            .collect();
+
+        let (head, word) = split_word(line);
+        let words: Vec<&str> = head.split_whitespace().collect();
+        let candidates = match words.split_first() {
+            None => self.commands(word, ctx),
+            Some((command, args)) => self.arguments(command, args, word, ctx),
+        };
+        for (completion, kind) in candidates {
+            if found.len() >= limit {
+                break;
+            }
+            let full = format!("{}{}", head, completion);
+            if full.len() > line.len() && !found.iter().any(|s| s.line == full) {
+                found.push(Suggestion { line: full, label: completion, kind });
+            }
+        }
+        found
+    }
+
+    /// Candidates for the first word: executables of a local shell, the
+    /// spec'd commands on a remote one, and paths (`./run.sh`).
+    fn commands(&self, word: &str, ctx: &Context) -> Vec<(String, Kind)> {
+        if word.contains('/') {
+            return files(word, ctx.cwd);
+        }
+        let mut executables = self.executables.borrow_mut();
+        let names: Vec<&str> = match ctx.host {
+            None => executables.names().iter().map(String::as_str).collect(),
+            Some(_) => self.specs.iter().map(|s| s.name.as_str()).collect(),
+        };
+        names
+            .into_iter()
+            .filter(|name| name.starts_with(word))
+            .map(|name| (name.to_string(), Kind::Command))
+            .collect()
+    }
+
+    /// Candidates for a later word: the options and subcommands the spec of
+    /// `command` has at this point, then file names.
+    fn arguments(&self, command: &str, args: &[&str], word: &str, ctx: &Context) -> Vec<(String, Kind)> {
+        let mut candidates = Vec::new();
+        if let Some(mut spec) = self.specs.iter().find(|s| s.name == command) {
+            for arg in args {
+                if let Some(sub) = spec.subcommands.iter().find(|s| s.name == *arg) {
+                    spec = sub;
+                }
+            }
+            if word.starts_with('-') {
+                candidates.extend(spec.options.iter().filter(|o| o.starts_with(word)).map(|o| (o.clone(), Kind::Option)));
+            } else {
+                let subs = spec.subcommands.iter().filter(|s| s.name.starts_with(word));
+                candidates.extend(subs.map(|s| (s.name.clone(), Kind::Subcommand)));
+            }
+        }
+        if !word.starts_with('-') {
+            candidates.extend(files(word, ctx.cwd));
+        }
+        candidates
+    }
+}
+
+/// Split `line` before its last word (empty after a space); a space
+/// escaped with `\` belongs to the word.
+fn split_word(line: &str) -> (&str, &str) {
+    let bytes = line.as_bytes();
+    let start = (0..bytes.len())
+        .rev()
+        .find(|&i| bytes[i] == b' ' && (i == 0 || bytes[i - 1] != b'\\'))
+        .map_or(0, |i| i + 1);
+    line.split_at(start)
+}
+
+/// Entries of the directory `word` points into (relative to `cwd`) whose
+/// names continue it; directories end in `/`. Hidden ones only for a
+/// word starting with a dot.
+fn files(word: &str, cwd: Option<&Path>) -> Vec<(String, Kind)> {
+    let Some(cwd) = cwd else {
+        return Vec::new();
+    };
+    let (dir_part, prefix) = match word.rfind('/') {
+        Some(i) => word.split_at(i + 1),
+        None => ("", word),
+    };
+    let unescaped = unescape(dir_part);
+    let dir = match unescaped.strip_prefix("~/") {
+        Some(rest) => std::env::var_os("HOME").map_or_else(|| cwd.join(&unescaped), |home| PathBuf::from(home).join(rest)),
+        None => cwd.join(&unescaped), // an absolute path replaces `cwd`
+    };
+    let prefix = unescape(prefix);
+    let mut found: Vec<(String, Kind)> = std::fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            let shown = name.starts_with(&prefix) && (!name.starts_with('.') || prefix.starts_with('.'));
+            shown.then_some((entry, name))
+        })
+        .take(DIR_MATCHES)
+        .map(|(entry, name)| {
+            let escaped = name.replace(' ', "\\ ");
+            match entry.path().is_dir() {
+                true => (format!("{}{}/", dir_part, escaped), Kind::Directory),
+                false => (format!("{}{}", dir_part, escaped), Kind::File),
+            }
+        })
+        .collect();
+    found.sort_by(|a, b| a.0.cmp(&b.0));
+    found
+}
+
+fn unescape(text: &str) -> String {
+    text.replace("\\ ", " ")
+}
+
+/// Names of the programs in the `path` directories, sorted.
+fn path_executables(path: Option<&std::ffi::OsStr>) -> Vec<String> {
+    let mut names: Vec<String> = path
+        .map(|path| std::env::split_paths(path).collect::<Vec<_>>())
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|dir| std::fs::read_dir(dir).ok())
+        .flatten()
+        .flatten()
+        .filter(is_executable)
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .map(program_name)
+        .collect();
+    names.sort();
+    names.dedup();
+    names
+}
+
+/// The name a program is typed as (`cargo` for `cargo.exe` on Windows).
+fn program_name(file_name: String) -> String {
+    #[cfg(windows)]
+    if let Some(dot) = file_name.rfind('.') {
+        return file_name[..dot].to_string();
+    }
+    file_name
+}
+
+fn is_executable(entry: &std::fs::DirEntry) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        // Through symlinks, which is how many programs are installed
+        std::fs::metadata(entry.path()).is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+    }
+    #[cfg(not(unix))]
+    {
+        let ext = entry.path().extension().map(|e| e.to_ascii_lowercase());
+        ext.is_some_and(|e| e == "exe" || e == "bat" || e == "cmd")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sources() -> Sources {
+        let (mut sources, errors) = Sources::load(Path::new("/nonexistent"));
+        assert!(errors.is_empty());
+        let names = vec!["cal".to_string(), "cargo".into(), "cat".into(), "git".into()];
+        let scanned = Some(Instant::now());
+        sources.executables = RefCell::new(Executables { path: std::env::var_os("PATH"), scanned, names });
+        sources
+    }
+
+    fn lines(found: Vec<Suggestion>) -> Vec<String> {
+        found.into_iter().map(|s| s.line).collect()
+    }
+
+    #[test]
+    fn completes_commands_from_path_or_specs() {
+        let sources = sources();
+        let local = Context::default();
+        assert_eq!(lines(sources.complete("ca", &local, 5)), ["cal", "cargo", "cat"]);
+        let remote = Context { host: Some("web"), cwd: None };
+        assert_eq!(lines(sources.complete("ca", &remote, 5)), ["cargo"]);
+        assert!(sources.complete("", &local, 5).is_empty());
+    }
+
+    #[test]
+    fn follows_nested_subcommands_and_options() {
+        let sources = sources();
+        let ctx = Context::default();
+        assert_eq!(lines(sources.complete("git sta", &ctx, 5)), ["git status", "git stash"]);
+        assert_eq!(lines(sources.complete("git stash p", &ctx, 5)), ["git stash push", "git stash pop"]);
+        assert_eq!(lines(sources.complete("docker compose up --b", &ctx, 5)), ["docker compose up --build"]);
+        let found = sources.complete("cargo build --rel", &ctx, 5);
+        assert_eq!(found[0].label, "--release");
+        assert_eq!(found[0].kind, Kind::Option);
+    }
+
+    #[test]
+    fn history_comes_first_and_per_host() {
+        let mut sources = sources();
+        sources.add_history(None, "git status --short");
+        sources.add_history(Some("web"), "git stash pop");
+        sources.add_history(None, "git status --short");
+        sources.extend_history([(None, "git status --short".to_string()), (None, "git stage".to_string())]);
+        let ctx = Context::default();
+        let found = sources.complete("git st", &ctx, 3);
+        assert_eq!(lines(found.clone()), ["git status --short", "git stage", "git status"]);
+        assert_eq!(found[0].kind, Kind::History);
+        let remote = Context { host: Some("web"), cwd: None };
+        assert_eq!(lines(sources.complete("git st", &remote, 1)), ["git stash pop"]);
+    }
+
+    #[test]
+    fn completes_file_names() {
+        let dir = std::env::temp_dir().join(format!("termix-complete-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("src dir")).unwrap();
+        std::fs::write(dir.join("src dir/main.rs"), "").unwrap();
+        std::fs::write(dir.join("Cargo.toml"), "").unwrap();
+        std::fs::write(dir.join(".hidden"), "").unwrap();
+        let sources = sources();
+        let ctx = Context { host: None, cwd: Some(&dir) };
+
+        assert_eq!(lines(sources.complete("vim ", &ctx, 5)), ["vim Cargo.toml", "vim src\\ dir/"]);
+        assert_eq!(lines(sources.complete("vim src\\ dir/m", &ctx, 5)), ["vim src\\ dir/main.rs"]);
+        assert_eq!(lines(sources.complete("cat .h", &ctx, 5)), ["cat .hidden"]);
+        // git's subcommands first, then files
+        assert_eq!(lines(sources.complete("git c", &ctx, 5))[..2], ["git commit", "git checkout"]);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn path_scan_is_redone_when_path_changes_or_ages() {
+        let now = Instant::now();
+        let path = Some(OsString::from("/usr/bin:/bin"));
+        let scan = Executables { path: path.clone(), scanned: Some(now), names: Vec::new() };
+        assert!(!scan.is_stale(&path, now + Duration::from_secs(1)));
+        assert!(scan.is_stale(&Some(OsString::from("/usr/bin")), now));
+        assert!(scan.is_stale(&path, now + RESCAN_AFTER));
+        assert!(Executables::default().is_stale(&None, now));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinked_programs_are_commands() {
+        use std::os::unix::fs::{symlink, PermissionsExt};
+        let dir = std::env::temp_dir().join(format!("termix-complete-{}-links", std::process::id()));
+        let bin = dir.join("bin");
+        std::fs::create_dir_all(&bin).unwrap();
+        std::fs::write(dir.join("python3.12"), "").unwrap();
+        std::fs::set_permissions(dir.join("python3.12"), std::fs::Permissions::from_mode(0o755)).unwrap();
+        std::fs::write(dir.join("notes.txt"), "").unwrap();
+        symlink(dir.join("python3.12"), bin.join("python3")).unwrap();
+        symlink(dir.join("notes.txt"), bin.join("notes")).unwrap();
+        symlink(dir.join("gone"), bin.join("dangling")).unwrap();
+
+        let names = path_executables(Some(bin.as_os_str()));
+        assert_eq!(names, ["python3"]);
+        let (mut sources, _) = Sources::load(Path::new("/nonexistent"));
+        sources.executables = RefCell::new(Executables { path: std::env::var_os("PATH"), scanned: Some(Instant::now()), names });
+        assert_eq!(lines(sources.complete("pyth", &Context::default(), 5)), ["python3"]);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn spec_files_replace_builtins() {
+        let dir = std::env::temp_dir().join(format!("termix-specs-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("git.toml"), "name = \"git\"\nsubcommands = [{ name = \"sync\" }]").unwrap();
+        std::fs::write(dir.join("broken.toml"), "name = ").unwrap();
+        let (sources, errors) = Sources::load(&dir);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(lines(sources.complete("git s", &Context::default(), 5)), ["git sync"]);
+        assert!(sources.specs.iter().any(|s| s.name == "cargo"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
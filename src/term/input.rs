@@ -0,0 +1,172 @@
+//! Bytes a terminal sends to the program for keys and pastes, following
+//! xterm. Front-ends translate their own key events into `Key`.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Key {
+    Char(char),
+    Enter,
+    Backspace,
+    Tab,
+    BackTab,
+    Esc,
+    Up,
+    Down,
+    Left,
+    Right,
+    Home,
+    End,
+    Insert,
+    Delete,
+    PageUp,
+    PageDown,
+    F(u8),
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Modifiers {
+    pub ctrl: bool,
+    pub alt: bool,
+    pub shift: bool,
+}
+
+impl Modifiers {
+    pub const NONE: Modifiers = Modifiers { ctrl: false, alt: false, shift: false };
+    pub const CTRL: Modifiers = Modifiers { ctrl: true, alt: false, shift: false };
+
+    /// xterm modifier parameter for cursor and function keys (1 = none).
+    fn parameter(self) -> u8 {
+        1 + self.shift as u8 + 2 * self.alt as u8 + 4 * self.ctrl as u8
+    }
+}
+
+/// Bytes for `key`; `application_cursor` is the mode the program switched
+/// the cursor keys to (`Screen::application_cursor`).
+pub fn encode_key(key: Key, modifiers: Modifiers, application_cursor: bool) -> Option<Vec<u8>> {
+    let modifier = modifiers.parameter();
+    let cursor_key = |c: char| -> Vec<u8> {
+        if modifier > 1 {
+            format!("\x1b[1;{}{}", modifier, c).into_bytes()
+        } else if application_cursor {
+            format!("\x1bO{}", c).into_bytes()
+        } else {
+            format!("\x1b[{}", c).into_bytes()
+        }
+    };
+    let tilde_key = |n: u8| -> Vec<u8> {
+        if modifier > 1 {
+            format!("\x1b[{};{}~", n, modifier).into_bytes()
+        } else {
+            format!("\x1b[{}~", n).into_bytes()
+        }
+    };
+    let bytes = match key {
+        Key::Char(c) if modifiers.ctrl => match c.to_ascii_lowercase() {
+            c @ 'a'..='z' => vec![c as u8 - b'a' + 1],
+            ' ' | '@' | '2' => vec![0],
+            '[' | '3' => vec![0x1b],
+            '\\' | '4' => vec![0x1c],
+            ']' | '5' => vec![0x1d],
+            '^' | '6' => vec![0x1e],
+            '_' | '-' | '7' => vec![0x1f],
+            '?' | '8' => vec![0x7f],
+            c => c.to_string().into_bytes(),
+        },
+        Key::Char(c) => c.to_string().into_bytes(),
+        Key::Enter => vec![b'\r'],
+        Key::Backspace => vec![0x7f],
+        Key::Tab => vec![b'\t'],
+        Key::BackTab => b"\x1b[Z".to_vec(),
+        Key::Esc => vec![0x1b],
+        Key::Up => return Some(cursor_key('A')),
+        Key::Down => return Some(cursor_key('B')),
+        Key::Right => return Some(cursor_key('C')),
+        Key::Left => return Some(cursor_key('D')),
+        Key::Home => return Some(cursor_key('H')),
+        Key::End => return Some(cursor_key('F')),
+        Key::Insert => return Some(tilde_key(2)),
+        Key::Delete => return Some(tilde_key(3)),
+        Key::PageUp => return Some(tilde_key(5)),
+        Key::PageDown => return Some(tilde_key(6)),
+        Key::F(n @ 1..=4) => {
+            let c = (b'P' + n - 1) as char;
+            return Some(if modifier > 1 { format!("\x1b[1;{}{}", modifier, c) } else { format!("\x1bO{}", c) }.into_bytes());
+        }
+        Key::F(n @ 5..=12) => return Some(tilde_key([15, 17, 18, 19, 20, 21, 23, 24][n as usize - 5])),
+        Key::F(_) => return None,
+    };
+    // Alt sends ESC before the key
+    Some(if modifiers.alt { [vec![0x1b], bytes].concat() } else { bytes })
+}
+
+/// Pasted text with line breaks as Enter, wrapped in bracketed-paste
+/// markers when the program asked for them.
+pub fn encode_paste(text: &str, bracketed: bool) -> Vec<u8> {
+    let text = text.replace("\r\n", "\r").replace('\n', "\r");
+    if bracketed {
+        format!("\x1b[200~{}\x1b[201~", text).into_bytes()
+    } else {
+        text.into_bytes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode(key: Key, modifiers: Modifiers) -> Vec<u8> {
+        encode_key(key, modifiers, false).unwrap()
+    }
+
+    #[test]
+    fn plain_characters() {
+        assert_eq!(encode(Key::Char('a'), Modifiers::NONE), b"a");
+        assert_eq!(encode(Key::Char('ß'), Modifiers::NONE), "ß".as_bytes());
+        assert_eq!(encode(Key::Enter, Modifiers::NONE), b"\r");
+        assert_eq!(encode(Key::Backspace, Modifiers::NONE), [0x7f]);
+    }
+
+    #[test]
+    fn control_characters() {
+        assert_eq!(encode(Key::Char('c'), Modifiers::CTRL), [0x03]);
+        assert_eq!(encode(Key::Char('D'), Modifiers::CTRL), [0x04]);
+        assert_eq!(encode(Key::Char(' '), Modifiers::CTRL), [0x00]);
+        assert_eq!(encode(Key::Char('['), Modifiers::CTRL), [0x1b]);
+        assert_eq!(encode(Key::Char('?'), Modifiers::CTRL), [0x7f]);
+    }
+
+    #[test]
+    fn alt_prefixes_escape() {
+        let alt = Modifiers { alt: true, ..Modifiers::NONE };
+        assert_eq!(encode(Key::Char('x'), alt), b"\x1bx");
+        assert_eq!(encode(Key::Char('x'), Modifiers { ctrl: true, ..alt }), [0x1b, 0x18]);
+    }
+
+    #[test]
+    fn cursor_keys_follow_the_mode() {
+        assert_eq!(encode_key(Key::Up, Modifiers::NONE, false).unwrap(), b"\x1b[A");
+        assert_eq!(encode_key(Key::Up, Modifiers::NONE, true).unwrap(), b"\x1bOA");
+        assert_eq!(encode_key(Key::Home, Modifiers::NONE, false).unwrap(), b"\x1b[H");
+        // Modifiers win over application mode
+        assert_eq!(encode_key(Key::Left, Modifiers::CTRL, true).unwrap(), b"\x1b[1;5D");
+        let shift = Modifiers { shift: true, ..Modifiers::NONE };
+        assert_eq!(encode(Key::End, shift), b"\x1b[1;2F");
+    }
+
+    #[test]
+    fn tilde_and_function_keys() {
+        assert_eq!(encode(Key::PageUp, Modifiers::NONE), b"\x1b[5~");
+        assert_eq!(encode(Key::Delete, Modifiers::CTRL), b"\x1b[3;5~");
+        assert_eq!(encode(Key::F(1), Modifiers::NONE), b"\x1bOP");
+        assert_eq!(encode(Key::F(4), Modifiers::CTRL), b"\x1b[1;5S");
+        assert_eq!(encode(Key::F(5), Modifiers::NONE), b"\x1b[15~");
+        assert_eq!(encode(Key::F(12), Modifiers::NONE), b"\x1b[24~");
+        assert_eq!(encode_key(Key::F(13), Modifiers::NONE, false), None);
+        assert_eq!(encode(Key::BackTab, Modifiers::NONE), b"\x1b[Z");
+    }
+
+    #[test]
+    fn paste() {
+        assert_eq!(encode_paste("a\r\nb\nc", false), b"a\rb\rc");
+        assert_eq!(encode_paste("ls", true), b"\x1b[200~ls\x1b[201~");
+    }
+}
@@ -0,0 +1,344 @@
+//! Headless terminal core shared by the GUI and the TUI: the emulated screen
+//! with its scrollback, selection and search, key and paste encoding, the
+//! color palette and command suggestions. Nothing here draws or reads real
+//! input, so it is tested without a window or a TTY; the front-ends only
+//! map their events in and paint `Terminal::screen` out. Shells that send
+//! prompt marks (OSC 133) also report the commands they run.
+
+pub mod color;
+pub mod complete;
+pub mod input;
+pub mod search;
+pub mod selection;
+pub mod session;
+pub mod shell;
+pub mod suggest;
+pub mod view;
+
+pub use search::Match;
+pub use selection::{Point, Selection};
+pub use session::Session;
+pub use shell::ShellEvent;
+
+/// Escape sequences that do not change the screen.
+#[derive(Default)]
+struct Events {
+    title: String,
+    shell: shell::Marks,
+}
+
+impl vt100::Callbacks for Events {
+    fn set_window_title(&mut self, _: &mut vt100::Screen, title: &[u8]) {
+        self.title = String::from_utf8_lossy(title).into_owned();
+    }
+
+    fn unhandled_osc(&mut self, screen: &mut vt100::Screen, params: &[&[u8]]) {
+        self.shell.osc(screen, params);
+    }
+}
+
+/// Emulated screen plus scrollback. Lines are addressed absolutely: line 0
+/// is the oldest scrollback line, the screen follows the scrollback.
+pub struct Terminal {
+    parser: vt100::Parser<Events>,
+    rows: u16,
+    cols: u16,
+    history: usize, // scrollback lines above the screen
+    // Plain text of scrollback + screen, rebuilt on demand after new output
+    lines: Vec<String>,
+    lines_dirty: bool,
+    pub selection: Option<Selection>,
+}
+
+impl Terminal {
+    pub fn new(rows: u16, cols: u16, scrollback: usize) -> Self {
+        Terminal {
+            parser: vt100::Parser::new_with_callbacks(rows, cols, scrollback, Events::default()),
+            rows,
+            cols,
+            history: 0,
+            lines: Vec::new(),
+            lines_dirty: true,
+            selection: None,
+        }
+    }
+
+    /// Feed output of the program running in the terminal.
+    pub fn process(&mut self, bytes: &[u8]) {
+        self.parser.process(bytes);
+        self.update_history();
+    }
+
+    // vt100 only tells the scrollback length as the largest scroll offset
+    fn update_history(&mut self) {
+        let screen = self.parser.screen_mut();
+        let offset = screen.scrollback();
+        screen.set_scrollback(usize::MAX);
+        self.history = screen.scrollback();
+        screen.set_scrollback(offset);
+        self.lines_dirty = true;
+    }
+
+    pub fn screen(&self) -> &vt100::Screen {
+        self.parser.screen()
+    }
+
+    /// Window title set by the program (OSC 0/2), empty if none.
+    pub fn title(&self) -> &str {
+        &self.parser.callbacks().title
+    }
+
+    /// Whether the shell sends prompt marks, i.e. `ShellEvent::Command`s.
+    pub fn shell_integration(&self) -> bool {
+        self.parser.callbacks().shell.seen
+    }
+
+    /// Working directory last reported by the shell (OSC 7).
+    pub fn reported_cwd(&self) -> Option<&std::path::Path> {
+        self.parser.callbacks().shell.cwd.as_deref()
+    }
+
+    /// The command line typed so far (up to the cursor), where the shell
+    /// marks it with OSC 133; None while a command runs.
+    pub fn input_line(&self) -> Option<String> {
+        if self.screen().alternate_screen() {
+            return None;
+        }
+        self.parser.callbacks().shell.input(self.screen())
+    }
+
+    /// Without prompt marks: the command line typed so far, guessed from
+    /// the prompt sign on the cursor row.
+    pub fn guessed_input_line(&self) -> Option<String> {
+        if self.screen().alternate_screen() {
+            return None;
+        }
+        shell::guess_input(self.screen())
+    }
+
+    /// Shell events since the last call, oldest first.
+    pub fn take_shell_events(&mut self) -> Vec<ShellEvent> {
+        std::mem::take(&mut self.parser.callbacks_mut().shell.events)
+    }
+
+    pub fn rows(&self) -> u16 {
+        self.rows
+    }
+
+    pub fn cols(&self) -> u16 {
+        self.cols
+    }
+
+    /// Returns false when the size did not change.
+    pub fn resize(&mut self, rows: u16, cols: u16) -> bool {
+        if rows == self.rows && cols == self.cols {
+            return false;
+        }
+        self.rows = rows;
+        self.cols = cols;
+        self.parser.screen_mut().set_size(rows, cols);
+        self.update_history();
+        true
+    }
+
+    /// How many lines the view is scrolled back (0 = following the output).
+    pub fn scroll_offset(&self) -> usize {
+        self.parser.screen().scrollback()
+    }
+
+    /// Scroll the view back; clamped to the available scrollback.
+    pub fn set_scroll_offset(&mut self, offset: usize) {
+        self.parser.screen_mut().set_scrollback(offset);
+    }
+
+    /// Number of scrollback lines above the screen.
+    pub fn history_len(&self) -> usize {
+        self.history
+    }
+
+    /// Absolute line shown in screen row `row` at the current scroll offset.
+    pub fn line_at_row(&self, row: u16) -> usize {
+        self.history_len() - self.scroll_offset() + row as usize
+    }
+
+    /// Scroll just enough to bring absolute `line` into view.
+    pub fn scroll_to_line(&mut self, line: usize) {
+        let history = self.history_len();
+        let top = history - self.scroll_offset();
+        let rows = self.rows as usize;
+        if line < top {
+            self.set_scroll_offset(history - line);
+        } else if line >= top + rows {
+            self.set_scroll_offset((history + rows).saturating_sub(line + 1));
+        }
+    }
+
+    /// Scrollback and screen as plain text, one entry per row.
+    pub fn lines(&mut self) -> &[String] {
+        if self.lines_dirty {
+            self.lines = self.collect_lines();
+            self.lines_dirty = false;
+        }
+        &self.lines
+    }
+
+    fn collect_lines(&self) -> Vec<String> {
+        let rows = self.rows as usize;
+        let mut lines = Vec::with_capacity(self.history + rows);
+        // vt100 only exposes the visible rows, so page a copy through the
+        // scrollback and leave the view alone
+        let mut screen = self.parser.screen().clone();
+        let mut back = self.history;
+        while back > 0 {
+            screen.set_scrollback(back);
+            let take = back.min(rows);
+            lines.extend(screen.rows(0, self.cols).take(take));
+            back -= take;
+        }
+        screen.set_scrollback(0);
+        lines.extend(screen.rows(0, self.cols));
+        lines
+    }
+
+    /// Last `count` lines of output, without trailing blank lines.
+    pub fn tail(&self, count: usize) -> String {
+        let fresh;
+        let lines = if self.lines_dirty {
+            fresh = self.collect_lines();
+            &fresh
+        } else {
+            &self.lines
+        };
+        let end = lines.iter().rposition(|l| !l.trim().is_empty()).map_or(0, |i| i + 1);
+        lines[end.saturating_sub(count)..end].join("\n")
+    }
+
+    /// Print saved output followed by `separator` on a dimmed line, e.g. to
+    /// show what a restored session displayed before.
+    pub fn restore(&mut self, text: &str, separator: &str) {
+        if text.is_empty() {
+            return;
+        }
+        let mut bytes = text.replace('\n', "\r\n");
+        bytes.push_str(&format!("\r\n\x1b[2m── {} ──\x1b[0m\r\n", separator));
+        self.process(bytes.as_bytes());
+    }
+
+    /// Text under the selection, if there is one.
+    pub fn selected_text(&mut self) -> Option<String> {
+        let selection = self.selection?;
+        Some(selection.text(self.lines()))
+    }
+
+    /// Every occurrence of `query` in scrollback and screen, oldest first.
+    pub fn search(&mut self, query: &str) -> Vec<Match> {
+        search::find(self.lines(), query)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn numbered(term: &mut Terminal, count: usize) {
+        for i in 0..count {
+            term.process(format!("line {}\r\n", i).as_bytes());
+        }
+    }
+
+    #[test]
+    fn lines_include_scrollback_in_order() {
+        let mut term = Terminal::new(4, 20, 100);
+        numbered(&mut term, 10);
+        let lines = term.lines().to_vec();
+        assert_eq!(lines.len(), 7 + 4);
+        assert_eq!(lines[0], "line 0");
+        assert_eq!(lines[9], "line 9");
+        assert_eq!(lines[10], "");
+        assert_eq!(term.history_len(), 7);
+    }
+
+    #[test]
+    fn scrollback_is_limited() {
+        let mut term = Terminal::new(4, 20, 5);
+        numbered(&mut term, 20);
+        assert_eq!(term.history_len(), 5);
+        assert_eq!(term.lines()[0], "line 12");
+    }
+
+    #[test]
+    fn collecting_lines_keeps_the_scroll_offset() {
+        let mut term = Terminal::new(4, 20, 100);
+        numbered(&mut term, 10);
+        term.set_scroll_offset(3);
+        term.lines();
+        assert_eq!(term.scroll_offset(), 3);
+        assert_eq!(term.screen().rows(0, 20).next().unwrap(), "line 4");
+        assert_eq!(term.line_at_row(0), 4);
+    }
+
+    #[test]
+    fn scroll_offset_is_clamped() {
+        let mut term = Terminal::new(4, 20, 100);
+        numbered(&mut term, 6);
+        term.set_scroll_offset(1000);
+        assert_eq!(term.scroll_offset(), 3);
+    }
+
+    #[test]
+    fn scroll_to_line_brings_it_into_view() {
+        let mut term = Terminal::new(4, 20, 100);
+        numbered(&mut term, 20);
+        term.scroll_to_line(2);
+        assert_eq!(term.line_at_row(0), 2);
+        // Already visible: no movement
+        term.scroll_to_line(4);
+        assert_eq!(term.line_at_row(0), 2);
+        term.scroll_to_line(20);
+        assert_eq!(term.scroll_offset(), 0);
+    }
+
+    #[test]
+    fn tail_skips_trailing_blank_lines() {
+        let mut term = Terminal::new(5, 20, 100);
+        numbered(&mut term, 8);
+        assert_eq!(term.tail(2), "line 6\nline 7");
+        assert_eq!(Terminal::new(5, 20, 100).tail(3), "");
+    }
+
+    #[test]
+    fn restore_prints_text_and_separator() {
+        let mut term = Terminal::new(5, 30, 100);
+        term.restore("one\ntwo", "restored");
+        let lines = term.lines().to_vec();
+        assert_eq!(&lines[..3], ["one", "two", "── restored ──"]);
+        assert_eq!(term.screen().cursor_position(), (3, 0));
+    }
+
+    #[test]
+    fn programs_set_the_title() {
+        let mut term = Terminal::new(5, 30, 100);
+        assert_eq!(term.title(), "");
+        term.process(b"\x1b]2;vim notes.md\x07");
+        assert_eq!(term.title(), "vim notes.md");
+    }
+
+    #[test]
+    fn resize_reports_changes() {
+        let mut term = Terminal::new(5, 30, 100);
+        assert!(!term.resize(5, 30));
+        assert!(term.resize(10, 40));
+        assert_eq!((term.rows(), term.cols()), (10, 40));
+        assert_eq!(term.screen().size(), (10, 40));
+    }
+
+    #[test]
+    fn selection_and_search_use_absolute_lines() {
+        let mut term = Terminal::new(3, 20, 100);
+        numbered(&mut term, 6);
+        term.selection = Some(Selection::new(Point::new(1, 5), Point::new(2, 3)));
+        assert_eq!(term.selected_text().unwrap(), "1\nline");
+        let matches = term.search("line 4");
+        assert_eq!(matches, vec![Match { line: 4, start: 0, end: 6 }]);
+    }
+}
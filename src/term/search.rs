@@ -0,0 +1,113 @@
+//! Plain-text search over scrollback and screen. A query without capitals
+//! ignores case ("smart case"), one with capitals matches exactly.
+
+use super::Point;
+
+/// One hit: absolute line and the half-open range of character columns.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Match {
+    pub line: usize,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Match {
+    pub fn start_point(&self) -> Point {
+        Point::new(self.line, self.start)
+    }
+
+    pub fn contains(&self, line: usize, col: usize) -> bool {
+        line == self.line && (self.start..self.end).contains(&col)
+    }
+}
+
+/// Non-overlapping occurrences of `query`, oldest line first.
+pub fn find(lines: &[String], query: &str) -> Vec<Match> {
+    if query.is_empty() {
+        return Vec::new();
+    }
+    let ignore_case = !query.chars().any(char::is_uppercase);
+    let fold = |c: char| if ignore_case { c.to_lowercase().next().unwrap_or(c) } else { c };
+    let needle: Vec<char> = query.chars().map(fold).collect();
+    let mut matches = Vec::new();
+    for (line, text) in lines.iter().enumerate() {
+        let haystack: Vec<char> = text.chars().map(fold).collect();
+        let mut col = 0;
+        while col + needle.len() <= haystack.len() {
+            if haystack[col..col + needle.len()] == needle[..] {
+                matches.push(Match { line, start: col, end: col + needle.len() });
+                col += needle.len();
+            } else {
+                col += 1;
+            }
+        }
+    }
+    matches
+}
+
+/// Index of the first match after `from` (or before it when `backwards`),
+/// wrapping around the ends.
+pub fn next_match(matches: &[Match], from: Point, backwards: bool) -> Option<usize> {
+    if matches.is_empty() {
+        return None;
+    }
+    if backwards {
+        matches.iter().rposition(|m| m.start_point() < from).or(Some(matches.len() - 1))
+    } else {
+        matches.iter().position(|m| m.start_point() > from).or(Some(0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(text: &str) -> Vec<String> {
+        text.lines().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn finds_every_occurrence() {
+        let text = lines("cargo build\nno match\nbuild, build");
+        assert_eq!(
+            find(&text, "build"),
+            vec![
+                Match { line: 0, start: 6, end: 11 },
+                Match { line: 2, start: 0, end: 5 },
+                Match { line: 2, start: 7, end: 12 },
+            ]
+        );
+    }
+
+    #[test]
+    fn smart_case() {
+        let text = lines("Error\nerror");
+        assert_eq!(find(&text, "error").len(), 2);
+        assert_eq!(find(&text, "Error"), vec![Match { line: 0, start: 0, end: 5 }]);
+    }
+
+    #[test]
+    fn matches_do_not_overlap() {
+        assert_eq!(find(&lines("aaaa"), "aa").len(), 2);
+    }
+
+    #[test]
+    fn columns_count_characters() {
+        assert_eq!(find(&lines("größe ok"), "ok"), vec![Match { line: 0, start: 6, end: 8 }]);
+    }
+
+    #[test]
+    fn empty_query_finds_nothing() {
+        assert!(find(&lines("abc"), "").is_empty());
+    }
+
+    #[test]
+    fn next_match_wraps_around() {
+        let matches = find(&lines("x\n.x.x\nx"), "x");
+        assert_eq!(next_match(&matches, Point::new(1, 1), false), Some(2));
+        assert_eq!(next_match(&matches, Point::new(2, 0), false), Some(0));
+        assert_eq!(next_match(&matches, Point::new(1, 1), true), Some(0));
+        assert_eq!(next_match(&matches, Point::new(0, 0), true), Some(3));
+        assert_eq!(next_match(&[], Point::new(0, 0), false), None);
+    }
+}
@@ -0,0 +1,124 @@
+//! Text selection over absolute lines (see `Terminal::lines`). Columns count
+//! characters, so a wide glyph is one column here.
+
+/// A cell position: absolute line and character column.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Point {
+    pub line: usize,
+    pub col: usize,
+}
+
+impl Point {
+    pub fn new(line: usize, col: usize) -> Self {
+        Point { line, col }
+    }
+}
+
+/// Range from where the selection started to where it was dragged to; both
+/// ends are included.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Selection {
+    pub anchor: Point,
+    pub head: Point,
+}
+
+impl Selection {
+    pub fn new(anchor: Point, head: Point) -> Self {
+        Selection { anchor, head }
+    }
+
+    /// Start and end in reading order.
+    pub fn ordered(&self) -> (Point, Point) {
+        if self.anchor <= self.head {
+            (self.anchor, self.head)
+        } else {
+            (self.head, self.anchor)
+        }
+    }
+
+    pub fn contains(&self, point: Point) -> bool {
+        let (start, end) = self.ordered();
+        start <= point && point <= end
+    }
+
+    /// Selected columns of `line` as a half-open range, if any.
+    pub fn columns(&self, line: usize, width: usize) -> Option<std::ops::Range<usize>> {
+        let (start, end) = self.ordered();
+        if line < start.line || line > end.line {
+            return None;
+        }
+        let from = if line == start.line { start.col } else { 0 };
+        let to = if line == end.line { end.col + 1 } else { width }.min(width);
+        (from < to).then_some(from..to)
+    }
+
+    /// Selected text of `lines`; rows are joined with newlines and trailing
+    /// blanks of each row are dropped.
+    pub fn text(&self, lines: &[String]) -> String {
+        let (start, end) = self.ordered();
+        let mut out = Vec::new();
+        for (line, text) in lines.iter().enumerate().take(end.line + 1).skip(start.line) {
+            let chars: Vec<char> = text.chars().collect();
+            let from = if line == start.line { start.col.min(chars.len()) } else { 0 };
+            let to = if line == end.line { (end.col + 1).min(chars.len()) } else { chars.len() };
+            let part: String = chars[from..to.max(from)].iter().collect();
+            out.push(part.trim_end().to_string());
+        }
+        out.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(text: &str) -> Vec<String> {
+        text.lines().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn backwards_selection_is_ordered() {
+        let selection = Selection::new(Point::new(3, 1), Point::new(1, 4));
+        assert_eq!(selection.ordered(), (Point::new(1, 4), Point::new(3, 1)));
+        assert!(selection.contains(Point::new(2, 0)));
+        assert!(selection.contains(Point::new(3, 1)));
+        assert!(!selection.contains(Point::new(3, 2)));
+        assert!(!selection.contains(Point::new(1, 3)));
+    }
+
+    #[test]
+    fn text_within_one_line() {
+        let selection = Selection::new(Point::new(0, 6), Point::new(0, 10));
+        assert_eq!(selection.text(&lines("hello world")), "world");
+    }
+
+    #[test]
+    fn text_across_lines() {
+        let text = lines("first line\nsecond\nthird line");
+        let selection = Selection::new(Point::new(2, 4), Point::new(0, 6));
+        assert_eq!(selection.text(&text), "line\nsecond\nthird");
+    }
+
+    #[test]
+    fn text_past_the_end_of_short_lines() {
+        let text = lines("ab\n\ncd");
+        assert_eq!(Selection::new(Point::new(0, 1), Point::new(2, 40)).text(&text), "b\n\ncd");
+        assert_eq!(Selection::new(Point::new(0, 5), Point::new(0, 9)).text(&text), "");
+    }
+
+    #[test]
+    fn wide_characters_are_one_column() {
+        let selection = Selection::new(Point::new(0, 1), Point::new(0, 2));
+        assert_eq!(selection.text(&lines("日本語です")), "本語");
+    }
+
+    #[test]
+    fn columns_per_line() {
+        let selection = Selection::new(Point::new(1, 3), Point::new(3, 2));
+        assert_eq!(selection.columns(0, 10), None);
+        assert_eq!(selection.columns(1, 10), Some(3..10));
+        assert_eq!(selection.columns(2, 10), Some(0..10));
+        assert_eq!(selection.columns(3, 10), Some(0..3));
+        assert_eq!(selection.columns(4, 10), None);
+    }
+}
@@ -0,0 +1,149 @@
+//! A running program (local shell, SSH shell or server session) together
+//! with the terminal it draws into.
+
+use super::input::{self, Key, Modifiers};
+use super::Terminal;
+use crate::pty::PtyProcess;
+use std::sync::mpsc::TryRecvError;
+
+pub struct Session {
+    pub pty: PtyProcess,
+    pub term: Terminal,
+}
+
+/// What `Session::poll` found.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Poll {
+    pub output: bool, // the screen changed
+    pub exited: bool, // the program is gone, no more output will come
+}
+
+impl Session {
+    pub fn new(pty: PtyProcess, rows: u16, cols: u16, scrollback: usize) -> Self {
+        Session { pty, term: Terminal::new(rows, cols, scrollback) }
+    }
+
+    /// Feed all pending output into the terminal.
+    pub fn poll(&mut self) -> Poll {
+        let mut poll = Poll::default();
+        loop {
+            match self.pty.rx.try_recv() {
+                Ok(data) => {
+                    self.term.process(&data);
+                    poll.output = true;
+                }
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    poll.exited = true;
+                    break;
+                }
+            }
+        }
+        poll
+    }
+
+    pub fn write(&self, bytes: &[u8]) {
+        let _ = self.pty.writer.send(bytes.to_vec());
+    }
+
+    pub fn send_key(&self, key: Key, modifiers: Modifiers) {
+        if let Some(bytes) = input::encode_key(key, modifiers, self.term.screen().application_cursor()) {
+            self.write(&bytes);
+        }
+    }
+
+    pub fn paste(&self, text: &str) {
+        self.write(&input::encode_paste(text, self.term.screen().bracketed_paste()));
+    }
+
+    /// Resize the screen and tell the program about it.
+    pub fn resize(&mut self, rows: u16, cols: u16) {
+        if self.term.resize(rows, cols) {
+            self.pty.resize(rows, cols);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use portable_pty::{NativePtySystem, PtySize, PtySystem};
+    use std::sync::mpsc::{self, Receiver, Sender};
+
+    /// Session on channels instead of a program: returns the sender that
+    /// plays the program's output and the receiver of what was typed.
+    fn fake() -> (Session, Sender<Vec<u8>>, Receiver<Vec<u8>>) {
+        let (output_tx, output_rx) = mpsc::channel();
+        let (input_tx, input_rx) = mpsc::channel();
+        let pair = NativePtySystem::default()
+            .openpty(PtySize { rows: 5, cols: 20, pixel_width: 0, pixel_height: 0 })
+            .unwrap();
+        let pty = PtyProcess { rx: output_rx, writer: input_tx, master: pair.master, child_pid: None };
+        (Session::new(pty, 5, 20, 100), output_tx, input_rx)
+    }
+
+    #[test]
+    fn poll_feeds_output_into_the_screen() {
+        let (mut session, output, _) = fake();
+        assert_eq!(session.poll(), Poll::default());
+        output.send(b"hello\r\nworld".to_vec()).unwrap();
+        assert_eq!(session.poll(), Poll { output: true, exited: false });
+        assert_eq!(&session.term.lines()[..2], ["hello", "world"]);
+    }
+
+    #[test]
+    fn poll_reports_exit_after_the_last_output() {
+        let (mut session, output, _) = fake();
+        output.send(b"bye".to_vec()).unwrap();
+        drop(output);
+        assert_eq!(session.poll(), Poll { output: true, exited: true });
+        assert_eq!(session.term.lines()[0], "bye");
+    }
+
+    #[test]
+    fn keys_follow_the_cursor_mode_of_the_program() {
+        let (mut session, output, typed) = fake();
+        session.send_key(Key::Up, Modifiers::NONE);
+        assert_eq!(typed.try_recv().unwrap(), b"\x1b[A");
+        // DECCKM, as sent by vim or less
+        output.send(b"\x1b[?1h".to_vec()).unwrap();
+        session.poll();
+        session.send_key(Key::Up, Modifiers::NONE);
+        assert_eq!(typed.try_recv().unwrap(), b"\x1bOA");
+    }
+
+    #[test]
+    fn paste_follows_the_bracketed_mode_of_the_program() {
+        let (mut session, output, typed) = fake();
+        session.paste("a\nb");
+        assert_eq!(typed.try_recv().unwrap(), b"a\rb");
+        output.send(b"\x1b[?2004h".to_vec()).unwrap();
+        session.poll();
+        session.paste("a");
+        assert_eq!(typed.try_recv().unwrap(), b"\x1b[200~a\x1b[201~");
+    }
+
+    #[test]
+    fn resize_changes_the_screen_and_the_pty() {
+        let (mut session, _, _) = fake();
+        session.resize(10, 40);
+        assert_eq!(session.term.screen().size(), (10, 40));
+        assert_eq!(session.pty.master.get_size().unwrap().cols, 40);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn runs_a_real_shell() {
+        let pty = crate::pty::spawn_shell(5, 40, None).unwrap();
+        let mut session = Session::new(pty, 5, 40, 100);
+        session.write(b"echo core-$((40+2))\rexit\r");
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+        let mut exited = false;
+        while !exited && std::time::Instant::now() < deadline {
+            exited = session.poll().exited;
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        assert!(exited, "shell did not exit");
+        assert!(session.term.lines().iter().any(|l| l.contains("core-42")));
+    }
+}
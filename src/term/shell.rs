@@ -0,0 +1,191 @@
+//! Shell integration: the prompt marks of OSC 133 (`A` prompt, `B` input,
+//! `C` command runs, `D;<exit>` command done) and the working directory of
+//! OSC 7 (`file://host/path`), for shells set up to send them.
+
+use std::path::PathBuf;
+
+/// Events kept for a front-end that does not take them (the TUI).
+const MAX_EVENTS: usize = 64;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ShellEvent {
+    /// The command line the shell is about to run, as shown on the screen.
+    Command(String),
+    /// The last command finished, with its exit code when the shell sent one.
+    Finished(Option<i32>),
+    Cwd(PathBuf),
+}
+
+#[derive(Default)]
+pub(super) struct Marks {
+    input_col: Option<u16>, // cursor column at `B`
+    pub events: Vec<ShellEvent>,
+    pub seen: bool, // any OSC 133 arrived, so `Command` events will come
+    pub cwd: Option<PathBuf>,
+}
+
+impl Marks {
+    pub fn osc(&mut self, screen: &vt100::Screen, params: &[&[u8]]) {
+        match params {
+            [b"133", mark, rest @ ..] => {
+                self.seen = true;
+                match *mark {
+                    b"A" => self.input_col = None,
+                    b"B" => self.input_col = Some(screen.cursor_position().1),
+                    b"C" => {
+                        if let Some(command) = self.input_col.take().and_then(|col| command_line(screen, col)) {
+                            self.push(ShellEvent::Command(command));
+                        }
+                    }
+                    b"D" => {
+                        let code = rest.first().and_then(|c| std::str::from_utf8(c).ok()).and_then(|c| c.trim().parse().ok());
+                        self.push(ShellEvent::Finished(code));
+                    }
+                    _ => {}
+                }
+            }
+            [b"7", url @ ..] if !url.is_empty() => {
+                let url = String::from_utf8_lossy(&url.join(&b';')).into_owned();
+                if let Some(path) = file_url_path(&url) {
+                    self.cwd = Some(path.clone());
+                    self.push(ShellEvent::Cwd(path));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// The line typed so far, up to the cursor, while the shell reads one
+    /// (between `B` and `C`).
+    pub fn input(&self, screen: &vt100::Screen) -> Option<String> {
+        let col = self.input_col?;
+        let (row, cursor_col) = screen.cursor_position();
+        let start = run_start(screen, row);
+        if start == row && cursor_col < col {
+            return None;
+        }
+        Some(text_before_cursor(screen, start, col))
+    }
+
+    fn push(&mut self, event: ShellEvent) {
+        if self.events.len() == MAX_EVENTS {
+            self.events.remove(0);
+        }
+        self.events.push(event);
+    }
+}
+
+/// The input that started at column `col`: `C` comes after Enter, so it
+/// ends on the row above the cursor and starts where that row's wrapped
+/// run begins (the rows may have scrolled since `B`, the column has not).
+fn command_line(screen: &vt100::Screen, col: u16) -> Option<String> {
+    let end = screen.cursor_position().0.checked_sub(1)?;
+    let start = run_start(screen, end);
+    let (_, cols) = screen.size();
+    // A leading space stays: it keeps the command out of the history
+    let text = screen.contents_between(start, col, end, cols);
+    let text = text.trim_end();
+    (!text.trim_start().is_empty()).then(|| text.to_string())
+}
+
+/// Without prompt marks, a guess at the line typed so far: what follows the
+/// first prompt sign (`$ `, `# `, `% `, `> `, `❯ `) before the cursor.
+pub fn guess_input(screen: &vt100::Screen) -> Option<String> {
+    let (row, _) = screen.cursor_position();
+    let text = text_before_cursor(screen, run_start(screen, row), 0);
+    let end = ["$ ", "# ", "% ", "> ", "❯ "]
+        .iter()
+        .filter_map(|sign| text.find(sign).map(|i| i + sign.len()))
+        .min()?;
+    Some(text[end..].to_string())
+}
+
+/// First row of the wrapped run that `row` belongs to.
+fn run_start(screen: &vt100::Screen, row: u16) -> u16 {
+    let mut start = row;
+    while start > 0 && screen.row_wrapped(start - 1) {
+        start -= 1;
+    }
+    start
+}
+
+/// Text from `start`/`col` to the cursor. Blank cells right before the
+/// cursor count as spaces, as typed (`git ` asks for a subcommand).
+fn text_before_cursor(screen: &vt100::Screen, start: u16, col: u16) -> String {
+    let (row, cursor_col) = screen.cursor_position();
+    let text = screen.contents_between(start, col, row, cursor_col);
+    let blank = |c: u16| screen.cell(row, c).is_none_or(|cell| cell.contents().trim().is_empty());
+    let trailing = (0..cursor_col).rev().take_while(|c| blank(*c)).count();
+    let mut text = text.trim_end().to_string();
+    text.extend(std::iter::repeat_n(' ', trailing));
+    text
+}
+
+/// Path of a `file://host/path` URL, percent-decoded.
+fn file_url_path(url: &str) -> Option<PathBuf> {
+    let rest = url.strip_prefix("file://")?;
+    let path = &rest[rest.find('/')?..];
+    let mut bytes = Vec::with_capacity(path.len());
+    let mut iter = path.bytes();
+    while let Some(b) = iter.next() {
+        if b == b'%' {
+            let hex = [iter.next()?, iter.next()?];
+            bytes.push(u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?);
+        } else {
+            bytes.push(b);
+        }
+    }
+    Some(PathBuf::from(String::from_utf8(bytes).ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::term::Terminal;
+
+    #[test]
+    fn reads_the_command_between_marks() {
+        let mut term = Terminal::new(5, 40, 100);
+        term.process(b"\x1b]133;A\x07$ \x1b]133;B\x07ls -la | wc\r\n\x1b]133;C\x07");
+        term.process(b"3\r\n\x1b]133;D;1\x07\x1b]133;A\x07$ ");
+        assert!(term.shell_integration());
+        assert_eq!(term.take_shell_events(), [ShellEvent::Command("ls -la | wc".into()), ShellEvent::Finished(Some(1))]);
+        assert!(term.take_shell_events().is_empty());
+    }
+
+    #[test]
+    fn follows_scrolling_and_wrapped_input() {
+        let mut term = Terminal::new(3, 10, 100);
+        term.process(b"one\r\ntwo\r\n\x1b]133;B\x07> echo abcdefghij\r\n\x1b]133;C\x07");
+        assert_eq!(term.take_shell_events(), [ShellEvent::Command("> echo abcdefghij".into())]);
+        let mut term = Terminal::new(3, 10, 100);
+        term.process(b"one\r\ntwo\r\n> \x1b]133;B\x07echo abcdefghij\r\n\x1b]133;C\x07");
+        assert_eq!(term.take_shell_events(), [ShellEvent::Command("echo abcdefghij".into())]);
+    }
+
+    #[test]
+    fn reads_the_line_being_typed() {
+        let mut term = Terminal::new(5, 40, 100);
+        term.process(b"\x1b]133;A\x07~/src $ \x1b]133;B\x07git ");
+        assert_eq!(term.input_line().as_deref(), Some("git "));
+        term.process(b"ch\x1b[D");
+        assert_eq!(term.input_line().as_deref(), Some("git c"));
+        term.process(b"\r\n\x1b]133;C\x07");
+        assert_eq!(term.input_line(), None);
+
+        let mut term = Terminal::new(5, 40, 100);
+        term.process(b"user@box:~$ cargo b");
+        assert_eq!(term.guessed_input_line().as_deref(), Some("cargo b"));
+        term.process(b"\r\n");
+        assert_eq!(term.guessed_input_line(), None);
+    }
+
+    #[test]
+    fn tracks_the_reported_directory() {
+        let mut term = Terminal::new(5, 40, 100);
+        term.process(b"\x1b]7;file://box/home/me/My%20Files\x07");
+        assert_eq!(term.reported_cwd(), Some(std::path::Path::new("/home/me/My Files")));
+        assert!(!term.shell_integration());
+        assert_eq!(file_url_path("http://x/y"), None);
+    }
+}
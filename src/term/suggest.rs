@@ -0,0 +1,239 @@
+//! Command suggestions: tracks what the user types on the prompt and offers
+//! ways to continue it from the `complete` sources.
+
+use super::complete::{Context, Sources, Suggestion};
+
+/// At most this many suggestions are offered at once.
+const LIMIT: usize = 5;
+
+#[derive(Default)]
+pub struct Suggestions {
+    input: String, // typed since the last Enter, as far as it was tracked
+    lost: bool,    // edited in ways not tracked (arrows, shell completion)
+    stale: bool,   // `items` were made for an older input
+    items: Vec<Suggestion>,
+    selected: usize,
+    open: bool,
+}
+
+impl Suggestions {
+    pub fn items(&self) -> &[Suggestion] {
+        &self.items
+    }
+
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open && !self.items.is_empty()
+    }
+
+    /// The command line as typed since the last reset, unless it was lost.
+    pub fn line(&self) -> Option<&str> {
+        (!self.lost).then_some(self.input.as_str())
+    }
+
+    /// Track typed text; control characters (tabs, escapes) are ignored.
+    pub fn type_text(&mut self, text: &str) {
+        let before = self.input.len();
+        self.input.extend(text.chars().filter(|c| !c.is_control()));
+        if self.input.len() != before {
+            self.edited();
+        }
+    }
+
+    pub fn backspace(&mut self) {
+        self.input.pop();
+        self.edited();
+    }
+
+    /// Take the line as the terminal shows it, when it differs from the
+    /// tracked one; this also recovers a lost line. The list stays closed
+    /// until the next key.
+    pub fn sync(&mut self, line: &str) {
+        if self.lost || self.input != line {
+            self.input = line.to_string();
+            self.lost = false;
+            self.stale = true;
+        }
+    }
+
+    /// Forget the line, e.g. after Enter or Ctrl+C.
+    pub fn reset(&mut self) {
+        self.input.clear();
+        self.lost = false;
+        self.stale = false;
+        self.items.clear();
+        self.open = false;
+    }
+
+    /// Stop trusting the tracked line until the next reset, after a key that
+    /// edits it on the shell's side.
+    pub fn lose_track(&mut self) {
+        self.lost = true;
+        self.items.clear();
+        self.open = false;
+    }
+
+    /// Close the list but keep tracking the line.
+    pub fn close(&mut self) {
+        self.open = false;
+    }
+
+    pub fn select_previous(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    pub fn select_next(&mut self) {
+        self.selected = (self.selected + 1).min(self.items.len().saturating_sub(1));
+    }
+
+    /// Take the selected suggestion; returns the text still to be typed.
+    pub fn accept(&mut self) -> Option<String> {
+        if !self.is_open() {
+            return None;
+        }
+        let suggestion = self.items[self.selected].line.clone();
+        let rest = suggestion[self.input.len()..].to_string();
+        self.input = suggestion;
+        self.open = false;
+        Some(rest)
+    }
+
+    /// Whether `refresh` has work to do.
+    pub fn is_stale(&self) -> bool {
+        self.stale
+    }
+
+    /// Make the suggestions for the current line, after it changed.
+    pub fn refresh(&mut self, sources: &Sources, ctx: &Context) {
+        if !self.stale {
+            return;
+        }
+        self.stale = false;
+        self.items = if self.lost { Vec::new() } else { sources.complete(&self.input, ctx, LIMIT) };
+        self.selected = 0;
+    }
+
+    fn edited(&mut self) {
+        self.stale = !self.lost;
+        self.open = !self.lost;
+        if self.lost {
+            self.items.clear();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sources() -> Sources {
+        let mut sources = Sources::default();
+        for command in ["df", "df -h", "git pull", "git push", "cal", "cat", "cd", "cp", "chmod", "chown"] {
+            sources.add_history(None, command);
+        }
+        sources
+    }
+
+    fn typed(s: &mut Suggestions, text: &str) {
+        s.type_text(text);
+        s.refresh(&sources(), &Context::default());
+    }
+
+    fn lines(s: &Suggestions) -> Vec<&str> {
+        s.items().iter().map(|i| i.line.as_str()).collect()
+    }
+
+    #[test]
+    fn offers_matching_commands() {
+        let mut s = Suggestions::default();
+        typed(&mut s, "git p");
+        assert!(s.is_open());
+        assert_eq!(lines(&s), ["git push", "git pull"]);
+    }
+
+    #[test]
+    fn limits_the_list() {
+        let mut s = Suggestions::default();
+        s.type_text("c");
+        let mut sources = sources();
+        for n in 0..10 {
+            sources.add_history(None, &format!("c {}", n));
+        }
+        s.refresh(&sources, &Context { host: None, cwd: None });
+        assert!(s.items().len() <= LIMIT);
+    }
+
+    #[test]
+    fn accept_returns_the_missing_part() {
+        let mut s = Suggestions::default();
+        typed(&mut s, "git p");
+        s.select_next();
+        s.select_next();
+        assert_eq!(s.accept().as_deref(), Some("ull"));
+        assert_eq!(s.input, "git pull");
+        assert!(!s.is_open());
+        assert_eq!(s.accept(), None);
+    }
+
+    #[test]
+    fn backspace_and_reset() {
+        let mut s = Suggestions::default();
+        typed(&mut s, "df x");
+        assert!(!s.is_open());
+        s.backspace();
+        s.refresh(&sources(), &Context::default());
+        assert_eq!(lines(&s), ["df -h"]);
+        s.reset();
+        assert_eq!(s.input, "");
+        assert!(!s.is_open());
+    }
+
+    #[test]
+    fn ignores_control_characters() {
+        let mut s = Suggestions::default();
+        s.type_text("\t\x1b");
+        assert_eq!(s.input, "");
+        assert!(!s.is_stale());
+    }
+
+    #[test]
+    fn tracks_the_whole_line_until_it_is_lost() {
+        let mut s = Suggestions::default();
+        s.type_text("grep -c 'a|b' *.rs");
+        assert_eq!(s.line(), Some("grep -c 'a|b' *.rs"));
+        s.lose_track();
+        assert_eq!(s.line(), None);
+        typed(&mut s, " && ls");
+        assert!(!s.is_open());
+        s.reset();
+        assert_eq!(s.line(), Some(""));
+    }
+
+    #[test]
+    fn sync_recovers_the_line() {
+        let mut s = Suggestions::default();
+        typed(&mut s, "git");
+        s.lose_track(); // e.g. ↑ recalled an older command
+        s.sync("git pu");
+        assert_eq!(s.line(), Some("git pu"));
+        s.refresh(&sources(), &Context::default());
+        assert!(!s.is_open(), "syncing alone does not open the list");
+        typed(&mut s, "s");
+        assert_eq!(lines(&s), ["git push"]);
+        assert!(s.is_open());
+    }
+
+    #[test]
+    fn close_keeps_the_input() {
+        let mut s = Suggestions::default();
+        typed(&mut s, "d");
+        s.close();
+        assert!(!s.is_open());
+        assert_eq!(s.input, "d");
+        typed(&mut s, "f");
+        assert!(s.is_open());
+    }
+}
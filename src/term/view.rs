@@ -0,0 +1,394 @@
+//! Everything about an interactive terminal except drawing it: what keys,
+//! pastes and the wheel do to the session and the suggestions, selecting
+//! with the mouse and the search bar. Front-ends translate their events into
+//! `Input` and paint the result.
+
+use super::input::{Key, Modifiers};
+use super::session::{Poll, Session};
+use super::suggest::Suggestions;
+use super::{Match, Point, Selection};
+
+/// A front-end event, already stripped of what belongs to the app (tab
+/// shortcuts and the like).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Input<'a> {
+    Text(&'a str),
+    Paste(&'a str),
+    /// The copy shortcut: copies a selection, otherwise it is Ctrl+C.
+    Copy,
+    Key(Key, Modifiers),
+    /// Wheel movement in lines, positive is up.
+    Scroll(f32),
+}
+
+/// What the front-end has to do after `View::input`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    None,
+    /// Put this on the clipboard.
+    Copied(String),
+    /// Enter was pressed on this line, as far as it was tracked.
+    Submitted(Option<String>),
+}
+
+pub struct View {
+    pub session: Session,
+    pub suggestions: Suggestions,
+    drag_anchor: Option<Point>,
+    // Search bar (None while closed) and what it found
+    pub search: Option<String>,
+    pub matches: Vec<Match>,
+    pub current_match: Option<usize>,
+    pub scroll_to_line: Option<usize>, // one-shot request for the front-end
+    // Broadcast group membership and input waiting to be mirrored
+    pub broadcast: bool,
+    pub broadcast_out: Vec<Vec<u8>>,
+}
+
+impl View {
+    pub fn new(session: Session) -> Self {
+        View {
+            session,
+            suggestions: Suggestions::default(),
+            drag_anchor: None,
+            search: None,
+            matches: Vec::new(),
+            current_match: None,
+            scroll_to_line: None,
+            broadcast: false,
+            broadcast_out: Vec::new(),
+        }
+    }
+
+    /// Feed pending output into the screen and follow it with the command
+    /// line and the search matches.
+    pub fn poll(&mut self) -> Poll {
+        let poll = self.session.poll();
+        if poll.output {
+            self.sync_input_line();
+            if self.search.is_some() {
+                self.update_matches();
+            }
+        }
+        poll
+    }
+
+    pub fn send_str(&mut self, s: &str) {
+        self.send_bytes(s.as_bytes());
+    }
+
+    /// Write to the program, mirrored to the broadcast group.
+    pub fn send_bytes(&mut self, b: &[u8]) {
+        self.session.write(b);
+        if self.broadcast {
+            self.broadcast_out.push(b.to_vec());
+        }
+    }
+
+    pub fn send_key(&mut self, key: Key, modifiers: Modifiers) {
+        let application_cursor = self.session.term.screen().application_cursor();
+        if let Some(bytes) = super::input::encode_key(key, modifiers, application_cursor) {
+            self.send_bytes(&bytes);
+        }
+    }
+
+    /// Paste `text` the way the program asks for (bracketed or not).
+    pub fn paste(&mut self, text: &str) {
+        if text.contains(['\n', '\r']) {
+            self.suggestions.lose_track();
+        } else {
+            self.suggestions.type_text(text);
+        }
+        let bytes = super::input::encode_paste(text, self.session.term.screen().bracketed_paste());
+        self.send_bytes(&bytes);
+    }
+
+    /// Type `text` on the command line, e.g. a command from the history,
+    /// without running it: its lines are joined into one.
+    pub fn insert(&mut self, text: &str) {
+        let line = join_lines(text);
+        self.suggestions.type_text(&line);
+        self.suggestions.close();
+        self.send_str(&line);
+    }
+
+    /// Write to the program without mirroring to the broadcast group.
+    pub fn write_raw(&mut self, b: &[u8]) {
+        self.session.write(b);
+    }
+
+    pub fn input(&mut self, input: Input) -> Outcome {
+        match input {
+            Input::Text(text) => {
+                if !text.is_empty() {
+                    self.suggestions.type_text(text);
+                    self.send_str(text);
+                }
+            }
+            Input::Paste(text) => self.paste(text),
+            Input::Copy => match self.session.term.selected_text() {
+                Some(text) => {
+                    self.session.term.selection = None;
+                    return Outcome::Copied(text);
+                }
+                None => {
+                    self.suggestions.reset();
+                    self.send_key(Key::Char('c'), Modifiers::CTRL);
+                }
+            },
+            Input::Key(key, modifiers) => return self.key(key, modifiers),
+            Input::Scroll(lines) => {
+                // Full-screen programs (less, vim) get the wheel as arrow keys
+                if !self.suggestions.is_open() && self.session.term.screen().alternate_screen() {
+                    if lines > 0.0 {
+                        self.send_key(Key::Up, Modifiers::NONE);
+                    }
+                    if lines < 0.0 {
+                        self.send_key(Key::Down, Modifiers::NONE);
+                    }
+                }
+            }
+        }
+        Outcome::None
+    }
+
+    fn key(&mut self, key: Key, modifiers: Modifiers) -> Outcome {
+        let suggesting = self.suggestions.is_open();
+        match key {
+            Key::Enter => {
+                let line = self.suggestions.line().map(str::to_string);
+                self.suggestions.reset();
+                self.send_key(Key::Enter, modifiers);
+                return Outcome::Submitted(line);
+            }
+            Key::Backspace => {
+                self.suggestions.backspace();
+                self.send_key(Key::Backspace, modifiers);
+            }
+            // Ctrl+Tab switches tabs
+            Key::Tab if modifiers.ctrl => {}
+            Key::Tab if modifiers.shift => self.send_key(Key::BackTab, Modifiers::NONE),
+            // Complete with the selected suggestion, else the shell does
+            Key::Tab => match self.suggestions.accept() {
+                Some(rest) => self.send_str(&rest),
+                None => {
+                    self.suggestions.lose_track();
+                    self.send_key(Key::Tab, modifiers);
+                }
+            },
+            Key::Up if suggesting => self.suggestions.select_previous(),
+            Key::Down if suggesting => self.suggestions.select_next(),
+            Key::Esc if suggesting => self.suggestions.close(),
+            Key::Esc | Key::Char(_) => self.send_key(key, modifiers),
+            // Arrows, Home, Delete, ... edit the line on the shell's side
+            key => {
+                self.suggestions.lose_track();
+                self.send_key(key, modifiers);
+            }
+        }
+        Outcome::None
+    }
+
+    /// Mouse selection: `pressed` on the frame the button went down, `down`
+    /// while it is held, `hovered` the cell under the pointer. A click
+    /// without dragging drops the selection.
+    pub fn drag(&mut self, pressed: bool, down: bool, hovered: Option<Point>) {
+        if pressed && hovered.is_some() {
+            self.drag_anchor = hovered;
+            self.session.term.selection = None;
+        } else if let (true, Some(anchor), Some(point)) = (down, self.drag_anchor, hovered) {
+            if point != anchor {
+                self.session.term.selection = Some(Selection::new(anchor, point));
+            }
+        }
+        if !down {
+            self.drag_anchor = None;
+        }
+    }
+
+    /// Search scrollback and screen again, e.g. after new output.
+    pub fn update_matches(&mut self) {
+        self.matches = match &self.search {
+            Some(query) => self.session.term.search(query),
+            None => Vec::new(),
+        };
+        self.current_match = None;
+    }
+
+    /// Open the search bar, or close it; true when it is open now.
+    pub fn toggle_search(&mut self) -> bool {
+        self.search = if self.search.is_some() { None } else { Some(String::new()) };
+        self.update_matches();
+        self.search.is_some()
+    }
+
+    /// Scroll to the next search match; without a current one, the newest
+    /// match comes first when going `backwards`.
+    pub fn jump_to_match(&mut self, backwards: bool) {
+        let from = match self.current_match {
+            Some(i) => self.matches[i].start_point(),
+            None => Point::new(self.session.term.history_len() + self.session.term.rows() as usize, 0),
+        };
+        self.current_match = super::search::next_match(&self.matches, from, backwards);
+        self.scroll_to_line = self.current_match.map(|i| self.matches[i].line);
+    }
+
+    /// Follow the command line on the screen: exactly between prompt marks,
+    /// otherwise guessed from the prompt once the tracked line was lost.
+    fn sync_input_line(&mut self) {
+        let term = &self.session.term;
+        let line = if term.shell_integration() {
+            term.input_line()
+        } else if self.suggestions.line().is_none() {
+            term.guessed_input_line()
+        } else {
+            None
+        };
+        if let Some(line) = line {
+            self.suggestions.sync(&line);
+        }
+    }
+}
+
+/// `text` on one line, as the shell would read it: a `\` at the end of a
+/// line continues it, other line breaks become spaces.
+fn join_lines(text: &str) -> String {
+    let mut joined = String::new();
+    for (idx, line) in text.trim_end_matches(['\n', '\r']).split('\n').enumerate() {
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        if idx == 0 {
+            joined.push_str(line);
+            continue;
+        }
+        let continued = joined.ends_with('\\');
+        if continued {
+            joined.pop();
+        }
+        // The indentation of a continued line still separates words
+        let rest = line.trim_start_matches([' ', '\t']);
+        if (!continued || rest.len() < line.len()) && !joined.ends_with([' ', '\t']) {
+            joined.push(' ');
+        }
+        joined.push_str(rest);
+    }
+    joined
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pty::PtyProcess;
+    use portable_pty::{NativePtySystem, PtySize, PtySystem};
+    use std::sync::mpsc::{self, Receiver, Sender};
+
+    /// View on channels instead of a program, like `session::tests::fake`.
+    fn fake() -> (View, Sender<Vec<u8>>, Receiver<Vec<u8>>) {
+        let (output_tx, output_rx) = mpsc::channel();
+        let (input_tx, input_rx) = mpsc::channel();
+        let pair = NativePtySystem::default()
+            .openpty(PtySize { rows: 5, cols: 20, pixel_width: 0, pixel_height: 0 })
+            .unwrap();
+        let pty = PtyProcess { rx: output_rx, writer: input_tx, master: pair.master, child_pid: None };
+        (View::new(Session::new(pty, 5, 20, 100)), output_tx, input_rx)
+    }
+
+    fn typed(rx: &Receiver<Vec<u8>>) -> Vec<u8> {
+        rx.try_iter().flatten().collect()
+    }
+
+    #[test]
+    fn typing_tracks_the_line_until_enter() {
+        let (mut view, _, rx) = fake();
+        view.input(Input::Text("ls -l"));
+        view.input(Input::Key(Key::Backspace, Modifiers::NONE));
+        assert_eq!(view.suggestions.line(), Some("ls -"));
+        assert_eq!(view.input(Input::Key(Key::Enter, Modifiers::NONE)), Outcome::Submitted(Some("ls -".into())));
+        assert_eq!(typed(&rx), b"ls -l\x7f\r");
+        assert_eq!(view.suggestions.line(), Some(""));
+
+        // Editing on the shell's side loses the line
+        view.input(Input::Text("cd"));
+        view.input(Input::Key(Key::Left, Modifiers::NONE));
+        assert_eq!(view.input(Input::Key(Key::Enter, Modifiers::NONE)), Outcome::Submitted(None));
+    }
+
+    #[test]
+    fn inserted_text_is_not_run() {
+        let (mut view, _, rx) = fake();
+        view.insert("kubectl get pods \\\n  -A\r\n");
+        assert_eq!(typed(&rx), b"kubectl get pods -A");
+        assert_eq!(view.suggestions.line(), Some("kubectl get pods -A"));
+
+        assert_eq!(join_lines("select *\r\n  from t\nwhere id = 1;\n"), "select * from t where id = 1;");
+        assert_eq!(join_lines("ls \\\n-l\\\n  -a"), "ls -l -a");
+        assert_eq!(join_lines("ls -l"), "ls -l");
+    }
+
+    #[test]
+    fn app_keys_do_not_reach_the_program() {
+        let (mut view, _, rx) = fake();
+        view.input(Input::Key(Key::Tab, Modifiers::CTRL));
+        assert!(typed(&rx).is_empty());
+        view.input(Input::Key(Key::Tab, Modifiers { shift: true, ..Modifiers::NONE }));
+        view.input(Input::Key(Key::Char('d'), Modifiers::CTRL));
+        assert_eq!(typed(&rx), b"\x1b[Z\x04");
+    }
+
+    #[test]
+    fn copy_takes_the_selection_or_interrupts() {
+        let (mut view, output, rx) = fake();
+        output.send(b"hello world".to_vec()).unwrap();
+        view.poll();
+        view.drag(true, true, Some(Point::new(0, 0)));
+        view.drag(false, true, Some(Point::new(0, 4)));
+        view.drag(false, false, None);
+        assert_eq!(view.input(Input::Copy), Outcome::Copied("hello".into()));
+        assert!(view.session.term.selection.is_none());
+        assert_eq!(view.input(Input::Copy), Outcome::None);
+        assert_eq!(typed(&rx), [0x03]);
+
+        // A click without dragging selects nothing
+        view.drag(true, true, Some(Point::new(0, 2)));
+        view.drag(false, false, Some(Point::new(0, 2)));
+        assert!(view.session.term.selection.is_none());
+    }
+
+    #[test]
+    fn wheel_scrolls_full_screen_programs_only() {
+        let (mut view, output, rx) = fake();
+        view.input(Input::Scroll(1.0));
+        assert!(typed(&rx).is_empty());
+        output.send(b"\x1b[?1049h".to_vec()).unwrap();
+        view.poll();
+        view.input(Input::Scroll(1.0));
+        view.input(Input::Scroll(-2.0));
+        assert_eq!(typed(&rx), b"\x1b[A\x1b[B");
+    }
+
+    #[test]
+    fn broadcast_mirrors_input_but_not_raw_writes() {
+        let (mut view, _, rx) = fake();
+        view.broadcast = true;
+        view.input(Input::Text("a"));
+        view.write_raw(b"b");
+        view.paste("c");
+        assert_eq!(view.broadcast_out, [b"a".to_vec(), b"c".to_vec()]);
+        assert_eq!(typed(&rx), b"abc");
+    }
+
+    #[test]
+    fn search_follows_new_output() {
+        let (mut view, output, _) = fake();
+        assert!(view.toggle_search());
+        view.search = Some("err".into());
+        output.send(b"error 1\r\nok\r\nerror 2".to_vec()).unwrap();
+        view.poll();
+        assert_eq!(view.matches.len(), 2);
+        view.jump_to_match(true);
+        assert_eq!((view.current_match, view.scroll_to_line), (Some(1), Some(2)));
+        view.jump_to_match(true);
+        assert_eq!(view.current_match, Some(0));
+        assert!(!view.toggle_search());
+        assert!(view.matches.is_empty());
+    }
+}
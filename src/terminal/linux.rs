@@ -1,12 +0,0 @@
-pub fn init_terminal() {
-    // Initialize the terminal for Linux
-}
-
-pub fn read_input() -> String {
-    // Read input from the user in the Linux terminal
-    String::new()
-}
-
-pub fn write_output(output: &str) {
-    // Write output to the Linux terminal
-}
\ No newline at end of file
@@ -1,5 +1,37 @@
-mod linux;
+//! Platform layer for the terminal termix runs in (TUI, `--attach`) and for
+//! the shells it starts: raw mode, size and resize notification, the user's
+//! shell and which program a shell is running in the foreground.
+
+#[cfg(unix)]
+mod unix;
+#[cfg(windows)]
 mod windows;
 
-pub use linux::*;
-pub use windows::*;
\ No newline at end of file
+#[cfg(unix)]
+pub use unix::*;
+#[cfg(windows)]
+pub use windows::*;
+
+use std::io;
+
+/// Raw mode of the controlling terminal for as long as the guard lives, so
+/// an early return or a panic does not leave the terminal unusable.
+pub struct RawMode(());
+
+impl RawMode {
+    pub fn enable() -> io::Result<Self> {
+        crossterm::terminal::enable_raw_mode()?;
+        Ok(RawMode(()))
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        let _ = crossterm::terminal::disable_raw_mode();
+    }
+}
+
+/// Columns and rows of the controlling terminal.
+pub fn size() -> io::Result<(u16, u16)> {
+    crossterm::terminal::size()
+}
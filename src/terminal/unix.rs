@@ -0,0 +1,118 @@
+//! Unix side: SIGWINCH, `/etc/passwd` and process groups of a PTY.
+
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+
+/// New sizes (columns, rows) of the controlling terminal, sent on every
+/// SIGWINCH until the receiver is dropped.
+pub fn resize_events() -> std::io::Result<Receiver<(u16, u16)>> {
+    // signal-hook chains with the handler crossterm installs for its own
+    // resize events, so both keep working
+    let mut signals = signal_hook::iterator::Signals::new([signal_hook::consts::SIGWINCH])?;
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        for _ in signals.forever() {
+            let Ok(size) = super::size() else {
+                continue;
+            };
+            if tx.send(size).is_err() {
+                break;
+            }
+        }
+    });
+    Ok(rx)
+}
+
+/// The user's login shell: `$SHELL`, else the entry in `/etc/passwd`,
+/// else `/bin/sh`.
+#[cfg(any(feature = "tui", feature = "gui", feature = "ssh"))]
+pub fn default_shell() -> String {
+    std::env::var("SHELL")
+        .ok()
+        .filter(|s| !s.is_empty())
+        .or_else(|| {
+            let passwd = std::fs::read_to_string("/etc/passwd").ok()?;
+            // SAFETY: getuid cannot fail
+            passwd_shell(&passwd, unsafe { libc::getuid() })
+        })
+        .unwrap_or_else(|| "/bin/sh".into())
+}
+
+/// Shell field of the `/etc/passwd` entry for `uid`.
+#[cfg(any(feature = "tui", feature = "gui", feature = "ssh"))]
+fn passwd_shell(passwd: &str, uid: u32) -> Option<String> {
+    passwd.lines().filter(|l| !l.starts_with('#')).find_map(|line| {
+        let fields: Vec<&str> = line.split(':').collect();
+        match fields.as_slice() {
+            [_, _, id, _, _, _, shell] if id.parse() == Ok(uid) && !shell.is_empty() => Some(shell.to_string()),
+            _ => None,
+        }
+    })
+}
+
+/// Process group in the foreground of the terminal behind `fd` (for a PTY
+/// master: whatever the shell is running, or the shell itself).
+#[cfg(any(feature = "tui", feature = "gui", feature = "ssh"))]
+pub fn foreground_process_group(fd: std::os::unix::io::RawFd) -> Option<u32> {
+    // SAFETY: tcgetpgrp only reads; a bad fd yields -1
+    match unsafe { libc::tcgetpgrp(fd) } {
+        pgid if pgid > 0 => Some(pgid as u32),
+        _ => None,
+    }
+}
+
+/// Process group of `pid`.
+#[cfg(any(feature = "tui", feature = "gui", feature = "ssh"))]
+pub fn process_group(pid: u32) -> Option<u32> {
+    // SAFETY: getpgid only reads; an unknown pid yields -1
+    match unsafe { libc::getpgid(pid as libc::pid_t) } {
+        pgid if pgid > 0 => Some(pgid as u32),
+        _ => None,
+    }
+}
+
+/// Command name of a process, where the OS exposes it.
+#[cfg(any(feature = "tui", feature = "gui", feature = "ssh"))]
+pub fn process_name(pid: u32) -> Option<String> {
+    #[cfg(target_os = "linux")]
+    return std::fs::read_to_string(format!("/proc/{}/comm", pid)).ok().map(|s| s.trim_end().to_string());
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = pid;
+        None
+    }
+}
+
+#[cfg(all(test, any(feature = "tui", feature = "gui", feature = "ssh")))]
+mod tests {
+    use super::*;
+
+    const PASSWD: &str = "\
+root:x:0:0:root:/root:/bin/bash
+# comment
+daemon:x:1:1:daemon:/usr/sbin:/usr/sbin/nologin
+ada:x:1000:1000:Ada,,,:/home/ada:/usr/bin/fish
+nobody:x:65534:65534::/nonexistent:
+";
+
+    #[test]
+    fn passwd_shell_of_user() {
+        assert_eq!(passwd_shell(PASSWD, 1000).as_deref(), Some("/usr/bin/fish"));
+        assert_eq!(passwd_shell(PASSWD, 0).as_deref(), Some("/bin/bash"));
+    }
+
+    #[test]
+    fn passwd_shell_missing() {
+        assert_eq!(passwd_shell(PASSWD, 42), None);
+        // Empty shell field
+        assert_eq!(passwd_shell(PASSWD, 65534), None);
+    }
+
+    #[test]
+    fn own_process() {
+        let pid = std::process::id();
+        assert!(process_group(pid).is_some());
+        #[cfg(target_os = "linux")]
+        assert!(process_name(pid).is_some_and(|n| !n.is_empty()));
+    }
+}
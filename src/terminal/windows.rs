@@ -1,19 +1,42 @@
-mod windows {
-    use std::io::{self, Write, Read};
+//! Windows side: no SIGWINCH, so the console size is polled; shells come
+//! from `%COMSPEC%`. Process groups of a console are not inspected.
 
-    pub fn clear_screen() {
-        print!("{}[2J", 27 as char);
-        print!("{}[1;1H", 27 as char);
-        io::stdout().flush().unwrap();
-    }
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::Duration;
 
-    pub fn read_input() -> String {
-        let mut input = String::new();
-        io::stdin().read_line(&mut input).unwrap();
-        input.trim().to_string()
-    }
+/// New sizes (columns, rows) of the console, polled four times a second;
+/// the poller ends at the first change after the receiver is dropped.
+pub fn resize_events() -> std::io::Result<Receiver<(u16, u16)>> {
+    let (tx, rx) = mpsc::channel();
+    let mut last = super::size()?;
+    thread::spawn(move || loop {
+        thread::sleep(Duration::from_millis(250));
+        match super::size() {
+            Ok(size) if size != last => {
+                last = size;
+                if tx.send(size).is_err() {
+                    break;
+                }
+            }
+            _ => {}
+        }
+    });
+    Ok(rx)
+}
 
-    pub fn write_output(output: &str) {
-        println!("{}", output);
-    }
-}
\ No newline at end of file
+/// The command interpreter: `%COMSPEC%`, else `cmd.exe`.
+#[cfg(any(feature = "tui", feature = "gui", feature = "ssh"))]
+pub fn default_shell() -> String {
+    std::env::var("COMSPEC").ok().filter(|s| !s.is_empty()).unwrap_or_else(|| "cmd.exe".into())
+}
+
+#[cfg(any(feature = "tui", feature = "gui", feature = "ssh"))]
+pub fn process_group(_pid: u32) -> Option<u32> {
+    None
+}
+
+#[cfg(any(feature = "tui", feature = "gui", feature = "ssh"))]
+pub fn process_name(_pid: u32) -> Option<String> {
+    None
+}
@@ -1,3 +1,5 @@
+#[cfg(all(feature = "tui", feature = "ssh"))]
+use crate::config::ssh::SshConnection;
 use crossterm::{
     cursor,
     event::{self, Event, KeyCode, KeyEvent},
@@ -8,9 +10,56 @@ use crossterm::{
 };
 use std::io::{stdout, Write};
 
+#[cfg(feature = "tui")]
+mod mux;
+mod settings;
+#[cfg(all(feature = "tui", feature = "ssh"))]
+mod ssh;
+
+#[derive(Clone, Copy, PartialEq)]
+enum MenuItem {
+    Terminal,
+    #[cfg(all(feature = "tui", feature = "ssh"))]
+    Ssh,
+    Settings,
+    About,
+    Quit,
+}
+
+const MENU: &[MenuItem] = &[
+    MenuItem::Terminal,
+    #[cfg(all(feature = "tui", feature = "ssh"))]
+    MenuItem::Ssh,
+    MenuItem::Settings,
+    MenuItem::About,
+    MenuItem::Quit,
+];
+
+impl MenuItem {
+    fn label(self) -> String {
+        match self {
+            MenuItem::Terminal => rust_i18n::t!("tui.open_terminal"),
+            #[cfg(all(feature = "tui", feature = "ssh"))]
+            MenuItem::Ssh => rust_i18n::t!("ssh.title"),
+            MenuItem::Settings => rust_i18n::t!("menu.settings"),
+            MenuItem::About => rust_i18n::t!("tui.about"),
+            MenuItem::Quit => rust_i18n::t!("menu.exit"),
+        }
+        .to_string()
+    }
+}
+
 pub struct UI {
     selected_menu: usize,
     running: bool,
+    // Terminal sessions survive a trip back to the menu
+    #[cfg(feature = "tui")]
+    mux: Option<mux::Mux>,
+    // Opened from the command line: start in the terminal, quit after it
+    #[cfg(feature = "tui")]
+    start_in_terminal: bool,
+    #[cfg(all(feature = "tui", feature = "ssh"))]
+    pending_ssh: Option<(SshConnection, Option<String>)>, // host and tab title
 }
 
 impl UI {
@@ -18,21 +67,50 @@ impl UI {
         UI {
             selected_menu: 0,
             running: true,
+            #[cfg(feature = "tui")]
+            mux: None,
+            #[cfg(feature = "tui")]
+            start_in_terminal: false,
+            #[cfg(all(feature = "tui", feature = "ssh"))]
+            pending_ssh: None,
         }
     }
 
+    /// Open the terminal asked for with `-e`, `--cwd`, `--title` or `--ssh`.
+    /// `run` then starts in it and quits once its last shell has exited;
+    /// Ctrl+B d still leads to the menu. SSH connects once `run` has taken
+    /// over the screen, where it can ask for a password.
+    #[cfg(feature = "tui")]
+    pub fn open(&mut self, options: &crate::cli::Options) -> anyhow::Result<()> {
+        self.start_in_terminal = true;
+        #[cfg(feature = "ssh")]
+        if let Some(conn) = &options.ssh_connection {
+            self.pending_ssh = Some((conn.clone(), options.title.clone()));
+            return Ok(());
+        }
+        let mut mux = mux::Mux::empty()?;
+        mux.open_tab(&options.command, options.cwd.as_deref(), options.title.clone(), options.profile_config.as_ref())?;
+        self.mux = Some(mux);
+        Ok(())
+    }
+
     pub fn run(&mut self) -> Result<()> {
-        terminal::enable_raw_mode()?;
+        let raw_mode = crate::terminal::RawMode::enable()?;
         let mut stdout = stdout();
         execute!(stdout, terminal::EnterAlternateScreen, cursor::Hide)?;
 
+        #[cfg(feature = "tui")]
+        if std::mem::take(&mut self.start_in_terminal) {
+            self.start(&mut stdout)?;
+        }
+
         while self.running {
             self.render()?;
             self.handle_input()?;
         }
 
         execute!(stdout, terminal::LeaveAlternateScreen, cursor::Show)?;
-        terminal::disable_raw_mode()?;
+        drop(raw_mode);
         Ok(())
     }
 
@@ -77,22 +155,18 @@ impl UI {
     }
 
     fn print_menu(&self, stdout: &mut impl Write) -> Result<()> {
-        let menu_items = ["1. Terminal öffnen",
-            "2. Einstellungen",
-            "3. Über diese Anwendung",
-            "4. Beenden"];
-
         execute!(
             stdout,
             cursor::MoveTo(5, 5),
             SetForegroundColor(Color::Cyan),
         )?;
         writeln!(stdout, "╔═══════════════════════════════════════╗")?;
-        writeln!(stdout, "     ║          HAUPTMENÜ                    ║")?;
+        writeln!(stdout, "     ║          {:<29}║", rust_i18n::t!("tui.main_menu"))?;
         writeln!(stdout, "     ╠═══════════════════════════════════════╣")?;
         execute!(stdout, ResetColor)?;
 
-        for (index, item) in menu_items.iter().enumerate() {
+        for (index, item) in MENU.iter().enumerate() {
+            let item = format!("{}. {:<24}", index + 1, item.label());
             if index == self.selected_menu {
                 execute!(
                     stdout,
@@ -100,7 +174,7 @@ impl UI {
                     SetBackgroundColor(Color::Green),
                     SetForegroundColor(Color::Black),
                 )?;
-                writeln!(stdout, "     ║  ▶ {}                  ║", item)?;
+                writeln!(stdout, "     ║  ▶ {}         ║", item)?;
                 execute!(stdout, ResetColor)?;
             } else {
                 execute!(
@@ -108,13 +182,13 @@ impl UI {
                     cursor::MoveTo(5, 8 + index as u16),
                     SetForegroundColor(Color::White),
                 )?;
-                writeln!(stdout, "     ║    {}                  ║", item)?;
+                writeln!(stdout, "     ║    {}         ║", item)?;
             }
         }
 
         execute!(
             stdout,
-            cursor::MoveTo(5, 12),
+            cursor::MoveTo(5, 8 + MENU.len() as u16),
             SetForegroundColor(Color::Cyan),
         )?;
         writeln!(stdout, "     ╚═══════════════════════════════════════╝")?;
@@ -131,13 +205,14 @@ impl UI {
         )?;
         writeln!(stdout)?;
         writeln!(stdout, "  ┌─────────────────────────────────────────────────────────────────────────┐")?;
-        writeln!(stdout, "  │ ↑/↓: Navigation  │  Enter: Auswählen  │  ESC/Q: Beenden              │")?;
+        writeln!(stdout, "  │ {:<72}│", rust_i18n::t!("tui.menu_hint"))?;
         writeln!(stdout, "  └─────────────────────────────────────────────────────────────────────────┘")?;
         execute!(stdout, ResetColor)?;
 
         Ok(())
     }
 
+    #[allow(clippy::collapsible_match)]
     fn handle_input(&mut self) -> Result<()> {
         if event::poll(std::time::Duration::from_millis(100))? {
             if let Event::Key(KeyEvent { code, .. }) = event::read()? {
@@ -148,7 +223,7 @@ impl UI {
                         }
                     }
                     KeyCode::Down => {
-                        if self.selected_menu < 3 {
+                        if self.selected_menu + 1 < MENU.len() {
                             self.selected_menu += 1;
                         }
                     }
@@ -168,60 +243,144 @@ impl UI {
     fn execute_menu_action(&mut self) -> Result<()> {
         let mut stdout = stdout();
         
-        match self.selected_menu {
-            0 => {
-                // Terminal öffnen
+        match MENU[self.selected_menu] {
+            #[cfg(feature = "tui")]
+            MenuItem::Terminal => self.open_terminal(&mut stdout)?,
+            #[cfg(not(feature = "tui"))]
+            MenuItem::Terminal => {
                 execute!(
                     stdout,
                     Clear(ClearType::All),
                     cursor::MoveTo(0, 0),
                     SetForegroundColor(Color::Green),
                 )?;
-                writeln!(stdout, "\n  Terminal-Modus wird gestartet...")?;
-                writeln!(stdout, "  (Diese Funktion wird noch implementiert)")?;
+                writeln!(stdout, "\n  {}", rust_i18n::t!("tui.terminal_starting"))?;
+                writeln!(stdout, "  {}", rust_i18n::t!("tui.not_implemented"))?;
                 execute!(stdout, ResetColor)?;
-                writeln!(stdout, "\n  Drücke eine beliebige Taste zum Fortfahren...")?;
+                writeln!(stdout, "\n  {}", rust_i18n::t!("common.press_any_key"))?;
                 event::read()?;
             }
-            1 => {
-                // Einstellungen
+            #[cfg(all(feature = "tui", feature = "ssh"))]
+            MenuItem::Ssh => self.ssh_manager(&mut stdout, None, None)?,
+            MenuItem::Settings => {
+                let saved = settings::SettingsForm::new().run(&mut stdout)?;
+                #[cfg(feature = "tui")]
+                if let (Some(settings), Some(mux)) = (saved, self.mux.as_mut()) {
+                    mux.apply_settings(&settings);
+                }
+                #[cfg(not(feature = "tui"))]
+                let _ = saved;
+            }
+            MenuItem::About => {
                 execute!(
                     stdout,
                     Clear(ClearType::All),
                     cursor::MoveTo(0, 0),
-                    SetForegroundColor(Color::Yellow),
+                    SetForegroundColor(Color::Cyan),
                 )?;
-                writeln!(stdout, "\n  ⚙️  EINSTELLUNGEN")?;
+                writeln!(stdout, "\n  ℹ️  {}", rust_i18n::t!("tui.about").to_uppercase())?;
                 writeln!(stdout, "  ═══════════════════════════════════")?;
                 execute!(stdout, ResetColor)?;
-                writeln!(stdout, "\n  - Farbschema: Standard")?;
-                writeln!(stdout, "  - Schriftgröße: Mittel")?;
-                writeln!(stdout, "  - Plattform: {}", std::env::consts::OS)?;
-                writeln!(stdout, "\n  Drücke eine beliebige Taste zum Fortfahren...")?;
+                writeln!(stdout, "\n  TermiX v{}", env!("CARGO_PKG_VERSION"))?;
+                writeln!(stdout, "  {}", rust_i18n::t!("tui.runs_on"))?;
+                writeln!(stdout, "  {}", rust_i18n::t!("tui.built_with"))?;
+                writeln!(stdout, "\n  {}", rust_i18n::t!("common.press_any_key"))?;
                 event::read()?;
             }
-            2 => {
-                // Über
+            MenuItem::Quit => {
+                self.running = false;
+            }
+        }
+        Ok(())
+    }
+
+    /// First screen after `open`; the menu only follows when shells are
+    /// left running.
+    #[cfg(feature = "tui")]
+    fn start(&mut self, stdout: &mut impl Write) -> Result<()> {
+        #[cfg(feature = "ssh")]
+        if let Some((conn, title)) = self.pending_ssh.take() {
+            self.ssh_manager(stdout, Some(conn), title)?;
+            self.running = self.mux.is_some();
+            return Ok(());
+        }
+        self.open_terminal(stdout)?;
+        self.running = self.mux.is_some();
+        Ok(())
+    }
+
+    /// Show the running shells, starting one if there are none yet.
+    #[cfg(feature = "tui")]
+    fn open_terminal(&mut self, stdout: &mut impl Write) -> Result<()> {
+        let mux = match self.mux.take() {
+            Some(mux) => Ok(mux),
+            None => mux::Mux::new(),
+        };
+        match mux {
+            Ok(mut mux) => {
+                mux.run(stdout)?;
+                if !mux.is_empty() {
+                    self.mux = Some(mux);
+                }
+            }
+            Err(e) => {
                 execute!(
                     stdout,
                     Clear(ClearType::All),
                     cursor::MoveTo(0, 0),
-                    SetForegroundColor(Color::Cyan),
+                    SetForegroundColor(Color::Red),
                 )?;
-                writeln!(stdout, "\n  ℹ️  ÜBER DIESE ANWENDUNG")?;
-                writeln!(stdout, "  ═══════════════════════════════════")?;
+                writeln!(stdout, "\n  {}: {}", rust_i18n::t!("terminal.start_failed"), e)?;
                 execute!(stdout, ResetColor)?;
-                writeln!(stdout, "\n  TermiX v0.1.0")?;
-                writeln!(stdout, "  Läuft auf: Linux & Windows")?;
-                writeln!(stdout, "  Entwickelt mit Rust & Crossterm")?;
-                writeln!(stdout, "\n  Drücke eine beliebige Taste zum Fortfahren...")?;
+                writeln!(stdout, "\n  {}", rust_i18n::t!("common.press_any_key"))?;
                 event::read()?;
             }
-            3 => {
-                // Beenden
-                self.running = false;
+        }
+        Ok(())
+    }
+
+    /// Saved SSH hosts; a picked one opens as a new mux tab. `first` (from
+    /// `--ssh`) is connected to before the list is shown, and the tabs get
+    /// `title` instead of the connection name.
+    #[cfg(all(feature = "tui", feature = "ssh"))]
+    fn ssh_manager(&mut self, stdout: &mut impl Write, first: Option<SshConnection>, title: Option<String>) -> Result<()> {
+        let mut manager = ssh::SshManager::new();
+        let mut next = first.and_then(|conn| manager.prepare(conn));
+        loop {
+            let conn = match next.take() {
+                Some(conn) => conn,
+                None => match manager.run(stdout)? {
+                    Some(conn) => conn,
+                    None => break,
+                },
+            };
+            execute!(stdout, Clear(ClearType::All), cursor::MoveTo(0, 0))?;
+            let target = format!("{}@{}:{}", conn.username, conn.host, conn.port);
+            writeln!(stdout, "\r\n  {}\r", rust_i18n::t!("ssh.connecting", target = target))?;
+            stdout.flush()?;
+            let opened = match self.mux.take() {
+                Some(mux) => Ok(mux),
+                None => mux::Mux::empty(),
+            }
+            .and_then(|mut mux| {
+                let result = mux.open_ssh(&conn, title.clone());
+                if !mux.is_empty() {
+                    self.mux = Some(mux);
+                }
+                result
+            });
+            match opened {
+                Ok(()) => {
+                    if let Some(mux) = self.mux.as_mut() {
+                        mux.run(stdout)?;
+                        if mux.is_empty() {
+                            self.mux = None;
+                        }
+                    }
+                    break;
+                }
+                Err(e) => manager.show_error(format!("{}: {}", rust_i18n::t!("ssh.connect_failed"), e)),
             }
-            _ => {}
         }
         Ok(())
     }
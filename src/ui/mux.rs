@@ -0,0 +1,944 @@
+//! Terminal multiplexer of the TUI: tabs of split panes, each a shell
+//! session of the terminal core drawn from its screen, controlled with
+//! tmux-style prefix keys (Ctrl+B, then a command key).
+
+use crate::config::profile::{self, Profile};
+#[cfg(feature = "ssh")]
+use crate::config::ssh::SshConnection;
+use crate::config::AppSettings;
+use crate::pty::{self, PtyProcess};
+use crate::term::input::{Key, Modifiers};
+use crate::term::{search, Match, Point, Selection, Session, Terminal};
+use crossterm::{
+    cursor,
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    execute, queue,
+    style::{Attribute, Color, Print, SetAttribute, SetBackgroundColor, SetForegroundColor},
+    terminal::{self, Clear, ClearType},
+    Result,
+};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::Path;
+use std::time::Duration;
+
+/// Prefix key table in two columns, translated at draw time.
+fn help_lines() -> Vec<String> {
+    use rust_i18n::t;
+    type Cell = (Cow<'static, str>, Cow<'static, str>);
+    let rows: [(Cell, Cell); 8] = [
+        (("c".into(), t!("tui.help_new_tab")), ("n / p".into(), t!("tui.help_next_tab"))),
+        (("0-9".into(), t!("tui.help_pick_tab")), ("&".into(), t!("tui.help_close_tab"))),
+        (("%".into(), t!("tui.help_split_side")), ("\"".into(), t!("tui.help_split_stacked"))),
+        ((t!("tui.help_arrows"), t!("tui.help_focus")), ("o".into(), t!("tui.help_next_pane"))),
+        ((t!("tui.help_ctrl_arrows"), t!("tui.help_resize")), ("z".into(), t!("tui.help_zoom"))),
+        (("x".into(), t!("tui.help_close_pane")), ("[".into(), t!("tui.help_copy_mode"))),
+        (("]".into(), t!("tui.help_paste")), ("?".into(), t!("tui.help_help"))),
+        (("d".into(), t!("tui.help_menu")), (t!("tui.ctrl_b"), t!("tui.help_send_prefix"))),
+    ];
+    let column = |cell: fn(&(Cell, Cell)) -> &str| rows.iter().map(|r| cell(r).chars().count()).max().unwrap_or(0) + 2;
+    let key_w = column(|((key, _), _)| key);
+    let desc_w = column(|((_, desc), _)| desc);
+    let right_w = column(|(_, (key, _))| key);
+    let mut lines = vec![t!("tui.help_title").to_string(), String::new()];
+    lines.extend(rows.iter().map(|((key, desc), (right_key, right_desc))| {
+        format!("  {:<key_w$}{:<desc_w$}{:<right_w$}{}", key, desc, right_key, right_desc)
+    }));
+    lines.extend([String::new(), t!("tui.help_close").to_string()]);
+    lines
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Split {
+    SideBySide,
+    Stacked,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Direction {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+struct Rect {
+    x: u16,
+    y: u16,
+    w: u16,
+    h: u16,
+}
+
+/// Pane rectangles and dividers of the visible tab.
+type Layout = (Vec<(usize, Rect)>, Vec<(Rect, Split)>);
+
+/// Split tree of one tab; leaves are pane ids.
+enum Node {
+    Leaf(usize),
+    Split { split: Split, ratio: f32, first: Box<Node>, second: Box<Node> },
+}
+
+impl Node {
+    fn contains(&self, pane: usize) -> bool {
+        match self {
+            Node::Leaf(id) => *id == pane,
+            Node::Split { first, second, .. } => first.contains(pane) || second.contains(pane),
+        }
+    }
+
+    fn leaves(&self, out: &mut Vec<usize>) {
+        match self {
+            Node::Leaf(id) => out.push(*id),
+            Node::Split { first, second, .. } => {
+                first.leaves(out);
+                second.leaves(out);
+            }
+        }
+    }
+
+    /// Replace the leaf `target` with a split of it and `new`.
+    fn split(&mut self, target: usize, split: Split, new: usize) -> bool {
+        match self {
+            Node::Leaf(id) if *id == target => {
+                *self = Node::Split {
+                    split,
+                    ratio: 0.5,
+                    first: Box::new(Node::Leaf(target)),
+                    second: Box::new(Node::Leaf(new)),
+                };
+                true
+            }
+            Node::Leaf(_) => false,
+            Node::Split { first, second, .. } => first.split(target, split, new) || second.split(target, split, new),
+        }
+    }
+
+    /// The tree without `target`; its sibling takes the parent's place.
+    fn without(self, target: usize) -> Option<Node> {
+        match self {
+            Node::Leaf(id) if id == target => None,
+            Node::Leaf(id) => Some(Node::Leaf(id)),
+            Node::Split { split, ratio, first, second } => match (first.without(target), second.without(target)) {
+                (Some(first), Some(second)) => Some(Node::Split { split, ratio, first: Box::new(first), second: Box::new(second) }),
+                (Some(node), None) | (None, Some(node)) => Some(node),
+                (None, None) => None,
+            },
+        }
+    }
+
+    /// Move the nearest divider of orientation `split` around `target`.
+    fn resize(&mut self, target: usize, split: Split, delta: f32) -> bool {
+        let Node::Split { split: own, ratio, first, second } = self else {
+            return false;
+        };
+        let inner = if first.contains(target) {
+            first.resize(target, split, delta)
+        } else if second.contains(target) {
+            second.resize(target, split, delta)
+        } else {
+            return false;
+        };
+        if !inner && *own == split {
+            *ratio = (*ratio + delta).clamp(0.1, 0.9);
+            return true;
+        }
+        inner
+    }
+
+    /// Pane rectangles and divider cells (`│` or `─` lines) inside `area`.
+    fn layout(&self, area: Rect, panes: &mut Vec<(usize, Rect)>, dividers: &mut Vec<(Rect, Split)>) {
+        match self {
+            Node::Leaf(id) => panes.push((*id, area)),
+            Node::Split { split, ratio, first, second } => {
+                let total = if *split == Split::SideBySide { area.w } else { area.h };
+                let a = ((total.saturating_sub(1) as f32 * ratio).round() as u16).clamp(1.min(total), total.saturating_sub(2).max(1));
+                let b = total.saturating_sub(a + 1);
+                let (first_area, divider, second_area) = match split {
+                    Split::SideBySide => (
+                        Rect { w: a, ..area },
+                        Rect { x: area.x + a, w: 1, ..area },
+                        Rect { x: area.x + a + 1, w: b, ..area },
+                    ),
+                    Split::Stacked => (
+                        Rect { h: a, ..area },
+                        Rect { y: area.y + a, h: 1, ..area },
+                        Rect { y: area.y + a + 1, h: b, ..area },
+                    ),
+                };
+                first.layout(first_area, panes, dividers);
+                dividers.push((divider, *split));
+                second.layout(second_area, panes, dividers);
+            }
+        }
+    }
+}
+
+struct Pane {
+    session: Session,
+    title: Option<String>, // shown until the shell sets a window title
+}
+
+/// Browsing the focused pane's scrollback with a cursor (Ctrl+B [).
+struct CopyMode {
+    cursor: Point,
+    prompt: Option<String>, // search being typed after `/`
+    query: String,
+    matches: Vec<Match>,
+}
+
+struct Tab {
+    root: Node,
+    focused: usize,
+    zoomed: bool,
+}
+
+pub struct Mux {
+    panes: HashMap<usize, Pane>,
+    next_pane: usize,
+    tabs: Vec<Tab>,
+    active: usize,
+    scrollback: usize,
+    profiles: Vec<Profile>,
+    default_profile: String, // for new tabs and splits; empty = plain shell
+    width: u16,
+    height: u16,
+    prefix: bool, // Ctrl+B pressed, waiting for the command key
+    copy: Option<CopyMode>,
+    buffer: Option<String>, // text copied in copy mode, pasted with Ctrl+B ]
+    help: bool,
+    message: Option<String>,
+}
+
+impl Mux {
+    pub fn new() -> anyhow::Result<Self> {
+        let mut mux = Self::empty()?;
+        mux.new_tab()?;
+        Ok(mux)
+    }
+
+    /// A mux without tabs; `run` returns at once until one is opened.
+    pub fn empty() -> anyhow::Result<Self> {
+        let (width, height) = terminal::size()?;
+        let settings = crate::config::load_settings();
+        Ok(Mux {
+            panes: HashMap::new(),
+            next_pane: 0,
+            tabs: Vec::new(),
+            active: 0,
+            scrollback: settings.scrollback_lines,
+            profiles: settings.profiles,
+            default_profile: settings.default_profile,
+            width,
+            height,
+            prefix: false,
+            copy: None,
+            buffer: None,
+            help: false,
+            message: None,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tabs.is_empty()
+    }
+
+    /// Scrollback and profiles for panes opened from now on.
+    pub fn apply_settings(&mut self, settings: &AppSettings) {
+        self.scrollback = settings.scrollback_lines;
+        self.profiles = settings.profiles.clone();
+        self.default_profile = settings.default_profile.clone();
+    }
+
+    /// Run until every shell has exited or the user goes back to the menu
+    /// (Ctrl+B d). Shells keep running while the menu is shown.
+    pub fn run(&mut self, out: &mut impl Write) -> Result<()> {
+        let (width, height) = terminal::size()?;
+        self.width = width;
+        self.height = height;
+        execute!(out, Clear(ClearType::All), event::EnableBracketedPaste)?;
+        let mut dirty = true;
+        loop {
+            dirty |= self.poll_output();
+            if self.tabs.is_empty() {
+                break;
+            }
+            if dirty {
+                self.render(out)?;
+                dirty = false;
+            }
+            if !event::poll(Duration::from_millis(16))? {
+                continue;
+            }
+            let mut leave = false;
+            // Drain everything queued so typing and pasting stay snappy
+            loop {
+                match event::read()? {
+                    Event::Key(key) if key.kind != KeyEventKind::Release => leave |= !self.handle_key(key),
+                    Event::Paste(text) => self.paste(&text),
+                    Event::Resize(width, height) => {
+                        self.width = width;
+                        self.height = height;
+                        execute!(out, Clear(ClearType::All))?;
+                    }
+                    _ => {}
+                }
+                if leave || !event::poll(Duration::ZERO)? {
+                    break;
+                }
+            }
+            if leave {
+                break;
+            }
+            dirty = true;
+        }
+        execute!(out, event::DisableBracketedPaste, cursor::Hide, SetAttribute(Attribute::Reset))?;
+        Ok(())
+    }
+
+    fn profile(&self, name: &str) -> Option<Profile> {
+        profile::find(&self.profiles, name).cloned()
+    }
+
+    /// Start `argv` (the shell when empty) with `profile` in a new pane.
+    fn spawn_pane(&mut self, profile: Option<&Profile>, argv: &[String], cwd: Option<&Path>, title: Option<String>) -> anyhow::Result<usize> {
+        let (rows, cols) = (self.height.saturating_sub(1).max(2), self.width.max(2));
+        let pty = match (profile, argv) {
+            (Some(profile), argv) => pty::spawn_profile(profile, argv, rows, cols, cwd)?,
+            (None, []) => pty::spawn_shell(rows, cols, cwd)?,
+            (None, argv) => pty::spawn_command(argv, rows, cols, cwd)?,
+        };
+        Ok(self.add_pane(pty, rows, title, profile))
+    }
+
+    fn add_pane(&mut self, pty: PtyProcess, rows: u16, title: Option<String>, profile: Option<&Profile>) -> usize {
+        let id = self.next_pane;
+        self.next_pane += 1;
+        let scrollback = profile.and_then(|p| p.scrollback_lines).unwrap_or(self.scrollback);
+        let session = Session::new(pty, rows, self.width.max(2), scrollback);
+        self.panes.insert(id, Pane { session, title });
+        id
+    }
+
+    /// Open a tab running `command` (the shell when empty) in `cwd`, as
+    /// asked for on the command line; `profile` replaces the default one.
+    pub fn open_tab(&mut self, command: &[String], cwd: Option<&Path>, title: Option<String>, profile: Option<&Profile>) -> anyhow::Result<()> {
+        let profile = profile.cloned().or_else(|| self.profile(&self.default_profile));
+        let id = self.spawn_pane(profile.as_ref(), command, cwd, title)?;
+        self.push_tab(id);
+        Ok(())
+    }
+
+    /// Open a tab with a shell on the saved host `conn` (password filled in),
+    /// titled "SSH: name" unless `title` is given.
+    #[cfg(feature = "ssh")]
+    pub fn open_ssh(&mut self, conn: &SshConnection, title: Option<String>) -> anyhow::Result<()> {
+        let rows = self.height.saturating_sub(1).max(2);
+        let profile = conn.profile.as_deref().and_then(|name| self.profile(name));
+        let pty = crate::ssh::spawn_shell(conn, profile.as_ref(), rows, self.width.max(2))?;
+        let title = title.unwrap_or_else(|| format!("SSH: {}", conn.name));
+        let id = self.add_pane(pty, rows, Some(title), profile.as_ref());
+        self.push_tab(id);
+        Ok(())
+    }
+
+    fn push_tab(&mut self, pane: usize) {
+        self.tabs.push(Tab { root: Node::Leaf(pane), focused: pane, zoomed: false });
+        self.active = self.tabs.len() - 1;
+    }
+
+    fn focused_cwd(&self) -> Option<std::path::PathBuf> {
+        let tab = self.tabs.get(self.active)?;
+        self.panes.get(&tab.focused)?.session.pty.cwd()
+    }
+
+    /// A pane with the default profile, started in its directory or else
+    /// where the focused shell is.
+    fn spawn_default_pane(&mut self) -> anyhow::Result<usize> {
+        let profile = self.profile(&self.default_profile);
+        let cwd = profile.as_ref().and_then(Profile::start_dir).or_else(|| self.focused_cwd());
+        self.spawn_pane(profile.as_ref(), &[], cwd.as_deref(), None)
+    }
+
+    fn new_tab(&mut self) -> anyhow::Result<()> {
+        let id = self.spawn_default_pane()?;
+        self.push_tab(id);
+        Ok(())
+    }
+
+    fn split(&mut self, split: Split) -> anyhow::Result<()> {
+        let id = self.spawn_default_pane()?;
+        let tab = &mut self.tabs[self.active];
+        tab.root.split(tab.focused, split, id);
+        tab.focused = id;
+        tab.zoomed = false;
+        Ok(())
+    }
+
+    /// Drop a pane (ending its shell) and tidy up its tab.
+    fn remove_pane(&mut self, id: usize) {
+        self.panes.remove(&id);
+        let Some(index) = self.tabs.iter().position(|t| t.root.contains(id)) else {
+            return;
+        };
+        let tab = &mut self.tabs[index];
+        let root = std::mem::replace(&mut tab.root, Node::Leaf(usize::MAX));
+        match root.without(id) {
+            Some(root) => {
+                tab.root = root;
+                if tab.focused == id {
+                    let mut leaves = Vec::new();
+                    tab.root.leaves(&mut leaves);
+                    tab.focused = leaves[0];
+                    tab.zoomed = false;
+                }
+            }
+            None => {
+                self.tabs.remove(index);
+                if self.active > index || self.active >= self.tabs.len() {
+                    self.active = self.active.saturating_sub(1);
+                }
+            }
+        }
+    }
+
+    fn close_tab(&mut self) {
+        let mut leaves = Vec::new();
+        self.tabs[self.active].root.leaves(&mut leaves);
+        for id in leaves {
+            self.remove_pane(id);
+        }
+    }
+
+    /// Feed pending output into the screens; panes whose shell exited go away.
+    fn poll_output(&mut self) -> bool {
+        let mut changed = false;
+        let mut exited = Vec::new();
+        for (id, pane) in self.panes.iter_mut() {
+            let poll = pane.session.poll();
+            changed |= poll.output;
+            if poll.exited {
+                exited.push(*id);
+            }
+        }
+        for id in exited {
+            self.remove_pane(id);
+            changed = true;
+        }
+        changed
+    }
+
+    fn focused_pane(&mut self) -> Option<&mut Pane> {
+        let id = self.tabs.get(self.active)?.focused;
+        self.panes.get_mut(&id)
+    }
+
+    fn write_focused(&mut self, bytes: &[u8]) {
+        if let Some(pane) = self.focused_pane() {
+            pane.session.write(bytes);
+        }
+    }
+
+    fn paste(&mut self, text: &str) {
+        if let Some(pane) = self.focused_pane() {
+            pane.session.paste(text);
+        }
+    }
+
+    /// Returns false when the multiplexer should hand back to the menu.
+    fn handle_key(&mut self, key: KeyEvent) -> bool {
+        self.message = None;
+        if self.help {
+            self.help = false;
+            return true;
+        }
+        if self.copy.is_some() {
+            self.copy_key(key);
+            return true;
+        }
+        if self.prefix {
+            self.prefix = false;
+            return self.prefix_command(key);
+        }
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('b') {
+            self.prefix = true;
+            return true;
+        }
+        if let (Some((key, modifiers)), Some(pane)) = (key_input(key), self.focused_pane()) {
+            pane.session.send_key(key, modifiers);
+        }
+        true
+    }
+
+    fn prefix_command(&mut self, key: KeyEvent) -> bool {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        let result = match key.code {
+            KeyCode::Char('b') if ctrl => {
+                self.write_focused(&[0x02]);
+                Ok(())
+            }
+            KeyCode::Char('c') => self.new_tab(),
+            KeyCode::Char('%') => self.split(Split::SideBySide),
+            KeyCode::Char('"') => self.split(Split::Stacked),
+            KeyCode::Char('n') => {
+                self.active = (self.active + 1) % self.tabs.len();
+                Ok(())
+            }
+            KeyCode::Char('p') => {
+                self.active = (self.active + self.tabs.len() - 1) % self.tabs.len();
+                Ok(())
+            }
+            KeyCode::Char(c @ '0'..='9') => {
+                let index = c as usize - '0' as usize;
+                if index < self.tabs.len() {
+                    self.active = index;
+                }
+                Ok(())
+            }
+            KeyCode::Char('o') => {
+                let tab = &mut self.tabs[self.active];
+                let mut leaves = Vec::new();
+                tab.root.leaves(&mut leaves);
+                let pos = leaves.iter().position(|id| *id == tab.focused).unwrap_or(0);
+                tab.focused = leaves[(pos + 1) % leaves.len()];
+                tab.zoomed = false;
+                Ok(())
+            }
+            KeyCode::Left | KeyCode::Right | KeyCode::Up | KeyCode::Down => {
+                let dir = match key.code {
+                    KeyCode::Left => Direction::Left,
+                    KeyCode::Right => Direction::Right,
+                    KeyCode::Up => Direction::Up,
+                    _ => Direction::Down,
+                };
+                if ctrl {
+                    self.resize_focused(dir);
+                } else {
+                    self.focus_direction(dir);
+                }
+                Ok(())
+            }
+            KeyCode::Char('z') => {
+                let tab = &mut self.tabs[self.active];
+                tab.zoomed = !tab.zoomed;
+                Ok(())
+            }
+            KeyCode::Char('x') => {
+                let id = self.tabs[self.active].focused;
+                self.remove_pane(id);
+                Ok(())
+            }
+            KeyCode::Char('&') => {
+                self.close_tab();
+                Ok(())
+            }
+            KeyCode::Char('[') => {
+                self.enter_copy_mode();
+                Ok(())
+            }
+            KeyCode::Char(']') => {
+                if let Some(text) = self.buffer.clone() {
+                    self.paste(&text);
+                }
+                Ok(())
+            }
+            KeyCode::Char('?') => {
+                self.help = true;
+                Ok(())
+            }
+            KeyCode::Char('d') => return false,
+            _ => Ok(()),
+        };
+        if let Err(e) = result {
+            self.message = Some(format!("{}: {}", rust_i18n::t!("common.error"), e));
+        }
+        true
+    }
+
+    fn enter_copy_mode(&mut self) {
+        let Some(pane) = self.focused_pane() else {
+            return;
+        };
+        let term = &mut pane.session.term;
+        let (row, col) = term.screen().cursor_position();
+        let cursor = Point::new(term.line_at_row(row), col as usize);
+        self.copy = Some(CopyMode { cursor, prompt: None, query: String::new(), matches: Vec::new() });
+    }
+
+    fn leave_copy_mode(&mut self) {
+        self.copy = None;
+        if let Some(pane) = self.focused_pane() {
+            pane.session.term.selection = None;
+            pane.session.term.set_scroll_offset(0);
+        }
+    }
+
+    fn copy_key(&mut self, key: KeyEvent) {
+        let page = self.height.saturating_sub(1) as usize / 2;
+        let Some(id) = self.tabs.get(self.active).map(|t| t.focused) else {
+            return;
+        };
+        let (Some(pane), Some(copy)) = (self.panes.get_mut(&id), self.copy.as_mut()) else {
+            return;
+        };
+        let term = &mut pane.session.term;
+
+        if let Some(prompt) = &mut copy.prompt {
+            match key.code {
+                KeyCode::Char(c) => prompt.push(c),
+                KeyCode::Backspace => {
+                    prompt.pop();
+                }
+                KeyCode::Enter => {
+                    copy.query = copy.prompt.take().unwrap_or_default();
+                    copy.matches = term.search(&copy.query);
+                    // Output grows downwards, so the first hit is the newest one above
+                    self.message = copy.jump(true);
+                }
+                KeyCode::Esc => copy.prompt = None,
+                _ => {}
+            }
+            term.scroll_to_line(copy.cursor.line);
+            return;
+        }
+
+        let last_line = term.lines().len().saturating_sub(1);
+        let last_col = term.cols().saturating_sub(1) as usize;
+        let Point { line, col } = copy.cursor;
+        copy.cursor = match key.code {
+            KeyCode::Up | KeyCode::Char('k') => Point::new(line.saturating_sub(1), col),
+            KeyCode::Down | KeyCode::Char('j') => Point::new((line + 1).min(last_line), col),
+            KeyCode::Left | KeyCode::Char('h') => Point::new(line, col.saturating_sub(1)),
+            KeyCode::Right | KeyCode::Char('l') => Point::new(line, (col + 1).min(last_col)),
+            KeyCode::PageUp => Point::new(line.saturating_sub(page), col),
+            KeyCode::PageDown => Point::new((line + page).min(last_line), col),
+            KeyCode::Home | KeyCode::Char('g') => Point::new(0, 0),
+            KeyCode::End | KeyCode::Char('G') => Point::new(last_line, 0),
+            KeyCode::Char('0') => Point::new(line, 0),
+            KeyCode::Char('$') => Point::new(line, last_col),
+            KeyCode::Char('v') | KeyCode::Char(' ') => {
+                term.selection = match term.selection {
+                    Some(_) => None,
+                    None => Some(Selection::new(copy.cursor, copy.cursor)),
+                };
+                copy.cursor
+            }
+            KeyCode::Char('y') | KeyCode::Enter => {
+                if let Some(text) = term.selected_text() {
+                    self.message = Some(rust_i18n::t!("tui.copied", count = text.chars().count()).to_string());
+                    self.buffer = Some(text);
+                }
+                self.leave_copy_mode();
+                return;
+            }
+            KeyCode::Char('/') => {
+                copy.prompt = Some(String::new());
+                return;
+            }
+            KeyCode::Char('n') | KeyCode::Char('N') => {
+                self.message = copy.jump(key.code == KeyCode::Char('n'));
+                copy.cursor
+            }
+            KeyCode::Esc | KeyCode::Char('q') => {
+                self.leave_copy_mode();
+                return;
+            }
+            _ => copy.cursor,
+        };
+        if let Some(selection) = &mut term.selection {
+            selection.head = copy.cursor;
+        }
+        term.scroll_to_line(copy.cursor.line);
+    }
+
+    fn area(&self) -> Rect {
+        Rect { x: 0, y: 0, w: self.width, h: self.height.saturating_sub(1) }
+    }
+
+    fn layout(&self) -> Layout {
+        let (mut panes, mut dividers) = (Vec::new(), Vec::new());
+        if let Some(tab) = self.tabs.get(self.active) {
+            if tab.zoomed {
+                panes.push((tab.focused, self.area()));
+            } else {
+                tab.root.layout(self.area(), &mut panes, &mut dividers);
+            }
+        }
+        (panes, dividers)
+    }
+
+    fn focus_direction(&mut self, dir: Direction) {
+        let (rects, _) = self.layout();
+        let tab = &mut self.tabs[self.active];
+        let Some(&(_, from)) = rects.iter().find(|(id, _)| *id == tab.focused) else {
+            return;
+        };
+        let (cx, cy) = (from.x as i32 + from.w as i32 / 2, from.y as i32 + from.h as i32 / 2);
+        let best = rects
+            .iter()
+            .filter(|(id, _)| *id != tab.focused)
+            .filter_map(|(id, r)| {
+                let (rx, ry) = (r.x as i32 + r.w as i32 / 2, r.y as i32 + r.h as i32 / 2);
+                // Distance along the direction, and how far off-axis the pane is
+                let (ahead, off) = match dir {
+                    Direction::Left => ((from.x as i32) - (r.x as i32 + r.w as i32), (ry - cy).abs()),
+                    Direction::Right => (r.x as i32 - (from.x as i32 + from.w as i32), (ry - cy).abs()),
+                    Direction::Up => ((from.y as i32) - (r.y as i32 + r.h as i32), (rx - cx).abs()),
+                    Direction::Down => (r.y as i32 - (from.y as i32 + from.h as i32), (rx - cx).abs()),
+                };
+                (ahead >= 0).then_some((ahead * 1000 + off, *id))
+            })
+            .min();
+        if let Some((_, id)) = best {
+            tab.focused = id;
+        }
+    }
+
+    fn resize_focused(&mut self, dir: Direction) {
+        let tab = &mut self.tabs[self.active];
+        let (split, delta) = match dir {
+            Direction::Left => (Split::SideBySide, -0.05),
+            Direction::Right => (Split::SideBySide, 0.05),
+            Direction::Up => (Split::Stacked, -0.05),
+            Direction::Down => (Split::Stacked, 0.05),
+        };
+        tab.root.resize(tab.focused, split, delta);
+    }
+
+    fn render(&mut self, out: &mut impl Write) -> Result<()> {
+        if self.width < 2 || self.height < 2 {
+            return Ok(());
+        }
+        let (rects, dividers) = self.layout();
+        // Shells learn their new size before we draw them
+        for (id, rect) in &rects {
+            if let Some(pane) = self.panes.get_mut(id) {
+                if rect.h > 0 && rect.w > 0 {
+                    pane.session.resize(rect.h, rect.w);
+                }
+            }
+        }
+
+        queue!(out, terminal::BeginSynchronizedUpdate, cursor::Hide)?;
+        let focused = self.tabs.get(self.active).map(|t| t.focused);
+        for (id, rect) in &rects {
+            if let Some(pane) = self.panes.get(id) {
+                let copy = self.copy.as_ref().filter(|_| focused == Some(*id));
+                draw_screen(out, &pane.session.term, copy, *rect)?;
+            }
+        }
+        queue!(out, SetAttribute(Attribute::Reset), SetForegroundColor(Color::DarkGrey))?;
+        for (rect, split) in &dividers {
+            for i in 0..rect.w.max(rect.h) {
+                let (x, y, ch) = match split {
+                    Split::SideBySide => (rect.x, rect.y + i, "│"),
+                    Split::Stacked => (rect.x + i, rect.y, "─"),
+                };
+                queue!(out, cursor::MoveTo(x, y), Print(ch))?;
+            }
+        }
+        self.draw_status(out)?;
+        if self.help {
+            self.draw_help(out)?;
+        }
+
+        // Cursor of the focused pane
+        if let Some((pane, rect)) = focused.and_then(|id| Some((self.panes.get(&id)?, rects.iter().find(|(p, _)| *p == id)?.1))) {
+            let screen = pane.session.term.screen();
+            let (row, col) = screen.cursor_position();
+            if !self.help && self.copy.is_none() && !screen.hide_cursor() && row < rect.h && col < rect.w {
+                queue!(out, cursor::MoveTo(rect.x + col, rect.y + row), cursor::Show)?;
+            }
+        }
+        queue!(out, terminal::EndSynchronizedUpdate)?;
+        out.flush()
+    }
+
+    fn draw_status(&self, out: &mut impl Write) -> Result<()> {
+        let y = self.height - 1;
+        queue!(
+            out,
+            cursor::MoveTo(0, y),
+            SetAttribute(Attribute::Reset),
+            SetBackgroundColor(Color::DarkBlue),
+            SetForegroundColor(Color::White),
+            Print(" TermiX "),
+        )?;
+        let mut used = 8usize;
+        for (i, tab) in self.tabs.iter().enumerate() {
+            // The running program, else what the shell named its window
+            let pane = self.panes.get(&tab.focused);
+            let program = pane.and_then(|p| p.session.pty.foreground_process());
+            let title = program
+                .as_deref()
+                .or_else(|| pane.and_then(|p| Some(p.session.term.title()).filter(|t| !t.is_empty()).or(p.title.as_deref())))
+                .unwrap_or("Terminal");
+            let zoom = if tab.zoomed { " ⛶" } else { "" };
+            let label: String = format!(" {}:{}{} ", i, title, zoom).chars().take(24).collect();
+            used += label.chars().count();
+            if i == self.active {
+                queue!(out, SetBackgroundColor(Color::Green), SetForegroundColor(Color::Black), Print(&label))?;
+            } else {
+                queue!(out, SetBackgroundColor(Color::DarkBlue), SetForegroundColor(Color::White), Print(&label))?;
+            }
+        }
+        let hint = if let Some(message) = &self.message {
+            message.clone()
+        } else if self.prefix {
+            rust_i18n::t!("tui.prefix_pending").to_string()
+        } else if let Some(copy) = &self.copy {
+            copy.status()
+        } else {
+            rust_i18n::t!("tui.help_hint").to_string()
+        };
+        let hint = format!(" {} ", hint);
+        let free = (self.width as usize).saturating_sub(used);
+        let hint_len = hint.chars().count();
+        let color = if self.prefix || self.copy.is_some() || self.message.is_some() { Color::Yellow } else { Color::Grey };
+        queue!(
+            out,
+            SetBackgroundColor(Color::DarkBlue),
+            Print(" ".repeat(free.saturating_sub(hint_len))),
+            SetForegroundColor(color),
+            Print(hint.chars().take(free).collect::<String>()),
+            SetAttribute(Attribute::Reset),
+        )
+    }
+
+    fn draw_help(&self, out: &mut impl Write) -> Result<()> {
+        let help = help_lines();
+        let width = help.iter().map(|l| l.chars().count()).max().unwrap_or(0) as u16 + 4;
+        let height = help.len() as u16 + 2;
+        let x = self.width.saturating_sub(width) / 2;
+        let y = self.height.saturating_sub(height) / 2;
+        queue!(out, SetBackgroundColor(Color::Black), SetForegroundColor(Color::Cyan))?;
+        queue!(out, cursor::MoveTo(x, y), Print(format!("╔{}╗", "═".repeat(width as usize - 2))))?;
+        for (i, line) in help.iter().enumerate() {
+            let pad = width as usize - 4 - line.chars().count();
+            queue!(out, cursor::MoveTo(x, y + 1 + i as u16), Print(format!("║ {}{} ║", line, " ".repeat(pad))))?;
+        }
+        queue!(out, cursor::MoveTo(x, y + height - 1), Print(format!("╚{}╝", "═".repeat(width as usize - 2))))?;
+        queue!(out, SetAttribute(Attribute::Reset))
+    }
+}
+
+impl CopyMode {
+    /// Move the cursor to the next match; returns a message if there is none.
+    fn jump(&mut self, backwards: bool) -> Option<String> {
+        match search::next_match(&self.matches, self.cursor, backwards) {
+            Some(i) => {
+                self.cursor = self.matches[i].start_point();
+                None
+            }
+            None => Some(rust_i18n::t!("tui.no_matches", query = self.query).to_string()),
+        }
+    }
+
+    fn status(&self) -> String {
+        if let Some(prompt) = &self.prompt {
+            format!("/{}", prompt)
+        } else if !self.matches.is_empty() {
+            rust_i18n::t!("tui.search_status", count = self.matches.len(), query = self.query).to_string()
+        } else {
+            rust_i18n::t!("tui.copy_status", line = self.cursor.line + 1).to_string()
+        }
+    }
+
+    /// Cells shown inverted: the cursor, the selection and search matches.
+    fn marks(&self, line: usize, col: usize, selection: Option<Selection>) -> bool {
+        let point = Point::new(line, col);
+        point == self.cursor || selection.is_some_and(|s| s.contains(point)) || self.matches.iter().any(|m| m.contains(line, col))
+    }
+}
+
+/// Paint a terminal screen into `rect`, emitting style changes only where
+/// they differ from the previous cell.
+fn draw_screen(out: &mut impl Write, term: &Terminal, copy: Option<&CopyMode>, rect: Rect) -> Result<()> {
+    let top = term.line_at_row(0);
+    let selection = term.selection;
+    let screen = term.screen();
+    let mut current = None;
+    for row in 0..rect.h {
+        queue!(out, cursor::MoveTo(rect.x, rect.y + row))?;
+        let mut col = 0;
+        while col < rect.w {
+            let cell = screen.cell(row, col);
+            if cell.is_some_and(|c| c.is_wide_continuation()) {
+                col += 1;
+                continue;
+            }
+            let marked = copy.is_some_and(|c| c.marks(top + row as usize, col as usize, selection));
+            let style = cell.map(|c| (c.fgcolor(), c.bgcolor(), c.bold(), c.italic(), c.underline(), c.inverse() != marked));
+            if style != current {
+                queue!(out, SetAttribute(Attribute::Reset))?;
+                if let Some((fg, bg, bold, italic, underline, inverse)) = style {
+                    queue!(out, SetForegroundColor(vt_color(fg)), SetBackgroundColor(vt_color(bg)))?;
+                    if bold {
+                        queue!(out, SetAttribute(Attribute::Bold))?;
+                    }
+                    if italic {
+                        queue!(out, SetAttribute(Attribute::Italic))?;
+                    }
+                    if underline {
+                        queue!(out, SetAttribute(Attribute::Underlined))?;
+                    }
+                    if inverse {
+                        queue!(out, SetAttribute(Attribute::Reverse))?;
+                    }
+                }
+                current = style;
+            }
+            match cell {
+                Some(c) if c.has_contents() && !(c.is_wide() && col + 1 >= rect.w) => {
+                    queue!(out, Print(c.contents()))?;
+                    col += if c.is_wide() { 2 } else { 1 };
+                }
+                _ => {
+                    queue!(out, Print(' '))?;
+                    col += 1;
+                }
+            }
+        }
+    }
+    queue!(out, SetAttribute(Attribute::Reset))
+}
+
+fn vt_color(color: vt100::Color) -> Color {
+    match color {
+        vt100::Color::Default => Color::Reset,
+        vt100::Color::Idx(i) => Color::AnsiValue(i),
+        vt100::Color::Rgb(r, g, b) => Color::Rgb { r, g, b },
+    }
+}
+
+/// The core's key for a crossterm event; `None` for keys a terminal does
+/// not send.
+fn key_input(key: KeyEvent) -> Option<(Key, Modifiers)> {
+    let modifiers = Modifiers {
+        ctrl: key.modifiers.contains(KeyModifiers::CONTROL),
+        alt: key.modifiers.contains(KeyModifiers::ALT),
+        shift: key.modifiers.contains(KeyModifiers::SHIFT),
+    };
+    let key = match key.code {
+        KeyCode::Char(c) => Key::Char(c),
+        KeyCode::Enter => Key::Enter,
+        KeyCode::Backspace => Key::Backspace,
+        KeyCode::Tab => Key::Tab,
+        KeyCode::BackTab => Key::BackTab,
+        KeyCode::Esc => Key::Esc,
+        KeyCode::Up => Key::Up,
+        KeyCode::Down => Key::Down,
+        KeyCode::Left => Key::Left,
+        KeyCode::Right => Key::Right,
+        KeyCode::Home => Key::Home,
+        KeyCode::End => Key::End,
+        KeyCode::Insert => Key::Insert,
+        KeyCode::Delete => Key::Delete,
+        KeyCode::PageUp => Key::PageUp,
+        KeyCode::PageDown => Key::PageDown,
+        KeyCode::F(n) => Key::F(n),
+        _ => return None,
+    };
+    Some((key, modifiers))
+}
@@ -0,0 +1,278 @@
+//! Settings form of the TUI. Edits the same `settings.toml` as the GUI.
+
+use crate::config::{self, AppSettings, Rgba};
+use crossterm::{
+    cursor,
+    event::{self, Event, KeyCode, KeyEventKind},
+    execute,
+    style::{Color, ResetColor, SetBackgroundColor, SetForegroundColor},
+    terminal::{Clear, ClearType},
+    Result,
+};
+use std::io::Write;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Field {
+    Language,
+    Theme,
+    Scrollback,
+    Profile,
+    TerminalColor,
+    CursorColor,
+    CursorShape,
+    CursorBlinking,
+    Save,
+    Cancel,
+}
+
+const FIELDS: &[Field] = &[
+    Field::Language,
+    Field::Theme,
+    Field::Scrollback,
+    Field::Profile,
+    Field::TerminalColor,
+    Field::CursorColor,
+    Field::CursorShape,
+    Field::CursorBlinking,
+    Field::Save,
+    Field::Cancel,
+];
+
+impl Field {
+    fn label(self) -> String {
+        // The GUI labels end in a colon, the form adds its own spacing
+        let label = match self {
+            Field::Language => rust_i18n::t!("settings.language"),
+            Field::Theme => rust_i18n::t!("settings.theme"),
+            Field::Scrollback => rust_i18n::t!("settings.scrollback_lines"),
+            Field::Profile => rust_i18n::t!("settings.default_profile"),
+            Field::TerminalColor => rust_i18n::t!("settings.terminal_color"),
+            Field::CursorColor => rust_i18n::t!("settings.cursor_color_short"),
+            Field::CursorShape => rust_i18n::t!("settings.cursor_shape"),
+            Field::CursorBlinking => rust_i18n::t!("settings.cursor_blink"),
+            Field::Save => return format!("💾 {}", rust_i18n::t!("common.save")),
+            Field::Cancel => return format!("↩ {}", rust_i18n::t!("common.back")),
+        };
+        label.trim_end_matches(':').to_string()
+    }
+
+    /// Typed in rather than picked from a list.
+    fn is_text(self) -> bool {
+        matches!(self, Field::Scrollback | Field::TerminalColor | Field::CursorColor)
+    }
+}
+
+pub struct SettingsForm {
+    settings: AppSettings,
+    selected: usize,
+    editing: Option<String>,
+    message: Option<(String, bool)>, // text, is error
+    dirty: bool,
+}
+
+impl SettingsForm {
+    pub fn new() -> Self {
+        SettingsForm {
+            settings: config::load_settings(),
+            selected: 0,
+            editing: None,
+            message: None,
+            dirty: false,
+        }
+    }
+
+    /// Show the form until the user leaves it; returns the settings when
+    /// they were saved.
+    pub fn run(&mut self, out: &mut impl Write) -> Result<Option<AppSettings>> {
+        let mut saved = None;
+        loop {
+            self.render(out)?;
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind == KeyEventKind::Release {
+                continue;
+            }
+            let field = FIELDS[self.selected];
+            if let Some(buffer) = &mut self.editing {
+                match key.code {
+                    KeyCode::Enter => self.commit_edit(field),
+                    KeyCode::Esc => self.editing = None,
+                    KeyCode::Backspace => {
+                        buffer.pop();
+                    }
+                    KeyCode::Char(c) => buffer.push(c),
+                    _ => {}
+                }
+                continue;
+            }
+            self.message = None;
+            match key.code {
+                KeyCode::Up => self.selected = (self.selected + FIELDS.len() - 1) % FIELDS.len(),
+                KeyCode::Down | KeyCode::Tab => self.selected = (self.selected + 1) % FIELDS.len(),
+                KeyCode::Left => self.cycle(field, false),
+                KeyCode::Right | KeyCode::Char(' ') => self.cycle(field, true),
+                KeyCode::Char(c) if field.is_text() => self.editing = Some(c.to_string()),
+                KeyCode::Enter if field.is_text() => self.editing = Some(self.value(field)),
+                KeyCode::Enter if field == Field::Save => {
+                    if let Some(settings) = self.save() {
+                        saved = Some(settings);
+                    }
+                }
+                KeyCode::Enter if field == Field::Cancel => break,
+                KeyCode::Enter => self.cycle(field, true),
+                KeyCode::Char('s') | KeyCode::Char('S') => {
+                    if let Some(settings) = self.save() {
+                        saved = Some(settings);
+                    }
+                }
+                KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('Q') => break,
+                _ => {}
+            }
+        }
+        Ok(saved)
+    }
+
+    fn value(&self, field: Field) -> String {
+        let s = &self.settings;
+        match field {
+            Field::Language => match s.language.as_str() {
+                "en" => "English (en)".into(),
+                _ => "Deutsch (de)".into(),
+            },
+            Field::Theme => s.theme.clone(),
+            Field::Scrollback => s.scrollback_lines.to_string(),
+            Field::Profile => match s.default_profile() {
+                Some(profile) => profile.name.clone(),
+                None => rust_i18n::t!("profile.plain_shell").into(),
+            },
+            Field::TerminalColor => s.terminal_text_color.to_hex(),
+            Field::CursorColor => s.cursor_color.to_hex(),
+            Field::CursorShape => s.cursor_shape.clone(),
+            Field::CursorBlinking => if s.cursor_blinking { rust_i18n::t!("common.enabled") } else { rust_i18n::t!("common.disabled") }.into(),
+            Field::Save | Field::Cancel => String::new(),
+        }
+    }
+
+    /// Step a choice field to its next (or previous) value.
+    fn cycle(&mut self, field: Field, forward: bool) {
+        fn step(options: &[&str], current: &str, forward: bool) -> String {
+            let pos = options.iter().position(|o| *o == current).unwrap_or(0);
+            let next = if forward { (pos + 1) % options.len() } else { (pos + options.len() - 1) % options.len() };
+            options[next].to_string()
+        }
+        let s = &mut self.settings;
+        match field {
+            Field::Language => s.language = step(config::LANGUAGES, &s.language, forward),
+            Field::Theme => s.theme = step(config::THEMES, &s.theme, forward),
+            Field::CursorShape => s.cursor_shape = step(config::CURSOR_SHAPES, &s.cursor_shape, forward),
+            Field::CursorBlinking => s.cursor_blinking = !s.cursor_blinking,
+            Field::Profile => {
+                // The plain shell (empty name) comes first
+                let names: Vec<&str> = std::iter::once("").chain(s.profiles.iter().map(|p| p.name.as_str())).collect();
+                let current = s.default_profile().map_or("", |p| p.name.as_str());
+                s.default_profile = step(&names, current, forward);
+            }
+            _ => return,
+        }
+        self.dirty = true;
+    }
+
+    fn commit_edit(&mut self, field: Field) {
+        let Some(text) = self.editing.take() else {
+            return;
+        };
+        let result = match field {
+            Field::Scrollback => match text.trim().parse::<usize>() {
+                Ok(n) if config::SCROLLBACK_RANGE.contains(&n) => {
+                    self.settings.scrollback_lines = n;
+                    Ok(())
+                }
+                _ => Err(rust_i18n::t!(
+                    "settings.scrollback_range",
+                    min = config::SCROLLBACK_RANGE.start(),
+                    max = config::SCROLLBACK_RANGE.end()
+                )
+                .into()),
+            },
+            Field::TerminalColor => Rgba::from_hex(&text).map(|c| self.settings.terminal_text_color = c),
+            Field::CursorColor => Rgba::from_hex(&text).map(|c| self.settings.cursor_color = c),
+            _ => Ok(()),
+        };
+        match result {
+            Ok(()) => self.dirty = true,
+            Err(e) => {
+                self.message = Some((e, true));
+                // Let the user fix the typo
+                self.editing = Some(text);
+            }
+        }
+    }
+
+    fn save(&mut self) -> Option<AppSettings> {
+        let result = self.settings.validate().and_then(|_| config::write_settings(&self.settings));
+        match result {
+            Ok(()) => {
+                self.dirty = false;
+                rust_i18n::set_locale(&self.settings.language);
+                self.message = Some((format!("✓ {}", rust_i18n::t!("common.saved")), false));
+                Some(self.settings.clone())
+            }
+            Err(e) => {
+                self.message = Some((format!("{}: {}", rust_i18n::t!("common.error"), e), true));
+                None
+            }
+        }
+    }
+
+    fn render(&self, out: &mut impl Write) -> Result<()> {
+        execute!(out, Clear(ClearType::All), cursor::MoveTo(0, 0), SetForegroundColor(Color::Yellow))?;
+        let dirty = if self.dirty { format!("  ({})", rust_i18n::t!("common.unsaved")) } else { String::new() };
+        writeln!(out, "\r\n  ⚙️  {}{}\r", rust_i18n::t!("settings.title").to_uppercase(), dirty)?;
+        writeln!(out, "  ═══════════════════════════════════\r")?;
+        execute!(out, ResetColor)?;
+        writeln!(out, "\r")?;
+
+        for (index, field) in FIELDS.iter().enumerate() {
+            if *field == Field::Save {
+                writeln!(out, "\r")?;
+            }
+            let value = match (&self.editing, index == self.selected) {
+                (Some(buffer), true) => format!("{}▏", buffer),
+                _ => self.value(*field),
+            };
+            let choice = !field.is_text() && !matches!(field, Field::Save | Field::Cancel);
+            let value = if choice && index == self.selected { format!("◀ {} ▶", value) } else { value };
+            let line = if value.is_empty() {
+                format!("  {}", field.label())
+            } else {
+                format!("  {:<20} {}", field.label(), value)
+            };
+            if index == self.selected {
+                execute!(out, SetBackgroundColor(Color::Green), SetForegroundColor(Color::Black))?;
+                write!(out, "  ▶{:<50}", line)?;
+                execute!(out, ResetColor)?;
+                writeln!(out, "\r")?;
+            } else {
+                writeln!(out, "   {}\r", line)?;
+            }
+        }
+
+        writeln!(out, "\r")?;
+        if let Some((text, error)) = &self.message {
+            execute!(out, SetForegroundColor(if *error { Color::Red } else { Color::Green }))?;
+            writeln!(out, "  {}\r", text)?;
+            execute!(out, ResetColor)?;
+        }
+        execute!(out, SetForegroundColor(Color::DarkGrey))?;
+        writeln!(out, "\r\n  {}: {}\r", rust_i18n::t!("common.file"), config::settings_path().display())?;
+        let hint = if self.editing.is_some() {
+            rust_i18n::t!("tui.edit_hint")
+        } else {
+            rust_i18n::t!("tui.field_hint")
+        };
+        writeln!(out, "  {}\r", hint)?;
+        execute!(out, ResetColor)?;
+        out.flush()
+    }
+}
@@ -0,0 +1,494 @@
+//! SSH connection manager of the TUI. Lists and edits the same
+//! `ssh_connections.toml` as the GUI; connecting opens a mux tab.
+
+use crate::config::layout::expand_home;
+use crate::config::ssh::{self, SshConnection};
+use crossterm::{
+    cursor,
+    event::{self, Event, KeyCode, KeyEventKind},
+    execute,
+    style::{Color, ResetColor, SetBackgroundColor, SetForegroundColor},
+    terminal::{self, Clear, ClearType},
+    Result,
+};
+use std::io::Write;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Field {
+    Name,
+    Host,
+    Port,
+    User,
+    IdentityFile,
+    Group,
+    Tags,
+    Profile,
+    Save,
+    Cancel,
+}
+
+const FIELDS: &[Field] = &[
+    Field::Name,
+    Field::Host,
+    Field::Port,
+    Field::User,
+    Field::IdentityFile,
+    Field::Group,
+    Field::Tags,
+    Field::Profile,
+    Field::Save,
+    Field::Cancel,
+];
+
+impl Field {
+    fn label(self) -> String {
+        // Shared with the GUI dialog, whose labels end in a colon
+        let label = match self {
+            Field::Name => rust_i18n::t!("ssh.name"),
+            Field::Host => rust_i18n::t!("ssh.host"),
+            Field::Port => rust_i18n::t!("ssh.port"),
+            Field::User => rust_i18n::t!("ssh.username"),
+            Field::IdentityFile => rust_i18n::t!("ssh.identity_file"),
+            Field::Group => rust_i18n::t!("ssh.group"),
+            Field::Tags => rust_i18n::t!("ssh.tags_comma"),
+            Field::Profile => rust_i18n::t!("ssh.profile"),
+            Field::Save => return format!("💾 {}", rust_i18n::t!("ssh.save")),
+            Field::Cancel => return format!("↩ {}", rust_i18n::t!("ssh.cancel")),
+        };
+        label.trim_end_matches(':').to_string()
+    }
+}
+
+/// Connection being added or edited.
+struct Editor {
+    index: Option<usize>, // None = new connection
+    conn: SshConnection,
+    tags: String,
+    selected: usize,
+    editing: Option<String>,
+}
+
+enum Mode {
+    List,
+    Search,
+    Edit(Editor),
+    Password { conn: SshConnection, input: String },
+    ConfirmDelete(usize),
+}
+
+pub struct SshManager {
+    connections: Vec<SshConnection>,
+    search: String,
+    selected: usize, // position in `visible()`
+    mode: Mode,
+    message: Option<(String, bool)>, // text, is error
+    load_error: Option<String>,      // the file could not be read; saving would overwrite it
+}
+
+impl SshManager {
+    pub fn new() -> Self {
+        let (connections, message) = match ssh::load_connections() {
+            Ok((connections, None)) => (connections, None),
+            Ok((connections, Some(legacy))) => {
+                (connections, Some((rust_i18n::t!("ssh.legacy_imported", path = legacy.display()).into(), false)))
+            }
+            Err(e) => (Vec::new(), Some((e, true))),
+        };
+        let load_error = message.as_ref().filter(|(_, error)| *error).map(|(e, _)| e.clone());
+        SshManager { connections, search: String::new(), selected: 0, mode: Mode::List, message, load_error }
+    }
+
+    /// Shown above the list the next time the menu is drawn.
+    pub fn show_error(&mut self, error: String) {
+        self.message = Some((error, true));
+    }
+
+    /// `conn` when it can be connected to right away; otherwise `run` starts
+    /// by asking for its password.
+    pub fn prepare(&mut self, conn: SshConnection) -> Option<SshConnection> {
+        if conn.password.is_empty() && conn.identity_file.is_none() {
+            self.mode = Mode::Password { conn, input: String::new() };
+            return None;
+        }
+        Some(conn)
+    }
+
+    /// Show the manager until the user picks a host or leaves; the returned
+    /// connection carries the password typed in for it.
+    pub fn run(&mut self, out: &mut impl Write) -> Result<Option<SshConnection>> {
+        loop {
+            self.render(out)?;
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind == KeyEventKind::Release {
+                continue;
+            }
+            match std::mem::replace(&mut self.mode, Mode::List) {
+                Mode::List => {
+                    self.message = None;
+                    let visible = self.visible();
+                    let current = visible.get(self.selected).copied();
+                    match key.code {
+                        KeyCode::Up if self.selected > 0 => self.selected -= 1,
+                        KeyCode::Down if self.selected + 1 < visible.len() => self.selected += 1,
+                        KeyCode::Enter => {
+                            if let Some(conn) = current.and_then(|index| self.prepare(self.connections[index].clone())) {
+                                return Ok(Some(conn));
+                            }
+                        }
+                        KeyCode::Char('/') => self.mode = Mode::Search,
+                        KeyCode::Char('a') | KeyCode::Char('A') => self.mode = Mode::Edit(Editor::new(None, SshConnection::default())),
+                        KeyCode::Char('e') | KeyCode::Char('E') => {
+                            if let Some(index) = current {
+                                self.mode = Mode::Edit(Editor::new(Some(index), self.connections[index].clone()));
+                            }
+                        }
+                        KeyCode::Char('f') | KeyCode::Char('F') => {
+                            if let Some(index) = current {
+                                self.connections[index].favorite = !self.connections[index].favorite;
+                                self.save();
+                            }
+                        }
+                        KeyCode::Char('d') | KeyCode::Char('D') | KeyCode::Delete => {
+                            if let Some(index) = current {
+                                self.mode = Mode::ConfirmDelete(index);
+                            }
+                        }
+                        KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('Q') => return Ok(None),
+                        _ => {}
+                    }
+                }
+                Mode::Search => match key.code {
+                    KeyCode::Enter | KeyCode::Down => {}
+                    KeyCode::Esc => self.search.clear(),
+                    KeyCode::Backspace => {
+                        self.search.pop();
+                        self.selected = 0;
+                        self.mode = Mode::Search;
+                    }
+                    KeyCode::Char(c) => {
+                        self.search.push(c);
+                        self.selected = 0;
+                        self.mode = Mode::Search;
+                    }
+                    _ => self.mode = Mode::Search,
+                },
+                Mode::Edit(mut editor) => {
+                    if !self.edit_key(&mut editor, key.code) {
+                        self.mode = Mode::Edit(editor);
+                    }
+                }
+                Mode::Password { mut conn, mut input } => match key.code {
+                    KeyCode::Enter => {
+                        conn.password = input;
+                        return Ok(Some(conn));
+                    }
+                    KeyCode::Esc => {}
+                    KeyCode::Backspace => {
+                        input.pop();
+                        self.mode = Mode::Password { conn, input };
+                    }
+                    KeyCode::Char(c) => {
+                        input.push(c);
+                        self.mode = Mode::Password { conn, input };
+                    }
+                    _ => self.mode = Mode::Password { conn, input },
+                },
+                Mode::ConfirmDelete(index) => {
+                    if matches!(key.code, KeyCode::Char('j') | KeyCode::Char('J') | KeyCode::Char('y') | KeyCode::Char('Y')) {
+                        let removed = self.connections.remove(index);
+                        self.selected = self.selected.min(self.visible().len().saturating_sub(1));
+                        self.save();
+                        if self.message.is_none() {
+                            self.message = Some((rust_i18n::t!("ssh.deleted", name = removed.name).into(), false));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Indices of the connections to list: search hits by score, otherwise
+    /// favorites first.
+    fn visible(&self) -> Vec<usize> {
+        if self.search.trim().is_empty() {
+            let (mut favorites, others): (Vec<usize>, Vec<usize>) =
+                (0..self.connections.len()).partition(|&i| self.connections[i].favorite);
+            favorites.extend(others);
+            return favorites;
+        }
+        let mut hits: Vec<(usize, i64)> = self
+            .connections
+            .iter()
+            .enumerate()
+            .filter_map(|(i, conn)| conn.search_score(&self.search).map(|score| (i, score)))
+            .collect();
+        hits.sort_by_key(|&(_, score)| std::cmp::Reverse(score));
+        hits.into_iter().map(|(i, _)| i).collect()
+    }
+
+    /// Write the connections; refused while the file on disk could not be
+    /// read, so its contents are not replaced by the (partial) list here.
+    fn save(&mut self) {
+        let result = match &self.load_error {
+            Some(e) => Err(rust_i18n::t!("ssh.not_saved", error = e).to_string()),
+            None => ssh::save_connections(&self.connections),
+        };
+        if let Err(e) = result {
+            self.message = Some((format!("{}: {}", rust_i18n::t!("ssh.save_failed"), e), true));
+        }
+    }
+
+    /// Handle a key in the editor; returns true once it is closed.
+    fn edit_key(&mut self, editor: &mut Editor, code: KeyCode) -> bool {
+        let field = FIELDS[editor.selected];
+        if let Some(buffer) = &mut editor.editing {
+            match code {
+                KeyCode::Enter => {
+                    if let Err(e) = editor.commit(field) {
+                        self.message = Some((e, true));
+                    } else {
+                        self.message = None;
+                    }
+                }
+                KeyCode::Esc => editor.editing = None,
+                KeyCode::Backspace => {
+                    buffer.pop();
+                }
+                KeyCode::Char(c) => buffer.push(c),
+                _ => {}
+            }
+            return false;
+        }
+        self.message = None;
+        match code {
+            KeyCode::Up => editor.selected = (editor.selected + FIELDS.len() - 1) % FIELDS.len(),
+            KeyCode::Down | KeyCode::Tab => editor.selected = (editor.selected + 1) % FIELDS.len(),
+            KeyCode::Enter if field == Field::Save => {
+                editor.conn.tags = editor
+                    .tags
+                    .split(',')
+                    .map(|t| t.trim().trim_start_matches('#').to_string())
+                    .filter(|t| !t.is_empty())
+                    .collect();
+                editor.conn.group = editor.conn.group.trim().to_string();
+                if let Err(e) = editor.conn.validate() {
+                    self.message = Some((e, true));
+                    return false;
+                }
+                let text = match editor.index.filter(|&i| i < self.connections.len()) {
+                    Some(index) => {
+                        self.connections[index] = editor.conn.clone();
+                        rust_i18n::t!("ssh.updated", name = editor.conn.name).to_string()
+                    }
+                    None => {
+                        self.connections.push(editor.conn.clone());
+                        rust_i18n::t!("ssh.added", name = editor.conn.name).to_string()
+                    }
+                };
+                self.message = Some((text, false));
+                self.save();
+                return true;
+            }
+            KeyCode::Enter if field == Field::Cancel => return true,
+            KeyCode::Enter => editor.editing = Some(editor.value(field)),
+            KeyCode::Char(c) if field != Field::Save && field != Field::Cancel => editor.editing = Some(c.to_string()),
+            KeyCode::Esc => return true,
+            _ => {}
+        }
+        false
+    }
+
+    fn render(&self, out: &mut impl Write) -> Result<()> {
+        execute!(out, Clear(ClearType::All), cursor::MoveTo(0, 0), SetForegroundColor(Color::Yellow))?;
+        match &self.mode {
+            Mode::Edit(editor) => {
+                let title = if editor.index.is_some() { rust_i18n::t!("ssh.edit_connection") } else { rust_i18n::t!("ssh.new_connection") };
+                writeln!(out, "\r\n  🔌 {}\r", title.to_uppercase())?;
+            }
+            _ => writeln!(out, "\r\n  🔌 {}\r", rust_i18n::t!("ssh.title").to_uppercase())?,
+        }
+        writeln!(out, "  ═══════════════════════════════════\r")?;
+        execute!(out, ResetColor)?;
+        writeln!(out, "\r")?;
+
+        let hint = match &self.mode {
+            Mode::Edit(editor) => {
+                self.render_editor(out, editor)?;
+                if editor.editing.is_some() {
+                    rust_i18n::t!("tui.edit_hint")
+                } else {
+                    rust_i18n::t!("ssh.edit_hint")
+                }
+            }
+            Mode::Password { conn, input } => {
+                let target = format!("{}@{}:{}", conn.username, conn.host, conn.port);
+                writeln!(out, "  {}\r", rust_i18n::t!("ssh.password_for", target = target))?;
+                writeln!(out, "\r\n  > {}▏\r", "•".repeat(input.chars().count()))?;
+                rust_i18n::t!("ssh.password_hint")
+            }
+            Mode::ConfirmDelete(index) => {
+                execute!(out, SetForegroundColor(Color::Red))?;
+                writeln!(out, "  {}\r", rust_i18n::t!("ssh.confirm_delete", name = self.connections[*index].name))?;
+                execute!(out, ResetColor)?;
+                "".into()
+            }
+            Mode::List | Mode::Search => {
+                self.render_list(out)?;
+                if matches!(self.mode, Mode::Search) {
+                    rust_i18n::t!("ssh.search_hint_tui")
+                } else {
+                    rust_i18n::t!("ssh.list_hint")
+                }
+            }
+        };
+
+        writeln!(out, "\r")?;
+        if let Some((text, error)) = &self.message {
+            execute!(out, SetForegroundColor(if *error { Color::Red } else { Color::Green }))?;
+            writeln!(out, "  {}\r", text)?;
+            execute!(out, ResetColor)?;
+        }
+        execute!(out, SetForegroundColor(Color::DarkGrey))?;
+        writeln!(out, "\r\n  {}: {}\r", rust_i18n::t!("common.file"), ssh::connections_path().display())?;
+        writeln!(out, "  {}\r", hint)?;
+        execute!(out, ResetColor)?;
+        out.flush()
+    }
+
+    fn render_list(&self, out: &mut impl Write) -> Result<()> {
+        if matches!(self.mode, Mode::Search) || !self.search.is_empty() {
+            writeln!(out, "  🔍 {}{}\r\n\r", self.search, if matches!(self.mode, Mode::Search) { "▏" } else { "" })?;
+        }
+        let visible = self.visible();
+        if visible.is_empty() {
+            execute!(out, SetForegroundColor(Color::DarkGrey))?;
+            if self.connections.is_empty() {
+                writeln!(out, "   {}\r", rust_i18n::t!("ssh.no_connections_tui"))?;
+            } else {
+                writeln!(out, "   {}\r", rust_i18n::t!("ssh.no_matches"))?;
+            }
+            return execute!(out, ResetColor);
+        }
+        // Scroll so the selection stays on screen
+        let (_, height) = terminal::size()?;
+        let rows = (height as usize).saturating_sub(14).max(3);
+        let first = self.selected.saturating_sub(rows - 1);
+        for (pos, &index) in visible.iter().enumerate().skip(first).take(rows) {
+            let conn = &self.connections[index];
+            let mut line = format!(
+                "{} {:<20} {}@{}:{}",
+                if conn.favorite { "★" } else { " " },
+                conn.name,
+                conn.username,
+                conn.host,
+                conn.port
+            );
+            if conn.identity_file.is_some() {
+                line.push_str(" 🔑");
+            }
+            if !conn.group.is_empty() {
+                line.push_str(&format!("  [{}]", conn.group));
+            }
+            for tag in &conn.tags {
+                line.push_str(&format!(" #{}", tag));
+            }
+            if pos == self.selected {
+                execute!(out, SetBackgroundColor(Color::Green), SetForegroundColor(Color::Black))?;
+                write!(out, "  ▶{:<60}", line)?;
+                execute!(out, ResetColor)?;
+                writeln!(out, "\r")?;
+            } else {
+                let color = conn.color.map_or(Color::White, |c| Color::Rgb { r: c.r, g: c.g, b: c.b });
+                execute!(out, SetForegroundColor(color))?;
+                writeln!(out, "   {}\r", line)?;
+                execute!(out, ResetColor)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn render_editor(&self, out: &mut impl Write, editor: &Editor) -> Result<()> {
+        for (index, field) in FIELDS.iter().enumerate() {
+            if *field == Field::Save {
+                writeln!(out, "\r")?;
+            }
+            let value = match (&editor.editing, index == editor.selected) {
+                (Some(buffer), true) => format!("{}▏", buffer),
+                _ => editor.value(*field),
+            };
+            let line = if matches!(field, Field::Save | Field::Cancel) {
+                format!("  {}", field.label())
+            } else {
+                format!("  {:<16} {}", field.label(), value)
+            };
+            if index == editor.selected {
+                execute!(out, SetBackgroundColor(Color::Green), SetForegroundColor(Color::Black))?;
+                write!(out, "  ▶{:<50}", line)?;
+                execute!(out, ResetColor)?;
+                writeln!(out, "\r")?;
+            } else {
+                writeln!(out, "   {}\r", line)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Editor {
+    fn new(index: Option<usize>, conn: SshConnection) -> Self {
+        Editor { index, tags: conn.tags.join(", "), conn, selected: 0, editing: None }
+    }
+
+    fn value(&self, field: Field) -> String {
+        let conn = &self.conn;
+        match field {
+            Field::Name => conn.name.clone(),
+            Field::Host => conn.host.clone(),
+            Field::Port => conn.port.to_string(),
+            Field::User => conn.username.clone(),
+            Field::IdentityFile => conn.identity_file.clone().unwrap_or_default(),
+            Field::Group => conn.group.clone(),
+            Field::Tags => self.tags.clone(),
+            Field::Profile => conn.profile.clone().unwrap_or_default(),
+            Field::Save | Field::Cancel => String::new(),
+        }
+    }
+
+    /// Apply the edited text to `field`; on error the text stays in the
+    /// editor so the user can fix it.
+    fn commit(&mut self, field: Field) -> std::result::Result<(), String> {
+        let Some(text) = self.editing.take() else {
+            return Ok(());
+        };
+        let value = text.trim().to_string();
+        match field {
+            Field::Name => self.conn.name = value,
+            Field::Host => self.conn.host = value,
+            Field::User => self.conn.username = value,
+            Field::Group => self.conn.group = value,
+            Field::Tags => self.tags = value,
+            Field::Profile if value.is_empty() => self.conn.profile = None,
+            Field::Profile => match crate::config::load_settings().profile(&value) {
+                Some(profile) => self.conn.profile = Some(profile.name.clone()),
+                None => {
+                    self.editing = Some(text);
+                    return Err(rust_i18n::t!("profile.unknown", profile = value).into());
+                }
+            },
+            Field::IdentityFile => {
+                self.conn.identity_file = (!value.is_empty()).then(|| expand_home(&value).display().to_string());
+            }
+            Field::Port => match value.parse::<u16>() {
+                Ok(port) if port > 0 => self.conn.port = port,
+                _ => {
+                    self.editing = Some(text);
+                    return Err(rust_i18n::t!("ssh.invalid_port").into());
+                }
+            },
+            Field::Save | Field::Cancel => {}
+        }
+        Ok(())
+    }
+}
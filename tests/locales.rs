@@ -0,0 +1,123 @@
+//! Every locale must define the same keys with the same `%{…}` placeholders,
+//! and every key the code passes to `t!` must exist in all of them.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// `section.key` -> text of one `locales/<lang>.yml`. The files only use
+/// two levels: `section:` lines and indented `key: "value"` lines.
+fn parse_locale(path: &Path) -> BTreeMap<String, String> {
+    let content = fs::read_to_string(path).unwrap();
+    let mut keys = BTreeMap::new();
+    let mut section = String::new();
+    for line in content.lines() {
+        if line.trim().is_empty() || line.trim_start().starts_with('#') {
+            continue;
+        }
+        let (name, value) = line.split_once(':').unwrap_or_else(|| panic!("{}: bad line {:?}", path.display(), line));
+        if !line.starts_with(' ') {
+            section = name.to_string();
+            continue;
+        }
+        let value = value.trim();
+        let value = value.strip_prefix('"').and_then(|v| v.strip_suffix('"')).unwrap_or(value);
+        let key = format!("{}.{}", section, name.trim());
+        assert!(keys.insert(key.clone(), value.to_string()).is_none(), "{}: duplicate key {}", path.display(), key);
+    }
+    keys
+}
+
+fn locales() -> BTreeMap<String, BTreeMap<String, String>> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("locales");
+    let mut locales = BTreeMap::new();
+    for entry in fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.extension().is_some_and(|e| e == "yml") {
+            let lang = path.file_stem().unwrap().to_string_lossy().into_owned();
+            locales.insert(lang, parse_locale(&path));
+        }
+    }
+    assert!(locales.len() >= 2, "expected at least two locales");
+    locales
+}
+
+fn placeholders(text: &str) -> BTreeSet<&str> {
+    text.match_indices("%{").filter_map(|(i, _)| text[i + 2..].split_once('}').map(|(name, _)| name)).collect()
+}
+
+fn rust_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    for entry in fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            rust_files(&path, files);
+        } else if path.extension().is_some_and(|e| e == "rs") {
+            files.push(path);
+        }
+    }
+}
+
+/// Literal keys passed to `t!("…")` anywhere in `src`.
+fn used_keys() -> BTreeMap<String, PathBuf> {
+    let mut files = Vec::new();
+    rust_files(&Path::new(env!("CARGO_MANIFEST_DIR")).join("src"), &mut files);
+    let mut keys = BTreeMap::new();
+    for file in files {
+        let source = fs::read_to_string(&file).unwrap();
+        for (i, _) in source.match_indices("t!(\"") {
+            // Skip macros that merely end in `t`, like `format!` or `assert!`
+            if source[..i].chars().next_back().is_some_and(|c| c.is_alphanumeric() || c == '_') {
+                continue;
+            }
+            let rest = &source[i + 4..];
+            let key = &rest[..rest.find('"').unwrap()];
+            keys.entry(key.to_string()).or_insert_with(|| file.clone());
+        }
+    }
+    keys
+}
+
+#[test]
+fn locales_have_the_same_keys() {
+    let locales = locales();
+    let all: BTreeSet<&String> = locales.values().flat_map(|keys| keys.keys()).collect();
+    for (lang, keys) in &locales {
+        let missing: Vec<_> = all.iter().filter(|k| !keys.contains_key(**k)).collect();
+        assert!(missing.is_empty(), "locales/{}.yml is missing {:?}", lang, missing);
+    }
+}
+
+#[test]
+fn placeholders_match_across_locales() {
+    let locales = locales();
+    let (first_lang, first) = locales.iter().next().unwrap();
+    for (lang, keys) in &locales {
+        for (key, text) in keys {
+            if let Some(other) = first.get(key) {
+                assert_eq!(
+                    placeholders(text),
+                    placeholders(other),
+                    "placeholders of {} differ between {} and {}",
+                    key,
+                    lang,
+                    first_lang
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn used_keys_exist_in_every_locale() {
+    let locales = locales();
+    let used = used_keys();
+    assert!(!used.is_empty());
+    for (lang, keys) in &locales {
+        let missing: Vec<String> = used
+            .iter()
+            .filter(|(key, _)| !keys.contains_key(*key))
+            .map(|(key, file)| format!("{} ({})", key, file.display()))
+            .collect();
+        assert!(missing.is_empty(), "locales/{}.yml is missing keys used in the code: {:#?}", lang, missing);
+    }
+}